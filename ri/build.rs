@@ -0,0 +1,40 @@
+// Build script to compile the runtime library that is linked into every natively compiled SS program.
+//
+// The runtime library is compiled into a static library with the same rustc that compiles ri,
+// and its path and the system libraries it needs are passed to ri's native back end as environment variables at compile time.
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=runtime");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let library = out_dir.join("libss_runtime.a");
+
+    let output = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .args(["--crate-name", "ss_runtime", "--crate-type", "staticlib", "--edition", "2021"])
+        .args(["-C", "opt-level=3", "-C", "panic=abort"])
+        .args(["--print", "native-static-libs"])
+        .arg("runtime/lib.rs")
+        .arg("-o")
+        .arg(&library)
+        .output()
+        .expect("Failed to run rustc to compile the runtime library");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        panic!("Failed to compile the runtime library:\n{}", stderr);
+    }
+
+    // rustc prints the system libraries needed to link the static library as a note
+    let native_libraries = stderr
+        .lines()
+        .find_map(|line| line.split("native-static-libs: ").nth(1))
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    println!("cargo:rustc-env=SS_RUNTIME_LIBRARY={}", library.display());
+    println!("cargo:rustc-env=SS_RUNTIME_NATIVE_LIBRARIES={}", native_libraries);
+}
@@ -0,0 +1,180 @@
+// Mark and sweep garbage collected heap of the runtime library
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use crate::value::{Object, ObjectKind, Value};
+
+/// Minimum number of allocations between garbage collections, so that small programs never collect
+const MINIMUM_COLLECTION_THRESHOLD: usize = 1024 * 64;
+
+/*
+    Compiled code keeps values in Cranelift SSA values, which live in machine registers and stack slots without any stack maps,
+    so the roots are found by scanning the whole machine stack conservatively like the Boehm GC does,
+    where every word on the stack that is the address of an object on the heap keeps that object alive.
+
+    Objects found this way are marked precisely, by following the values held by arrays, functions and environments.
+    Values held in callee saved registers are spilled onto the stack before scanning it, so they are scanned too.
+*/
+pub struct Heap {
+    /// Address of every object on the heap, to check if a word on the machine stack points to an object
+    objects: HashSet<usize>,
+
+    /// Number of objects allocated since the last collection, and the number of allocations to start the next collection at
+    allocations: usize,
+    collection_threshold: usize,
+
+    /// Address of the bottom of the machine stack, which is in the frame of the runtime's main function
+    stack_bottom: usize,
+
+    /// Bool and Null values are allocated once and never collected
+    pub true_value: Value,
+    pub false_value: Value,
+    pub null_value: Value,
+}
+
+thread_local! {
+    static HEAP: RefCell<Option<Heap>> = const { RefCell::new(None) };
+}
+
+/// Create the heap, with the bottom of the machine stack to scan for roots up to
+pub fn init(stack_bottom: usize) {
+    let permanent = |kind| Value(Box::into_raw(Box::new(Object { marked: false, kind })));
+
+    HEAP.with(|heap| {
+        *heap.borrow_mut() = Some(Heap {
+            objects: HashSet::new(),
+            allocations: 0,
+            collection_threshold: MINIMUM_COLLECTION_THRESHOLD,
+            stack_bottom,
+            true_value: permanent(ObjectKind::Bool(true)),
+            false_value: permanent(ObjectKind::Bool(false)),
+            null_value: permanent(ObjectKind::Null),
+        })
+    });
+}
+
+pub fn with_heap<T>(f: impl FnOnce(&mut Heap) -> T) -> T {
+    HEAP.with(|heap| f(heap.borrow_mut().as_mut().expect("Internal Runtime Error: Heap is not initialized")))
+}
+
+/// Allocate a new object on the heap, collecting garbage first once enough objects are allocated
+pub fn allocate(kind: ObjectKind) -> Value {
+    if with_heap(|heap| heap.allocations >= heap.collection_threshold) {
+        collect();
+    }
+
+    let value = Value(Box::into_raw(Box::new(Object { marked: false, kind })));
+    with_heap(|heap| {
+        heap.objects.insert(value.0 as usize);
+        heap.allocations += 1;
+    });
+    value
+}
+
+/// Spill the callee saved registers onto the stack before scanning it, since they can hold values of the compiled code's callers
+#[inline(never)]
+pub fn collect() {
+    let mut registers = [0usize; 12];
+    spill_registers(&mut registers);
+
+    // Scan from the spilled registers, which are in this function's frame, up to the bottom of the stack
+    let stack_top = registers.as_ptr() as usize;
+    with_heap(|heap| heap.collect(stack_top));
+
+    // Keep the spilled registers alive until the stack is scanned
+    std::hint::black_box(&registers);
+}
+
+#[inline(never)]
+fn spill_registers(registers: &mut [usize; 12]) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::asm!(
+            "mov [{0}], rbx",
+            "mov [{0} + 8], rbp",
+            "mov [{0} + 16], r12",
+            "mov [{0} + 24], r13",
+            "mov [{0} + 32], r14",
+            "mov [{0} + 40], r15",
+            in(reg) registers.as_mut_ptr(),
+        );
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!(
+            "stp x19, x20, [{0}]",
+            "stp x21, x22, [{0}, #16]",
+            "stp x23, x24, [{0}, #32]",
+            "stp x25, x26, [{0}, #48]",
+            "stp x27, x28, [{0}, #64]",
+            "stp x29, x30, [{0}, #80]",
+            in(reg) registers.as_mut_ptr(),
+        );
+    }
+}
+
+impl Heap {
+    fn collect(&mut self, stack_top: usize) {
+        let mut gray_objects: Vec<Value> = Vec::new();
+
+        // Every aligned word on the stack that is the address of an object is a root
+        let mut address = stack_top & !(std::mem::size_of::<usize>() - 1);
+        while address < self.stack_bottom {
+            let word = unsafe { std::ptr::read_volatile(address as *const usize) };
+            if self.objects.contains(&word) {
+                self.mark(Value(word as *mut Object), &mut gray_objects);
+            }
+            address += std::mem::size_of::<usize>();
+        }
+
+        // Trace the values held by the marked objects until there are no more marked objects to trace
+        while let Some(value) = gray_objects.pop() {
+            match value.kind() {
+                ObjectKind::Array(elements) => {
+                    for element in elements.iter() {
+                        self.mark(*element, &mut gray_objects);
+                    }
+                }
+                ObjectKind::Function(function) => self.mark(function.closure, &mut gray_objects),
+                ObjectKind::Environment(environment) => {
+                    self.mark(environment.enclosing, &mut gray_objects);
+                    for slot in environment.slots.iter() {
+                        self.mark(*slot, &mut gray_objects);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Free every object that is not marked, and clear the marks of the objects left for the next collection
+        self.objects.retain(|&address| {
+            let object = address as *mut Object;
+            unsafe {
+                if (*object).marked {
+                    (*object).marked = false;
+                    true
+                } else {
+                    drop(Box::from_raw(object));
+                    false
+                }
+            }
+        });
+
+        self.allocations = 0;
+        self.collection_threshold = std::cmp::max(MINIMUM_COLLECTION_THRESHOLD, self.objects.len());
+    }
+
+    /// Mark the object of the value if it is on the heap and not marked yet, to trace the values that it holds later
+    fn mark(&self, value: Value, gray_objects: &mut Vec<Value>) {
+        if !self.objects.contains(&(value.0 as usize)) {
+            return;
+        }
+
+        let object = unsafe { &mut *value.0 };
+        if !object.marked {
+            object.marked = true;
+            gray_objects.push(value);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! Runtime library linked into every SS program compiled ahead of time into a native executable, like the Go runtime.
+//!
+//! Compiled code only decides the control flow of the program, and calls into this library for everything that involves values,
+//! i.e. creating values, operators, environments, function calls and printing, so that compiled programs behave exactly like ri.
+//! The library is built into a static library by ri's build script, and is never compiled as part of ri itself.
+#![allow(clippy::missing_safety_doc)]
+
+mod heap;
+mod native;
+mod value;
+
+use std::io::Write;
+
+use value::{Code, Environment, Function, ObjectKind, Value};
+
+extern "C" {
+    /// Top level code of the compiled program
+    fn ss_main();
+}
+
+#[no_mangle]
+pub extern "C" fn main(_argc: i32, _argv: *const *const u8) -> i32 {
+    // Compiled code runs in frames below this one, so the roots on the stack are all above this local's address
+    let stack_bottom = 0usize;
+    heap::init(&stack_bottom as *const usize as usize);
+
+    unsafe { ss_main() };
+
+    std::io::stdout().flush().unwrap();
+    0
+}
+
+/// Report a runtime error the same way ri reports errors from the interpreter, and exit
+fn runtime_error(message: String) -> ! {
+    std::io::stdout().flush().unwrap();
+    eprintln!("-------- Interpreter ERROR --------");
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// String literals and identifiers are passed in as pointers into the compiled program's data
+unsafe fn string_from<'a>(pointer: *const u8, length: usize) -> &'a str {
+    std::str::from_utf8_unchecked(std::slice::from_raw_parts(pointer, length))
+}
+
+/* ==========================  Start of value constructors  ========================== */
+
+#[no_mangle]
+pub extern "C" fn ss_number(number: f64) -> Value {
+    heap::allocate(ObjectKind::Number(number))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ss_string(pointer: *const u8, length: usize) -> Value {
+    heap::allocate(ObjectKind::String(string_from(pointer, length).to_string()))
+}
+
+#[no_mangle]
+pub extern "C" fn ss_bool(boolean: i8) -> Value {
+    heap::with_heap(|heap| if boolean != 0 { heap.true_value } else { heap.false_value })
+}
+
+#[no_mangle]
+pub extern "C" fn ss_null() -> Value {
+    heap::with_heap(|heap| heap.null_value)
+}
+
+#[no_mangle]
+pub extern "C" fn ss_array(capacity: usize) -> Value {
+    heap::allocate(ObjectKind::Array(Vec::with_capacity(capacity)))
+}
+
+#[no_mangle]
+pub extern "C" fn ss_array_push(array: Value, element: Value) {
+    if let ObjectKind::Array(elements) = array.kind_mut() {
+        elements.push(element);
+    }
+}
+
+/// Create a SS function value, where a NULL name pointer means that the function is anonymous
+#[no_mangle]
+pub unsafe extern "C" fn ss_function(code: Code, arity: usize, closure: Value, name: *const u8, name_length: usize) -> Value {
+    heap::allocate(ObjectKind::Function(Function {
+        code,
+        arity,
+        closure,
+        name: if name.is_null() { None } else { Some(string_from(name, name_length).to_string()) },
+        native: false,
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ss_native_function(code: Code, arity: usize, name: *const u8, name_length: usize) -> Value {
+    heap::allocate(ObjectKind::Function(Function {
+        code,
+        arity,
+        closure: Value::NULL_POINTER,
+        name: Some(string_from(name, name_length).to_string()),
+        native: true,
+    }))
+}
+
+/* ==========================  End of value constructors  ========================== */
+
+/* ==========================  Start of environments  ========================== */
+
+/// Create the environment of a new scope, enclosed by the given environment, which is a NULL pointer for the global scope
+#[no_mangle]
+pub extern "C" fn ss_environment(enclosing: Value) -> Value {
+    heap::allocate(ObjectKind::Environment(Environment { enclosing, slots: Vec::new() }))
+}
+
+/// Get the value of an identifier, using the scope distance value from the resolver and the slot assigned by the compiler
+#[no_mangle]
+pub unsafe extern "C" fn ss_environment_get(
+    environment: Value,
+    distance: usize,
+    slot: usize,
+    line: usize,
+    name: *const u8,
+    name_length: usize,
+) -> Value {
+    let mut environment = environment;
+    for _ in 0..distance {
+        environment = environment.environment().enclosing;
+    }
+
+    match environment.environment().slots.get(slot) {
+        Some(value) if !value.is_null_pointer() => *value,
+        _ => runtime_error(format!(
+            "[line {}] ReferenceError: Tried to use undefined identifier '{}'",
+            line,
+            string_from(name, name_length)
+        )),
+    }
+}
+
+/// Define a value in the environment, which is also used to update function definitions like the interpreter does
+#[no_mangle]
+pub extern "C" fn ss_environment_define(environment: Value, slot: usize, value: Value) {
+    let slots = &mut environment.environment().slots;
+    if slots.len() <= slot {
+        slots.resize(slot + 1, Value::NULL_POINTER);
+    }
+    slots[slot] = value;
+}
+
+/// Define a const value in the environment, which fails if the identifier is already used in the scope,
+/// e.g. by a const definition in the body of a while loop without a block statement
+#[no_mangle]
+pub unsafe extern "C" fn ss_environment_define_const(
+    environment: Value,
+    slot: usize,
+    value: Value,
+    name: *const u8,
+    name_length: usize,
+) {
+    if let Some(existing_value) = environment.environment().slots.get(slot) {
+        if !existing_value.is_null_pointer() {
+            runtime_error(format!(
+                "ReferenceError: Identifier '{}' already used in current scope!",
+                string_from(name, name_length)
+            ));
+        }
+    }
+    ss_environment_define(environment, slot, value);
+}
+
+/* ==========================  End of environments  ========================== */
+
+/* ==========================  Start of functions  ========================== */
+
+/// Get the native code to call for a function call, which fails if the callee is not a function
+#[no_mangle]
+pub extern "C" fn ss_function_code(callee: Value, number_of_arguments: usize, line: usize) -> Code {
+    match callee.kind() {
+        ObjectKind::Function(function) => {
+            // Type checker ensures that the number of arguments match, and compiled code can only be called with its arity
+            if function.arity != number_of_arguments {
+                runtime_error(format!(
+                    "Internal interpreter error: Expected {} arguments but got {} for {}",
+                    function.arity, number_of_arguments, callee
+                ));
+            }
+            function.code
+        }
+        _ => runtime_error(format!("[line {}] Attempted to call non-callable: {}", line, callee)),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ss_function_closure(function: Value) -> Value {
+    match function.kind() {
+        ObjectKind::Function(function) => function.closure,
+        _ => Value::NULL_POINTER,
+    }
+}
+
+/* ==========================  End of functions  ========================== */
+
+/* ==========================  Start of operators  ========================== */
+
+// Macro to define a binary operator on 2 Numbers, with the same error messages as the interpreter
+macro_rules! numeric_binary_op {
+    ($name:ident, $operator:tt, $result:ident, $op_name:literal) => {
+        #[no_mangle]
+        pub extern "C" fn $name(left: Value, right: Value) -> Value {
+            match (left.kind(), right.kind()) {
+                (ObjectKind::Number(left_number), ObjectKind::Number(right_number)) => {
+                    $result(*left_number $operator *right_number)
+                }
+                _ => runtime_error(format!("Type Error: {}", $op_name)),
+            }
+        }
+    };
+}
+
+fn bool_value(boolean: bool) -> Value {
+    ss_bool(boolean as i8)
+}
+
+/// Addition is overloaded to support string concatenation
+#[no_mangle]
+pub extern "C" fn ss_add(left: Value, right: Value) -> Value {
+    match (left.kind(), right.kind()) {
+        (ObjectKind::Number(left_number), ObjectKind::Number(right_number)) => ss_number(left_number + right_number),
+        (ObjectKind::String(left_string), ObjectKind::String(right_string)) => {
+            let string = left_string.clone() + right_string;
+            heap::allocate(ObjectKind::String(string))
+        }
+        _ => runtime_error("Type Error: Invalid types used for addition!".to_string()),
+    }
+}
+
+numeric_binary_op!(ss_subtract, -, ss_number, "Invalid types used for subtraction!");
+numeric_binary_op!(ss_multiply, *, ss_number, "Invalid types used for multiplication!");
+numeric_binary_op!(ss_divide, /, ss_number, "Invalid types used for division!");
+
+numeric_binary_op!(ss_greater, >, bool_value, "Invalid types used for Greater!");
+numeric_binary_op!(ss_greater_equal, >=, bool_value, "Invalid types used for Greater Equal!");
+numeric_binary_op!(ss_less, <, bool_value, "Invalid types used for Less!");
+numeric_binary_op!(ss_less_equal, <=, bool_value, "Invalid types used for Less Equal!");
+
+#[no_mangle]
+pub extern "C" fn ss_equal(left: Value, right: Value) -> Value {
+    bool_value(left.equals(right))
+}
+
+#[no_mangle]
+pub extern "C" fn ss_not_equal(left: Value, right: Value) -> Value {
+    bool_value(!left.equals(right))
+}
+
+#[no_mangle]
+pub extern "C" fn ss_negate(value: Value) -> Value {
+    match value.kind() {
+        ObjectKind::Number(number) => ss_number(-number),
+        _ => runtime_error("Type Error: Invalid types used for number negation!".to_string()),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ss_not(value: Value) -> Value {
+    match value.kind() {
+        ObjectKind::Bool(boolean) => bool_value(!boolean),
+        _ => runtime_error("Type Error: Invalid types used for boolean negation!".to_string()),
+    }
+}
+
+/// Get the Bool value of a condition, with the same strict checking as the interpreter's bool_or_err method
+#[no_mangle]
+pub unsafe extern "C" fn ss_condition(value: Value, message: *const u8, message_length: usize) -> i8 {
+    match value.kind() {
+        ObjectKind::Bool(boolean) => *boolean as i8,
+        _ => runtime_error(format!(
+            "Type Error: Expected Bool but found type and value: {:?}\n{}",
+            value,
+            string_from(message, message_length)
+        )),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ss_array_get(array: Value, index: Value) -> Value {
+    let elements = match array.kind() {
+        ObjectKind::Array(elements) => elements,
+        _ => runtime_error(format!(
+            "Type Error: Array element access failed, expect array to be of type Value::Array, found -> {:?}",
+            array
+        )),
+    };
+
+    let index_number = match index.kind() {
+        ObjectKind::Number(number) => *number,
+        _ => runtime_error(format!(
+            "Type Error: Array element access failed, expect index to be of type Value::Number, found -> {:?}",
+            index
+        )),
+    };
+
+    // Same bounds check as the interpreter, where negative indexes are checked before casting the index to usize
+    let usize_index = index_number as usize;
+    if index_number >= 0.0 && usize_index < elements.len() {
+        elements[usize_index]
+    } else {
+        runtime_error(format!(
+            "Array Index Out Of Bounds Error: Expect index to be 0 to {}, found -> {}",
+            elements.len() as isize - 1,
+            index
+        ))
+    }
+}
+
+/* ==========================  End of operators  ========================== */
+
+#[no_mangle]
+pub extern "C" fn ss_print(value: Value) {
+    println!("{}", value);
+}
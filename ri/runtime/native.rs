@@ -0,0 +1,15 @@
+// Native functions of the prelude, which are called by compiled code the same way as SS functions
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::value::Value;
+
+/// Same as the interpreter's clock native function, returning the current time in milliseconds
+#[no_mangle]
+pub extern "C" fn ss_clock(_closure: Value) -> Value {
+    crate::ss_number(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as f64,
+    )
+}
@@ -0,0 +1,134 @@
+// Values of SS programs compiled ahead of time, which are all pointers to objects allocated on the garbage collected heap
+use std::fmt;
+
+/// Value passed between the compiled code and the runtime library, which is a pointer to an object on the heap.
+///
+/// Compiled code never looks into objects, and only passes values back into the runtime library's functions,
+/// so the layout of the objects is private to the runtime library.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Value(pub *mut Object);
+
+pub struct Object {
+    /// Set by the garbage collector for every object reachable from the roots, and cleared again by the sweep
+    pub marked: bool,
+    pub kind: ObjectKind,
+}
+
+pub enum ObjectKind {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Null,
+    Array(Vec<Value>),
+    Function(Function),
+
+    /// Environments are objects too, since closures keep the environment they are defined in alive
+    Environment(Environment),
+}
+
+/// Signature of every function's native code, taking the closure environment and then the arguments,
+/// where the number of arguments is the function's arity
+pub type Code = *const u8;
+
+pub struct Function {
+    pub code: Code,
+    pub arity: usize,
+
+    /// Environment surrounding the function definition, which is NULL for native functions
+    pub closure: Value,
+
+    /// None for anonymous functions
+    pub name: Option<String>,
+    pub native: bool,
+}
+
+/// Scope of a block or function call, where identifiers are resolved at compile time to a slot in the scope they are defined in,
+/// and a scope distance value to find that scope by walking up the enclosing environments at runtime
+pub struct Environment {
+    pub enclosing: Value,
+
+    /// Values are NULL until they are defined, since identifiers can be used before they are defined in the same scope
+    pub slots: Vec<Value>,
+}
+
+impl Value {
+    pub const NULL_POINTER: Value = Value(std::ptr::null_mut());
+
+    pub fn is_null_pointer(self) -> bool {
+        self.0.is_null()
+    }
+
+    pub fn kind(&self) -> &ObjectKind {
+        unsafe { &(*self.0).kind }
+    }
+
+    pub fn kind_mut(&self) -> &mut ObjectKind {
+        unsafe { &mut (*self.0).kind }
+    }
+
+    pub fn environment(&self) -> &mut Environment {
+        match self.kind_mut() {
+            ObjectKind::Environment(environment) => environment,
+            _ => panic!("Internal Runtime Error: Expected an environment"),
+        }
+    }
+
+    /// Same equality as the interpreter's Value, where arrays are never equal and functions are only equal to themselves
+    pub fn equals(self, other: Value) -> bool {
+        match (self.kind(), other.kind()) {
+            (ObjectKind::Number(number), ObjectKind::Number(other)) => number == other,
+            (ObjectKind::String(string), ObjectKind::String(other)) => string == other,
+            (ObjectKind::Bool(b), ObjectKind::Bool(other)) => b == other,
+            (ObjectKind::Null, ObjectKind::Null) => true,
+            (ObjectKind::Function(_), ObjectKind::Function(_)) => self == other,
+            _ => false,
+        }
+    }
+}
+
+// Same as the interpreter's Value Display trait implementation, so that compiled programs print exactly what ri prints
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind() {
+            ObjectKind::Number(number) => write!(f, "{}", number),
+            ObjectKind::String(string) => write!(f, "'{}'", string),
+            ObjectKind::Bool(boolean) => write!(f, "{}", boolean),
+            ObjectKind::Null => write!(f, "NULL"),
+
+            ObjectKind::Array(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+
+            ObjectKind::Function(function) => match (function.native, &function.name) {
+                (true, Some(name)) => write!(f, "<function-native: {}>", name),
+                (false, Some(name)) => write!(f, "<function-ss: {}>", name),
+                (_, None) => write!(f, "<function-ss: [anonymous]>"),
+            },
+
+            ObjectKind::Environment(_) => write!(f, "<environment>"),
+        }
+    }
+}
+
+// Same as the interpreter's derived Debug trait implementation of Value, used in runtime error messages
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind() {
+            ObjectKind::Number(number) => write!(f, "Number({:?})", number),
+            ObjectKind::String(string) => write!(f, "String({:?})", string),
+            ObjectKind::Bool(boolean) => write!(f, "Bool({:?})", boolean),
+            ObjectKind::Null => write!(f, "Null"),
+            ObjectKind::Array(elements) => write!(f, "Array({:?})", elements),
+            ObjectKind::Function(_) => write!(f, "Func({})", self),
+            ObjectKind::Environment(_) => write!(f, "{}", self),
+        }
+    }
+}
@@ -0,0 +1,676 @@
+use std::collections::HashMap;
+
+use cranelift_codegen::entity::EntityRef;
+use cranelift_codegen::ir::{types, AbiParam, Block, FuncRef, InstBuilder, Signature, Type, Value};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_module::{default_libcall_names, DataDescription, DataId, FuncId, Linkage, Module};
+use cranelift_object::{ObjectBuilder, ObjectModule};
+
+use super::AotError;
+
+use crate::literal::Literal;
+use crate::parser::expr::Expr;
+use crate::parser::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// Identifiers of a single scope, mapped to their slot in the scope's environment
+type Scope = HashMap<String, usize>;
+
+/// Native functions of the prelude, mapped to the runtime library function implementing them and their arity,
+/// which must be the same as the global identifiers the resolver defines
+const PRELUDE: [(&str, &str, usize); 1] = [("clock", "ss_clock", 0)];
+
+// Shorthand for the types of runtime library function parameters, where every SS value is a pointer to a heap object
+const P: Type = types::I64;
+const F: Type = types::F64;
+const B: Type = types::I8;
+
+/// Signature of every runtime library function called by compiled code, as (name, parameter types, return types)
+const RUNTIME_FUNCTIONS: &[(&str, &[Type], &[Type])] = &[
+    ("ss_number", &[F], &[P]),
+    ("ss_string", &[P, P], &[P]),
+    ("ss_bool", &[B], &[P]),
+    ("ss_null", &[], &[P]),
+    ("ss_array", &[P], &[P]),
+    ("ss_array_push", &[P, P], &[]),
+    ("ss_array_get", &[P, P], &[P]),
+    ("ss_function", &[P, P, P, P, P], &[P]),
+    ("ss_native_function", &[P, P, P, P], &[P]),
+    ("ss_function_code", &[P, P, P], &[P]),
+    ("ss_function_closure", &[P], &[P]),
+    ("ss_environment", &[P], &[P]),
+    ("ss_environment_get", &[P, P, P, P, P, P], &[P]),
+    ("ss_environment_define", &[P, P, P], &[]),
+    ("ss_environment_define_const", &[P, P, P, P, P], &[]),
+    ("ss_add", &[P, P], &[P]),
+    ("ss_subtract", &[P, P], &[P]),
+    ("ss_multiply", &[P, P], &[P]),
+    ("ss_divide", &[P, P], &[P]),
+    ("ss_equal", &[P, P], &[P]),
+    ("ss_not_equal", &[P, P], &[P]),
+    ("ss_greater", &[P, P], &[P]),
+    ("ss_greater_equal", &[P, P], &[P]),
+    ("ss_less", &[P, P], &[P]),
+    ("ss_less_equal", &[P, P], &[P]),
+    ("ss_negate", &[P], &[P]),
+    ("ss_not", &[P], &[P]),
+    ("ss_condition", &[P, P, P], &[B]),
+    ("ss_print", &[P], &[]),
+    ("ss_clock", &[P], &[P]),
+];
+
+/// Compile the resolved and type checked AST into a relocatable object file,
+/// defining the 'ss_main' function called by the runtime library's main function
+pub fn compile_object(ast: &Vec<Stmt>) -> Result<Vec<u8>, AotError> {
+    let mut flag_builder = settings::builder();
+    flag_builder.set("opt_level", "speed").unwrap();
+    // Executables are linked as position independent executables by default
+    flag_builder.set("is_pic", "true").unwrap();
+    let isa = cranelift_native::builder()
+        .map_err(|error| AotError::UnsupportedHost(error.to_string()))?
+        .finish(settings::Flags::new(flag_builder))
+        .map_err(|error| AotError::UnsupportedHost(error.to_string()))?;
+
+    let object_builder = ObjectBuilder::new(isa, "ss_program", default_libcall_names())
+        .map_err(|error| AotError::UnsupportedHost(error.to_string()))?;
+
+    let mut compiler = Compiler {
+        module: ObjectModule::new(object_builder),
+        runtime_functions: HashMap::new(),
+        strings: HashMap::new(),
+        function_count: 0,
+    };
+
+    compiler.compile_main(ast)?;
+
+    compiler
+        .module
+        .finish()
+        .emit()
+        .map_err(|error| AotError::Codegen("<object file>".to_string(), error.to_string()))
+}
+
+/// State shared by the compilation of every function in the program
+struct Compiler {
+    module: ObjectModule,
+
+    /// Runtime library functions declared as imports so far
+    runtime_functions: HashMap<&'static str, FuncId>,
+
+    /// String literals and identifiers stored in the object file's data so far, so every string is only stored once
+    strings: HashMap<String, DataId>,
+
+    /// Number of SS functions compiled so far, used to give every function a unique symbol name
+    function_count: usize,
+}
+
+impl Compiler {
+    /// Compile the top level statements into the 'ss_main' function, which runs them in the global environment
+    fn compile_main(&mut self, ast: &Vec<Stmt>) -> Result<(), AotError> {
+        let signature = self.module.make_signature();
+        self.compile_function_body("ss_main", Linkage::Export, signature, |translator| {
+            let enclosing = translator.null_pointer();
+            let global_environment = translator.call_runtime("ss_environment", &[enclosing]);
+            translator.builder.def_var(translator.environment, global_environment);
+            translator.scopes.push(Scope::new());
+
+            // Define the prelude's native functions in the global environment
+            for (name, runtime_function, arity) in PRELUDE {
+                let slot = translator.declare(name);
+                let function_id = translator.compiler.runtime_function(runtime_function);
+                let function_ref = translator.function_ref(function_id);
+                let code = translator.builder.ins().func_addr(P, function_ref);
+                let arity = translator.builder.ins().iconst(P, arity as i64);
+                let (name, name_length) = translator.string(name);
+                let native_function = translator.call_runtime("ss_native_function", &[code, arity, name, name_length]);
+                let slot = translator.builder.ins().iconst(P, slot as i64);
+                translator.call_runtime("ss_environment_define", &[global_environment, slot, native_function]);
+            }
+
+            for stmt in ast {
+                translator.translate_statement(stmt)?;
+            }
+
+            translator.builder.ins().return_(&[]);
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    /// Compile a SS function, which takes its closure environment followed by its arguments, and returns its return value.
+    ///
+    /// 'scopes' are the scopes surrounding the function definition, which the function body's identifiers are resolved against.
+    fn compile_function(
+        &mut self,
+        name: Option<&String>,
+        parameters: &[Token],
+        body: &Stmt,
+        scopes: Vec<Scope>,
+    ) -> Result<FuncId, AotError> {
+        self.function_count += 1;
+        let symbol = format!(
+            "ss_function_{}_{}",
+            self.function_count,
+            name.map(|name| name.as_str()).unwrap_or("anonymous")
+        );
+
+        let mut signature = self.module.make_signature();
+        signature.returns.push(AbiParam::new(P));
+        for _ in 0..parameters.len() + 1 {
+            signature.params.push(AbiParam::new(P));
+        }
+
+        self.compile_function_body(&symbol, Linkage::Local, signature, |translator| {
+            let closure = translator.builder.block_params(translator.entry_block)[0];
+            let arguments = translator.builder.block_params(translator.entry_block)[1..].to_vec();
+
+            // Like Function's call method, a new environment enclosed by the closure holds the arguments and the body's values
+            let environment = translator.call_runtime("ss_environment", &[closure]);
+            translator.builder.def_var(translator.environment, environment);
+            translator.scopes = scopes;
+            translator.scopes.push(Scope::new());
+
+            for (parameter, argument) in parameters.iter().zip(arguments) {
+                let slot = translator.declare(parameter.lexeme.as_ref().unwrap());
+                let slot = translator.builder.ins().iconst(P, slot as i64);
+                translator.call_runtime("ss_environment_define", &[environment, slot, argument]);
+            }
+
+            // Body statements run in the same scope as the parameters, without a new scope for the block statement
+            match body {
+                Stmt::Block(ref stmts, _) => {
+                    for stmt in stmts {
+                        translator.translate_statement(stmt)?;
+                    }
+                }
+                unmatched_stmt_variant => {
+                    return Err(AotError::Codegen(
+                        translator.name.clone(),
+                        format!("Function body must be a Block Statement, found: {}", unmatched_stmt_variant),
+                    ))
+                }
+            }
+
+            // Functions that end without a return statement return Null
+            let null = translator.call_runtime("ss_null", &[]);
+            translator.builder.ins().jump(translator.exit_block, &[null]);
+
+            translator.builder.switch_to_block(translator.exit_block);
+            let return_value = translator.builder.block_params(translator.exit_block)[0];
+            translator.builder.ins().return_(&[return_value]);
+            Ok(())
+        })
+    }
+
+    /// Create a function translator for a new function, and define the function in the module once 'translate' generates its body
+    fn compile_function_body(
+        &mut self,
+        symbol: &str,
+        linkage: Linkage,
+        signature: Signature,
+        translate: impl FnOnce(&mut FunctionTranslator) -> Result<(), AotError>,
+    ) -> Result<FuncId, AotError> {
+        let function_id = self
+            .module
+            .declare_function(symbol, linkage, &signature)
+            .map_err(|error| AotError::Codegen(symbol.to_string(), error.to_string()))?;
+
+        let mut context = self.module.make_context();
+        context.func.signature = signature;
+
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+
+        let environment = Variable::new(0);
+        builder.declare_var(environment, P);
+
+        let entry_block = builder.create_block();
+        builder.append_block_params_for_function_params(entry_block);
+        builder.switch_to_block(entry_block);
+
+        let exit_block = builder.create_block();
+        builder.append_block_param(exit_block, P);
+
+        let mut translator = FunctionTranslator {
+            builder,
+            compiler: self,
+            name: symbol.to_string(),
+            entry_block,
+            exit_block,
+            environment,
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            function_refs: HashMap::new(),
+        };
+        translate(&mut translator)?;
+
+        translator.builder.seal_all_blocks();
+        translator.builder.finalize();
+
+        self.module
+            .define_function(function_id, &mut context)
+            .map_err(|error| AotError::Codegen(symbol.to_string(), format!("{:?}", error)))?;
+
+        Ok(function_id)
+    }
+
+    /// Declare a runtime library function as an import, using its signature from the RUNTIME_FUNCTIONS table
+    fn runtime_function(&mut self, name: &'static str) -> FuncId {
+        if let Some(&function_id) = self.runtime_functions.get(name) {
+            return function_id;
+        }
+
+        let (_, parameters, returns) = RUNTIME_FUNCTIONS
+            .iter()
+            .find(|(function_name, _, _)| *function_name == name)
+            .unwrap_or_else(|| panic!("Internal Error: Runtime library function '{}' is not defined", name));
+
+        // Bool parameters are extended like C compilers do, as the runtime library is compiled with the C calling convention
+        let abi_param = |&value_type: &Type| match value_type {
+            B => AbiParam::new(B).sext(),
+            value_type => AbiParam::new(value_type),
+        };
+        let mut signature = self.module.make_signature();
+        signature.params.extend(parameters.iter().map(abi_param));
+        signature.returns.extend(returns.iter().map(abi_param));
+
+        let function_id = self.module.declare_function(name, Linkage::Import, &signature).unwrap();
+        self.runtime_functions.insert(name, function_id);
+        function_id
+    }
+
+    /// Store the string in the object file's read only data
+    fn string(&mut self, string: &str) -> DataId {
+        if let Some(&data_id) = self.strings.get(string) {
+            return data_id;
+        }
+
+        let data_id = self.module.declare_anonymous_data(false, false).unwrap();
+        let mut data_description = DataDescription::new();
+        data_description.define(string.as_bytes().to_vec().into_boxed_slice());
+        self.module.define_data(data_id, &data_description).unwrap();
+
+        self.strings.insert(string.to_string(), data_id);
+        data_id
+    }
+}
+
+/// Translates the statements of a single function into Cranelift IR
+struct FunctionTranslator<'a, 'b> {
+    builder: FunctionBuilder<'a>,
+    compiler: &'b mut Compiler,
+
+    /// Symbol name of the function, used in error messages
+    name: String,
+
+    entry_block: Block,
+
+    /// Block that returns its parameter as the return value of the function
+    exit_block: Block,
+
+    /// Variable holding the environment of the current scope, which changes as the function enters and exits block statements
+    environment: Variable,
+
+    /// Identifiers of every scope from the global scope to the current scope, mirroring the resolver's scopes
+    /// so that the resolver's scope distance values find the scope of an identifier to get its slot from
+    scopes: Vec<Scope>,
+
+    /// Condition block and environment of every while loop the current statement is in, from the outermost loop
+    loops: Vec<(Block, Value)>,
+
+    /// Functions referenced from this function so far
+    function_refs: HashMap<FuncId, FuncRef>,
+}
+
+impl<'a, 'b> FunctionTranslator<'a, 'b> {
+    fn translate_statement(&mut self, stmt: &Stmt) -> Result<(), AotError> {
+        match stmt {
+            Stmt::Print(ref expr, _) => {
+                let value = self.translate_expression(expr)?;
+                self.call_runtime("ss_print", &[value]);
+            }
+
+            Stmt::Expr(ref expr, _) | Stmt::Ignore(ref expr, _) => {
+                self.translate_expression(expr)?;
+            }
+
+            Stmt::Const(ref token, ref expr) => {
+                // Identifier is declared before its expression is resolved, so the expression can reference the identifier itself
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self.declare(name);
+                let value = self.translate_expression(expr)?;
+                let environment = self.builder.use_var(self.environment);
+                let slot = self.builder.ins().iconst(P, slot as i64);
+                let (name, name_length) = self.string(name);
+                self.call_runtime("ss_environment_define_const", &[environment, slot, value, name, name_length]);
+            }
+
+            Stmt::Block(ref stmts, _) => {
+                let parent_environment = self.builder.use_var(self.environment);
+                let environment = self.call_runtime("ss_environment", &[parent_environment]);
+                self.builder.def_var(self.environment, environment);
+                self.scopes.push(Scope::new());
+
+                for stmt in stmts {
+                    self.translate_statement(stmt)?;
+                }
+
+                self.scopes.pop();
+                self.builder.def_var(self.environment, parent_environment);
+            }
+
+            Stmt::Func(ref token, ref parameters, ref body) => {
+                // Function identifier is defined before its body is resolved, so that functions can call themselves recursively
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self.declare(name);
+                let function = self.translate_function(Some(name), parameters, body)?;
+                let environment = self.builder.use_var(self.environment);
+                let slot = self.builder.ins().iconst(P, slot as i64);
+                self.call_runtime("ss_environment_define", &[environment, slot, function]);
+            }
+
+            Stmt::If(ref condition, ref true_branch, ref else_branch, _) => {
+                let condition = self.translate_condition(
+                    condition,
+                    "Invalid condition value type, only Boolean values can be used as conditionals!",
+                )?;
+
+                let true_block = self.builder.create_block();
+                let else_block = self.builder.create_block();
+                let merge_block = self.builder.create_block();
+                self.builder.ins().brif(condition, true_block, &[], else_block, &[]);
+
+                self.builder.switch_to_block(true_block);
+                self.translate_statement(true_branch)?;
+                self.builder.ins().jump(merge_block, &[]);
+
+                self.builder.switch_to_block(else_block);
+                if let Some(ref else_branch) = else_branch {
+                    self.translate_statement(else_branch)?;
+                }
+                self.builder.ins().jump(merge_block, &[]);
+
+                self.builder.switch_to_block(merge_block);
+            }
+
+            Stmt::While(ref condition, ref loop_body, _) => {
+                let condition_block = self.builder.create_block();
+                let body_block = self.builder.create_block();
+                let exit_block = self.builder.create_block();
+
+                let environment = self.builder.use_var(self.environment);
+                self.builder.ins().jump(condition_block, &[]);
+
+                self.builder.switch_to_block(condition_block);
+                let condition = self.translate_condition(condition, "Expected Boolean from While loop expression")?;
+                self.builder.ins().brif(condition, body_block, &[], exit_block, &[]);
+
+                self.builder.switch_to_block(body_block);
+                self.loops.push((condition_block, environment));
+                self.translate_statement(loop_body)?;
+                self.loops.pop();
+                self.builder.ins().jump(condition_block, &[]);
+
+                self.builder.switch_to_block(exit_block);
+            }
+
+            Stmt::Return(ref expr, _) => {
+                let value = self.translate_expression(expr)?;
+
+                // The interpreter's While arm discards the value of its loop body statement,
+                // so a return statement in a loop body only stops the current iteration like ri does, continuing with the next one
+                match self.loops.last() {
+                    Some(&(condition_block, environment)) => {
+                        self.builder.def_var(self.environment, environment);
+                        self.builder.ins().jump(condition_block, &[]);
+                    }
+                    None => {
+                        self.builder.ins().jump(self.exit_block, &[value]);
+                    }
+                }
+
+                // Statements after a return statement are unreachable, and are generated into a block with no predecessors
+                let unreachable_block = self.builder.create_block();
+                self.builder.switch_to_block(unreachable_block);
+            }
+
+            unmatched_stmt_variant => {
+                return Err(AotError::Codegen(
+                    self.name.clone(),
+                    format!("Unimplemented Stmt variant: {}", unmatched_stmt_variant),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn translate_expression(&mut self, expr: &Expr) -> Result<Value, AotError> {
+        Ok(match expr {
+            Expr::Literal(ref literal) => match literal {
+                Literal::Number(number) => {
+                    let number = self.builder.ins().f64const(*number);
+                    self.call_runtime("ss_number", &[number])
+                }
+                Literal::String(ref string) => {
+                    let (string, length) = self.string(string);
+                    self.call_runtime("ss_string", &[string, length])
+                }
+                Literal::Bool(boolean) => {
+                    let boolean = self.builder.ins().iconst(B, *boolean as i64);
+                    self.call_runtime("ss_bool", &[boolean])
+                }
+                Literal::Null => self.call_runtime("ss_null", &[]),
+            },
+
+            Expr::Const(ref token, distance) => {
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self
+                    .scopes
+                    .iter()
+                    .rev()
+                    .nth(*distance)
+                    .and_then(|scope| scope.get(name))
+                    .copied()
+                    .ok_or_else(|| {
+                        AotError::Codegen(self.name.clone(), format!("Identifier '{}' is not resolved", name))
+                    })?;
+
+                let environment = self.builder.use_var(self.environment);
+                let distance = self.builder.ins().iconst(P, *distance as i64);
+                let slot = self.builder.ins().iconst(P, slot as i64);
+                let line = self.builder.ins().iconst(P, token.line as i64);
+                let (name, name_length) = self.string(name);
+                self.call_runtime("ss_environment_get", &[environment, distance, slot, line, name, name_length])
+            }
+
+            Expr::AnonymousFunc(ref stmt) => match **stmt {
+                Stmt::AnonymousFunc(ref parameters, ref body) => self.translate_function(None, parameters, body)?,
+                ref unmatched_stmt_variant => {
+                    return Err(AotError::Codegen(
+                        self.name.clone(),
+                        format!("Expected anonymous function, found: {}", unmatched_stmt_variant),
+                    ))
+                }
+            },
+
+            Expr::Call(ref callee, ref arguments, ref token) => {
+                // Callee is checked to be a function before the arguments are evaluated, like the interpreter does
+                let callee = self.translate_expression(callee)?;
+                let number_of_arguments = self.builder.ins().iconst(P, arguments.len() as i64);
+                let line = self.builder.ins().iconst(P, token.line as i64);
+                let code = self.call_runtime("ss_function_code", &[callee, number_of_arguments, line]);
+                let closure = self.call_runtime("ss_function_closure", &[callee]);
+
+                let mut argument_values = vec![closure];
+                for argument in arguments {
+                    argument_values.push(self.translate_expression(argument)?);
+                }
+
+                let mut signature = self.compiler.module.make_signature();
+                signature.params.extend(argument_values.iter().map(|_| AbiParam::new(P)));
+                signature.returns.push(AbiParam::new(P));
+                let signature = self.builder.import_signature(signature);
+
+                let call = self.builder.ins().call_indirect(signature, code, &argument_values);
+                self.builder.inst_results(call)[0]
+            }
+
+            Expr::Array(_, ref elements) => {
+                let capacity = self.builder.ins().iconst(P, elements.len() as i64);
+                let array = self.call_runtime("ss_array", &[capacity]);
+                for element in elements {
+                    let element = self.translate_expression(element)?;
+                    self.call_runtime("ss_array_push", &[array, element]);
+                }
+                array
+            }
+
+            Expr::ArrayAccess(ref array, ref index) => {
+                let array = self.translate_expression(array)?;
+                let index = self.translate_expression(index)?;
+                self.call_runtime("ss_array_get", &[array, index])
+            }
+
+            Expr::Grouping(ref expr) => self.translate_expression(expr)?,
+
+            Expr::Unary(ref operator, ref expr) => {
+                let value = self.translate_expression(expr)?;
+                match operator.token_type {
+                    TokenType::Minus => self.call_runtime("ss_negate", &[value]),
+                    TokenType::Bang => self.call_runtime("ss_not", &[value]),
+                    ref operator => {
+                        return Err(AotError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid unary operator: {:?}", operator),
+                        ))
+                    }
+                }
+            }
+
+            Expr::Binary(ref left, ref operator, ref right) => {
+                let left = self.translate_expression(left)?;
+                let right = self.translate_expression(right)?;
+                let runtime_function = match operator.token_type {
+                    TokenType::Plus => "ss_add",
+                    TokenType::Minus => "ss_subtract",
+                    TokenType::Star => "ss_multiply",
+                    TokenType::Slash => "ss_divide",
+                    TokenType::EqualEqual => "ss_equal",
+                    TokenType::BangEqual => "ss_not_equal",
+                    TokenType::Greater => "ss_greater",
+                    TokenType::GreaterEqual => "ss_greater_equal",
+                    TokenType::Less => "ss_less",
+                    TokenType::LessEqual => "ss_less_equal",
+                    ref operator => {
+                        return Err(AotError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid binary operator: {:?}", operator),
+                        ))
+                    }
+                };
+                self.call_runtime(runtime_function, &[left, right])
+            }
+
+            // Strict boolean operators that short circuit, evaluating to a Bool value
+            Expr::Logical(ref left, ref operator, ref right) => {
+                let message = "Logical operations only work with Bool Types";
+                let left = self.translate_condition(left, message)?;
+
+                let right_block = self.builder.create_block();
+                let merge_block = self.builder.create_block();
+                self.builder.append_block_param(merge_block, B);
+
+                match operator.token_type {
+                    TokenType::Or => self.builder.ins().brif(left, merge_block, &[left], right_block, &[]),
+                    TokenType::And => self.builder.ins().brif(left, right_block, &[], merge_block, &[left]),
+                    ref operator => {
+                        return Err(AotError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid Token Type for logical expr -> {:?}", operator),
+                        ))
+                    }
+                };
+
+                self.builder.switch_to_block(right_block);
+                let right = self.translate_condition(right, message)?;
+                self.builder.ins().jump(merge_block, &[right]);
+
+                self.builder.switch_to_block(merge_block);
+                let result = self.builder.block_params(merge_block)[0];
+                self.call_runtime("ss_bool", &[result])
+            }
+        })
+    }
+
+    /// Evaluate the expression into an I8 boolean, which fails at runtime with the message if the value is not a Bool
+    fn translate_condition(&mut self, expr: &Expr, message: &str) -> Result<Value, AotError> {
+        let value = self.translate_expression(expr)?;
+        let (message, message_length) = self.string(message);
+        Ok(self.call_runtime("ss_condition", &[value, message, message_length]))
+    }
+
+    /// Compile the function and create its function value, with the current environment as its closure
+    fn translate_function(
+        &mut self,
+        name: Option<&String>,
+        parameters: &[Token],
+        body: &Stmt,
+    ) -> Result<Value, AotError> {
+        let function_id = self.compiler.compile_function(name, parameters, body, self.scopes.clone())?;
+
+        let function_ref = self.function_ref(function_id);
+        let code = self.builder.ins().func_addr(P, function_ref);
+        let arity = self.builder.ins().iconst(P, parameters.len() as i64);
+        let environment = self.builder.use_var(self.environment);
+        let (name, name_length) = match name {
+            Some(name) => self.string(name),
+            None => (self.null_pointer(), self.null_pointer()),
+        };
+        Ok(self.call_runtime("ss_function", &[code, arity, environment, name, name_length]))
+    }
+
+    /// Declare the identifier in the current scope, returning its slot in the scope's environment
+    fn declare(&mut self, name: &str) -> usize {
+        let scope = self.scopes.last_mut().unwrap();
+        let slot = scope.len();
+        *scope.entry(name.to_string()).or_insert(slot)
+    }
+
+    fn function_ref(&mut self, function_id: FuncId) -> FuncRef {
+        if let Some(&function_ref) = self.function_refs.get(&function_id) {
+            return function_ref;
+        }
+
+        let function_ref = self.compiler.module.declare_func_in_func(function_id, self.builder.func);
+        self.function_refs.insert(function_id, function_ref);
+        function_ref
+    }
+
+    /// Call a runtime library function, returning its return value, or a null pointer if it does not return anything
+    fn call_runtime(&mut self, name: &'static str, arguments: &[Value]) -> Value {
+        let function_id = self.compiler.runtime_function(name);
+        let function_ref = self.function_ref(function_id);
+        let call = self.builder.ins().call(function_ref, arguments);
+        match self.builder.inst_results(call).first() {
+            Some(&value) => value,
+            None => self.null_pointer(),
+        }
+    }
+
+    /// Get the address and length of the string stored in the object file's data
+    fn string(&mut self, string: &str) -> (Value, Value) {
+        let data_id = self.compiler.string(string);
+        let global_value = self.compiler.module.declare_data_in_func(data_id, self.builder.func);
+        let pointer = self.builder.ins().symbol_value(P, global_value);
+        let length = self.builder.ins().iconst(P, string.len() as i64);
+        (pointer, length)
+    }
+
+    fn null_pointer(&mut self) -> Value {
+        self.builder.ins().iconst(P, 0)
+    }
+}
@@ -0,0 +1,27 @@
+/// Errors from compiling a SS program ahead of time into a native executable
+#[derive(Debug)]
+pub enum AotError {
+    /// Native code generator could not be created for the host machine
+    UnsupportedHost(String),
+
+    /// Cranelift failed to compile a function
+    /// Codegen(function symbol name, error message)
+    Codegen(String, String),
+
+    /// Object file could not be written or removed
+    Io(String),
+
+    /// System linker failed to link the object file with the runtime library
+    Link(String),
+}
+
+impl std::fmt::Display for AotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AotError::UnsupportedHost(error) => write!(f, "Unable to generate native code for this machine: {}", error),
+            AotError::Codegen(name, error) => write!(f, "Unable to compile function '{}': {}", name, error),
+            AotError::Io(error) => write!(f, "Unable to write object file: {}", error),
+            AotError::Link(error) => write!(f, "Unable to link executable: {}", error),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::Path;
+use std::process::{self, Command};
+use std::{env, fs};
+
+use super::AotError;
+
+/// Static library of the runtime, compiled by the build script
+const RUNTIME_LIBRARY: &str = env!("SS_RUNTIME_LIBRARY");
+
+/// System libraries that the runtime library depends on, as linker arguments
+const RUNTIME_NATIVE_LIBRARIES: &str = env!("SS_RUNTIME_NATIVE_LIBRARIES");
+
+/// Write the object file into a temporary file, and link it with the runtime library using the system's C compiler.
+/// The object file is never written next to the output, as it could be the output itself, e.g. for '-o program.o'
+pub fn link(object: Vec<u8>, output: &Path) -> Result<(), AotError> {
+    let file_name = output.file_name().unwrap_or_default().to_string_lossy();
+    let object_path = env::temp_dir().join(format!("ss-{}-{}.o", process::id(), file_name));
+    fs::write(&object_path, object).map_err(|error| AotError::Io(format!("{}: {}", object_path.display(), error)))?;
+
+    let result = Command::new("cc")
+        .arg("-o")
+        .arg(output)
+        .arg(&object_path)
+        .arg(RUNTIME_LIBRARY)
+        // Runtime library includes the whole Rust standard library, so unused sections are dropped to keep executables small
+        .arg("-Wl,--gc-sections")
+        .args(RUNTIME_NATIVE_LIBRARIES.split_whitespace())
+        .output();
+
+    // Object file is only an intermediate file, and is removed whether linking succeeds or not
+    fs::remove_file(&object_path).map_err(|error| AotError::Io(format!("{}: {}", object_path.display(), error)))?;
+
+    match result {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(AotError::Link(String::from_utf8_lossy(&output.stderr).to_string())),
+        Err(error) => Err(AotError::Link(format!("Failed to run the system C compiler 'cc': {}", error))),
+    }
+}
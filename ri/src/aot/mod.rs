@@ -0,0 +1,36 @@
+mod codegen;
+mod error;
+mod link;
+
+use std::path::Path;
+
+use crate::parser::stmt::Stmt;
+
+pub use self::error::AotError;
+
+/*
+    Ahead of time compiler, enabled with '--backend native', which compiles a SS program into a native executable.
+
+    The resolved and type checked AST is compiled with Cranelift into an object file defining 'ss_main',
+    which is linked with the runtime library (see runtime/lib.rs) by the system's C compiler into an executable.
+    The runtime library defines the executable's main function, which sets up the garbage collected heap before calling 'ss_main'.
+
+    Every SS value is a pointer to an object on the runtime library's heap, and compiled code calls into the runtime library
+    for every operation on values, so only control flow is compiled into native code.
+
+    Environments are arrays of slots instead of hash maps, where the compiler assigns a slot to every identifier of a scope,
+    and the scope distance value the resolver saved into every Expr::Const node selects the environment to read the slot of.
+
+    Calling convention of compiled SS functions:
+        extern "C" fn(closure: Value, arguments: Value...) -> Value
+
+    closure         Environment that the function is defined in, which encloses the function's own environment
+    arguments       One argument value for each parameter, which is checked against the function's arity before the call
+    return value    Return value of the function, which is Null if the function does not return a value
+*/
+
+/// Compile the resolved and type checked AST into a native executable at the output path
+pub fn compile(ast: &Vec<Stmt>, output: &Path) -> Result<(), AotError> {
+    let object = codegen::compile_object(ast)?;
+    link::link(object, output)
+}
@@ -0,0 +1,6 @@
+// Debug adapter binary, see the debugger module for details
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    simple_script::debugger::run()
+}
@@ -0,0 +1,6 @@
+// Language server binary, see the lsp module for details
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    simple_script::lsp::run()
+}
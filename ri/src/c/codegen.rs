@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+
+use super::CError;
+
+use crate::literal::Literal;
+use crate::parser::expr::Expr;
+use crate::parser::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// Identifiers of a single scope, mapped to their slot in the scope's environment
+type Scope = HashMap<String, usize>;
+
+/// Generates a C99 program from the AST, where every SS function is generated as a C function and the top level statements as main
+pub struct CGenerator {
+    /// Prototype of every generated function, so that functions can be defined in any order
+    prototypes: Vec<String>,
+
+    /// Definition of every generated function, in the order they finish generating
+    functions: Vec<String>,
+
+    /// Number of SS functions generated so far, used to give every function a unique C name
+    function_count: usize,
+}
+
+impl CGenerator {
+    /// Generate the C program, which includes the runtime header from the same directory
+    pub fn generate(ast: &Vec<Stmt>, source_name: &str) -> Result<String, CError> {
+        let mut generator = CGenerator {
+            prototypes: Vec::new(),
+            functions: Vec::new(),
+            function_count: 0,
+        };
+
+        let main = generator.generate_main(ast)?;
+
+        let mut output = format!("// Generated by simple_script from {}\n", source_name);
+        output.push_str("#include \"ss_runtime.h\"\n\n");
+        for prototype in generator.prototypes.iter() {
+            output.push_str(prototype);
+            output.push('\n');
+        }
+        for function in generator.functions.iter() {
+            output.push('\n');
+            output.push_str(function);
+        }
+        output.push('\n');
+        output.push_str(&main);
+        Ok(output)
+    }
+
+    /// Generate the main function, which runs the top level statements in the global environment
+    fn generate_main(&mut self, ast: &Vec<Stmt>) -> Result<String, CError> {
+        let mut function = FunctionGenerator::new(self, "main", Vec::new());
+        function.output.push_str("int main(void) {\n");
+        function.indentation += 1;
+
+        // Native functions of the prelude are defined in the global environment like the global environment of ri
+        function.scopes.push(Scope::new());
+        function.line(&format!("ss_env *env0 = ss_env_new(NULL, {});", number_of_slots(ast) + 1));
+        function.environments.push("env0".to_string());
+        let slot = function.declare("clock");
+        function.line(&format!(
+            "ss_env_define(env0, {} /* clock */, ss_function_new(ss_clock, 0, NULL, \"clock\", true));",
+            slot
+        ));
+
+        for stmt in ast {
+            function.statement(stmt)?;
+        }
+
+        function.line("ss_env_release(env0);");
+        function.line("return 0;");
+        function.indentation -= 1;
+        function.output.push_str("}\n");
+        Ok(function.output)
+    }
+
+    /// Generate a SS function, which takes its closure environment and its arguments, and returns its return value.
+    /// Returns the C name of the function.
+    ///
+    /// 'scopes' are the scopes surrounding the function definition, which the function body's identifiers are resolved against.
+    fn generate_function(
+        &mut self,
+        name: Option<&String>,
+        parameters: &[Token],
+        body: &Stmt,
+        scopes: Vec<Scope>,
+    ) -> Result<String, CError> {
+        self.function_count += 1;
+        let c_name = format!(
+            "ss_fn_{}_{}",
+            self.function_count,
+            name.map(|name| name.as_str()).unwrap_or("anonymous")
+        );
+        let signature = format!("static ss_value {}(ss_env *closure, ss_value *arguments)", c_name);
+        self.prototypes.push(format!("{};", signature));
+
+        let stmts = match body {
+            Stmt::Block(ref stmts, _) => stmts,
+            unmatched_stmt_variant => {
+                return Err(CError::Codegen(
+                    c_name,
+                    format!("Function body must be a Block Statement, found: {}", unmatched_stmt_variant),
+                ))
+            }
+        };
+
+        let mut function = FunctionGenerator::new(self, &c_name, scopes);
+        let parameter_names: Vec<&str> = parameters.iter().map(|parameter| parameter.lexeme.as_ref().unwrap().as_str()).collect();
+        function.output.push_str(&format!(
+            "/* fn {}({}) */\n{} {{\n",
+            name.map(|name| name.as_str()).unwrap_or("[anonymous]"),
+            parameter_names.join(", "),
+            signature
+        ));
+        function.indentation += 1;
+
+        // Like Function's call method, a new environment enclosed by the closure holds the arguments and the body's values
+        function.scopes.push(Scope::new());
+        function.line(&format!("ss_env *env0 = ss_env_new(closure, {});", parameters.len() + number_of_slots(stmts)));
+        function.environments.push("env0".to_string());
+        if parameters.is_empty() {
+            function.line("(void)arguments;");
+        }
+        for (index, parameter) in parameter_names.iter().enumerate() {
+            let slot = function.declare(parameter);
+            function.line(&format!("ss_env_define(env0, {} /* {} */, arguments[{}]);", slot, parameter, index));
+        }
+
+        // Body statements run in the same scope as the parameters, without a new scope for the block statement
+        for stmt in stmts {
+            function.statement(stmt)?;
+        }
+
+        // Functions that end without a return statement return Null
+        if !matches!(stmts.last(), Some(Stmt::Return(..))) {
+            function.line("ss_env_release(env0);");
+            function.line("return ss_null();");
+        }
+        function.indentation -= 1;
+        function.output.push_str("}\n");
+
+        let output = function.output;
+        self.functions.push(output);
+        Ok(c_name)
+    }
+}
+
+/// Generates the body of a single C function
+struct FunctionGenerator<'a> {
+    generator: &'a mut CGenerator,
+
+    /// C name of the function, used in error messages
+    name: String,
+
+    output: String,
+
+    /// Indentation level of the current line
+    indentation: usize,
+
+    /// Number of temporaries declared so far, used to give every temporary a unique name
+    temporaries: usize,
+
+    /// Variable names of the environments of every scope in this function, from the function's own environment to the current one
+    environments: Vec<String>,
+
+    /// Identifiers of every scope from the global scope to the current scope, mirroring the resolver's scopes
+    /// so that the resolver's scope distance values find the scope of an identifier to get its slot from
+    scopes: Vec<Scope>,
+
+    /// Number of environments in this function when every while loop the current statement is in started, from the outermost loop
+    loops: Vec<usize>,
+}
+
+impl<'a> FunctionGenerator<'a> {
+    fn new(generator: &'a mut CGenerator, name: &str, scopes: Vec<Scope>) -> FunctionGenerator<'a> {
+        FunctionGenerator {
+            generator,
+            name: name.to_string(),
+            output: String::new(),
+            indentation: 0,
+            temporaries: 0,
+            environments: Vec::new(),
+            scopes,
+            loops: Vec::new(),
+        }
+    }
+
+    /* ==========================  Start of output helpers  ========================== */
+
+    fn line(&mut self, code: &str) {
+        self.output.push_str(&"    ".repeat(self.indentation));
+        self.output.push_str(code);
+        self.output.push('\n');
+    }
+
+    /// Declare a new temporary holding the value of the C expression, returning its name
+    fn temporary(&mut self, c_type: &str, expression: &str) -> String {
+        self.temporaries += 1;
+        let temporary = format!("t{}", self.temporaries);
+        self.line(&format!("{} {} = {};", c_type, temporary, expression));
+        temporary
+    }
+
+    fn environment(&self) -> &str {
+        self.environments.last().unwrap()
+    }
+
+    /// Release the environments created after the first 'keep' environments of this function
+    fn release_environments(&mut self, keep: usize) {
+        let environments: Vec<String> = self.environments[keep..].iter().rev().cloned().collect();
+        for environment in environments {
+            self.line(&format!("ss_env_release({});", environment));
+        }
+    }
+
+    /* ==========================  End of output helpers  ========================== */
+
+    /* ==========================  Start of statements  ========================== */
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<(), CError> {
+        match stmt {
+            Stmt::Print(ref expr, _) => {
+                let value = self.expression(expr)?;
+                self.line(&format!("ss_print({});", value));
+            }
+
+            Stmt::Expr(ref expr, _) | Stmt::Ignore(ref expr, _) => {
+                let value = self.expression(expr)?;
+                self.line(&format!("ss_release({});", value));
+            }
+
+            Stmt::Const(ref token, ref expr) => {
+                // Identifier is declared before its expression is resolved, so the expression can reference the identifier itself
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self.declare(name);
+                let value = self.expression(expr)?;
+                let environment = self.environment().to_string();
+                self.line(&format!("ss_env_define({}, {} /* {} */, {});", environment, slot, name, value));
+            }
+
+            Stmt::Block(ref stmts, _) => {
+                self.line("{");
+                self.indentation += 1;
+                self.block(stmts)?;
+                self.indentation -= 1;
+                self.line("}");
+            }
+
+            Stmt::Func(ref token, ref parameters, ref body) => {
+                // Function identifier is defined before its body is resolved, so that functions can call themselves recursively
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self.declare(name);
+                let function = self.function(Some(name), parameters, body)?;
+                let environment = self.environment().to_string();
+                self.line(&format!("ss_env_define({}, {} /* {} */, {});", environment, slot, name, function));
+            }
+
+            Stmt::If(ref condition, ref true_branch, ref else_branch, _) => {
+                let condition = self.condition(
+                    condition,
+                    "Invalid condition value type, only Boolean values can be used as conditionals!",
+                )?;
+                self.line(&format!("if ({}) {{", condition));
+                self.branch(true_branch)?;
+                if let Some(ref else_branch) = else_branch {
+                    self.line("} else {");
+                    self.branch(else_branch)?;
+                }
+                self.line("}");
+            }
+
+            Stmt::While(ref condition, ref loop_body, _) => {
+                self.line("while (true) {");
+                self.indentation += 1;
+                let condition = self.condition(condition, "Expected Boolean from While loop expression")?;
+                self.line(&format!("if (!{}) break;", condition));
+                self.indentation -= 1;
+
+                self.loops.push(self.environments.len());
+                self.branch(loop_body)?;
+                self.loops.pop();
+                self.line("}");
+            }
+
+            Stmt::Return(ref expr, _) => {
+                let value = self.expression(expr)?;
+
+                // The interpreter's While arm discards the value of its loop body statement,
+                // so a return statement in a loop body only stops the current iteration like ri does, continuing with the next one
+                match self.loops.last() {
+                    Some(&environments) => {
+                        self.line(&format!("ss_release({});", value));
+                        self.release_environments(environments);
+                        self.line("continue;");
+                    }
+                    None => {
+                        self.release_environments(0);
+                        self.line(&format!("return {};", value));
+                    }
+                }
+            }
+
+            unmatched_stmt_variant => {
+                return Err(CError::Codegen(
+                    self.name.clone(),
+                    format!("Unimplemented Stmt variant: {}", unmatched_stmt_variant),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate the statements of a block statement in a new environment, without the braces around them
+    fn block(&mut self, stmts: &[Stmt]) -> Result<(), CError> {
+        let environment = format!("env{}", self.environments.len());
+        self.line(&format!(
+            "ss_env *{} = ss_env_new({}, {});",
+            environment,
+            self.environment(),
+            number_of_slots(stmts)
+        ));
+        self.environments.push(environment.clone());
+        self.scopes.push(Scope::new());
+
+        for stmt in stmts {
+            self.statement(stmt)?;
+        }
+
+        self.scopes.pop();
+        self.environments.pop();
+        self.line(&format!("ss_env_release({});", environment));
+        Ok(())
+    }
+
+    /// Generate the body of an if or while statement inside the braces of the C statement
+    fn branch(&mut self, stmt: &Stmt) -> Result<(), CError> {
+        self.indentation += 1;
+        match stmt {
+            Stmt::Block(ref stmts, _) => self.block(stmts)?,
+            stmt => self.statement(stmt)?,
+        }
+        self.indentation -= 1;
+        Ok(())
+    }
+
+    /* ==========================  End of statements  ========================== */
+
+    /* ==========================  Start of expressions  ========================== */
+
+    /// Generate the statements evaluating the expression, returning the name of the temporary holding its value
+    fn expression(&mut self, expr: &Expr) -> Result<String, CError> {
+        Ok(match expr {
+            Expr::Literal(ref literal) => {
+                let value = match literal {
+                    // Debug format of f64 always converts back to the same number, and is a valid C double literal
+                    Literal::Number(number) => format!("ss_number({:?})", number),
+                    Literal::String(ref string) => format!("ss_string_literal({}, {})", c_string(string), string.len()),
+                    Literal::Bool(boolean) => format!("ss_bool({})", boolean),
+                    Literal::Null => "ss_null()".to_string(),
+                };
+                self.temporary("ss_value", &value)
+            }
+
+            Expr::Const(ref token, distance) => {
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self
+                    .scopes
+                    .iter()
+                    .rev()
+                    .nth(*distance)
+                    .and_then(|scope| scope.get(name))
+                    .copied()
+                    .ok_or_else(|| {
+                        CError::Codegen(self.name.clone(), format!("Identifier '{}' is not resolved", name))
+                    })?;
+
+                let value = format!(
+                    "ss_env_get({}, {}, {}, {}, {})",
+                    self.environment(),
+                    distance,
+                    slot,
+                    token.line,
+                    c_string(name)
+                );
+                self.temporary("ss_value", &value)
+            }
+
+            Expr::AnonymousFunc(ref stmt) => match **stmt {
+                Stmt::AnonymousFunc(ref parameters, ref body) => self.function(None, parameters, body)?,
+                ref unmatched_stmt_variant => {
+                    return Err(CError::Codegen(
+                        self.name.clone(),
+                        format!("Expected anonymous function, found: {}", unmatched_stmt_variant),
+                    ))
+                }
+            },
+
+            Expr::Call(ref callee, ref arguments, ref token) => {
+                // Callee is checked to be a function before the arguments are evaluated, like the interpreter does
+                let callee = self.expression(callee)?;
+                self.line(&format!("ss_check_callable({}, {}, {});", callee, arguments.len(), token.line));
+
+                let mut argument_values = Vec::new();
+                for argument in arguments {
+                    argument_values.push(self.expression(argument)?);
+                }
+
+                let arguments = match argument_values.is_empty() {
+                    true => "NULL".to_string(),
+                    false => format!("(ss_value[]){{{}}}", argument_values.join(", ")),
+                };
+                self.temporary("ss_value", &format!("ss_call({}, {})", callee, arguments))
+            }
+
+            Expr::Array(_, ref elements) => {
+                let mut values = vec![elements.len().to_string()];
+                for element in elements {
+                    values.push(self.expression(element)?);
+                }
+                self.temporary("ss_value", &format!("ss_array_of({})", values.join(", ")))
+            }
+
+            Expr::ArrayAccess(ref array, ref index) => {
+                let array = self.expression(array)?;
+                let index = self.expression(index)?;
+                self.temporary("ss_value", &format!("ss_array_get({}, {})", array, index))
+            }
+
+            Expr::Grouping(ref expr) => self.expression(expr)?,
+
+            Expr::Unary(ref operator, ref expr) => {
+                let value = self.expression(expr)?;
+                let runtime_function = match operator.token_type {
+                    TokenType::Minus => "ss_negate",
+                    TokenType::Bang => "ss_not",
+                    ref operator => {
+                        return Err(CError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid unary operator: {:?}", operator),
+                        ))
+                    }
+                };
+                self.temporary("ss_value", &format!("{}({})", runtime_function, value))
+            }
+
+            Expr::Binary(ref left, ref operator, ref right) => {
+                let left = self.expression(left)?;
+                let right = self.expression(right)?;
+                let runtime_function = match operator.token_type {
+                    TokenType::Plus => "ss_add",
+                    TokenType::Minus => "ss_subtract",
+                    TokenType::Star => "ss_multiply",
+                    TokenType::Slash => "ss_divide",
+                    TokenType::EqualEqual => "ss_equal",
+                    TokenType::BangEqual => "ss_not_equal",
+                    TokenType::Greater => "ss_greater",
+                    TokenType::GreaterEqual => "ss_greater_equal",
+                    TokenType::Less => "ss_less",
+                    TokenType::LessEqual => "ss_less_equal",
+                    ref operator => {
+                        return Err(CError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid binary operator: {:?}", operator),
+                        ))
+                    }
+                };
+                self.temporary("ss_value", &format!("{}({}, {})", runtime_function, left, right))
+            }
+
+            // Strict boolean operators that short circuit, evaluating to a Bool value
+            Expr::Logical(ref left, ref operator, ref right) => {
+                let message = "Logical operations only work with Bool Types";
+                let left = self.condition(left, message)?;
+                let result = self.temporary("bool", &left);
+
+                match operator.token_type {
+                    TokenType::Or => self.line(&format!("if (!{}) {{", result)),
+                    TokenType::And => self.line(&format!("if ({}) {{", result)),
+                    ref operator => {
+                        return Err(CError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid Token Type for logical expr -> {:?}", operator),
+                        ))
+                    }
+                }
+                self.indentation += 1;
+                let right = self.condition(right, message)?;
+                self.line(&format!("{} = {};", result, right));
+                self.indentation -= 1;
+                self.line("}");
+
+                self.temporary("ss_value", &format!("ss_bool({})", result))
+            }
+        })
+    }
+
+    /// Evaluate the expression, returning the C expression that converts its value to a bool,
+    /// which fails at runtime with the message if the value is not a Bool
+    fn condition(&mut self, expr: &Expr, message: &str) -> Result<String, CError> {
+        let value = self.expression(expr)?;
+        Ok(format!("ss_condition({}, {})", value, c_string(message)))
+    }
+
+    /// Generate the function and create its function value, with the current environment as its closure
+    fn function(&mut self, name: Option<&String>, parameters: &[Token], body: &Stmt) -> Result<String, CError> {
+        let c_name = self.generator.generate_function(name, parameters, body, self.scopes.clone())?;
+        let name = match name {
+            Some(name) => c_string(name),
+            None => "NULL".to_string(),
+        };
+        let value = format!(
+            "ss_function_new({}, {}, {}, {}, false)",
+            c_name,
+            parameters.len(),
+            self.environment(),
+            name
+        );
+        Ok(self.temporary("ss_value", &value))
+    }
+
+    /// Declare the identifier in the current scope, returning its slot in the scope's environment
+    fn declare(&mut self, name: &str) -> usize {
+        let scope = self.scopes.last_mut().unwrap();
+        let slot = scope.len();
+        *scope.entry(name.to_string()).or_insert(slot)
+    }
+
+    /* ==========================  End of expressions  ========================== */
+}
+
+/// Number of slots the environment of a scope needs, which is one for every identifier the scope's statements declare
+fn number_of_slots(stmts: &[Stmt]) -> usize {
+    stmts.iter().filter(|stmt| matches!(stmt, Stmt::Const(..) | Stmt::Func(..))).count()
+}
+
+/// C string literal of the string, where every byte that is not printable ASCII is escaped in octal
+fn c_string(string: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in string.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            // Question marks are escaped so that they never form trigraphs
+            b'?' => literal.push_str("\\?"),
+            b' '..=b'~' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    literal.push('"');
+    literal
+}
@@ -0,0 +1,19 @@
+/// Errors from generating a C program from a SS program
+#[derive(Debug)]
+pub enum CError {
+    /// AST node that cannot be generated, which should have been rejected by the earlier passes
+    /// Codegen(C function name, error message)
+    Codegen(String, String),
+
+    /// Generated C file or runtime header could not be written
+    Io(String),
+}
+
+impl std::fmt::Display for CError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CError::Codegen(name, error) => write!(f, "Unable to generate function '{}': {}", name, error),
+            CError::Io(error) => write!(f, "Unable to write output file: {}", error),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+mod codegen;
+mod error;
+
+use std::fs;
+use std::path::Path;
+
+use crate::parser::stmt::Stmt;
+
+use self::codegen::CGenerator;
+pub use self::error::CError;
+
+/*
+    C back end, enabled with '--backend c', which generates a single C99 file from the resolved and type checked AST,
+    as a low dependency way to build native executables with the system C compiler.
+
+    The runtime (see ss_runtime.h) is a single header written next to the generated file, which implements tagged values,
+    reference counted strings, arrays, functions and environments, and every operation on values with the same error messages as ri.
+
+    Like the native back end, environments are arrays of slots, where the generator assigns a slot to every identifier of a scope,
+    and closures are functions holding a reference to the environment they are defined in.
+    Every SS function is generated as a C function with this signature:
+        static ss_value ss_fn_<n>_<name>(ss_env *closure, ss_value *arguments)
+
+    Expressions are generated as a sequence of temporaries, one for every sub expression, so that the generated code evaluates
+    them in the same order as ri does, and is easy to step through when debugging the semantics of a program.
+*/
+
+/// Runtime header included by every generated file
+const RUNTIME_HEADER: &str = include_str!("ss_runtime.h");
+
+/// Generate the C program at the output path, and write the runtime header it includes into the same directory
+pub fn compile(ast: &Vec<Stmt>, source_path: &Path, output: &Path) -> Result<(), CError> {
+    let source_name = source_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let code = CGenerator::generate(ast, &source_name)?;
+
+    let header_path = output.with_file_name("ss_runtime.h");
+    fs::write(output, code).map_err(|error| CError::Io(format!("{}: {}", output.display(), error)))?;
+    fs::write(&header_path, RUNTIME_HEADER).map_err(|error| CError::Io(format!("{}: {}", header_path.display(), error)))
+}
@@ -0,0 +1,564 @@
+/*
+    SimpleScript runtime for programs compiled into C by ri's C back end.
+
+    The whole runtime is defined in this header, as every program is a single C99 file that includes it,
+    so a program builds with just the system C compiler:
+        cc -std=c99 -O2 -o program program.c
+
+    Every runtime function is 'static inline', so that compilers do not warn about the functions a program does not use.
+
+    Every SS value is a tagged ss_value, where strings, arrays, functions and environments are reference counted heap objects.
+    Values are owned references: runtime functions consume the values passed to them and return new references,
+    unless a parameter is documented as borrowed. Cycles between environments and the closures defined in them are never freed.
+*/
+#ifndef SS_RUNTIME_H
+#define SS_RUNTIME_H
+
+/* clock_gettime is POSIX, not C99 */
+#ifndef _POSIX_C_SOURCE
+#define _POSIX_C_SOURCE 199309L
+#endif
+
+#include <math.h>
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+
+/* ==========================  Start of values  ========================== */
+
+typedef enum {
+    /* Value of an environment slot before its identifier is defined */
+    SS_UNDEFINED,
+    SS_NUMBER,
+    SS_STRING,
+    SS_BOOL,
+    SS_NULL,
+    SS_ARRAY,
+    SS_FUNCTION
+} ss_tag;
+
+typedef struct ss_string ss_string;
+typedef struct ss_array ss_array;
+typedef struct ss_function ss_function;
+typedef struct ss_env ss_env;
+
+typedef struct {
+    ss_tag tag;
+    union {
+        double number;
+        bool boolean;
+        ss_string *string;
+        ss_array *array;
+        ss_function *function;
+    } as;
+} ss_value;
+
+struct ss_string {
+    size_t refcount;
+    size_t length;
+    char bytes[];
+};
+
+struct ss_array {
+    size_t refcount;
+    size_t length;
+    ss_value elements[];
+};
+
+/* Every SS function is compiled into a C function taking its closure and its arguments, which it consumes */
+typedef ss_value (*ss_code)(ss_env *closure, ss_value *arguments);
+
+struct ss_function {
+    size_t refcount;
+    ss_code code;
+    size_t arity;
+    ss_env *closure;
+    /* Name of the function, which is NULL for anonymous functions */
+    const char *name;
+    bool native;
+};
+
+/* Environment of a scope, with a slot for every identifier the scope declares */
+struct ss_env {
+    size_t refcount;
+    ss_env *enclosing;
+    size_t length;
+    ss_value slots[];
+};
+
+static inline void *ss_allocate(size_t size) {
+    void *object = calloc(1, size);
+    if (object == NULL) {
+        fputs("Out of memory\n", stderr);
+        exit(1);
+    }
+    return object;
+}
+
+static inline void ss_env_release(ss_env *env);
+
+static inline ss_value ss_retain(ss_value value) {
+    switch (value.tag) {
+    case SS_STRING: value.as.string->refcount++; break;
+    case SS_ARRAY: value.as.array->refcount++; break;
+    case SS_FUNCTION: value.as.function->refcount++; break;
+    default: break;
+    }
+    return value;
+}
+
+static inline void ss_release(ss_value value) {
+    size_t index;
+    switch (value.tag) {
+    case SS_STRING:
+        if (--value.as.string->refcount == 0) free(value.as.string);
+        break;
+    case SS_ARRAY:
+        if (--value.as.array->refcount == 0) {
+            for (index = 0; index < value.as.array->length; index++) ss_release(value.as.array->elements[index]);
+            free(value.as.array);
+        }
+        break;
+    case SS_FUNCTION:
+        if (--value.as.function->refcount == 0) {
+            if (value.as.function->closure != NULL) ss_env_release(value.as.function->closure);
+            free(value.as.function);
+        }
+        break;
+    default: break;
+    }
+}
+
+static inline ss_value ss_number(double number) {
+    ss_value value;
+    value.tag = SS_NUMBER;
+    value.as.number = number;
+    return value;
+}
+
+/* String of the given length, where the caller writes its bytes */
+static inline ss_value ss_string_new(size_t length) {
+    ss_value value;
+    value.tag = SS_STRING;
+    value.as.string = ss_allocate(sizeof(ss_string) + length);
+    value.as.string->refcount = 1;
+    value.as.string->length = length;
+    return value;
+}
+
+static inline ss_value ss_string_literal(const char *bytes, size_t length) {
+    ss_value value = ss_string_new(length);
+    memcpy(value.as.string->bytes, bytes, length);
+    return value;
+}
+
+static inline ss_value ss_bool(bool boolean) {
+    ss_value value;
+    value.tag = SS_BOOL;
+    value.as.boolean = boolean;
+    return value;
+}
+
+static inline ss_value ss_null(void) {
+    ss_value value;
+    value.tag = SS_NULL;
+    value.as.number = 0;
+    return value;
+}
+
+/* Array of the given elements, which are passed as length number of ss_value arguments */
+static inline ss_value ss_array_of(size_t length, ...) {
+    ss_value value;
+    va_list elements;
+    size_t index;
+
+    value.tag = SS_ARRAY;
+    value.as.array = ss_allocate(sizeof(ss_array) + length * sizeof(ss_value));
+    value.as.array->refcount = 1;
+    value.as.array->length = length;
+
+    va_start(elements, length);
+    for (index = 0; index < length; index++) value.as.array->elements[index] = va_arg(elements, ss_value);
+    va_end(elements);
+    return value;
+}
+
+/* Function value with the closure environment, which is borrowed */
+static inline ss_value ss_function_new(ss_code code, size_t arity, ss_env *closure, const char *name, bool native) {
+    ss_value value;
+    value.tag = SS_FUNCTION;
+    value.as.function = ss_allocate(sizeof(ss_function));
+    value.as.function->refcount = 1;
+    value.as.function->code = code;
+    value.as.function->arity = arity;
+    value.as.function->closure = closure;
+    value.as.function->name = name;
+    value.as.function->native = native;
+    if (closure != NULL) closure->refcount++;
+    return value;
+}
+
+/* ==========================  End of values  ========================== */
+
+/* ==========================  Start of printing  ========================== */
+
+/* Write the number formatted like Rust's Display trait for f64 does, or Rust's Debug trait if debug is true */
+static inline void ss_write_number(FILE *stream, double number, bool debug) {
+    char buffer[32];
+    char digits[20];
+    int precision, exponent, point, length, index;
+    const char *character;
+
+    if (isnan(number)) {
+        fputs("NaN", stream);
+        return;
+    }
+    if (isinf(number)) {
+        fputs(number > 0 ? "inf" : "-inf", stream);
+        return;
+    }
+    if (number == 0) {
+        fputs(signbit(number) ? "-0" : "0", stream);
+        if (debug) fputs(".0", stream);
+        return;
+    }
+
+    /* Shortest number of digits that converts back to the same number, like Rust does */
+    for (precision = 0; precision < 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*e", precision, number);
+        if (strtod(buffer, NULL) == number) break;
+    }
+
+    length = 0;
+    for (character = buffer; *character != 'e'; character++) {
+        if (*character >= '0' && *character <= '9') digits[length++] = *character;
+    }
+    digits[length] = '\0';
+    exponent = atoi(character + 1);
+    /* Trailing zeros of the digits are never significant */
+    while (length > 1 && digits[length - 1] == '0') digits[--length] = '\0';
+
+    if (number < 0) fputc('-', stream);
+
+    /* Magnitude is compared without fabs, so that programs do not need to link the math library */
+    if (debug && ((number < 0 ? -number : number) < 1e-4 || (number < 0 ? -number : number) >= 1e16)) {
+        fputc(digits[0], stream);
+        if (length > 1) fprintf(stream, ".%s", digits + 1);
+        fprintf(stream, "e%d", exponent);
+        return;
+    }
+
+    /* Position of the decimal point in the digits */
+    point = exponent + 1;
+    if (point <= 0) {
+        fputs("0.", stream);
+        for (index = point; index < 0; index++) fputc('0', stream);
+        fputs(digits, stream);
+    } else if (point >= length) {
+        fputs(digits, stream);
+        for (index = length; index < point; index++) fputc('0', stream);
+        if (debug) fputs(".0", stream);
+    } else {
+        fwrite(digits, 1, (size_t)point, stream);
+        fprintf(stream, ".%s", digits + point);
+    }
+}
+
+static inline void ss_write_function(FILE *stream, const ss_function *function) {
+    if (function->native) {
+        fprintf(stream, "<function-native: %s>", function->name);
+    } else {
+        fprintf(stream, "<function-ss: %s>", function->name == NULL ? "[anonymous]" : function->name);
+    }
+}
+
+/* Pretty printer of values, the same as ri's Display trait for values. The value is borrowed. */
+static inline void ss_write(FILE *stream, ss_value value) {
+    size_t index;
+    switch (value.tag) {
+    case SS_NUMBER: ss_write_number(stream, value.as.number, false); break;
+    case SS_STRING:
+        fputc('\'', stream);
+        fwrite(value.as.string->bytes, 1, value.as.string->length, stream);
+        fputc('\'', stream);
+        break;
+    case SS_BOOL: fputs(value.as.boolean ? "true" : "false", stream); break;
+    case SS_NULL: fputs("NULL", stream); break;
+    case SS_ARRAY:
+        fputc('[', stream);
+        for (index = 0; index < value.as.array->length; index++) {
+            if (index > 0) fputs(", ", stream);
+            ss_write(stream, value.as.array->elements[index]);
+        }
+        fputc(']', stream);
+        break;
+    case SS_FUNCTION: ss_write_function(stream, value.as.function); break;
+    case SS_UNDEFINED: break;
+    }
+}
+
+/* Same as ri's derived Debug trait for values, which is used in error messages. The value is borrowed. */
+static inline void ss_write_debug(FILE *stream, ss_value value) {
+    size_t index;
+    switch (value.tag) {
+    case SS_NUMBER:
+        fputs("Number(", stream);
+        ss_write_number(stream, value.as.number, true);
+        fputc(')', stream);
+        break;
+    case SS_STRING:
+        fputs("String(\"", stream);
+        fwrite(value.as.string->bytes, 1, value.as.string->length, stream);
+        fputs("\")", stream);
+        break;
+    case SS_BOOL: fputs(value.as.boolean ? "Bool(true)" : "Bool(false)", stream); break;
+    case SS_NULL: fputs("Null", stream); break;
+    case SS_ARRAY:
+        fputs("Array([", stream);
+        for (index = 0; index < value.as.array->length; index++) {
+            if (index > 0) fputs(", ", stream);
+            ss_write_debug(stream, value.as.array->elements[index]);
+        }
+        fputs("])", stream);
+        break;
+    case SS_FUNCTION:
+        fputs("Func(", stream);
+        ss_write_function(stream, value.as.function);
+        fputc(')', stream);
+        break;
+    case SS_UNDEFINED: break;
+    }
+}
+
+static inline void ss_print(ss_value value) {
+    ss_write(stdout, value);
+    fputc('\n', stdout);
+    ss_release(value);
+}
+
+/* ==========================  End of printing  ========================== */
+
+/* ==========================  Start of runtime errors  ========================== */
+
+/* Runtime errors are reported like ri does, where the message is written to stderr between these 2 calls */
+static inline void ss_error_begin(void) {
+    fflush(stdout);
+    fputs("-------- Interpreter ERROR --------\n", stderr);
+}
+
+static inline void ss_error_end(void) {
+    fputc('\n', stderr);
+    exit(1);
+}
+
+static inline void ss_error(const char *format, ...) {
+    va_list arguments;
+    ss_error_begin();
+    va_start(arguments, format);
+    vfprintf(stderr, format, arguments);
+    va_end(arguments);
+    ss_error_end();
+}
+
+/* ==========================  End of runtime errors  ========================== */
+
+/* ==========================  Start of environments  ========================== */
+
+/* Environment enclosed by the enclosing environment, which is borrowed */
+static inline ss_env *ss_env_new(ss_env *enclosing, size_t length) {
+    ss_env *env = ss_allocate(sizeof(ss_env) + length * sizeof(ss_value));
+    env->refcount = 1;
+    env->enclosing = enclosing;
+    env->length = length;
+    if (enclosing != NULL) enclosing->refcount++;
+    return env;
+}
+
+static inline void ss_env_release(ss_env *env) {
+    size_t index;
+    if (--env->refcount > 0) return;
+    for (index = 0; index < env->length; index++) ss_release(env->slots[index]);
+    if (env->enclosing != NULL) ss_env_release(env->enclosing);
+    free(env);
+}
+
+static inline void ss_env_define(ss_env *env, size_t slot, ss_value value) {
+    ss_release(env->slots[slot]);
+    env->slots[slot] = value;
+}
+
+/* Get the value of an identifier, using the scope distance value from the resolver and the slot assigned by the compiler */
+static inline ss_value ss_env_get(ss_env *env, size_t distance, size_t slot, int line, const char *name) {
+    for (; distance > 0; distance--) env = env->enclosing;
+    if (env->slots[slot].tag == SS_UNDEFINED) {
+        ss_error("[line %d] ReferenceError: Tried to use undefined identifier '%s'", line, name);
+    }
+    return ss_retain(env->slots[slot]);
+}
+
+/* ==========================  End of environments  ========================== */
+
+/* ==========================  Start of functions  ========================== */
+
+/* Check that the callee is a function before its arguments are evaluated, like the interpreter does. The callee is borrowed. */
+static inline void ss_check_callable(ss_value callee, size_t number_of_arguments, int line) {
+    if (callee.tag != SS_FUNCTION) {
+        ss_error_begin();
+        fprintf(stderr, "[line %d] Attempted to call non-callable: ", line);
+        ss_write(stderr, callee);
+        ss_error_end();
+    }
+
+    /* Type checker ensures that the number of arguments match */
+    if (callee.as.function->arity != number_of_arguments) {
+        ss_error_begin();
+        fprintf(stderr, "Internal interpreter error: Expected %lu arguments but got %lu for ",
+                (unsigned long)callee.as.function->arity, (unsigned long)number_of_arguments);
+        ss_write(stderr, callee);
+        ss_error_end();
+    }
+}
+
+/* Call the function checked by ss_check_callable */
+static inline ss_value ss_call(ss_value callee, ss_value *arguments) {
+    ss_value result = callee.as.function->code(callee.as.function->closure, arguments);
+    ss_release(callee);
+    return result;
+}
+
+/* Same as the interpreter's clock native function, returning the current time in milliseconds */
+static inline ss_value ss_clock(ss_env *closure, ss_value *arguments) {
+    struct timespec now;
+    (void)closure;
+    (void)arguments;
+    clock_gettime(CLOCK_REALTIME, &now);
+    return ss_number((double)now.tv_sec * 1000 + (double)(now.tv_nsec / 1000000));
+}
+
+/* ==========================  End of functions  ========================== */
+
+/* ==========================  Start of operators  ========================== */
+
+/* Strict boolean check with the same message as the interpreter's bool_or_err method, as SS does not have truthy and falsy values */
+static inline bool ss_condition(ss_value value, const char *message) {
+    if (value.tag != SS_BOOL) {
+        ss_error_begin();
+        fputs("Type Error: Expected Bool but found type and value: ", stderr);
+        ss_write_debug(stderr, value);
+        fprintf(stderr, "\n%s", message);
+        ss_error_end();
+    }
+    return value.as.boolean;
+}
+
+static inline ss_value ss_add(ss_value left, ss_value right) {
+    ss_value result;
+    if (left.tag == SS_NUMBER && right.tag == SS_NUMBER) return ss_number(left.as.number + right.as.number);
+
+    if (left.tag == SS_STRING && right.tag == SS_STRING) {
+        result = ss_string_new(left.as.string->length + right.as.string->length);
+        memcpy(result.as.string->bytes, left.as.string->bytes, left.as.string->length);
+        memcpy(result.as.string->bytes + left.as.string->length, right.as.string->bytes, right.as.string->length);
+        ss_release(left);
+        ss_release(right);
+        return result;
+    }
+
+    ss_error("Type Error: Invalid types used for addition!");
+    return ss_null();
+}
+
+/* Define a binary operator on 2 Numbers, with the same error messages as the interpreter */
+#define SS_NUMERIC_OPERATOR(name, operator, result, operator_name)                   \
+    static inline ss_value name(ss_value left, ss_value right) {                     \
+        if (left.tag != SS_NUMBER || right.tag != SS_NUMBER) {                       \
+            ss_error("Type Error: Invalid types used for " operator_name "!");       \
+        }                                                                            \
+        return result(left.as.number operator right.as.number);                      \
+    }
+
+SS_NUMERIC_OPERATOR(ss_subtract, -, ss_number, "subtraction")
+SS_NUMERIC_OPERATOR(ss_multiply, *, ss_number, "multiplication")
+SS_NUMERIC_OPERATOR(ss_divide, /, ss_number, "division")
+SS_NUMERIC_OPERATOR(ss_greater, >, ss_bool, "Greater")
+SS_NUMERIC_OPERATOR(ss_greater_equal, >=, ss_bool, "Greater Equal")
+SS_NUMERIC_OPERATOR(ss_less, <, ss_bool, "Less")
+SS_NUMERIC_OPERATOR(ss_less_equal, <=, ss_bool, "Less Equal")
+
+/* Arrays are never equal, and functions are compared by identity like ri does */
+static inline bool ss_values_equal(ss_value left, ss_value right) {
+    if (left.tag != right.tag) return false;
+    switch (left.tag) {
+    case SS_NUMBER: return left.as.number == right.as.number;
+    case SS_STRING:
+        return left.as.string->length == right.as.string->length &&
+               memcmp(left.as.string->bytes, right.as.string->bytes, left.as.string->length) == 0;
+    case SS_BOOL: return left.as.boolean == right.as.boolean;
+    case SS_NULL: return true;
+    case SS_FUNCTION: return left.as.function == right.as.function;
+    default: return false;
+    }
+}
+
+static inline ss_value ss_equal(ss_value left, ss_value right) {
+    bool equal = ss_values_equal(left, right);
+    ss_release(left);
+    ss_release(right);
+    return ss_bool(equal);
+}
+
+static inline ss_value ss_not_equal(ss_value left, ss_value right) {
+    bool equal = ss_values_equal(left, right);
+    ss_release(left);
+    ss_release(right);
+    return ss_bool(!equal);
+}
+
+static inline ss_value ss_negate(ss_value value) {
+    if (value.tag != SS_NUMBER) ss_error("Type Error: Invalid types used for number negation!");
+    return ss_number(-value.as.number);
+}
+
+static inline ss_value ss_not(ss_value value) {
+    if (value.tag != SS_BOOL) ss_error("Type Error: Invalid types used for boolean negation!");
+    return ss_bool(!value.as.boolean);
+}
+
+/* Get an array element, with the same bounds check as the interpreter */
+static inline ss_value ss_array_get(ss_value array, ss_value index) {
+    ss_value element;
+
+    if (array.tag != SS_ARRAY) {
+        ss_error_begin();
+        fputs("Type Error: Array element access failed, expect array to be of type Value::Array, found -> ", stderr);
+        ss_write_debug(stderr, array);
+        ss_error_end();
+    }
+    if (index.tag != SS_NUMBER) {
+        ss_error_begin();
+        fputs("Type Error: Array element access failed, expect index to be of type Value::Number, found -> ", stderr);
+        ss_write_debug(stderr, index);
+        ss_error_end();
+    }
+
+    /* Negative indexes are checked before the index is truncated, like casting the index to usize in the interpreter */
+    if (!(index.as.number >= 0 && index.as.number < (double)array.as.array->length)) {
+        ss_error_begin();
+        fprintf(stderr, "Array Index Out Of Bounds Error: Expect index to be 0 to %ld, found -> ",
+                (long)array.as.array->length - 1);
+        ss_write_number(stderr, index.as.number, false);
+        ss_error_end();
+    }
+
+    element = ss_retain(array.as.array->elements[(size_t)index.as.number]);
+    ss_release(array);
+    return element;
+}
+
+/* ==========================  End of operators  ========================== */
+
+#endif
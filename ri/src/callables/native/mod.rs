@@ -1,3 +1,17 @@
 mod clock;
 
 pub use self::clock::ClockFunc;
+
+/// Signature of a native function in the prelude, used by tools like the language server to show what it does
+pub struct NativeSignature {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+}
+
+/// Signatures of every native function in the prelude, which must be kept in sync with the prelude defined in `Environment::global`
+pub const PRELUDE: [NativeSignature; 1] = [NativeSignature {
+    name: "clock",
+    signature: "fn clock() -> Type::Number",
+    description: "Get the current time as the number of milliseconds since the UNIX epoch",
+}];
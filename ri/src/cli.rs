@@ -1,10 +1,73 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
 /// CLI data structure
 // @todo Include a verbose flag https://crates.io/crates/clap-verbosity-flag
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)] // Read from `Cargo.toml`
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
+    /// Tools for SimpleScript source files, which are used instead of running a file
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// The path of the entry SimpleScript file to execute
-    pub file_path: String,
+    #[arg(required = true)]
+    pub file_path: Option<String>,
+
+    /// Back end used to run the program after it is type checked
+    #[arg(long, value_enum, default_value_t = Backend::Interpreter)]
+    pub backend: Backend,
+
+    /// Path of the file generated by the native, js, wasm and c back ends.
+    /// Defaults to the file path without its extension for native executables, and with a '.js', '.wasm' or '.c' extension otherwise,
+    /// or with a '.out' extension if that is the file path itself
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+/// Back ends that a type checked program can be run or compiled with
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum Backend {
+    /// Tree walking interpreter, running the AST directly
+    Interpreter,
+
+    /// Ahead of time compiler, compiling the program into a native executable with Cranelift instead of running it
+    Native,
+
+    /// Transpiler, generating a JavaScript file and its source map instead of running the program
+    Js,
+
+    /// WebAssembly emitter, compiling the program into a standalone '.wasm' module instead of running it
+    Wasm,
+
+    /// C generator, generating a C99 file and the runtime header it includes instead of running the program
+    C,
+
+    /// Bytecode generator, generating a chunk for rvm and running it on rvm's stack VM
+    Vm,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Format SimpleScript source files into their canonical layout in place
+    Fmt {
+        /// The paths of the SimpleScript source files to format
+        #[arg(required = true)]
+        file_paths: Vec<String>,
+
+        /// Only check that the files are formatted without changing them, failing if any file is not, e.g. for CI
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Check SimpleScript source files for style and semantic problems, failing if any rule configured as an error is broken
+    Lint {
+        /// The paths of the SimpleScript source files to lint
+        #[arg(required = true)]
+        file_paths: Vec<String>,
+
+        /// Path of the config file, instead of the 'sslint.json' file found in the directory of each linted file or its parents
+        #[arg(long)]
+        config: Option<String>,
+    },
 }
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde_json::{json, Value as Json};
+
+use crate::environment::environment::Environment;
+use crate::parser::expr::Expr;
+use crate::parser::parser_struct::Parser;
+use crate::parser::stmt::Stmt;
+use crate::scanner::scanner_struct::Scanner;
+use crate::value::value::Value;
+
+/// What a variables reference refers to, for the client to expand it into its variables
+enum Reference {
+    Scope(Rc<RefCell<Environment>>),
+    Array(Vec<Value>),
+}
+
+/// Variables references handed out while the program is paused, which are only valid until it is resumed.
+/// Reference n is stored at index n - 1, as reference 0 means that a variable cannot be expanded.
+#[derive(Default)]
+pub struct References {
+    references: Vec<Reference>,
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Bool(_) => "bool",
+        Value::Null => "null",
+        Value::Array(_) => "array",
+        Value::Func(_) => "function",
+        Value::Return(value) => type_of(value),
+    }
+}
+
+impl References {
+    pub fn clear(&mut self) {
+        self.references.clear();
+    }
+
+    fn add(&mut self, reference: Reference) -> usize {
+        self.references.push(reference);
+        self.references.len()
+    }
+
+    /// Get the scopes of the environment chain, from the innermost scope to the global scope
+    pub fn scopes(&mut self, env: Option<Rc<RefCell<Environment>>>) -> Vec<Json> {
+        let mut scopes = Vec::new();
+        let mut env = env;
+
+        while let Some(scope) = env {
+            let enclosing = scope.borrow().enclosing();
+            let name = match (scopes.len(), &enclosing) {
+                (_, None) => String::from("Global"),
+                (0, _) => String::from("Local"),
+                (depth, _) => format!("Enclosing {}", depth),
+            };
+            let reference = self.add(Reference::Scope(scope));
+            scopes.push(json!({ "name": name, "variablesReference": reference, "expensive": false }));
+            env = enclosing;
+        }
+
+        scopes
+    }
+
+    /// Get the variables of a scope ordered by name, or the elements of an array, as DAP variables
+    pub fn variables(&mut self, reference: usize) -> Option<Vec<Json>> {
+        let values: Vec<(String, Value)> = match self.references.get(reference.checked_sub(1)?)? {
+            Reference::Scope(scope) => {
+                let mut values: Vec<(String, Value)> = scope
+                    .borrow()
+                    .values()
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect();
+                values.sort_by(|(name, _), (other, _)| name.cmp(other));
+                values
+            }
+            Reference::Array(elements) => elements
+                .iter()
+                .enumerate()
+                .map(|(index, element)| (format!("[{}]", index), element.clone()))
+                .collect(),
+        };
+
+        Some(values.into_iter().map(|(name, value)| self.variable(name, value)).collect())
+    }
+
+    /// Describe the value as a DAP variable
+    fn variable(&mut self, name: String, value: Value) -> Json {
+        let (value, type_name, reference) = self.describe(value);
+        json!({ "name": name, "value": value, "type": type_name, "variablesReference": reference })
+    }
+
+    /// Describe the value as the body of an evaluate response
+    pub fn result(&mut self, value: Value) -> Json {
+        let (result, type_name, reference) = self.describe(value);
+        json!({ "result": result, "type": type_name, "variablesReference": reference })
+    }
+
+    /// Get the printed value, its type and its reference, where only arrays with elements can be expanded
+    fn describe(&mut self, value: Value) -> (String, &'static str, usize) {
+        let printed = value.to_string();
+        let type_name = type_of(&value);
+        let reference = match value {
+            Value::Array(elements) if !elements.is_empty() => self.add(Reference::Array(elements)),
+            _ => 0,
+        };
+        (printed, type_name, reference)
+    }
+}
+
+/// Parse an expression to evaluate in the environment, where identifiers are resolved to the nearest scope of the
+/// environment that defines them, since the resolver only resolves identifiers of the whole program
+pub fn parse_expression(expression: &str, env: &Environment) -> Result<Expr, String> {
+    let source = format!("{};", expression.trim().trim_end_matches(';'));
+    let tokens = Scanner::scan_tokens(source).map_err(|errors| errors[0].to_string())?;
+    let mut ast = Parser::parse(&tokens).map_err(|errors| errors[0].to_string())?;
+
+    let mut expr = match (ast.pop(), ast.is_empty()) {
+        (Some(Stmt::Expr(expr, _)), true) => expr,
+        _ => return Err(String::from("Only an expression can be evaluated")),
+    };
+    resolve(&mut expr, env)?;
+    Ok(expr)
+}
+
+fn resolve(expr: &mut Expr, env: &Environment) -> Result<(), String> {
+    match expr {
+        Expr::Const(token, distance) => {
+            let name = token.lexeme.clone().unwrap_or_default();
+            *distance = env
+                .distance(&name)
+                .ok_or_else(|| format!("'{}' is not defined in this scope", name))?;
+        }
+        Expr::AnonymousFunc(_) => return Err(String::from("Functions cannot be defined in an evaluated expression")),
+        Expr::Literal(_) => {}
+        Expr::Grouping(expr) | Expr::Unary(_, expr) => resolve(expr, env)?,
+        Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+            resolve(left, env)?;
+            resolve(right, env)?;
+        }
+        Expr::Call(callee, arguments, _) => {
+            resolve(callee, env)?;
+            for argument in arguments.iter_mut() {
+                resolve(argument, env)?;
+            }
+        }
+        Expr::Array(_, elements) => {
+            for element in elements.iter_mut() {
+                resolve(element, env)?;
+            }
+        }
+        Expr::ArrayAccess(array, index) => {
+            resolve(array, env)?;
+            resolve(index, env)?;
+        }
+    }
+    Ok(())
+}
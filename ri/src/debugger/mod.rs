@@ -0,0 +1,64 @@
+mod inspect;
+mod session;
+
+use std::io;
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+
+use self::session::Session;
+use crate::lsp::rpc;
+
+/*
+    Step debugger for SimpleScript, run with the 'ss-dap' binary, which speaks the Debug Adapter Protocol (DAP) over stdio
+    so that any DAP capable editor can debug SS programs with ri's interpreter.
+    DAP messages are framed just like the language server's, so they are read and written with the same functions.
+
+    The client launches a program with { "program": "path/to/file.ss", "stopOnEntry": false }, which is checked by ri's
+    front end before the launch succeeds, and the program is run once the client is done setting its breakpoints.
+    The interpreter calls the debugger's hooks (see 'interpreter::debugger::Debugger') while it runs the program:
+    - Before every stmt in 'Interpreter::interpret_stmt', where the debugger pauses at breakpoints and after steps.
+      Blocks are not paused at as they only group stmts, and a line with multiple stmts is only paused at once.
+      Loops also pause at their while line before every iteration after the first, so that single line loop bodies
+      are paused at on every iteration.
+    - When 'Function::call' calls a user defined function and when it returns, to track the call stack.
+      Stepping over a call runs it until it returns, stepping into it pauses at its first stmt, and stepping out of the
+      current call pauses at the next stmt of its caller.
+
+    While paused, every stack frame shows its scopes, from its innermost scope to the global scope, by walking the
+    'Environment' chain of the last stmt it ran, where arrays are expanded into their elements.
+    Expressions are evaluated in the scope of the selected frame, with identifiers resolved against its environment
+    instead of by the resolver, so they can use any value defined in the frame's scopes and call functions.
+    Since stdout is used to talk to the client, printed values are sent to the client as output events instead.
+
+    Messages are read on their own thread, so that requests like pause are received while the program is running.
+*/
+
+/// Run the debug adapter on stdin and stdout until the client disconnects
+pub fn run() -> ExitCode {
+    let (sender, messages) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reader = io::stdin().lock();
+
+        // Reading stops once the input is closed or cannot be read, or once the session ends
+        while let Some(message) = rpc::read_message(&mut reader).transpose() {
+            let is_error = message.is_err();
+            if sender.send(message).is_err() || is_error {
+                break;
+            }
+        }
+    });
+
+    let mut session = Session::new(io::stdout().lock(), messages);
+    match session.serve() {
+        Ok(true) => ExitCode::SUCCESS,
+
+        // The input is closed without the client disconnecting first
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("-------- Debug Adapter ERROR --------");
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,590 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use serde_json::{json, Value as Json};
+
+use super::inspect::{self, References};
+
+use crate::environment::environment::Environment;
+use crate::interpreter::debugger::Debugger;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::interpreter::Interpreter;
+use crate::lsp::rpc;
+use crate::parser::expr::Expr;
+use crate::parser::parser_struct::Parser;
+use crate::parser::stmt::Stmt;
+use crate::resolver::resolver::Resolver;
+use crate::scanner::scanner_struct::Scanner;
+use crate::type_checker::TypeChecker;
+
+/// Content of a message from the client, or the error reading it
+pub type Message = io::Result<Vec<u8>>;
+
+/// Id of the only thread, as SS programs are single threaded
+const THREAD_ID: u8 = 1;
+
+/// Connection to the client, which is shared with the output of the program
+struct Client<W: Write> {
+    writer: W,
+
+    /// Sequence number of the last message sent
+    seq: u64,
+}
+
+impl<W: Write> Client<W> {
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        rpc::write_message(&mut self.writer, &message)
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// Sends the values printed by the program to the client as output events, as stdout is used to talk to the client
+struct Output<W: Write> {
+    client: Rc<RefCell<Client<W>>>,
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let output = String::from_utf8_lossy(buf);
+        self.client
+            .borrow_mut()
+            .event("output", json!({ "category": "stdout", "output": output }))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The launched program, which is run once the client is done configuring the session
+struct Program {
+    path: PathBuf,
+    ast: Vec<Stmt>,
+
+    /// Lines of every stmt that the debugger can pause on, which breakpoints are moved to
+    lines: BTreeSet<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Waiting for the client to launch the program and set its breakpoints
+    Configuring,
+
+    /// Client is done configuring the launched program, so it can be run
+    Ready,
+    Running,
+    Paused,
+    Exited,
+}
+
+/// Where the program stops next, besides at breakpoints
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Continue,
+
+    /// Stop at the first stmt, when launched with 'stopOnEntry'
+    Entry,
+
+    /// Stop at the next stmt, as the client requested to pause
+    Pause,
+
+    /// Stop at the next stmt of any frame, stepping into function calls
+    In,
+
+    /// Stop at the next stmt of the frame with the depth, or of its callers if the frame returns
+    Over(usize),
+
+    /// Stop at the next stmt of the caller of the frame with the depth
+    Out(usize),
+}
+
+/// Stack frame of a function call, or of the program's top level stmts at the bottom of the call stack
+struct Frame {
+    name: String,
+    line: usize,
+
+    /// Environment of the last stmt of the frame that was run, which is None until its first stmt is run
+    env: Option<Rc<RefCell<Environment>>>,
+}
+
+pub struct Session<W: Write> {
+    client: Rc<RefCell<Client<W>>>,
+    messages: Receiver<Message>,
+    program: Option<Program>,
+    state: State,
+    step: Step,
+
+    /// Lines with a breakpoint, which are all moved to lines of stmts
+    breakpoints: BTreeSet<usize>,
+
+    /// Call stack, with the bottom frame first
+    frames: Vec<Frame>,
+
+    /// Depth and line of the stmt that the program was last resumed from.
+    /// Steps and breakpoints do not stop at another stmt of the same line and frame until a stmt elsewhere is run,
+    /// so that a line is only stopped at once even if it has multiple stmts, e.g. an if stmt and its branch.
+    resumed_from: Option<(usize, usize)>,
+
+    references: References,
+
+    /// Set once the client terminates the program, or disconnects which also ends the session
+    terminated: bool,
+    disconnected: bool,
+
+    /// Error talking to the client while the program is running, which terminates the program
+    error: Option<io::Error>,
+}
+
+/// Scan, parse, resolve and type check the program, just like before it is run by the interpreter
+fn check(source: String) -> Result<Vec<Stmt>, String> {
+    let tokens = Scanner::scan_tokens(source).map_err(|errors| format!("Syntax error: {}", errors[0]))?;
+    let mut ast = Parser::parse(&tokens).map_err(|errors| format!("Syntax error: {}", errors[0]))?;
+    Resolver::resolve(&mut ast).map_err(|error| format!("Resolver error: {}", error))?;
+    TypeChecker::check(&ast).map_err(|error| format!("Type error: {}", error))?;
+    Ok(ast)
+}
+
+/// Collect the lines of the stmts, including the stmts of every function and anonymous function
+fn stmt_lines(stmts: &[Stmt], lines: &mut BTreeSet<usize>) {
+    for stmt in stmts.iter() {
+        lines.extend(stmt.line());
+        match stmt {
+            Stmt::Print(expr, _) | Stmt::Expr(expr, _) | Stmt::Ignore(expr, _) | Stmt::Const(_, expr) => {
+                expr_lines(expr, lines)
+            }
+            Stmt::Return(expr, _) => expr_lines(expr, lines),
+            Stmt::Block(stmts, _) => stmt_lines(stmts, lines),
+            Stmt::Func(_, _, body) | Stmt::AnonymousFunc(_, body) => stmt_lines(std::slice::from_ref(body), lines),
+            Stmt::If(condition, true_branch, else_branch, _) => {
+                expr_lines(condition, lines);
+                stmt_lines(std::slice::from_ref(true_branch), lines);
+                if let Some(else_branch) = else_branch {
+                    stmt_lines(std::slice::from_ref(else_branch), lines);
+                }
+            }
+            Stmt::While(condition, body, _) => {
+                expr_lines(condition, lines);
+                stmt_lines(std::slice::from_ref(body), lines);
+            }
+        }
+    }
+}
+
+fn expr_lines(expr: &Expr, lines: &mut BTreeSet<usize>) {
+    match expr {
+        Expr::AnonymousFunc(stmt) => stmt_lines(std::slice::from_ref(stmt), lines),
+        Expr::Literal(_) | Expr::Const(..) => {}
+        Expr::Grouping(expr) | Expr::Unary(_, expr) => expr_lines(expr, lines),
+        Expr::Binary(left, _, right) | Expr::Logical(left, _, right) | Expr::ArrayAccess(left, right) => {
+            expr_lines(left, lines);
+            expr_lines(right, lines);
+        }
+        Expr::Call(callee, arguments, _) => {
+            expr_lines(callee, lines);
+            arguments.iter().for_each(|argument| expr_lines(argument, lines));
+        }
+        Expr::Array(_, elements) => elements.iter().for_each(|element| expr_lines(element, lines)),
+    }
+}
+
+impl<W: Write> Session<W> {
+    pub fn new(writer: W, messages: Receiver<Message>) -> Session<W> {
+        Session {
+            client: Rc::new(RefCell::new(Client { writer, seq: 0 })),
+            messages,
+            program: None,
+            state: State::Configuring,
+            step: Step::Continue,
+            breakpoints: BTreeSet::new(),
+            frames: Vec::new(),
+            resumed_from: None,
+            references: References::default(),
+            terminated: false,
+            disconnected: false,
+            error: None,
+        }
+    }
+
+    /// Handle messages and run the program once it is configured, until the client disconnects or the input is closed.
+    /// Returns true if the client disconnected properly.
+    pub fn serve(&mut self) -> io::Result<bool> {
+        while !self.disconnected {
+            let Ok(message) = self.messages.recv() else {
+                return Ok(false);
+            };
+            self.handle(message?, None)?;
+
+            if self.state == State::Ready {
+                self.run()?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Run the launched program until it completes, fails or is terminated, pausing in the debugger's hooks
+    fn run(&mut self) -> io::Result<()> {
+        let ast = match self.program {
+            Some(ref mut program) => std::mem::take(&mut program.ast),
+            None => Vec::new(),
+        };
+
+        self.state = State::Running;
+        self.frames = vec![Frame {
+            name: String::from("main"),
+            line: 0,
+            env: None,
+        }];
+
+        let output = Box::new(LineWriter::new(Output {
+            client: Rc::clone(&self.client),
+        }));
+        let result = Interpreter::debug(&ast, self, output);
+
+        self.state = State::Exited;
+        self.frames.clear();
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        let mut client = self.client.borrow_mut();
+        let exit_code = match result {
+            None | Some(RuntimeError::Terminated) => 0,
+            Some(error) => {
+                let output = format!("-------- Interpreter ERROR --------\n{}\n", error);
+                client.event("output", json!({ "category": "stderr", "output": output }))?;
+                1
+            }
+        };
+        client.event("exited", json!({ "exitCode": exit_code }))?;
+        client.event("terminated", json!({}))
+    }
+
+    /// Handle a request, where the interpreter is only given while the program is running
+    fn handle(&mut self, content: Vec<u8>, interpreter: Option<&mut Interpreter>) -> io::Result<()> {
+        let request: Json = match serde_json::from_slice(&content) {
+            Ok(request) => request,
+            Err(error) => {
+                let output = format!("Invalid message from the client: {}\n", error);
+                return self
+                    .client
+                    .borrow_mut()
+                    .event("output", json!({ "category": "console", "output": output }));
+            }
+        };
+
+        let command = request["command"].as_str().unwrap_or_default();
+        let arguments = &request["arguments"];
+        let result = match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsEvaluateForHovers": true,
+                "supportsTerminateRequest": true,
+            })),
+            "launch" => self.launch(arguments),
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "configurationDone" => self.configuration_done(),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => self.scopes(arguments),
+            "variables" => self.variables(arguments),
+            "evaluate" => self.evaluate(arguments, interpreter),
+            "continue" | "next" | "stepIn" | "stepOut" => self.resume(command),
+            "pause" => self.pause(),
+            "terminate" | "disconnect" => {
+                self.terminated = true;
+                self.disconnected = command == "disconnect";
+                Ok(json!({}))
+            }
+            _ => Err(format!("Unsupported request '{}'", command)),
+        };
+
+        let response = match result {
+            Ok(ref body) => json!({
+                "type": "response",
+                "request_seq": request["seq"],
+                "success": true,
+                "command": command,
+                "body": body,
+            }),
+            Err(ref message) => json!({
+                "type": "response",
+                "request_seq": request["seq"],
+                "success": false,
+                "command": command,
+                "message": message,
+            }),
+        };
+        let mut client = self.client.borrow_mut();
+        client.send(response)?;
+
+        // The client only starts configuring the session, e.g. setting breakpoints, once the program is launched
+        if command == "launch" && result.is_ok() {
+            client.event("initialized", json!({}))?;
+        }
+
+        // Programs that are not running are terminated right away, while running ones are terminated in the hooks
+        if command == "terminate" && matches!(self.state, State::Configuring | State::Ready) {
+            self.state = State::Exited;
+            client.event("terminated", json!({}))?;
+        }
+        Ok(())
+    }
+
+    fn launch(&mut self, arguments: &Json) -> Result<Json, String> {
+        if self.program.is_some() {
+            return Err(String::from("A program is already launched"));
+        }
+        let path = arguments["program"]
+            .as_str()
+            .ok_or("Launch argument 'program' must be the path of the program to debug")?;
+        let source = fs::read_to_string(path).map_err(|error| format!("'{}': {}", path, error))?;
+        let ast = check(source).map_err(|error| format!("'{}': {}", path, error))?;
+
+        let mut lines = BTreeSet::new();
+        stmt_lines(&ast, &mut lines);
+        self.program = Some(Program {
+            path: fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)),
+            ast,
+            lines,
+        });
+
+        if arguments["stopOnEntry"].as_bool() == Some(true) {
+            self.step = Step::Entry;
+        }
+        Ok(json!({}))
+    }
+
+    /// Replace every breakpoint, moving them to the first line with a stmt at or after the requested line
+    fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, String> {
+        let program = self.program.as_ref().ok_or("Breakpoints can only be set once the program is launched")?;
+        let is_program = arguments["source"]["path"]
+            .as_str()
+            .map(|path| fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)))
+            .is_some_and(|path| path == program.path);
+
+        let requested = arguments["breakpoints"].as_array().cloned().unwrap_or_default();
+        let mut breakpoints = Vec::new();
+        self.breakpoints.clear();
+
+        for breakpoint in requested.iter() {
+            let requested_line = breakpoint["line"].as_u64().unwrap_or_default() as usize;
+            let line = match is_program {
+                true => program.lines.range(requested_line..).next().copied(),
+                false => None,
+            };
+
+            breakpoints.push(match line {
+                Some(line) => {
+                    self.breakpoints.insert(line);
+                    json!({ "verified": true, "line": line })
+                }
+                None if !is_program => json!({
+                    "verified": false,
+                    "line": requested_line,
+                    "message": "Breakpoints can only be set in the launched program",
+                }),
+                None => json!({
+                    "verified": false,
+                    "line": requested_line,
+                    "message": "There is no statement on or after this line",
+                }),
+            });
+        }
+
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn configuration_done(&mut self) -> Result<Json, String> {
+        if self.program.is_none() {
+            return Err(String::from("The program must be launched before it can be run"));
+        }
+        if self.state == State::Configuring {
+            self.state = State::Ready;
+        }
+        Ok(json!({}))
+    }
+
+    fn stack_trace(&self) -> Result<Json, String> {
+        if self.state != State::Paused {
+            return Err(String::from("The call stack can only be inspected while the program is paused"));
+        }
+
+        let path = self.program.as_ref().map(|program| program.path.as_path()).unwrap_or(Path::new(""));
+        let source = json!({
+            "name": path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default(),
+            "path": path.to_string_lossy(),
+        });
+        let frames: Vec<Json> = self
+            .frames
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(id, frame)| {
+                json!({ "id": id, "name": frame.name, "line": frame.line, "column": 1, "source": source })
+            })
+            .collect();
+
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    /// Get the frame of the id, or the top frame if no id is given
+    fn frame(&self, id: &Json) -> Result<&Frame, String> {
+        if self.state != State::Paused {
+            return Err(String::from("The program can only be inspected while it is paused"));
+        }
+        let frame = match id.as_u64() {
+            Some(id) => self.frames.get(id as usize),
+            None => self.frames.last(),
+        };
+        frame.ok_or_else(|| format!("Unknown stack frame {}", id))
+    }
+
+    fn scopes(&mut self, arguments: &Json) -> Result<Json, String> {
+        let env = self.frame(&arguments["frameId"])?.env.clone();
+        Ok(json!({ "scopes": self.references.scopes(env) }))
+    }
+
+    fn variables(&mut self, arguments: &Json) -> Result<Json, String> {
+        let reference = arguments["variablesReference"].as_u64().unwrap_or_default() as usize;
+        let variables = self
+            .references
+            .variables(reference)
+            .ok_or_else(|| format!("Unknown variables reference {}", reference))?;
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// Evaluate an expression in the scope of the frame, which can call functions but cannot define them
+    fn evaluate(&mut self, arguments: &Json, interpreter: Option<&mut Interpreter>) -> Result<Json, String> {
+        let env = self
+            .frame(&arguments["frameId"])?
+            .env
+            .clone()
+            .ok_or("The stack frame has not run any statement yet")?;
+        let interpreter = interpreter.ok_or("Expressions can only be evaluated while the program is paused")?;
+
+        let expression = arguments["expression"].as_str().unwrap_or_default();
+        let expr = inspect::parse_expression(expression, &env.borrow())?;
+        let value = interpreter.evaluate(&expr, env).map_err(|error| error.to_string())?;
+        Ok(self.references.result(value))
+    }
+
+    fn resume(&mut self, command: &str) -> Result<Json, String> {
+        if self.state != State::Paused {
+            return Err(String::from("The program can only be resumed while it is paused"));
+        }
+
+        let depth = self.frames.len();
+        self.step = match command {
+            "next" => Step::Over(depth),
+            "stepIn" => Step::In,
+            "stepOut" => Step::Out(depth),
+            _ => Step::Continue,
+        };
+        self.state = State::Running;
+        Ok(json!({ "allThreadsContinued": true }))
+    }
+
+    fn pause(&mut self) -> Result<Json, String> {
+        if self.state != State::Running {
+            return Err(String::from("Only a running program can be paused"));
+        }
+        self.step = Step::Pause;
+        Ok(json!({}))
+    }
+
+    /// Get the reason to stop at the stmt of the line, if the program should stop at it
+    fn stop_reason(&self, line: usize) -> Option<&'static str> {
+        let depth = self.frames.len();
+        match self.step {
+            Step::Entry => return Some("entry"),
+            Step::Pause => return Some("pause"),
+            _ if self.resumed_from.is_some() => return None,
+            Step::In => return Some("step"),
+            Step::Over(frame) if depth <= frame => return Some("step"),
+            Step::Out(frame) if depth < frame => return Some("step"),
+            _ => {}
+        }
+        self.breakpoints.contains(&line).then_some("breakpoint")
+    }
+
+    /// Handle the requests received while the program is running, and pause at the stmt if it should stop at it.
+    /// Returns false if the program is terminated.
+    fn before_stmt_or_err(&mut self, interpreter: &mut Interpreter, line: usize) -> io::Result<bool> {
+        let frame = self.frames.last_mut().expect("Internal Error: Missing bottom stack frame");
+        frame.line = line;
+        frame.env = Some(interpreter.env());
+
+        let position = (self.frames.len(), line);
+        if self.resumed_from != Some(position) {
+            self.resumed_from = None;
+        }
+
+        loop {
+            match self.messages.try_recv() {
+                Ok(message) => self.handle(message?, Some(interpreter))?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(false),
+            }
+        }
+        if self.terminated {
+            return Ok(false);
+        }
+
+        let Some(reason) = self.stop_reason(line) else {
+            return Ok(true);
+        };
+        self.state = State::Paused;
+        self.client.borrow_mut().event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        )?;
+
+        while self.state == State::Paused && !self.terminated {
+            let Ok(message) = self.messages.recv() else {
+                return Ok(false);
+            };
+            self.handle(message?, Some(interpreter))?;
+        }
+
+        self.references.clear();
+        self.resumed_from = Some(position);
+        Ok(!self.terminated)
+    }
+}
+
+impl<W: Write> Debugger for Session<W> {
+    fn before_stmt(&mut self, interpreter: &mut Interpreter, line: usize) -> bool {
+        match self.before_stmt_or_err(interpreter, line) {
+            Ok(resume) => resume,
+            Err(error) => {
+                self.error = Some(error);
+                false
+            }
+        }
+    }
+
+    fn enter_function(&mut self, name: &str) {
+        let line = self.frames.last().map(|frame| frame.line).unwrap_or_default();
+        self.frames.push(Frame {
+            name: name.to_string(),
+            line,
+            env: None,
+        });
+    }
+
+    fn exit_function(&mut self) {
+        self.frames.pop();
+    }
+}
@@ -155,6 +155,25 @@ impl Environment {
         }
     }
 
+    // Get the values defined in this scope only, used by the debugger to show the variables of every scope
+    pub fn values(&self) -> &HashMap<String, Value> {
+        &self.values
+    }
+
+    pub fn enclosing(&self) -> Option<Rc<RefCell<Environment>>> {
+        self.enclosing.as_ref().map(Rc::clone)
+    }
+
+    // Get the scope distance to the nearest scope that defines the identifier, for identifiers that are not resolved
+    // by the resolver, like the ones in expressions evaluated by the debugger
+    pub fn distance(&self, key: &String) -> Option<usize> {
+        if self.values.contains_key(key) {
+            return Some(0);
+        }
+        let distance = self.enclosing.as_ref()?.borrow().distance(key)?;
+        Some(distance + 1)
+    }
+
     /* ==========================  End of getter methods  ========================== */
 
     // Clear map and free as much memory as possible
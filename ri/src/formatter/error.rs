@@ -0,0 +1,19 @@
+/// Errors from formatting a SS program
+#[derive(Debug)]
+pub enum FormatError {
+    /// Program cannot be formatted as it does not scan or parse, with the error of the pass that rejected it
+    Syntax(String),
+
+    /// Formatted program does not have the same tokens and comments as the original program, which is a bug in the formatter.
+    /// The formatted program is never written when this happens, so that programs are never changed by formatting them.
+    Changed(String),
+}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FormatError::Syntax(error) => write!(f, "Unable to format program with syntax error: {}", error),
+            FormatError::Changed(error) => write!(f, "Formatting changed the program: {}", error),
+        }
+    }
+}
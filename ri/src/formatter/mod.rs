@@ -0,0 +1,94 @@
+mod error;
+mod printer;
+
+use crate::parser::parser_struct::Parser;
+use crate::parser::stmt::Stmt;
+use crate::scanner::comment::Comment;
+use crate::scanner::scanner_struct::Scanner;
+use crate::token::Token;
+
+pub use self::error::FormatError;
+use self::printer::Printer;
+
+/*
+    Canonical formatter for SimpleScript, run with 'simple_script fmt', which prints every program in the same layout
+    regardless of how it was written, following the preferences in the spec.
+
+    Formatting is done on a lossless syntax tree, which is the AST together with the tokens and comments it is parsed from.
+    The scanner normally discards comments, so they are kept with 'Scanner::scan_tokens_and_comments' instead.
+    The AST is printed in its canonical layout, and since every AST node is printed with the same tokens that it is
+    parsed from, the printer walks the tokens alongside the AST to place every comment back before the token it came before:
+    - Comments on their own lines are printed on their own lines, indented like the statement after them.
+    - Comments after the last token of a statement's line stay at the end of that line.
+    - Comments within an expression stay within the expression.
+    - Comments that would separate a statement from its opening brace, or 'else' from the closing brace before it,
+      are moved into the block on their own lines, so that the braces stay on their canonical lines.
+
+    Canonical layout:
+    - Tabs are used for indentation, with every statement on its own line.
+    - Blank lines between statements are kept, but multiple blank lines are collapsed into one.
+    - Binary and logical operators are surrounded by spaces, and commas are followed by a space.
+    - Opening braces are on the same line as their statement, and 'else' is on the same line as the closing brace before it.
+    - Branches and loop bodies that are not blocks are indented on the next line.
+
+    Formatting is idempotent, as the printed layout only depends on the syntax tree, the order of the tokens and comments,
+    and whether there are blank lines, none of which are changed by formatting.
+    As a safeguard, the formatted program is parsed again and must have the same tokens and comments as the original program.
+*/
+
+/// AST of a program together with the tokens and comments that it is parsed from, which is enough to reproduce the program
+pub struct SyntaxTree {
+    pub ast: Vec<Stmt>,
+    pub tokens: Vec<Token>,
+    pub comments: Vec<Comment>,
+}
+
+impl SyntaxTree {
+    pub fn parse(source: &str) -> Result<SyntaxTree, FormatError> {
+        let (tokens, comments) = Scanner::scan_tokens_and_comments(source.to_string())
+            .map_err(|errors| FormatError::Syntax(errors[0].to_string()))?;
+        let ast = Parser::parse(&tokens).map_err(|errors| FormatError::Syntax(errors[0].to_string()))?;
+        Ok(SyntaxTree { ast, tokens, comments })
+    }
+
+    /// Get the line and column of the first token of every expression, as most expressions do not store their tokens.
+    /// Positions are in the order that expressions are visited by a pre-order walk of the AST, where the expressions
+    /// of every node are visited in the order they are written, including the null value of return stmts without a value.
+    pub fn expression_starts(&self) -> Vec<(usize, usize)> {
+        Printer::expression_starts(self)
+    }
+
+    /// Check that the other tree has the same tokens and comments in the same order, ignoring where they are
+    fn check_same(&self, other: &SyntaxTree) -> Result<(), FormatError> {
+        let differs = |token: &Token, other: &Token| {
+            token.token_type != other.token_type || token.literal != other.literal || token.lexeme != other.lexeme
+        };
+        if let Some((token, _)) = self
+            .tokens
+            .iter()
+            .zip(other.tokens.iter())
+            .find(|(token, other)| differs(token, other))
+        {
+            return Err(FormatError::Changed(format!("[line {}] {} is changed", token.line, token)));
+        }
+        if self.tokens.len() != other.tokens.len() {
+            return Err(FormatError::Changed(String::from("Tokens are added or removed")));
+        }
+
+        let texts = |tree: &SyntaxTree| tree.comments.iter().map(|comment| comment.text.clone()).collect::<Vec<String>>();
+        if texts(self) != texts(other) {
+            return Err(FormatError::Changed(String::from("Comments are changed")));
+        }
+        Ok(())
+    }
+}
+
+/// Format the source of a program into its canonical layout
+pub fn format(source: &str) -> Result<String, FormatError> {
+    let tree = SyntaxTree::parse(source)?;
+    let formatted = Printer::print(&tree);
+
+    let formatted_tree = SyntaxTree::parse(&formatted).map_err(|error| FormatError::Changed(error.to_string()))?;
+    tree.check_same(&formatted_tree)?;
+    Ok(formatted)
+}
@@ -0,0 +1,464 @@
+use crate::literal::Literal;
+use crate::parser::expr::Expr;
+use crate::parser::stmt::Stmt;
+use crate::scanner::comment::Comment;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+use super::SyntaxTree;
+
+/// Printer for the canonical layout of a syntax tree, which walks the tokens alongside the AST to place the comments
+pub struct Printer<'a> {
+    tokens: &'a [Token],
+    comments: &'a [Comment],
+
+    /// Index of the next token to be printed
+    current_token: usize,
+
+    /// Index of the next comment to be printed
+    current_comment: usize,
+
+    output: String,
+    indentation: usize,
+
+    /// Line of the last token or comment printed, used to keep blank lines and comments at the end of lines
+    last_line: usize,
+
+    /// Blank lines are not kept at the start of the program and of blocks
+    at_block_start: bool,
+
+    /// Line and column of the first token of every expression printed, in the order they are printed
+    expression_starts: Vec<(usize, usize)>,
+}
+
+/// Line and column of the first character of the token
+fn start_of(token: &Token) -> (usize, usize) {
+    // String tokens are saved with the line that they end on, as strings can span multiple lines
+    match token.literal {
+        Some(Literal::String(ref string)) => (token.line - string.matches('\n').count(), token.column),
+        _ => (token.line, token.column),
+    }
+}
+
+fn operator(token_type: &TokenType) -> &'static str {
+    match token_type {
+        TokenType::Plus => "+",
+        TokenType::Minus => "-",
+        TokenType::Star => "*",
+        TokenType::Slash => "/",
+        TokenType::Bang => "!",
+        TokenType::BangEqual => "!=",
+        TokenType::EqualEqual => "==",
+        TokenType::Greater => ">",
+        TokenType::GreaterEqual => ">=",
+        TokenType::Less => "<",
+        TokenType::LessEqual => "<=",
+        TokenType::And => "and",
+        TokenType::Or => "or",
+        _ => panic!("Internal Error: {:?} is not an operator", token_type),
+    }
+}
+
+impl<'a> Printer<'a> {
+    pub fn print(tree: &'a SyntaxTree) -> String {
+        Printer::run(tree).output
+    }
+
+    /// Get the line and column of the first token of every expression, in the order that they are visited by a
+    /// pre-order walk of the AST, where the expressions of a node are visited in the order they are written
+    pub fn expression_starts(tree: &'a SyntaxTree) -> Vec<(usize, usize)> {
+        Printer::run(tree).expression_starts
+    }
+
+    fn run(tree: &'a SyntaxTree) -> Printer<'a> {
+        let mut printer = Printer {
+            tokens: &tree.tokens,
+            comments: &tree.comments,
+            current_token: 0,
+            current_comment: 0,
+            output: String::new(),
+            indentation: 0,
+            last_line: 0,
+            at_block_start: true,
+            expression_starts: Vec::new(),
+        };
+
+        for stmt in tree.ast.iter() {
+            printer.statement(stmt);
+        }
+
+        // Comments after the last statement
+        printer.own_line_comments();
+        printer
+    }
+
+    /// Get the next comment if it comes before the next token
+    fn next_comment(&self) -> Option<&'a Comment> {
+        self.next_comment_before(self.current_token)
+    }
+
+    /// Get the next comment if it comes before the token at the index
+    fn next_comment_before(&self, token_index: usize) -> Option<&'a Comment> {
+        let comment = self.comments.get(self.current_comment)?;
+        match self.tokens.get(token_index) {
+            Some(token) if start_of(token) < (comment.line, comment.column) => None,
+            _ => Some(comment),
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.indentation {
+            self.output.push('\t');
+        }
+    }
+
+    /// Keep a single blank line before the statement or comment starting on the line, if there are any blank lines before it
+    fn separate(&mut self, line: usize) {
+        if !self.at_block_start && line > self.last_line + 1 {
+            self.write("\n");
+        }
+        self.at_block_start = false;
+    }
+
+    /// Print the comments before the next token on their own lines
+    fn own_line_comments(&mut self) {
+        self.own_line_comments_before(self.current_token);
+    }
+
+    /// Print the comments before the token at the index on their own lines
+    fn own_line_comments_before(&mut self, token_index: usize) {
+        while let Some(comment) = self.next_comment_before(token_index) {
+            self.separate(comment.line);
+            self.indent();
+            self.write(&comment.text);
+            self.write("\n");
+            self.last_line = comment.end_line();
+            self.current_comment += 1;
+        }
+    }
+
+    /// Print the comments that are on the same line as the last token printed, at the end of the line
+    fn end_of_line_comments(&mut self) {
+        while let Some(comment) = self.next_comment().filter(|comment| comment.line == self.last_line) {
+            self.write(" ");
+            self.write(&comment.text);
+            self.last_line = comment.end_line();
+            self.current_comment += 1;
+        }
+    }
+
+    /// Print the next token with its canonical text, after the comments before it within the statement being printed
+    fn token(&mut self, token_type: TokenType, text: &str) {
+        let token = &self.tokens[self.current_token];
+
+        // Every AST node is printed with the tokens it is parsed from, so this only happens if the printer has a bug,
+        // in which case the formatted program is rejected by the check for changed tokens instead
+        if token.token_type != token_type {
+            self.write(text);
+            return;
+        }
+
+        while let Some(comment) = self.next_comment() {
+            if !self.output.ends_with(char::is_whitespace) && !self.output.ends_with(['(', '[']) {
+                self.write(" ");
+            }
+            self.write(&comment.text);
+            self.last_line = comment.end_line();
+            self.current_comment += 1;
+
+            if !comment.is_block {
+                // Line comments end their line, so the rest of the statement continues on the next line
+                self.write("\n");
+                self.indentation += 1;
+                self.indent();
+                self.indentation -= 1;
+            } else if !matches!(
+                token_type,
+                TokenType::Semicolon | TokenType::Comma | TokenType::RightParen | TokenType::RightBracket
+            ) {
+                self.write(" ");
+            }
+        }
+
+        self.write(text);
+        self.last_line = token.line;
+        self.at_block_start = false;
+        self.current_token += 1;
+    }
+
+    /// Print a statement on its own lines, with the comments before it and at the end of its last line
+    fn statement(&mut self, stmt: &Stmt) {
+        self.own_line_comments();
+        let line = start_of(&self.tokens[self.current_token]).0;
+        self.separate(line);
+
+        self.indent();
+        self.stmt(stmt);
+        self.end_of_line_comments();
+        self.write("\n");
+    }
+
+    /// Print the body of a branch or loop, where bodies that are not blocks are indented on the next line
+    fn body(&mut self, stmt: &Stmt) {
+        if let Stmt::Block(_, Some(_)) = stmt {
+            self.write(" ");
+            self.stmt(stmt);
+            return;
+        }
+
+        self.end_of_line_comments();
+        self.write("\n");
+        self.indentation += 1;
+        self.at_block_start = true;
+        self.own_line_comments();
+        self.indent();
+        self.stmt(stmt);
+        self.end_of_line_comments();
+        self.indentation -= 1;
+    }
+
+    /// Print a block, where a block followed by 'else' also contains the comments between its closing brace and 'else'
+    fn block(&mut self, statements: &[Stmt], followed_by_else: bool) {
+        // A line comment before the opening brace, like in 'if (a) // comment', would push the brace onto the next
+        // line, so the comments before the brace are printed on their own lines at the start of the block instead
+        let is_line_comment_before_brace = self.comments[self.current_comment..]
+            .iter()
+            .take_while(|comment| (comment.line, comment.column) < start_of(&self.tokens[self.current_token]))
+            .any(|comment| !comment.is_block);
+        if is_line_comment_before_brace {
+            self.write("{");
+            self.last_line = self.tokens[self.current_token].line;
+            self.current_token += 1;
+        } else {
+            self.token(TokenType::LeftBrace, "{");
+        }
+
+        // 'else' is on the same line as the closing brace, so the comments before it are printed before the brace
+        let end = |printer: &Self| match followed_by_else {
+            true => printer.current_token + 1,
+            false => printer.current_token,
+        };
+        if statements.is_empty() && self.next_comment_before(end(self)).is_none() {
+            self.token(TokenType::RightBrace, "}");
+            return;
+        }
+
+        self.end_of_line_comments();
+        self.write("\n");
+        self.indentation += 1;
+        self.at_block_start = true;
+        if is_line_comment_before_brace {
+            // Blank lines after the moved comments are kept relative to the brace
+            let brace_line = self.last_line;
+            self.own_line_comments_before(self.current_token - 1);
+            self.last_line = brace_line;
+        }
+        for stmt in statements.iter() {
+            self.statement(stmt);
+        }
+
+        // Comments after the last statement of the block
+        self.own_line_comments();
+        if followed_by_else {
+            self.last_line = self.tokens[self.current_token].line;
+            self.own_line_comments_before(end(self));
+        }
+        self.indentation -= 1;
+        self.indent();
+        self.token(TokenType::RightBrace, "}");
+    }
+
+    fn parameters(&mut self, parameters: &[Token]) {
+        self.token(TokenType::LeftParen, "(");
+        for (index, parameter) in parameters.iter().enumerate() {
+            if index > 0 {
+                self.token(TokenType::Comma, ",");
+                self.write(" ");
+            }
+            self.token(TokenType::Identifier, parameter.lexeme.as_ref().unwrap());
+        }
+        self.token(TokenType::RightParen, ")");
+    }
+
+    /// Print a statement from its first token to its last token
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Print(ref expr, _) => {
+                self.token(TokenType::Print, "print");
+                self.write(" ");
+                self.expr(expr);
+                self.token(TokenType::Semicolon, ";");
+            }
+            Stmt::Expr(ref expr, _) => {
+                self.expr(expr);
+                self.token(TokenType::Semicolon, ";");
+            }
+            Stmt::Const(ref name, ref expr) => {
+                self.token(TokenType::Const, "const");
+                self.write(" ");
+                self.token(TokenType::Identifier, name.lexeme.as_ref().unwrap());
+                self.write(" ");
+                self.token(TokenType::Equal, "=");
+                self.write(" ");
+                self.expr(expr);
+                self.token(TokenType::Semicolon, ";");
+            }
+            Stmt::Block(ref statements, _) => self.block(statements, false),
+            Stmt::If(ref condition, ref true_branch, ref else_branch, _) => {
+                self.token(TokenType::If, "if");
+                self.write(" ");
+                self.token(TokenType::LeftParen, "(");
+                self.expr(condition);
+                self.token(TokenType::RightParen, ")");
+                match (&**true_branch, else_branch) {
+                    (Stmt::Block(ref statements, Some(_)), Some(_)) => {
+                        self.write(" ");
+                        self.block(statements, true);
+                    }
+                    _ => self.body(true_branch),
+                }
+
+                if let Some(ref else_branch) = else_branch {
+                    match **true_branch {
+                        Stmt::Block(_, Some(_)) => self.write(" "),
+                        _ => {
+                            // Comments before 'else' are on their own lines between the branches
+                            self.write("\n");
+                            self.own_line_comments();
+                            self.indent();
+                        }
+                    }
+                    self.token(TokenType::Else, "else");
+
+                    // Else if branches are printed as a chain instead of being nested
+                    match **else_branch {
+                        Stmt::If(..) => {
+                            self.write(" ");
+                            self.stmt(else_branch);
+                        }
+                        _ => self.body(else_branch),
+                    }
+                }
+            }
+            Stmt::While(ref condition, ref body, _) => {
+                self.token(TokenType::While, "while");
+                self.write(" ");
+                self.token(TokenType::LeftParen, "(");
+                self.expr(condition);
+                self.token(TokenType::RightParen, ")");
+                self.body(body);
+            }
+            Stmt::Func(ref name, ref parameters, ref body) => {
+                self.token(TokenType::Function, "fn");
+                self.write(" ");
+                self.token(TokenType::Identifier, name.lexeme.as_ref().unwrap());
+                self.parameters(parameters);
+                self.write(" ");
+                self.stmt(body);
+            }
+            Stmt::AnonymousFunc(ref parameters, ref body) => match **body {
+                // Arrow functions are parsed into a block without a closing brace, with a single return stmt
+                Stmt::Block(ref statements, None) => {
+                    self.parameters(parameters);
+                    self.write(" ");
+                    self.token(TokenType::Arrow, "=>");
+                    self.write(" ");
+                    if let Some(Stmt::Return(ref expr, _)) = statements.first() {
+                        self.expr(expr);
+                    }
+                }
+                _ => {
+                    self.token(TokenType::Function, "fn");
+                    self.write(" ");
+                    self.parameters(parameters);
+                    self.write(" ");
+                    self.stmt(body);
+                }
+            },
+            Stmt::Return(ref expr, _) => {
+                self.token(TokenType::Return, "return");
+
+                // Return stmts without a value are parsed with a null value, which is only printed if it is written
+                let is_written = self.tokens[self.current_token].token_type != TokenType::Semicolon;
+                if is_written {
+                    self.write(" ");
+                    self.expr(expr);
+                } else {
+                    // The null value is still an expression of the AST, which starts where it would have been written
+                    self.expression_starts.push(start_of(&self.tokens[self.current_token]));
+                }
+                self.token(TokenType::Semicolon, ";");
+            }
+            Stmt::Ignore(ref expr, _) => {
+                self.token(TokenType::Ignore, "ignore");
+                self.write(" ");
+                self.expr(expr);
+                self.token(TokenType::Semicolon, ";");
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        self.expression_starts.push(start_of(&self.tokens[self.current_token]));
+        match expr {
+            Expr::Literal(ref literal) => match literal {
+                Literal::Number(ref number) => self.token(TokenType::Number, &number.to_string()),
+                Literal::String(ref string) => self.token(TokenType::Str, &format!("\"{}\"", string)),
+                Literal::Bool(true) => self.token(TokenType::True, "true"),
+                Literal::Bool(false) => self.token(TokenType::False, "false"),
+                Literal::Null => self.token(TokenType::Null, "null"),
+            },
+            Expr::Binary(ref left, ref operator_token, ref right)
+            | Expr::Logical(ref left, ref operator_token, ref right) => {
+                self.expr(left);
+                self.write(" ");
+                self.token(operator_token.token_type.clone(), operator(&operator_token.token_type));
+                self.write(" ");
+                self.expr(right);
+            }
+            Expr::Grouping(ref expr) => {
+                self.token(TokenType::LeftParen, "(");
+                self.expr(expr);
+                self.token(TokenType::RightParen, ")");
+            }
+            Expr::Unary(ref operator_token, ref expr) => {
+                self.token(operator_token.token_type.clone(), operator(&operator_token.token_type));
+                self.expr(expr);
+            }
+            Expr::Const(ref token, _) => self.token(TokenType::Identifier, token.lexeme.as_ref().unwrap()),
+            Expr::AnonymousFunc(ref stmt) => self.stmt(stmt),
+            Expr::Array(_, ref elements) => {
+                self.token(TokenType::LeftBracket, "[");
+                self.list(elements);
+                self.token(TokenType::RightBracket, "]");
+            }
+            Expr::ArrayAccess(ref array, ref index) => {
+                self.expr(array);
+                self.token(TokenType::LeftBracket, "[");
+                self.expr(index);
+                self.token(TokenType::RightBracket, "]");
+            }
+            Expr::Call(ref callee, ref arguments, _) => {
+                self.expr(callee);
+                self.token(TokenType::LeftParen, "(");
+                self.list(arguments);
+                self.token(TokenType::RightParen, ")");
+            }
+        }
+    }
+
+    /// Print comma separated expressions, like arguments and array elements
+    fn list(&mut self, exprs: &[Expr]) {
+        for (index, expr) in exprs.iter().enumerate() {
+            if index > 0 {
+                self.token(TokenType::Comma, ",");
+                self.write(" ");
+            }
+            self.expr(expr);
+        }
+    }
+}
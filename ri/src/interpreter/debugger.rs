@@ -0,0 +1,16 @@
+use super::interpreter::Interpreter;
+
+// Hooks that the interpreter calls while interpreting a program with a debugger attached, see the debugger module.
+// The interpreter is passed back into the hooks so that the debugger can read its environment and evaluate expressions
+// while it is paused, during which the debugger is detached, so evaluated function calls do not call the hooks again.
+pub trait Debugger {
+    // Called before every stmt that stores a line is interpreted, i.e. every stmt except blocks,
+    // and with the line of a while stmt before its condition is evaluated again for the next iteration.
+    // The debugger may block until it is resumed, and returns false to terminate the program.
+    fn before_stmt(&mut self, interpreter: &mut Interpreter, line: usize) -> bool;
+
+    // Called when a user defined function is called and when it returns, so the debugger can track the call stack.
+    // Functions that fail do not return, as the runtime error ends the program.
+    fn enter_function(&mut self, name: &str);
+    fn exit_function(&mut self);
+}
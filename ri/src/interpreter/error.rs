@@ -38,6 +38,9 @@ pub enum RuntimeError {
     // usize holds the line number of the call site
     // String is the string representation of Value object that the user tried to call
     CallOnNonCallable(usize, String),
+
+    // The attached debugger terminated the program before it completed
+    Terminated,
     // DivideByZeroError(Token),
     // WrongArity(Token, usize, usize),
     // UndefinedProperty(Token),
@@ -69,6 +72,8 @@ impl std::fmt::Display for RuntimeError {
                 write!(f, "[line {}] Attempted to call non-callable: {}", line_number, value)
             }
 
+            RuntimeError::Terminated => write!(f, "Program was terminated by the debugger"),
+
             // If unimplemented yet print with debug symbol to prevent infinite recursive loop to calling the display trait
             runtime_error_variant => write!(f, "Internal error with unimplemented formatting:\n{:?}", runtime_error_variant)
             // RuntimeError::UndefinedVariable(ref token) => write!(
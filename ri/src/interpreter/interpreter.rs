@@ -1,5 +1,7 @@
+use super::debugger::Debugger;
 use super::error::RuntimeError;
 use std::cell::RefCell;
+use std::io::{self, Write};
 use std::rc::Rc;
 
 use crate::environment::environment::Environment;
@@ -10,9 +12,15 @@ use crate::token_type::TokenType;
 use crate::value::function::Function;
 use crate::value::value::Value;
 
-pub struct Interpreter {
+pub struct Interpreter<'a> {
     // Env tracks the current environment, changing as the interpreter enter and exit local scopes
     env: Rc<RefCell<Environment>>,
+
+    // Debugger attached when debugging a program, which is detached while it is running one of its hooks
+    debugger: Option<&'a mut dyn Debugger>,
+
+    // Printed values are written to stdout, unless debugging where stdout might be used to talk to the debugger's client
+    output: Box<dyn Write + 'a>,
 }
 
 // Macro to perform a binary arithmetic operation (+, -, *, /) on 2 operands
@@ -43,19 +51,31 @@ macro_rules! numeric_comparison_op {
     };
 }
 
-impl Interpreter {
-    // pub fn interpret( stmts: Vec<Stmt>, writer: Rc<RefCell<mut io::Write>>) -> Option<RuntimeError> {
-    pub fn interpret(stmts: Vec<Stmt>) -> Option<RuntimeError> {
-        let mut interpreter = Interpreter {
+impl<'a> Interpreter<'a> {
+    fn new(debugger: Option<&'a mut dyn Debugger>, output: Box<dyn Write + 'a>) -> Interpreter<'a> {
+        Interpreter {
             // Why did rlox clone the globals here?
             // The starting environment will always be the global scope
             env: Rc::new(RefCell::new(Environment::global())),
-        };
+            debugger,
+            output,
+        }
+    }
+
+    pub fn interpret(stmts: Vec<Stmt>) -> Option<RuntimeError> {
+        Interpreter::new(None, Box::new(io::stdout())).interpret_stmts(&stmts)
+    }
+
+    // Interpret the program with the debugger attached, writing printed values to the given output instead of stdout
+    pub fn debug(stmts: &[Stmt], debugger: &'a mut dyn Debugger, output: Box<dyn Write + 'a>) -> Option<RuntimeError> {
+        Interpreter::new(Some(debugger), output).interpret_stmts(stmts)
+    }
 
+    fn interpret_stmts(&mut self, stmts: &[Stmt]) -> Option<RuntimeError> {
         // Loop through all Expr/Stmt to evaluate and run them, returning any errors
         for stmt in stmts.iter() {
             // Interpreter to stop if there is any runtime error
-            if let Err(err) = interpreter.interpret_stmt(stmt) {
+            if let Err(err) = self.interpret_stmt(stmt) {
                 return Some(err);
             }
 
@@ -161,6 +181,11 @@ impl Interpreter {
     //
     // Returns a Value Option because not every statement evaluates to a Value
     fn interpret_stmt(&mut self, stmt: &Stmt) -> Result<Option<Value>, RuntimeError> {
+        // Let the debugger pause before the stmt, skipping blocks as they only group stmts which the debugger pauses on
+        if let Some(line) = stmt.line() {
+            self.before_stmt(line)?;
+        }
+
         // @todo Change match to use match *stmt instead of stmt
         // @todo Or change to Rc wraps instead of cloning, to minimize memory used and data duplication
         //
@@ -172,7 +197,7 @@ impl Interpreter {
             // It is only useful, if the expression contains a function call or something, that contains side effects,
             // But for other expressions, they can technically be skipped...
             // @todo Perhaps simplify this by wrapping the whole interpret_expr in a Option<Value> too to dont have to unwrap and rewrap here
-            Stmt::Expr(ref expr) => Some(self.interpret_expr(expr)?),
+            Stmt::Expr(ref expr, _) => Some(self.interpret_expr(expr)?),
 
             // Block statement, groups statements together in the same scope for execution
             Stmt::Block(ref statements, _) => {
@@ -255,13 +280,16 @@ impl Interpreter {
                 // })?
             }
 
-            Stmt::While(ref expr, ref loop_body, _) => {
+            Stmt::While(ref expr, ref loop_body, ref line) => {
                 while self
                     .interpret_expr(expr)?
                     .bool_or_err("Expected Boolean from While loop expression")?
                 {
                     // Execute stmt 1 by 1 and unwrap them with ? to allow any errors to stop execution and bubble up
                     self.interpret_stmt(loop_body)?;
+
+                    // Let the debugger pause on the while line again before the condition of the next iteration
+                    self.before_stmt(*line)?;
                 }
                 None
             }
@@ -312,17 +340,17 @@ impl Interpreter {
             }
 
             // Interpret the expression of an Ignore statement, bubbles up errors if any and does not evaluates a value
-            Stmt::Ignore(ref expr) => {
+            Stmt::Ignore(ref expr, _) => {
                 self.interpret_expr(expr)?;
                 None
             }
 
-            Stmt::Print(ref expr) => {
+            Stmt::Print(ref expr, _) => {
                 // Interpret expression and unwrap result to print
                 // @todo Use seperate print from interpreter's print method, to make them run independently
-                // @todo Dont just rely on println macro
                 // @todo Right now only works for literal values if not using debug printing
-                println!("{}", self.interpret_expr(expr)?);
+                let value = self.interpret_expr(expr)?;
+                writeln!(self.output, "{}", value).map_err(|error| RuntimeError::InternalError(error.to_string()))?;
 
                 None
             }
@@ -336,6 +364,45 @@ impl Interpreter {
         })
     }
 
+    // Call the debugger's hook, if any, before the stmt on the line is interpreted, erroring if it terminates the program
+    fn before_stmt(&mut self, line: usize) -> Result<(), RuntimeError> {
+        if let Some(debugger) = self.debugger.take() {
+            let resume = debugger.before_stmt(self, line);
+            self.debugger = Some(debugger);
+            if !resume {
+                return Err(RuntimeError::Terminated);
+            }
+        }
+        Ok(())
+    }
+
+    // Notify the debugger that a user defined function is called or has returned, for it to track the call stack
+    pub fn enter_function(&mut self, name: &str) {
+        if let Some(ref mut debugger) = self.debugger {
+            debugger.enter_function(name);
+        }
+    }
+
+    pub fn exit_function(&mut self) {
+        if let Some(ref mut debugger) = self.debugger {
+            debugger.exit_function();
+        }
+    }
+
+    // Get the current environment, used by the debugger to inspect the scopes of the stmt it paused on
+    pub fn env(&self) -> Rc<RefCell<Environment>> {
+        Rc::clone(&self.env)
+    }
+
+    // Evaluate an expression in the given environment instead of the current one, used by the debugger to evaluate
+    // expressions in the scope of the stack frame it paused in, where identifiers are resolved against that environment
+    pub fn evaluate(&mut self, expr: &Expr, env: Rc<RefCell<Environment>>) -> Result<Value, RuntimeError> {
+        let current_env = std::mem::replace(&mut self.env, env);
+        let result = self.interpret_expr(expr);
+        self.env = current_env;
+        result
+    }
+
     // Since interpreter might be passed into call method of callable trait, and it calls 'interpret_block',
     // which can modify the interpreter struct, a mutable ref to self (interpreter struct) is needed.
     fn interpret_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
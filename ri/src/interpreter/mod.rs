@@ -1,2 +1,3 @@
+pub mod debugger;
 pub mod error;
 pub mod interpreter;
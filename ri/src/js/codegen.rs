@@ -0,0 +1,469 @@
+use super::source_map::{json_string, SourceMap};
+use super::JsError;
+
+use crate::literal::Literal;
+use crate::parser::expr::Expr;
+use crate::parser::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// Runtime helpers inserted at the top of every generated file
+const RUNTIME: &str = include_str!("runtime.js");
+
+/// Identifiers that are valid in SS but reserved in JavaScript, which are suffixed with '$' since SS identifiers cannot use '$'
+const RESERVED_WORDS: &[&str] = &[
+    "arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do",
+    "else", "enum", "eval", "export", "extends", "false", "finally", "for", "function", "if", "implements", "import", "in",
+    "instanceof", "interface", "let", "new", "package", "private", "protected", "public", "static", "super", "switch",
+    "this", "throw", "true", "try", "typeof", "undefined", "var", "void", "with", "yield",
+];
+
+/// Generates JavaScript source code from the AST, while recording the source map of the generated code
+pub struct JsGenerator {
+    output: String,
+    source_map: SourceMap,
+
+    /// Current position in the generated code, 0 based
+    line: usize,
+    column: usize,
+
+    /// Indentation level of the current line
+    indentation: usize,
+
+    /// Number of while loops the current statement is in, within the current function
+    loop_depth: usize,
+}
+
+impl JsGenerator {
+    /// Generate the JavaScript program and its source map mappings, where every top level statement runs inside the runtime's run method
+    pub fn generate(ast: &Vec<Stmt>, source_name: &str) -> Result<(String, SourceMap), JsError> {
+        let mut generator = JsGenerator {
+            output: String::new(),
+            source_map: SourceMap::new(),
+            line: 0,
+            column: 0,
+            indentation: 0,
+            loop_depth: 0,
+        };
+
+        generator.write(&format!("// Generated by simple_script from {}\n", source_name));
+        generator.write("\"use strict\";\n\n");
+        generator.write(RUNTIME);
+        generator.write("\n$ss.run(() => {\n");
+        generator.indentation += 1;
+
+        // Native functions of the prelude are defined in the program's scope like the global environment of ri
+        generator.write_line("const clock = $ss.clock;");
+        generator.write_line("");
+
+        for stmt in ast {
+            generator.statement(stmt)?;
+        }
+
+        generator.indentation -= 1;
+        generator.write("});\n");
+
+        Ok((generator.output, generator.source_map))
+    }
+
+    /* ==========================  Start of output helpers  ========================== */
+
+    /// Append code to the output, keeping track of the current position for the source map
+    fn write(&mut self, code: &str) {
+        for character in code.chars() {
+            if character == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                // Source map columns are in UTF-16 code units like JavaScript strings
+                self.column += character.len_utf16();
+            }
+        }
+        self.output.push_str(code);
+    }
+
+    fn indent(&mut self) {
+        self.write(&"  ".repeat(self.indentation));
+    }
+
+    fn write_line(&mut self, code: &str) {
+        if !code.is_empty() {
+            self.indent();
+        }
+        self.write(code);
+        self.write("\n");
+    }
+
+    /// Map the current position in the generated code to the source line
+    fn map(&mut self, source_line: Option<usize>) {
+        if let Some(source_line) = source_line {
+            self.source_map.add(self.line, self.column, source_line);
+        }
+    }
+
+    /* ==========================  End of output helpers  ========================== */
+
+    /* ==========================  Start of statements  ========================== */
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<(), JsError> {
+        self.indent();
+        self.map(stmt_line(stmt));
+
+        match stmt {
+            Stmt::Print(ref expr, _) => {
+                self.write("$ss.print(");
+                self.expression(expr)?;
+                self.write(");\n");
+            }
+
+            Stmt::Expr(ref expr, _) | Stmt::Ignore(ref expr, _) => {
+                // Function expressions are wrapped in parenthesis so that they are not parsed as function declarations
+                let is_function = matches!(expr, Expr::AnonymousFunc(_));
+                if is_function {
+                    self.write("(");
+                }
+                self.expression(expr)?;
+                if is_function {
+                    self.write(")");
+                }
+                self.write(";\n");
+            }
+
+            Stmt::Const(ref token, ref expr) => {
+                self.write(&format!("const {} = ", identifier(token)));
+                self.const_initializer(expr)?;
+                self.write(";\n");
+            }
+
+            Stmt::Block(ref stmts, _) => {
+                self.write("{\n");
+                self.block_body(stmts)?;
+                self.indent();
+                self.write("}\n");
+            }
+
+            Stmt::Func(ref token, ref parameters, ref body) => {
+                self.write(&format!("function {}(", identifier(token)));
+                self.parameters(parameters);
+                self.write(") ");
+                self.function_body(body)?;
+                self.write("\n");
+            }
+
+            Stmt::If(ref condition, ref true_branch, ref else_branch, _) => {
+                self.write("if ($ss.ifCondition(");
+                self.expression(condition)?;
+                self.write(")) ");
+                self.branch(true_branch)?;
+                if let Some(ref else_branch) = else_branch {
+                    self.write(" else ");
+                    self.branch(else_branch)?;
+                }
+                self.write("\n");
+            }
+
+            Stmt::While(ref condition, ref loop_body, _) => {
+                self.write("while ($ss.whileCondition(");
+                self.expression(condition)?;
+                self.write(")) ");
+                self.loop_depth += 1;
+                self.branch(loop_body)?;
+                self.loop_depth -= 1;
+                self.write("\n");
+            }
+
+            Stmt::Return(ref expr, _) => {
+                if self.loop_depth == 0 {
+                    self.write("return ");
+                    self.expression(expr)?;
+                    self.write(";\n");
+                } else {
+                    // ri discards the value of a while loop's body statement,
+                    // so a return statement in a loop only ends the current iteration after evaluating its expression
+                    if !matches!(**expr, Expr::Literal(_)) {
+                        self.expression(expr)?;
+                        self.write("; ");
+                    }
+                    self.write("continue;\n");
+                }
+            }
+
+            unmatched_stmt_variant => {
+                return Err(JsError::Codegen(format!(
+                    "Unimplemented Stmt variant: {}",
+                    unmatched_stmt_variant
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate the statements of a block, indented on their own lines
+    fn block_body(&mut self, stmts: &[Stmt]) -> Result<(), JsError> {
+        self.indentation += 1;
+        for stmt in stmts {
+            self.statement(stmt)?;
+        }
+        self.indentation -= 1;
+        Ok(())
+    }
+
+    /// Generate the body of an if or while statement, which is always wrapped in braces even if it is not a block statement.
+    /// The closing brace is left on the current line for an else branch or newline to follow.
+    fn branch(&mut self, stmt: &Stmt) -> Result<(), JsError> {
+        self.write("{\n");
+        match stmt {
+            Stmt::Block(ref stmts, _) => self.block_body(stmts)?,
+            stmt => self.block_body(std::slice::from_ref(stmt))?,
+        }
+        self.indent();
+        self.write("}");
+        Ok(())
+    }
+
+    fn parameters(&mut self, parameters: &[Token]) {
+        let names: Vec<String> = parameters.iter().map(identifier).collect();
+        self.write(&names.join(", "));
+    }
+
+    /// Generate a function body, without a new scope for the block statement like ri
+    fn function_body(&mut self, body: &Stmt) -> Result<(), JsError> {
+        let stmts = match body {
+            Stmt::Block(ref stmts, _) => stmts,
+            unmatched_stmt_variant => {
+                return Err(JsError::Codegen(format!(
+                    "Function body must be a Block Statement, found: {}",
+                    unmatched_stmt_variant
+                )))
+            }
+        };
+
+        // Return statements in the function body are not in the loops surrounding the function definition
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        self.write("{\n");
+        self.block_body(stmts)?;
+        self.indent();
+        self.write("}");
+        self.loop_depth = loop_depth;
+        Ok(())
+    }
+
+    /// Anonymous functions assigned to a const are renamed by the runtime, so that JavaScript does not name them after the const
+    fn const_initializer(&mut self, expr: &Expr) -> Result<(), JsError> {
+        if let Expr::AnonymousFunc(_) = expr {
+            self.write("$ss.anonymous(");
+            self.expression(expr)?;
+            self.write(")");
+            Ok(())
+        } else {
+            self.expression(expr)
+        }
+    }
+
+    /* ==========================  End of statements  ========================== */
+
+    /* ==========================  Start of expressions  ========================== */
+
+    fn expression(&mut self, expr: &Expr) -> Result<(), JsError> {
+        match expr {
+            Expr::Literal(ref literal) => self.literal(literal),
+
+            Expr::Const(ref token, _) => self.write(&identifier(token)),
+
+            Expr::Grouping(ref expr) => {
+                self.write("(");
+                self.expression(expr)?;
+                self.write(")");
+            }
+
+            Expr::Array(_, ref elements) => {
+                self.write("[");
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        self.write(", ");
+                    }
+                    self.expression(element)?;
+                }
+                self.write("]");
+            }
+
+            Expr::ArrayAccess(ref array, ref index) => {
+                self.write("$ss.index(");
+                self.expression(array)?;
+                self.write(", ");
+                self.expression(index)?;
+                self.write(")");
+            }
+
+            Expr::AnonymousFunc(ref stmt) => match **stmt {
+                Stmt::AnonymousFunc(ref parameters, ref body) => {
+                    self.write("(");
+                    self.parameters(parameters);
+                    self.write(") => ");
+
+                    match **body {
+                        // Arrow functions are desugared into a block with only a return statement and no closing brace line number
+                        Stmt::Block(ref stmts, None) if stmts.len() == 1 => match stmts[0] {
+                            Stmt::Return(ref expr, _) => self.expression(expr)?,
+                            _ => self.function_body(body)?,
+                        },
+                        _ => self.function_body(body)?,
+                    }
+                }
+                ref unmatched_stmt_variant => {
+                    return Err(JsError::Codegen(format!(
+                        "Expected anonymous function, found: {}",
+                        unmatched_stmt_variant
+                    )))
+                }
+            },
+
+            Expr::Call(ref callee, ref arguments, ref token) => {
+                // Function expressions are wrapped in parenthesis so that they can be called immediately
+                let is_function = matches!(**callee, Expr::AnonymousFunc(_));
+                if is_function {
+                    self.write("(");
+                }
+                self.expression(callee)?;
+                if is_function {
+                    self.write(")");
+                }
+
+                self.map(Some(token.line));
+                self.write("(");
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        self.write(", ");
+                    }
+                    self.expression(argument)?;
+                }
+                self.write(")");
+            }
+
+            Expr::Unary(ref operator, ref expr) => match operator.token_type {
+                TokenType::Minus if is_number(expr) => {
+                    // Parenthesis prevent nested negations from being generated as the decrement operator
+                    let is_unary = matches!(**expr, Expr::Unary(_, _));
+                    self.write(if is_unary { "-(" } else { "-" });
+                    self.expression(expr)?;
+                    if is_unary {
+                        self.write(")");
+                    }
+                }
+                TokenType::Minus => self.helper_call("negate", &[expr])?,
+                TokenType::Bang => self.helper_call("not", &[expr])?,
+                ref operator => {
+                    return Err(JsError::Codegen(format!("Invalid unary operator: {:?}", operator)))
+                }
+            },
+
+            Expr::Binary(ref left, ref operator, ref right) => {
+                let (helper, js_operator) = match operator.token_type {
+                    TokenType::Plus => ("add", "+"),
+                    TokenType::Minus => ("subtract", "-"),
+                    TokenType::Star => ("multiply", "*"),
+                    TokenType::Slash => ("divide", "/"),
+                    TokenType::EqualEqual => ("equal", "==="),
+                    TokenType::BangEqual => ("notEqual", "!=="),
+                    TokenType::Greater => ("greater", ">"),
+                    TokenType::GreaterEqual => ("greaterEqual", ">="),
+                    TokenType::Less => ("less", "<"),
+                    TokenType::LessEqual => ("lessEqual", "<="),
+                    ref operator => {
+                        return Err(JsError::Codegen(format!("Invalid binary operator: {:?}", operator)))
+                    }
+                };
+
+                // Operators are only used directly when both operands are known to be numbers, where they behave the same in JavaScript,
+                // else the runtime helpers check the operand types like ri does
+                if is_number(left) && is_number(right) {
+                    self.expression(left)?;
+                    self.write(&format!(" {} ", js_operator));
+                    self.expression(right)?;
+                } else {
+                    self.map(Some(operator.line));
+                    self.helper_call(helper, &[left, right])?;
+                }
+            }
+
+            Expr::Logical(ref left, ref operator, ref right) => {
+                let js_operator = match operator.token_type {
+                    TokenType::And => "&&",
+                    TokenType::Or => "||",
+                    ref operator => {
+                        return Err(JsError::Codegen(format!(
+                            "Invalid Token Type for logical expr -> {:?}",
+                            operator
+                        )))
+                    }
+                };
+
+                self.helper_call("logical", &[left])?;
+                self.write(&format!(" {} ", js_operator));
+                self.helper_call("logical", &[right])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            Literal::Number(number) => self.write(&format!("{}", number)),
+            Literal::String(ref string) => self.write(&json_string(string)),
+            Literal::Bool(boolean) => self.write(&format!("{}", boolean)),
+            Literal::Null => self.write("null"),
+        }
+    }
+
+    fn helper_call(&mut self, helper: &str, arguments: &[&Expr]) -> Result<(), JsError> {
+        self.write(&format!("$ss.{}(", helper));
+        for (index, argument) in arguments.iter().enumerate() {
+            if index > 0 {
+                self.write(", ");
+            }
+            self.expression(argument)?;
+        }
+        self.write(")");
+        Ok(())
+    }
+
+    /* ==========================  End of expressions  ========================== */
+}
+
+/// JavaScript identifier for a SS identifier
+fn identifier(token: &Token) -> String {
+    let name = token.lexeme.as_ref().unwrap();
+    if RESERVED_WORDS.contains(&name.as_str()) {
+        format!("{}$", name)
+    } else {
+        name.clone()
+    }
+}
+
+/// Checks if the expression always evaluates to a number, either because it is a number literal,
+/// or because it is an arithmetic operation that fails at runtime if its operands are not numbers
+fn is_number(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(Literal::Number(_)) => true,
+        Expr::Grouping(ref expr) => is_number(expr),
+        Expr::Unary(ref operator, _) => operator.token_type == TokenType::Minus,
+        Expr::Binary(ref left, ref operator, ref right) => match operator.token_type {
+            TokenType::Minus | TokenType::Star | TokenType::Slash => true,
+            // Addition also concatenates strings
+            TokenType::Plus => is_number(left) && is_number(right),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Source line number of the statement, for the source map
+fn stmt_line(stmt: &Stmt) -> Option<usize> {
+    match stmt {
+        Stmt::Block(ref stmts, _) => stmts.first().and_then(stmt_line),
+        Stmt::AnonymousFunc(_, ref body) => stmt_line(body),
+        stmt => stmt.line(),
+    }
+}
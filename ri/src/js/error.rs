@@ -0,0 +1,18 @@
+/// Errors from transpiling a SS program into JavaScript
+#[derive(Debug)]
+pub enum JsError {
+    /// AST node that cannot be transpiled, which should have been rejected by the earlier passes
+    Codegen(String),
+
+    /// Generated JavaScript or source map file could not be written
+    Io(String),
+}
+
+impl std::fmt::Display for JsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JsError::Codegen(error) => write!(f, "Unable to transpile program: {}", error),
+            JsError::Io(error) => write!(f, "Unable to write output file: {}", error),
+        }
+    }
+}
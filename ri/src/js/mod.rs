@@ -0,0 +1,67 @@
+mod codegen;
+mod error;
+mod source_map;
+
+use std::fs;
+use std::path::Path;
+
+use crate::parser::stmt::Stmt;
+
+use self::codegen::JsGenerator;
+pub use self::error::JsError;
+
+/*
+    JavaScript transpiler, enabled with '--backend js', which generates readable ES2020 code from the resolved and type checked AST,
+    so that SS programs can run in browsers and any JavaScript engine.
+
+    Consts, functions, arrow functions and arrays are generated as their JavaScript equivalents, where SS closures are JavaScript closures.
+    A small runtime ('$ss', see runtime.js) is inserted at the top of the generated file for the semantics that JavaScript does not have:
+    - Conditions must be Bool values, as SS does not have truthy and falsy values
+    - Operators check the types of their operands, unless both operands are known to be numbers
+    - Array access is bounds checked
+    - Values are printed and runtime errors are reported the same way ri does
+
+    A version 3 source map is written next to the generated file, mapping the generated code back to the lines of the SS source file.
+*/
+
+/// Transpile the resolved and type checked AST into a JavaScript file at the output path, and its source map at '<output>.map'
+pub fn compile(ast: &Vec<Stmt>, source_path: &Path, output: &Path) -> Result<(), JsError> {
+    let source_content = fs::read_to_string(source_path).map_err(|error| JsError::Io(error.to_string()))?;
+    let file_name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    let (mut code, source_map) = JsGenerator::generate(ast, &file_name(source_path))?;
+
+    let map_path = output.with_file_name(format!("{}.map", file_name(output)));
+    code.push_str(&format!("//# sourceMappingURL={}\n", file_name(&map_path)));
+
+    let source_map = source_map.to_json(&file_name(output), &relative_path(source_path, output), &source_content);
+
+    fs::write(output, code).map_err(|error| JsError::Io(format!("{}: {}", output.display(), error)))?;
+    fs::write(&map_path, source_map).map_err(|error| JsError::Io(format!("{}: {}", map_path.display(), error)))
+}
+
+/// Path of the source file relative to the directory of the output file, which is how source maps reference their sources
+fn relative_path(source_path: &Path, output: &Path) -> String {
+    let absolute = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let source_path = absolute(source_path);
+    let output_directory = absolute(output.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new(".")));
+
+    let common_components = source_path
+        .components()
+        .zip(output_directory.components())
+        .take_while(|(source, output)| source == output)
+        .count();
+
+    let mut relative: Vec<String> = output_directory
+        .components()
+        .skip(common_components)
+        .map(|_| "..".to_string())
+        .collect();
+    relative.extend(
+        source_path
+            .components()
+            .skip(common_components)
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+    relative.join("/")
+}
@@ -0,0 +1,163 @@
+// SimpleScript runtime helpers, for the semantics that JavaScript does not have
+const $ss = (() => {
+  class RuntimeError extends Error {}
+
+  // SS Null values are JavaScript nulls, but functions without a return statement return undefined
+  const isNull = (value) => value === null || value === undefined;
+
+  // Formats numbers like Rust's Display trait for f64 does, which never uses exponential notation
+  function formatNumber(number) {
+    if (Number.isNaN(number)) return "NaN";
+    if (number === Infinity) return "inf";
+    if (number === -Infinity) return "-inf";
+    if (Object.is(number, -0)) return "-0";
+
+    const string = String(number);
+    if (!string.includes("e")) return string;
+
+    const [mantissa, exponent] = string.split("e");
+    const sign = mantissa.startsWith("-") ? "-" : "";
+    const unsignedMantissa = mantissa.replace("-", "");
+    const point = unsignedMantissa.indexOf(".");
+    const digits = unsignedMantissa.replace(".", "");
+    const pointPosition = (point === -1 ? unsignedMantissa.length : point) + Number(exponent);
+
+    if (pointPosition >= digits.length) return sign + digits + "0".repeat(pointPosition - digits.length);
+    if (pointPosition <= 0) return sign + "0." + "0".repeat(-pointPosition) + digits;
+    return sign + digits.slice(0, pointPosition) + "." + digits.slice(pointPosition);
+  }
+
+  // Formats numbers like Rust's Debug trait for f64 does, which is used in error messages
+  function debugNumber(number) {
+    const magnitude = Math.abs(number);
+    if (magnitude !== 0 && Number.isFinite(number) && (magnitude < 1e-4 || magnitude >= 1e16)) {
+      return number.toExponential().replace("e+", "e");
+    }
+    const string = formatNumber(number);
+    return Number.isInteger(number) ? string + ".0" : string;
+  }
+
+  function formatFunction(func) {
+    if (func.native) return `<function-native: ${func.name}>`;
+    return `<function-ss: ${func.name === "" ? "[anonymous]" : func.name}>`;
+  }
+
+  // Pretty printer of values, the same as ri's Display trait for values
+  function format(value) {
+    if (isNull(value)) return "NULL";
+    switch (typeof value) {
+      case "number":
+        return formatNumber(value);
+      case "string":
+        return `'${value}'`;
+      case "boolean":
+        return String(value);
+      case "function":
+        return formatFunction(value);
+    }
+    return `[${value.map(format).join(", ")}]`;
+  }
+
+  // Same as ri's derived Debug trait for values, which is used in error messages
+  function debug(value) {
+    if (isNull(value)) return "Null";
+    switch (typeof value) {
+      case "number":
+        return `Number(${debugNumber(value)})`;
+      case "string":
+        return `String(${JSON.stringify(value)})`;
+      case "boolean":
+        return `Bool(${value})`;
+      case "function":
+        return `Func(${formatFunction(value)})`;
+    }
+    return `Array([${value.map(debug).join(", ")}])`;
+  }
+
+  // Strict boolean check, as SS does not have truthy and falsy values
+  function bool(value, message) {
+    if (typeof value === "boolean") return value;
+    throw new RuntimeError(`Type Error: Expected Bool but found type and value: ${debug(value)}\n${message}`);
+  }
+
+  function numbers(left, right, name) {
+    if (typeof left !== "number" || typeof right !== "number") {
+      throw new RuntimeError(`Type Error: Invalid types used for ${name}!`);
+    }
+  }
+
+  function add(left, right) {
+    if ((typeof left === "number" && typeof right === "number") || (typeof left === "string" && typeof right === "string")) {
+      return left + right;
+    }
+    throw new RuntimeError("Type Error: Invalid types used for addition!");
+  }
+
+  // Arrays are never equal to anything, and every other value is compared like ri does
+  function equal(left, right) {
+    if (isNull(left) || isNull(right)) return isNull(left) && isNull(right);
+    if (Array.isArray(left) || Array.isArray(right)) return false;
+    return left === right;
+  }
+
+  function index(array, index) {
+    if (!Array.isArray(array)) {
+      throw new RuntimeError(`Type Error: Array element access failed, expect array to be of type Value::Array, found -> ${debug(array)}`);
+    }
+    if (typeof index !== "number") {
+      throw new RuntimeError(`Type Error: Array element access failed, expect index to be of type Value::Number, found -> ${debug(index)}`);
+    }
+    const position = Math.trunc(index);
+    if (index >= 0 && position < array.length) return array[position];
+    throw new RuntimeError(`Array Index Out Of Bounds Error: Expect index to be 0 to ${array.length - 1}, found -> ${formatNumber(index)}`);
+  }
+
+  // Anonymous functions assigned to consts are renamed, so that they are not named after the const by JavaScript
+  function anonymous(func) {
+    return Object.defineProperty(func, "name", { value: "" });
+  }
+
+  const clock = () => Date.now();
+  clock.native = true;
+
+  // Runs the program, reporting runtime errors the same way ri does
+  function run(program) {
+    try {
+      program();
+    } catch (error) {
+      if (!(error instanceof RuntimeError)) throw error;
+      console.error("-------- Interpreter ERROR --------");
+      console.error(error.message);
+      if (typeof process !== "undefined") process.exitCode = 1;
+    }
+  }
+
+  return {
+    print: (value) => console.log(format(value)),
+    ifCondition: (value) => bool(value, "Invalid condition value type, only Boolean values can be used as conditionals!"),
+    whileCondition: (value) => bool(value, "Expected Boolean from While loop expression"),
+    logical: (value) => bool(value, "Logical operations only work with Bool Types"),
+    add,
+    subtract: (left, right) => (numbers(left, right, "subtraction"), left - right),
+    multiply: (left, right) => (numbers(left, right, "multiplication"), left * right),
+    divide: (left, right) => (numbers(left, right, "division"), left / right),
+    greater: (left, right) => (numbers(left, right, "Greater"), left > right),
+    greaterEqual: (left, right) => (numbers(left, right, "Greater Equal"), left >= right),
+    less: (left, right) => (numbers(left, right, "Less"), left < right),
+    lessEqual: (left, right) => (numbers(left, right, "Less Equal"), left <= right),
+    equal,
+    notEqual: (left, right) => !equal(left, right),
+    negate: (value) => {
+      if (typeof value !== "number") throw new RuntimeError("Type Error: Invalid types used for number negation!");
+      return -value;
+    },
+    not: (value) => {
+      if (typeof value !== "boolean") throw new RuntimeError("Type Error: Invalid types used for boolean negation!");
+      return !value;
+    },
+    index,
+    anonymous,
+    clock,
+    run,
+  };
+})();
@@ -0,0 +1,118 @@
+// Builder for version 3 source maps, see https://sourcemaps.info/spec.html
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Mapping from a position in the generated JavaScript to a line in the SS source file.
+/// Tokens only store line numbers, so every mapping points to the start of a source line.
+struct Mapping {
+    generated_line: usize,
+    generated_column: usize,
+    source_line: usize,
+}
+
+pub struct SourceMap {
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap { mappings: Vec::new() }
+    }
+
+    /// Add a mapping, where generated positions are 0 based, and the source line is the 1 based line number from the token
+    pub fn add(&mut self, generated_line: usize, generated_column: usize, source_line: usize) {
+        // Skip mappings that would not change the source line of the previous mapping on the same generated line
+        if let Some(last) = self.mappings.last() {
+            if last.generated_line == generated_line && last.source_line == source_line {
+                return;
+            }
+        }
+
+        self.mappings.push(Mapping {
+            generated_line,
+            generated_column,
+            source_line: source_line.saturating_sub(1),
+        });
+    }
+
+    /// Serialize the source map into JSON, with the source file's content embedded so that the map works without the source file
+    pub fn to_json(&self, file: &str, source: &str, source_content: &str) -> String {
+        format!(
+            "{{\"version\":3,\"file\":{},\"sources\":[{}],\"sourcesContent\":[{}],\"names\":[],\"mappings\":\"{}\"}}\n",
+            json_string(file),
+            json_string(source),
+            json_string(source_content),
+            self.encode_mappings()
+        )
+    }
+
+    /// Encode the mappings into the 'mappings' field, where every generated line is separated by ';' and every segment by ',',
+    /// and every field of a segment is a base64 VLQ relative to the same field of the previous segment
+    fn encode_mappings(&self) -> String {
+        let mut encoded = String::new();
+        let mut generated_line = 0;
+        let mut previous_generated_column = 0;
+        let mut previous_source_line = 0;
+
+        for (index, mapping) in self.mappings.iter().enumerate() {
+            if index > 0 && mapping.generated_line == generated_line {
+                encoded.push(',');
+            } else {
+                while generated_line < mapping.generated_line {
+                    encoded.push(';');
+                    generated_line += 1;
+                }
+                // Generated column is relative to the previous segment only within the same generated line
+                previous_generated_column = 0;
+            }
+
+            encode_vlq(&mut encoded, mapping.generated_column as i64 - previous_generated_column as i64);
+            // Index of the only source file, which never changes
+            encode_vlq(&mut encoded, 0);
+            encode_vlq(&mut encoded, mapping.source_line as i64 - previous_source_line as i64);
+            // Source column, which is always the start of the line
+            encode_vlq(&mut encoded, 0);
+
+            previous_generated_column = mapping.generated_column;
+            previous_source_line = mapping.source_line;
+        }
+
+        encoded
+    }
+}
+
+/// Encode the value as a base64 VLQ, where the sign is stored in the lowest bit and every digit holds 5 bits of the value
+fn encode_vlq(encoded: &mut String, value: i64) {
+    let mut vlq = if value < 0 { ((-value) << 1) | 1 } else { value << 1 };
+
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+        if vlq > 0 {
+            // Continuation bit
+            digit |= 0b100000;
+        }
+        encoded.push(BASE64_ALPHABET[digit as usize] as char);
+
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Quote and escape the string as a JSON string, which is also a valid JavaScript string literal
+pub fn json_string(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for character in string.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            character if (character as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", character as u32)),
+            character => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
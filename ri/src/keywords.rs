@@ -1,5 +1,11 @@
 use crate::token_type::TokenType;
 
+/// Every keyword matched by `get_token_type_if_keyword`, used by tools like the language server to complete keywords.
+/// Must be kept in sync with the keywords matched below.
+pub const KEYWORDS: [&str; 13] = [
+    "and", "or", "while", "fn", "return", "ignore", "if", "else", "print", "null", "true", "false", "const",
+];
+
 // Function to return corresponding TokenType if input string is a keyword
 pub fn get_token_type_if_keyword(word_to_test_that_might_be_keyword: &str) -> Option<TokenType> {
     match word_to_test_that_might_be_keyword {
@@ -0,0 +1,24 @@
+// ri is both a library and the 'simple_script' binary (see main.rs), so that other tools like the language server
+// can reuse the scanner, parser, resolver and type checker without going through the CLI.
+pub mod aot;
+pub mod c;
+pub mod callables;
+pub mod debugger;
+pub mod environment;
+pub mod formatter;
+pub mod hash;
+pub mod interpreter;
+pub mod js;
+pub mod keywords;
+pub mod linter;
+pub mod literal;
+pub mod lsp;
+pub mod parser;
+pub mod resolver;
+pub mod scanner;
+pub mod token;
+pub mod token_type;
+pub mod type_checker;
+pub mod value;
+pub mod vm;
+pub mod wasm;
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::error::LintError;
+
+/// Name of the config file, which is looked up in the directory of the linted file and then in its parent directories
+pub const CONFIG_FILE_NAME: &str = "sslint.json";
+
+/// Rules of the linter, named in kebab case in config files and suppression comments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// Consts, functions and parameters are named in camelCase
+    NamingStyle,
+
+    /// Function parameters are used in the function body
+    UnusedParameter,
+
+    /// Conditions of if and while stmts are not constants
+    ConstantCondition,
+
+    /// Parentheses change the order of evaluation of the expression they are around
+    RedundantGrouping,
+
+    /// Functions with a block body return a value
+    MissingReturn,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::NamingStyle,
+        Rule::UnusedParameter,
+        Rule::ConstantCondition,
+        Rule::RedundantGrouping,
+        Rule::MissingReturn,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::NamingStyle => "naming-style",
+            Rule::UnusedParameter => "unused-parameter",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::RedundantGrouping => "redundant-grouping",
+            Rule::MissingReturn => "missing-return",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn from_name(name: &str) -> Option<Severity> {
+        match name {
+            "off" => Some(Severity::Off),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Severity::Off => write!(f, "off"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Severity of every rule, where rules that are not configured are warnings
+///
+/// Config files are JSON objects with the severity of the rules that are configured, e.g.
+/// { "rules": { "naming-style": "error", "redundant-grouping": "off" } }
+pub struct Config {
+    severities: HashMap<Rule, Severity>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            severities: Rule::ALL.into_iter().map(|rule| (rule, Severity::Warning)).collect(),
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(json: &str) -> Result<Config, LintError> {
+        let json: Value = serde_json::from_str(json).map_err(|error| LintError::Config(error.to_string()))?;
+        let mut config = Config::default();
+
+        let Some(rules) = json.get("rules") else {
+            return Ok(config);
+        };
+        let rules = rules
+            .as_object()
+            .ok_or_else(|| LintError::Config(String::from("'rules' must be an object")))?;
+
+        for (name, severity) in rules.iter() {
+            let rule = Rule::from_name(name).ok_or_else(|| LintError::Config(format!("Unknown rule '{}'", name)))?;
+            let severity = severity.as_str().and_then(Severity::from_name).ok_or_else(|| {
+                LintError::Config(format!(
+                    "Severity of rule '{}' must be one of \"off\", \"warning\" or \"error\"",
+                    name
+                ))
+            })?;
+            config.severities.insert(rule, severity);
+        }
+
+        Ok(config)
+    }
+
+    /// Read the config file at the path
+    pub fn read(path: &Path) -> Result<Config, LintError> {
+        let json = fs::read_to_string(path).map_err(|error| LintError::Config(format!("{}: {}", path.display(), error)))?;
+        Config::parse(&json).map_err(|error| match error {
+            LintError::Config(error) => LintError::Config(format!("{}: {}", path.display(), error)),
+            error => error,
+        })
+    }
+
+    /// Find the config file for the linted file, using the default config if there is none
+    pub fn find(file_path: &Path) -> Result<Config, LintError> {
+        let directory = fs::canonicalize(file_path)
+            .ok()
+            .and_then(|path| path.parent().map(PathBuf::from));
+
+        for directory in directory.iter().flat_map(|directory| directory.ancestors()) {
+            let path = directory.join(CONFIG_FILE_NAME);
+            if path.is_file() {
+                return Config::read(&path);
+            }
+        }
+        Ok(Config::default())
+    }
+
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.severities[&rule]
+    }
+}
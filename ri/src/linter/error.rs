@@ -0,0 +1,22 @@
+/// Errors that stop a SS program from being linted
+#[derive(Debug)]
+pub enum LintError {
+    /// Program does not scan or parse, with the error of the pass that rejected it
+    Syntax(String),
+
+    /// Program is rejected by the resolver, so identifiers cannot be linked to their definitions
+    Resolver(String),
+
+    /// Config file cannot be read or has an invalid rule or severity
+    Config(String),
+}
+
+impl std::fmt::Display for LintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LintError::Syntax(error) => write!(f, "Unable to lint program with syntax error: {}", error),
+            LintError::Resolver(error) => write!(f, "Unable to lint program with resolver error: {}", error),
+            LintError::Config(error) => write!(f, "Invalid linter config: {}", error),
+        }
+    }
+}
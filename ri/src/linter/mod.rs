@@ -0,0 +1,128 @@
+mod config;
+mod error;
+mod rules;
+
+use crate::formatter::{FormatError, SyntaxTree};
+use crate::resolver::resolver::Resolver;
+
+pub use self::config::{Config, Rule, Severity, CONFIG_FILE_NAME};
+pub use self::error::LintError;
+use self::rules::RuleChecker;
+
+/*
+    Linter for SimpleScript, run with 'simple_script lint', which checks the conventions and pitfalls documented in the spec
+    that are not rejected by the resolver and type checker.
+
+    Programs are parsed into the same lossless syntax tree as the formatter, so that comments are kept for suppressions,
+    and resolved before the rules are checked, so that every identifier can be linked to its definition.
+
+    Rules, named in kebab case:
+    - naming-style:         Consts, functions and parameters are named in camelCase.
+                            Parameters can start with an underscore to mark them as intentionally unused.
+    - unused-parameter:     Function parameters are used in the function body, unless they start with an underscore.
+    - constant-condition:   Conditions of if and while stmts are not made up of only literals.
+    - redundant-grouping:   Parentheses are not around a single value, or around a whole expression like an argument.
+    - missing-return:       Functions with a block body have a return stmt with a value.
+
+    Every rule is a warning by default. Severities are configured in a 'sslint.json' file, found in the directory of the
+    linted file or any of its parent directories, or passed with '--config', e.g.
+    { "rules": { "naming-style": "error", "redundant-grouping": "off" } }
+
+    Rules are suppressed for a single line with a 'lint-ignore' comment, optionally followed by the comma separated rules
+    to suppress, where all rules are suppressed if none are listed. The comment suppresses the line it is on if it is
+    after code, or the line of the code after it if it is on its own line, e.g.
+    const MyValue = 1; // lint-ignore naming-style
+*/
+
+/// A broken rule that is reported, with the severity configured for the rule
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}: {} [{}] {}", self.line, self.column, self.severity, self.rule.name(), self.message)
+    }
+}
+
+/// Rules suppressed by a 'lint-ignore' comment for a line, where None suppresses every rule
+struct Suppression {
+    line: usize,
+    rules: Option<Vec<Rule>>,
+}
+
+impl Suppression {
+    /// Get the suppressions of every 'lint-ignore' comment in the syntax tree
+    fn find_all(tree: &SyntaxTree) -> Vec<Suppression> {
+        let mut suppressions = Vec::new();
+
+        for comment in tree.comments.iter() {
+            let text = comment.text.trim_start_matches("//").trim_start_matches("/*").trim_end_matches("*/").trim();
+            let Some(rules) = text.strip_prefix("lint-ignore") else {
+                continue;
+            };
+            if !rules.is_empty() && !rules.starts_with(char::is_whitespace) {
+                continue;
+            }
+
+            let rules: Vec<Rule> = rules.split(',').filter_map(|rule| Rule::from_name(rule.trim())).collect();
+            let is_after_code = tree
+                .tokens
+                .iter()
+                .any(|token| token.line == comment.line && token.column < comment.column);
+            let line = if is_after_code {
+                comment.line
+            } else {
+                match tree.tokens.iter().find(|token| (token.line, token.column) > (comment.line, comment.column)) {
+                    Some(token) => token.line,
+                    None => continue,
+                }
+            };
+
+            suppressions.push(Suppression {
+                line,
+                rules: if rules.is_empty() { None } else { Some(rules) },
+            });
+        }
+
+        suppressions
+    }
+
+    fn suppresses(&self, rule: Rule, line: usize) -> bool {
+        self.line == line && self.rules.as_ref().is_none_or(|rules| rules.contains(&rule))
+    }
+}
+
+/// Lint the source of a program, returning the diagnostics of the rules that are not turned off, ordered by their position
+pub fn lint(source: &str, config: &Config) -> Result<Vec<Diagnostic>, LintError> {
+    let mut tree = SyntaxTree::parse(source).map_err(|error| match error {
+        FormatError::Syntax(error) => LintError::Syntax(error),
+        error => LintError::Syntax(error.to_string()),
+    })?;
+    Resolver::resolve(&mut tree.ast).map_err(|error| LintError::Resolver(error.to_string()))?;
+
+    let suppressions = Suppression::find_all(&tree);
+    let mut diagnostics: Vec<Diagnostic> = RuleChecker::check(&tree.ast, tree.expression_starts())
+        .into_iter()
+        .filter(|finding| config.severity(finding.rule) != Severity::Off)
+        .filter(|finding| {
+            !suppressions
+                .iter()
+                .any(|suppression| suppression.suppresses(finding.rule, finding.line))
+        })
+        .map(|finding| Diagnostic {
+            rule: finding.rule,
+            severity: config.severity(finding.rule),
+            line: finding.line,
+            column: finding.column,
+            message: finding.message,
+        })
+        .collect();
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    Ok(diagnostics)
+}
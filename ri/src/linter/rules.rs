@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use crate::literal::Literal;
+use crate::parser::expr::Expr;
+use crate::parser::stmt::Stmt;
+use crate::token::Token;
+
+use super::config::Rule;
+
+/// A rule that is broken at the line and column
+pub struct Finding {
+    pub rule: Rule,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Walks the resolved AST with the same scopes as the resolver, checking every rule on the way
+pub struct RuleChecker {
+    /// Line and column of every expression, in the order that they are visited, see `SyntaxTree::expression_starts`
+    expression_starts: Vec<(usize, usize)>,
+    next_expression: usize,
+
+    /// Stack of scopes, mapping identifiers to the index of their parameter for parameters, and None for other definitions
+    scopes: Vec<HashMap<String, Option<usize>>>,
+
+    /// Parameters of the functions being walked, and whether they are used
+    parameters: Vec<(Token, bool)>,
+
+    findings: Vec<Finding>,
+}
+
+/// Consts, functions and parameters are named in camelCase, i.e. starting with a lowercase letter without underscores.
+/// Parameters can start with an underscore to mark them as intentionally unused.
+fn is_camel_case(name: &str, is_parameter: bool) -> bool {
+    let name = match name.strip_prefix('_') {
+        Some("") if is_parameter => return true,
+        Some(name) if is_parameter => name,
+        _ => name,
+    };
+    name.starts_with(|character: char| character.is_lowercase()) && name.chars().all(char::is_alphanumeric)
+}
+
+/// Constant expressions are made up of only literals, so they always evaluate to the same value
+fn is_constant(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) => true,
+        Expr::Grouping(expr) | Expr::Unary(_, expr) => is_constant(expr),
+        Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => is_constant(left) && is_constant(right),
+        _ => false,
+    }
+}
+
+/// Check if any return stmt of the function body returns a value, where return stmts of nested functions are not checked.
+/// Return stmts without a value return null, so they do not count.
+fn returns_value(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(expr, _) => !matches!(**expr, Expr::Literal(Literal::Null)),
+        Stmt::Block(stmts, _) => stmts.iter().any(returns_value),
+        Stmt::If(_, true_branch, else_branch, _) => {
+            returns_value(true_branch) || else_branch.as_ref().is_some_and(|else_branch| returns_value(else_branch))
+        }
+        Stmt::While(_, body, _) => returns_value(body),
+        _ => false,
+    }
+}
+
+fn name_of(token: &Token) -> &str {
+    token.lexeme.as_deref().unwrap_or_default()
+}
+
+impl RuleChecker {
+    pub fn check(ast: &[Stmt], expression_starts: Vec<(usize, usize)>) -> Vec<Finding> {
+        let mut checker = RuleChecker {
+            expression_starts,
+            next_expression: 0,
+            scopes: vec![HashMap::new()],
+            parameters: Vec::new(),
+            findings: Vec::new(),
+        };
+        checker.statements(ast);
+        checker.findings
+    }
+
+    fn report(&mut self, rule: Rule, (line, column): (usize, usize), message: String) {
+        self.findings.push(Finding {
+            rule,
+            line,
+            column,
+            message,
+        });
+    }
+
+    fn define(&mut self, token: &Token, kind: &str, parameter: Option<usize>) {
+        let name = name_of(token);
+        if !is_camel_case(name, parameter.is_some()) {
+            let message = format!("{} '{}' should be named in camelCase", kind, name);
+            self.report(Rule::NamingStyle, (token.line, token.column), message);
+        }
+        self.scopes.last_mut().unwrap().insert(name.to_string(), parameter);
+    }
+
+    fn statements(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts.iter() {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expr(expr, _) | Stmt::Print(expr, _) | Stmt::Ignore(expr, _) => self.expression(expr, true),
+            Stmt::Return(expr, _) => self.expression(expr, true),
+
+            // Identifiers are declared before their initializer is resolved, just like in the resolver
+            Stmt::Const(token, expr) => {
+                self.define(token, "Const", None);
+                self.expression(expr, true);
+            }
+            Stmt::Block(stmts, _) => {
+                self.scopes.push(HashMap::new());
+                self.statements(stmts);
+                self.scopes.pop();
+            }
+            Stmt::Func(token, params, body) => {
+                self.define(token, "Function", None);
+                let description = format!("function '{}'", name_of(token));
+                self.function(params, body, &description, (token.line, token.column));
+            }
+
+            // Anonymous functions are checked with the position of their expression
+            Stmt::AnonymousFunc(..) => {}
+            Stmt::If(condition, true_branch, else_branch, _) => {
+                self.condition(condition, "if");
+                self.statement(true_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Stmt::While(condition, body, _) => {
+                self.condition(condition, "while");
+                self.statement(body);
+            }
+        }
+    }
+
+    fn condition(&mut self, condition: &Expr, keyword: &str) {
+        if is_constant(condition) {
+            let position = self.expression_starts[self.next_expression];
+            let message = format!("Condition of '{}' is a constant, so it always evaluates to the same value", keyword);
+            self.report(Rule::ConstantCondition, position, message);
+        }
+        self.expression(condition, true);
+    }
+
+    /// Parameters and the statements of the function body share the same scope, just like in the resolver
+    fn function(&mut self, params: &[Token], body: &Stmt, description: &str, position: (usize, usize)) {
+        let Stmt::Block(stmts, closing_brace_line) = body else {
+            return;
+        };
+
+        let first_parameter = self.parameters.len();
+        self.scopes.push(HashMap::new());
+        for param in params.iter() {
+            self.define(param, "Parameter", Some(self.parameters.len()));
+            self.parameters.push((param.clone(), false));
+        }
+        self.statements(stmts);
+        self.scopes.pop();
+
+        let parameters: Vec<(Token, bool)> = self.parameters.drain(first_parameter..).collect();
+        for (param, _) in parameters.iter().filter(|(param, used)| !used && !name_of(param).starts_with('_')) {
+            let message = format!("Parameter '{}' of {} is never used", name_of(param), description);
+            self.report(Rule::UnusedParameter, (param.line, param.column), message);
+        }
+
+        // Only the block statements of arrow functions do not have the line of a closing brace, which always return a value
+        if closing_brace_line.is_some() && !returns_value(body) {
+            self.report(Rule::MissingReturn, position, format!("The {} never returns a value", description));
+        }
+    }
+
+    /// Check the expression, where whole expressions are not part of another expression, e.g. the value of a const or an argument
+    fn expression(&mut self, expr: &Expr, is_whole: bool) {
+        let position = self.expression_starts[self.next_expression];
+        self.next_expression += 1;
+
+        match expr {
+            Expr::Const(token, distance) => {
+                let definition = self
+                    .scopes
+                    .len()
+                    .checked_sub(distance + 1)
+                    .and_then(|scope| self.scopes[scope].get(name_of(token)).copied());
+                if let Some(Some(parameter)) = definition {
+                    self.parameters[parameter].1 = true;
+                }
+            }
+            Expr::Grouping(inner) => {
+                let is_primary = matches!(
+                    **inner,
+                    Expr::Literal(_) | Expr::Const(..) | Expr::Call(..) | Expr::ArrayAccess(..) | Expr::Array(..) | Expr::Grouping(_)
+                );
+                if is_whole || is_primary {
+                    let message = String::from("Parentheses are redundant as they do not change the order of evaluation");
+                    self.report(Rule::RedundantGrouping, position, message);
+                }
+                self.expression(inner, false);
+            }
+            Expr::AnonymousFunc(stmt) => {
+                if let Stmt::AnonymousFunc(params, body) = &**stmt {
+                    self.function(params, body, "anonymous function", position);
+                }
+            }
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                self.expression(left, false);
+                self.expression(right, false);
+            }
+            Expr::Unary(_, expr) => self.expression(expr, false),
+            Expr::Literal(_) => {}
+            Expr::Call(callee, arguments, _) => {
+                self.expression(callee, false);
+                for argument in arguments.iter() {
+                    self.expression(argument, true);
+                }
+            }
+            Expr::Array(_, elements) => {
+                for element in elements.iter() {
+                    self.expression(element, true);
+                }
+            }
+            Expr::ArrayAccess(array, index) => {
+                self.expression(array, false);
+                self.expression(index, true);
+            }
+        }
+    }
+}
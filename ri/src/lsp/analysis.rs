@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::keywords::KEYWORDS;
+use crate::parser::expr::Expr;
+use crate::parser::parser_struct::Parser;
+use crate::parser::stmt::Stmt;
+use crate::resolver::resolver::Resolver;
+use crate::scanner::scanner_struct::Scanner;
+use crate::token::Token;
+use crate::token_type::TokenType;
+use crate::type_checker::{Type, TypeChecker};
+
+/// A position in a document as (line, character), where both start from 0 like in the LSP
+pub type Position = (usize, usize);
+
+/// A range of characters on a single line, where both the line and characters start from 0 like in the LSP
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Span of an identifier token, which is the only kind of token that symbols are created for
+    fn of_identifier(token: &Token) -> Span {
+        let start = token.column - 1;
+        Span {
+            line: token.line - 1,
+            start,
+            end: start + token.lexeme.as_ref().map_or(1, |lexeme| lexeme.chars().count()),
+        }
+    }
+
+    pub fn contains(&self, line: usize, character: usize) -> bool {
+        self.line == line && self.start <= character && character <= self.end
+    }
+
+    fn start_position(&self) -> Position {
+        (self.line, self.start)
+    }
+
+    fn end_position(&self) -> Position {
+        (self.line, self.end)
+    }
+}
+
+fn position_of(token: &Token) -> Position {
+    (token.line - 1, token.column - 1)
+}
+
+/// An error found by any of the front end's passes
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+
+    /// Name of the pass that found the error, e.g. "Resolver"
+    pub source: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Const,
+    Function,
+    Parameter,
+
+    /// Usage of an identifier defined elsewhere
+    Reference,
+}
+
+/// A definition or usage of an identifier
+pub struct Symbol {
+    pub name: String,
+    pub span: Span,
+    pub kind: SymbolKind,
+
+    /// End of the scope that a definition is visible in, from the end of its identifier.
+    /// None for references.
+    pub scope_end: Option<Position>,
+
+    /// Index of the symbol that defines this identifier, which is itself for definitions.
+    /// None for identifiers without a definition in the source like the prelude's 'clock'.
+    pub definition: Option<usize>,
+
+    /// Every distinct type inferred for the identifier, as functions are type checked again for every call
+    pub types: Vec<String>,
+}
+
+/// Result of running a document through the scanner, parser, resolver and type checker
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// Symbols are only available once the document is resolved without errors,
+    /// since go-to-definition and find-references use the scope distances saved in the AST by the resolver
+    pub symbols: Vec<Symbol>,
+
+    /// True if the document is resolved without errors, i.e. if its symbols are available
+    pub resolved: bool,
+}
+
+impl Analysis {
+    pub fn new(source: &str) -> Analysis {
+        let mut analysis = Analysis {
+            diagnostics: Vec::new(),
+            symbols: Vec::new(),
+            resolved: false,
+        };
+
+        // The front end panics on some invalid programs, which should not take down the whole server
+        let result = panic::catch_unwind(AssertUnwindSafe(|| analysis.analyse(source)));
+        if let Err(panic) = result {
+            let message = panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|message| message.to_string()))
+                .unwrap_or_default();
+            analysis.symbols.clear();
+            analysis.resolved = false;
+            analysis.diagnostics.push(Diagnostic {
+                span: Span { line: 0, start: 0, end: 0 },
+                message: format!("Internal error while analysing file: {}", message),
+                source: "ri",
+            });
+        }
+
+        analysis
+    }
+
+    fn analyse(&mut self, source: &str) {
+        let tokens = match Scanner::scan_tokens(source.to_string()) {
+            Ok(tokens) => tokens,
+            Err(errors) => {
+                for error in errors {
+                    let span = Span {
+                        line: error.line - 1,
+                        start: error.column - 1,
+                        end: error.column,
+                    };
+                    self.error(span, error.description, "Scanner");
+                }
+                return;
+            }
+        };
+
+        let mut ast = match Parser::parse(&tokens) {
+            Ok(ast) => ast,
+            Err(errors) => {
+                for error in errors {
+                    let token = error.token();
+                    let span = match token.lexeme {
+                        Some(_) => Span::of_identifier(token),
+                        None => Span {
+                            line: token.line - 1,
+                            start: token.column - 1,
+                            end: token.column,
+                        },
+                    };
+                    self.error(span, error.to_string(), "Parser");
+                }
+                return;
+            }
+        };
+
+        if let Err(error) = Resolver::resolve(&mut ast) {
+            let span = match error.token() {
+                Some(token) => Span::of_identifier(token),
+                None => line_span(source, error.line()),
+            };
+            self.error(span, error.to_string(), "Resolver");
+            return;
+        }
+
+        let mut collector = SymbolCollector {
+            scopes: vec![HashMap::new()],
+            symbols: Vec::new(),
+            tokens: &tokens,
+            blocks: blocks(&tokens),
+        };
+        collector.statements(&ast);
+        self.symbols = collector.symbols;
+        self.resolved = true;
+
+        // Types recorded before a type error are still valid, so they are used for hovers even if there is an error
+        let inference = TypeChecker::check_and_infer(&ast);
+        self.add_types(inference.types);
+        if let Some((line, error)) = inference.error {
+            self.error(line_span(source, line), error.to_string(), "TypeChecker");
+        }
+    }
+
+    fn error(&mut self, span: Span, message: String, source: &'static str) {
+        self.diagnostics.push(Diagnostic {
+            span,
+            message: without_line_prefix(&message).to_string(),
+            source,
+        });
+    }
+
+    /// Add the inferred types to the symbols of their tokens, where the deferred Type::Lazy of generic
+    /// parameters is only kept if the function is never called with concrete types
+    fn add_types(&mut self, inferred_types: Vec<(Token, Type)>) {
+        let index_of_span: HashMap<(usize, usize), usize> = self
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| ((symbol.span.line, symbol.span.start), index))
+            .collect();
+
+        for (token, value_type) in inferred_types {
+            let span = Span::of_identifier(&token);
+            if let Some(&index) = index_of_span.get(&(span.line, span.start)) {
+                let value_type = value_type.to_string();
+                if !self.symbols[index].types.contains(&value_type) {
+                    self.symbols[index].types.push(value_type);
+                }
+            }
+        }
+
+        let lazy = Type::Lazy.to_string();
+        for symbol in self.symbols.iter_mut() {
+            if symbol.types.len() > 1 {
+                symbol.types.retain(|value_type| *value_type != lazy);
+            }
+        }
+    }
+
+    /// Get the index of the symbol at the position
+    pub fn symbol_at(&self, line: usize, character: usize) -> Option<usize> {
+        self.symbols
+            .iter()
+            .position(|symbol| symbol.span.contains(line, character))
+    }
+
+    /// Get the indexes of the definitions that are visible at the position, where definitions shadowed by
+    /// definitions of the same name in inner scopes are left out
+    pub fn visible_definitions(&self, line: usize, character: usize) -> Vec<usize> {
+        let position = (line, character);
+        let mut visible: HashMap<&str, usize> = HashMap::new();
+
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            let Some(scope_end) = symbol.scope_end else {
+                continue;
+            };
+            if symbol.span.end_position() > position || position > scope_end {
+                continue;
+            }
+
+            // Definitions of inner scopes always come after the definitions of their enclosing scopes
+            match visible.get(symbol.name.as_str()) {
+                Some(&other) if self.symbols[other].span.start_position() > symbol.span.start_position() => {}
+                _ => {
+                    visible.insert(&symbol.name, index);
+                }
+            }
+        }
+
+        let mut definitions: Vec<usize> = visible.into_values().collect();
+        definitions.sort();
+        definitions
+    }
+
+    /// Get the indexes of every symbol that refers to the same definition as the given symbol, including the definition
+    pub fn references(&self, index: usize) -> Vec<usize> {
+        match self.symbols[index].definition {
+            Some(definition) => (0..self.symbols.len())
+                .filter(|&other| self.symbols[other].definition == Some(definition))
+                .collect(),
+            None => vec![index],
+        }
+    }
+
+    /// Get the spans to replace with the new name to rename the symbol's definition and every reference to it.
+    ///
+    /// Renaming is rejected if the new name is not an identifier, or if the renamed document would not resolve to the
+    /// same definitions, e.g. if the new name is already used in the same scope, or if a reference to the renamed
+    /// definition would then refer to another definition of the same name in an inner scope instead.
+    pub fn rename(&self, source: &str, index: usize, new_name: &str) -> Result<Vec<Span>, String> {
+        let symbol = &self.symbols[index];
+        let Some(definition) = symbol.definition else {
+            return Err(format!("Cannot rename '{}' as it is defined in the prelude", symbol.name));
+        };
+
+        let mut characters = new_name.chars();
+        let is_identifier = characters
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+            && characters.all(char::is_alphanumeric);
+        if !is_identifier || KEYWORDS.contains(&new_name) {
+            return Err(format!("'{}' is not a valid identifier", new_name));
+        }
+
+        let spans: Vec<Span> = self
+            .references(index)
+            .into_iter()
+            .map(|reference| self.symbols[reference].span)
+            .collect();
+
+        // Spans of the renamed identifiers, which shift if there are multiple of them on the same line
+        let length_change = new_name.chars().count() as isize - symbol.name.chars().count() as isize;
+        let renamed_spans: Vec<Span> = spans
+            .iter()
+            .map(|span| {
+                let renamed_before = spans
+                    .iter()
+                    .filter(|other| other.line == span.line && other.start < span.start)
+                    .count() as isize;
+                let start = (span.start as isize + renamed_before * length_change) as usize;
+                Span {
+                    line: span.line,
+                    start,
+                    end: start + new_name.chars().count(),
+                }
+            })
+            .collect();
+
+        let renamed = Analysis::new(&replace_spans(source, &spans, new_name));
+        let renamed_definition = spans
+            .iter()
+            .position(|span| *span == self.symbols[definition].span)
+            .map(|position| renamed_spans[position]);
+        let same_references = renamed.resolved
+            && renamed_definition
+                .and_then(|span| renamed.symbol_at(span.line, span.start))
+                .is_some_and(|renamed_index| {
+                    let mut references: Vec<Span> = renamed
+                        .references(renamed_index)
+                        .into_iter()
+                        .map(|reference| renamed.symbols[reference].span)
+                        .collect();
+                    references.sort_by_key(|span| span.start_position());
+                    let mut expected = renamed_spans.clone();
+                    expected.sort_by_key(|span| span.start_position());
+                    references == expected
+                });
+
+        if same_references {
+            Ok(spans)
+        } else {
+            Err(format!(
+                "Cannot rename '{}' to '{}', as it would change which definitions its identifiers refer to",
+                symbol.name, new_name
+            ))
+        }
+    }
+}
+
+/// Replace the characters in every span with the text
+fn replace_spans(source: &str, spans: &[Span], text: &str) -> String {
+    source
+        .split('\n')
+        .enumerate()
+        .map(|(line, content)| {
+            let mut characters: Vec<char> = content.chars().collect();
+            let mut spans_on_line: Vec<&Span> = spans.iter().filter(|span| span.line == line).collect();
+
+            // Replace from the end of the line so that the earlier spans are not shifted
+            spans_on_line.sort_by_key(|span| std::cmp::Reverse(span.start));
+            for span in spans_on_line {
+                characters.splice(span.start..span.end, text.chars());
+            }
+            characters.into_iter().collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Span of the whole line, for errors that only have a line number
+fn line_span(source: &str, line_number: usize) -> Span {
+    let line = line_number.max(1) - 1;
+    Span {
+        line,
+        start: 0,
+        end: source.lines().nth(line).map_or(0, |text| text.chars().count()),
+    }
+}
+
+/// Remove the '[line n] ' prefix of error messages, as the editor already shows where the error is
+fn without_line_prefix(message: &str) -> &str {
+    match message.strip_prefix("[line ") {
+        Some(rest) => rest.split_once("] ").map_or(message, |(_, rest)| rest),
+        None => message,
+    }
+}
+
+/// Get the start and end positions of every pair of braces, which are the only tokens that open and close scopes
+fn blocks(tokens: &[Token]) -> Vec<(Position, Position)> {
+    let mut blocks = Vec::new();
+    let mut opened = Vec::new();
+    for token in tokens {
+        match token.token_type {
+            TokenType::LeftBrace => opened.push(position_of(token)),
+            TokenType::RightBrace => {
+                if let Some(start) = opened.pop() {
+                    blocks.push((start, position_of(token)));
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// Walks the resolved AST with the same scopes as the resolver, to find the definition of every identifier usage
+/// by going up the number of scopes that the resolver saved into the Expr::Const node.
+struct SymbolCollector<'a> {
+    /// Stack of scopes, mapping identifiers to the index of their definition's symbol
+    scopes: Vec<HashMap<String, usize>>,
+    symbols: Vec<Symbol>,
+
+    /// Tokens and the braces in them, used to find where the scope of every definition ends,
+    /// as block statements only store the line of their closing brace
+    tokens: &'a [Token],
+    blocks: Vec<(Position, Position)>,
+}
+
+impl<'a> SymbolCollector<'a> {
+    fn define(&mut self, token: &Token, kind: SymbolKind, scope_end: Position) {
+        let name = token.lexeme.as_ref().unwrap().clone();
+        let index = self.symbols.len();
+        self.scopes.last_mut().unwrap().insert(name.clone(), index);
+        self.symbols.push(Symbol {
+            name,
+            span: Span::of_identifier(token),
+            kind,
+            scope_end: Some(scope_end),
+            definition: Some(index),
+            types: Vec::new(),
+        });
+    }
+
+    /// End of the innermost block containing the token, or the end of the document for the global scope
+    fn enclosing_block_end(&self, token: &Token) -> Position {
+        let position = position_of(token);
+        self.blocks
+            .iter()
+            .filter(|(start, end)| *start < position && position < *end)
+            .max_by_key(|(start, _)| *start)
+            .map_or((usize::MAX, usize::MAX), |(_, end)| *end)
+    }
+
+    /// End of the function body that follows the parameter, which is either a block or the single expression of an arrow function
+    fn function_body_end(&self, param: &Token, is_arrow_function: bool) -> Position {
+        let position = position_of(param);
+        let mut following = self.tokens.iter().skip_while(|token| position_of(token) <= position);
+
+        if !is_arrow_function {
+            return match following.find(|token| token.token_type == TokenType::LeftBrace) {
+                Some(brace) => self
+                    .blocks
+                    .iter()
+                    .find(|(start, _)| *start == position_of(brace))
+                    .map_or((usize::MAX, usize::MAX), |(_, end)| *end),
+                None => (usize::MAX, usize::MAX),
+            };
+        }
+
+        // The expression of an arrow function ends before the first token that is not nested in it
+        let mut depth = 0;
+        let mut end = (usize::MAX, usize::MAX);
+        for token in following.skip_while(|token| token.token_type != TokenType::Arrow).skip(1) {
+            match token.token_type {
+                TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+                TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace if depth > 0 => depth -= 1,
+                TokenType::Comma if depth > 0 => {}
+                TokenType::RightParen
+                | TokenType::RightBracket
+                | TokenType::RightBrace
+                | TokenType::Comma
+                | TokenType::Semicolon
+                | TokenType::Eof => {
+                    end = position_of(token);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        end
+    }
+
+    fn statements(&mut self, stmts: &Vec<Stmt>) {
+        for stmt in stmts {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expr(expr, _) | Stmt::Print(expr, _) | Stmt::Ignore(expr, _) => self.expression(expr),
+            Stmt::Return(expr, _) => self.expression(expr),
+
+            // Identifiers are declared before their initializer is resolved, just like in the resolver
+            Stmt::Const(token, expr) => {
+                self.define(token, SymbolKind::Const, self.enclosing_block_end(token));
+                self.expression(expr);
+            }
+            Stmt::Block(stmts, _) => {
+                self.scopes.push(HashMap::new());
+                self.statements(stmts);
+                self.scopes.pop();
+            }
+            Stmt::Func(token, params, body) => {
+                self.define(token, SymbolKind::Function, self.enclosing_block_end(token));
+                self.function(params, body);
+            }
+            Stmt::AnonymousFunc(params, body) => self.function(params, body),
+            Stmt::If(condition, then_branch, else_branch, _) => {
+                self.expression(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Stmt::While(condition, body, _) => {
+                self.expression(condition);
+                self.statement(body);
+            }
+        }
+    }
+
+    /// Parameters and the statements of the function body share the same scope, just like in the resolver
+    fn function(&mut self, params: &Vec<Token>, body: &Stmt) {
+        let Stmt::Block(stmts, closing_brace_line) = body else {
+            return;
+        };
+
+        // Only the block statements of arrow functions do not have the line of a closing brace
+        let is_arrow_function = closing_brace_line.is_none();
+
+        self.scopes.push(HashMap::new());
+        for param in params {
+            let scope_end = self.function_body_end(param, is_arrow_function);
+            self.define(param, SymbolKind::Parameter, scope_end);
+        }
+        self.statements(stmts);
+        self.scopes.pop();
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Const(token, distance) => {
+                let name = token.lexeme.as_ref().unwrap().clone();
+                let definition = self
+                    .scopes
+                    .len()
+                    .checked_sub(distance + 1)
+                    .and_then(|scope| self.scopes[scope].get(&name).copied());
+                self.symbols.push(Symbol {
+                    name,
+                    span: Span::of_identifier(token),
+                    kind: SymbolKind::Reference,
+                    scope_end: None,
+                    definition,
+                    types: Vec::new(),
+                });
+            }
+            Expr::AnonymousFunc(stmt) => self.statement(stmt),
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expr::Call(callee, arguments, _) => {
+                self.expression(callee);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            Expr::Grouping(expr) | Expr::Unary(_, expr) => self.expression(expr),
+            Expr::Literal(_) => {}
+            Expr::Array(_, elements) => {
+                for element in elements {
+                    self.expression(element);
+                }
+            }
+            Expr::ArrayAccess(array, index) => {
+                self.expression(array);
+                self.expression(index);
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+mod analysis;
+pub mod rpc;
+mod server;
+
+use std::io;
+use std::process::ExitCode;
+
+use self::server::Server;
+
+/*
+    Language server for SimpleScript, run with the 'ss-lsp' binary, which speaks JSON-RPC over stdio so that it works in any editor.
+
+    Every time a document is opened or changed, it is run through ri's front end:
+    - Errors of the scanner, parser, resolver and type checker are published as diagnostics.
+      Type errors do not store a line number, so they are reported on the line of the last token type checked.
+    - The resolved AST is walked with the same scopes as the resolver, and the scope distance that the resolver saved into
+      every identifier usage is used to find its definition, for go-to-definition and find-references.
+    - The type checker records the type it infers for every identifier, which is shown on hover.
+      Functions are type checked again for every call, so parameters show every type they are called with.
+    - Completions offer the definitions visible at the cursor, the prelude's native functions and the keywords.
+      Since a document usually does not parse while an identifier is being typed, the last analysis that resolved is used.
+    - Renames change the definition and its references only, leaving identifiers of the same name in other scopes alone,
+      and are rejected if the renamed document would resolve the references differently, e.g. if the new name conflicts.
+
+    Only full document sync is supported, which is fine since SS programs are small and analysing them is fast.
+*/
+
+/// Run the language server on stdin and stdout until the client exits
+pub fn run() -> ExitCode {
+    let mut server = Server::new(io::stdout().lock());
+    match server.serve(io::stdin().lock()) {
+        Ok(true) => ExitCode::SUCCESS,
+
+        // The client exited without shutting down the server first, or the input is closed unexpectedly
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("-------- Language Server ERROR --------");
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+/// Read the content of the next message, which is framed by a 'Content-Length' header and an empty line.
+/// Returns None once the input is closed.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        // Other headers like 'Content-Type' are ignored, as the content is always UTF-8 JSON
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Message is missing the 'Content-Length' header")
+    })?;
+
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Write the message with its 'Content-Length' header
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    writer.flush()
+}
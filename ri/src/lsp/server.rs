@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use super::analysis::{Analysis, Span, SymbolKind};
+use super::rpc;
+
+use crate::callables::native::PRELUDE;
+use crate::keywords::KEYWORDS;
+
+/// JSON-RPC error codes used by the server
+const PARSE_ERROR: i64 = -32700;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_REQUEST: i64 = -32600;
+const REQUEST_FAILED: i64 = -32803;
+
+/// Full document sync, where every change notification has the whole content of the document
+const TEXT_DOCUMENT_SYNC_FULL: u8 = 1;
+
+/// Error severity of diagnostics
+const SEVERITY_ERROR: u8 = 1;
+
+/// Kinds of completion items
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_VARIABLE: u8 = 6;
+const COMPLETION_KEYWORD: u8 = 14;
+const COMPLETION_CONSTANT: u8 = 21;
+
+/// An open document
+struct Document {
+    text: String,
+    analysis: Analysis,
+
+    /// Last analysis of the document that resolved without errors, if the current one did not.
+    /// Used for completions, since the document usually does not parse while an identifier is being typed.
+    last_resolved: Option<Analysis>,
+}
+
+/// Result of looking up the identifier at a request's position, as the document's URI, its analysis and the symbol's index
+type SymbolAt<'a> = Option<(&'a str, &'a Analysis, usize)>;
+
+pub struct Server<W: Write> {
+    writer: W,
+
+    /// Every open document, by URI
+    documents: HashMap<String, Document>,
+
+    /// Set once the client sends the shutdown request, after which the only message expected is the exit notification
+    shutdown: bool,
+}
+
+impl<W: Write> Server<W> {
+    pub fn new(writer: W) -> Server<W> {
+        Server {
+            writer,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Handle messages until the exit notification or until the input is closed.
+    /// Returns true if the client shut the server down properly before exiting.
+    pub fn serve(&mut self, mut reader: impl BufRead) -> io::Result<bool> {
+        while let Some(content) = rpc::read_message(&mut reader)? {
+            let message: Value = match serde_json::from_slice(&content) {
+                Ok(message) => message,
+                Err(error) => {
+                    self.respond(Value::Null, Err((PARSE_ERROR, error.to_string())))?;
+                    continue;
+                }
+            };
+
+            let method = message["method"].as_str().unwrap_or_default();
+            if method == "exit" {
+                return Ok(self.shutdown);
+            }
+
+            // Messages with an id are requests that must be responded to, and those without are notifications
+            match message.get("id") {
+                Some(id) => {
+                    let result = self.handle_request(method, &message["params"]);
+                    self.respond(id.clone(), result)?;
+                }
+                None => self.handle_notification(method, &message["params"])?,
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if self.shutdown {
+            return Err((INVALID_REQUEST, String::from("Server is shutting down")));
+        }
+
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": TEXT_DOCUMENT_SYNC_FULL,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                    "renameProvider": { "prepareProvider": true },
+                },
+                "serverInfo": {
+                    "name": "ss-lsp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/prepareRename" => self.prepare_rename(params),
+            "textDocument/rename" => self.rename(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unsupported method '{}'", method))),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.analyse(uri, text)
+            }
+
+            // Changes always have the whole document as full document sync is used
+            "textDocument/didChange" => match params["contentChanges"].as_array().and_then(|changes| changes.last()) {
+                Some(change) => self.analyse(uri, change["text"].as_str().unwrap_or_default()),
+                None => Ok(()),
+            },
+
+            // Clear the diagnostics of closed documents, as they are no longer kept up to date
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )
+            }
+
+            // Other notifications like 'initialized' do not need to be handled
+            _ => Ok(()),
+        }
+    }
+
+    /// Analyse the document's new content and publish its diagnostics
+    fn analyse(&mut self, uri: String, text: &str) -> io::Result<()> {
+        let analysis = Analysis::new(text);
+        let last_resolved = match self.documents.remove(&uri) {
+            Some(document) if document.analysis.resolved => Some(document.analysis),
+            Some(document) => document.last_resolved,
+            None => None,
+        };
+
+        let diagnostics: Vec<Value> = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                json!({
+                    "range": range(diagnostic.span),
+                    "severity": SEVERITY_ERROR,
+                    "source": diagnostic.source,
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+
+        self.documents.insert(
+            uri.clone(),
+            Document {
+                text: text.to_string(),
+                analysis,
+                last_resolved,
+            },
+        );
+        self.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    /// Get the URI and position of the request's params
+    fn position(params: &Value) -> Result<(&str, usize, usize), (i64, String)> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or((INVALID_PARAMS, String::from("Missing text document URI")))?;
+        match (
+            params["position"]["line"].as_u64(),
+            params["position"]["character"].as_u64(),
+        ) {
+            (Some(line), Some(character)) => Ok((uri, line as usize, character as usize)),
+            _ => Err((INVALID_PARAMS, String::from("Missing position"))),
+        }
+    }
+
+    /// Get the analysis of the document and the index of the symbol at the position of the request's params
+    fn symbol_at<'a>(&'a self, params: &'a Value) -> Result<SymbolAt<'a>, (i64, String)> {
+        let (uri, line, character) = Self::position(params)?;
+        Ok(self.documents.get(uri).and_then(|document| {
+            document
+                .analysis
+                .symbol_at(line, character)
+                .map(|index| (uri, &document.analysis, index))
+        }))
+    }
+
+    /// Show the inferred types of the identifier
+    fn hover(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let Some((_, analysis, index)) = self.symbol_at(params)? else {
+            return Ok(Value::Null);
+        };
+
+        let symbol = &analysis.symbols[index];
+        if symbol.types.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        Ok(json!({
+            "contents": {
+                "kind": "markdown",
+                "value": format!("```simplescript\n{}: {}\n```", symbol.name, symbol.types.join(" | ")),
+            },
+            "range": range(symbol.span),
+        }))
+    }
+
+    fn definition(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let Some((uri, analysis, index)) = self.symbol_at(params)? else {
+            return Ok(Value::Null);
+        };
+
+        Ok(match analysis.symbols[index].definition {
+            Some(definition) => location(uri, analysis.symbols[definition].span),
+            None => Value::Null,
+        })
+    }
+
+    fn references(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let Some((uri, analysis, index)) = self.symbol_at(params)? else {
+            return Ok(Value::Null);
+        };
+
+        let include_declaration = params["context"]["includeDeclaration"].as_bool().unwrap_or(true);
+        let locations: Vec<Value> = analysis
+            .references(index)
+            .into_iter()
+            .filter(|&reference| include_declaration || analysis.symbols[reference].definition != Some(reference))
+            .map(|reference| location(uri, analysis.symbols[reference].span))
+            .collect();
+
+        Ok(Value::Array(locations))
+    }
+
+    /// Complete the identifiers in scope, the prelude's native functions and keywords.
+    /// SS does not have records yet, so there are no fields to complete after a '.'.
+    fn completion(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let (uri, line, character) = Self::position(params)?;
+        let Some(document) = self.documents.get(uri) else {
+            return Ok(json!([]));
+        };
+
+        // Skip the part of the identifier that is already typed to check if it comes after a '.'
+        let before_cursor: Vec<char> = document
+            .text
+            .split('\n')
+            .nth(line)
+            .unwrap_or_default()
+            .chars()
+            .take(character)
+            .collect();
+        let is_field = before_cursor
+            .iter()
+            .rev()
+            .find(|character| !character.is_alphanumeric() && **character != '_')
+            .is_some_and(|character| *character == '.');
+        if is_field {
+            return Ok(json!([]));
+        }
+
+        let analysis = match document.last_resolved {
+            Some(ref last_resolved) if !document.analysis.resolved => last_resolved,
+            _ => &document.analysis,
+        };
+
+        let mut items = Vec::new();
+        let definitions = analysis.visible_definitions(line, character);
+        for index in definitions.iter() {
+            let symbol = &analysis.symbols[*index];
+            items.push(json!({
+                "label": symbol.name,
+                "kind": match symbol.kind {
+                    SymbolKind::Function => COMPLETION_FUNCTION,
+                    SymbolKind::Parameter => COMPLETION_VARIABLE,
+                    _ => COMPLETION_CONSTANT,
+                },
+                "detail": symbol.types.join(" | "),
+            }));
+        }
+
+        // Prelude functions can be shadowed by definitions in inner scopes
+        for native in PRELUDE.iter() {
+            if !definitions.iter().any(|index| analysis.symbols[*index].name == native.name) {
+                items.push(json!({
+                    "label": native.name,
+                    "kind": COMPLETION_FUNCTION,
+                    "detail": native.signature,
+                    "documentation": native.description,
+                }));
+            }
+        }
+
+        for keyword in KEYWORDS.iter() {
+            items.push(json!({ "label": keyword, "kind": COMPLETION_KEYWORD }));
+        }
+
+        Ok(Value::Array(items))
+    }
+
+    /// Check that the identifier at the position can be renamed, and get its range
+    fn prepare_rename(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let Some((_, analysis, index)) = self.symbol_at(params)? else {
+            return Ok(Value::Null);
+        };
+
+        let symbol = &analysis.symbols[index];
+        match symbol.definition {
+            Some(_) => Ok(range(symbol.span)),
+            None => Err((
+                REQUEST_FAILED,
+                format!("Cannot rename '{}' as it is defined in the prelude", symbol.name),
+            )),
+        }
+    }
+
+    /// Rename the identifier's definition and every reference to it, leaving identifiers of the same name in other scopes alone
+    fn rename(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let new_name = params["newName"]
+            .as_str()
+            .ok_or((INVALID_PARAMS, String::from("Missing new name")))?;
+        let Some((uri, analysis, index)) = self.symbol_at(params)? else {
+            return Err((REQUEST_FAILED, String::from("No identifier to rename at this position")));
+        };
+
+        let text = &self.documents[uri].text;
+        let spans = analysis
+            .rename(text, index, new_name)
+            .map_err(|message| (REQUEST_FAILED, message))?;
+
+        let edits: Vec<Value> = spans
+            .into_iter()
+            .map(|span| json!({ "range": range(span), "newText": new_name }))
+            .collect();
+        Ok(json!({ "changes": { uri: edits } }))
+    }
+
+    fn respond(&mut self, id: Value, result: Result<Value, (i64, String)>) -> io::Result<()> {
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        rpc::write_message(&mut self.writer, &response)
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        rpc::write_message(
+            &mut self.writer,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+    }
+}
+
+fn range(span: Span) -> Value {
+    json!({
+        "start": { "line": span.line, "character": span.start },
+        "end": { "line": span.line, "character": span.end },
+    })
+}
+
+fn location(uri: &str, span: Span) -> Value {
+    json!({ "uri": uri, "range": range(span) })
+}
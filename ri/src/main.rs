@@ -1,28 +1,20 @@
 use clap::Parser as CLI_Parser;
+use rvm::cli::{DebugFlags, JitFlags};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::Instant;
 
-mod callables;
 mod cli;
-mod environment;
-mod hash;
-mod interpreter;
-mod keywords;
-mod literal;
-mod parser;
-mod resolver;
-mod scanner;
-mod token;
-mod token_type;
-mod type_checker;
-mod value;
-
-use cli::Cli;
-use interpreter::interpreter::Interpreter;
-use parser::parser_struct::Parser;
-use resolver::resolver::Resolver;
-use scanner::scanner_struct::Scanner;
-use type_checker::TypeChecker;
+
+use cli::{Backend, Cli, Command};
+use simple_script::interpreter::interpreter::Interpreter;
+use simple_script::parser::parser_struct::Parser;
+use simple_script::resolver::resolver::Resolver;
+use simple_script::scanner::scanner_struct::Scanner;
+use simple_script::type_checker::TypeChecker;
+use simple_script::linter::{self, Config, Diagnostic, Severity};
+use simple_script::{aot, c, formatter, js, vm, wasm};
 
 // Macro wrapping around println! macro that only prints in debug builds or if verbose/debugging flag is set
 #[macro_export]
@@ -35,26 +27,130 @@ macro_rules! verbosePrintln {
     }};
 }
 
-fn main() {
+fn main() -> ExitCode {
     // Use Clap lib to parse out CLI arguments
     let args = Cli::parse();
 
+    match args.command {
+        Some(Command::Fmt { ref file_paths, check }) => return format_files(file_paths, check),
+        Some(Command::Lint {
+            ref file_paths,
+            ref config,
+        }) => return lint_files(file_paths, config.as_deref()),
+        None => {}
+    }
+
     // @todo Get the full file name instead of the relative path
-    println!("Entering file '{}'\n", &args.file_path);
+    println!("Entering file '{}'\n", file_path(&args));
 
     // Only track execution time for debug builds
     #[cfg(debug_assertions)]
     let start_of_main = Instant::now();
 
-    run_file(&args.file_path);
+    let exit_code = run_file(&args);
 
     // @todo To also ran before running the interpreter
     verbosePrintln!("\nCompleted in: {:?}\n", start_of_main.elapsed());
+
+    exit_code
+}
+
+/// File path is required by the CLI parser when no subcommand is used
+fn file_path(args: &Cli) -> &str {
+    args.file_path.as_deref().expect("Internal Error: Missing file path")
+}
+
+/// Format the files in place, or only check that they are formatted with the check flag.
+/// Every file is formatted even if some of them fail, and a failure exit code is returned if any of them failed.
+fn format_files(file_paths: &[String], check: bool) -> ExitCode {
+    let mut exit_code = ExitCode::SUCCESS;
+
+    for file_path in file_paths.iter() {
+        match format_file(file_path, check) {
+            Ok(true) => {}
+            Ok(false) => {
+                println!("'{}' is not formatted", file_path);
+                exit_code = ExitCode::FAILURE;
+            }
+            Err(e) => {
+                eprintln!("-------- Formatter ERROR --------");
+                eprintln!("'{}': {}", file_path, e);
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    exit_code
+}
+
+/// Format the file in place unless only checking it, returning if the file was already formatted when checking it
+fn format_file(file_path: &str, check: bool) -> Result<bool, String> {
+    let source = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+    let formatted = formatter::format(&source).map_err(|e| e.to_string())?;
+
+    if formatted == source {
+        return Ok(true);
+    }
+    if check {
+        return Ok(false);
+    }
+
+    fs::write(file_path, formatted).map_err(|e| e.to_string())?;
+    println!("Formatted '{}'", file_path);
+    Ok(true)
+}
+
+/// Lint the files and print the broken rules, with the config file if given, or else the one found for each file.
+/// Every file is linted even if some of them fail, and a failure exit code is returned if any of them failed or broke
+/// a rule configured as an error.
+fn lint_files(file_paths: &[String], config_path: Option<&str>) -> ExitCode {
+    let mut exit_code = ExitCode::SUCCESS;
+
+    for file_path in file_paths.iter() {
+        match lint_file(file_path, config_path) {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics.iter() {
+                    println!("{}:{}", file_path, diagnostic);
+                    if diagnostic.severity == Severity::Error {
+                        exit_code = ExitCode::FAILURE;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("-------- Linter ERROR --------");
+                eprintln!("'{}': {}", file_path, e);
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    exit_code
 }
 
-// @todo Should return a Result variant too! Can be a Runtime Variant?
-/// Function to compile and run a SimpleScript program file
-fn run_file(filename: &String) {
+fn lint_file(file_path: &str, config_path: Option<&str>) -> Result<Vec<Diagnostic>, String> {
+    let source = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+    let config = match config_path {
+        Some(config_path) => Config::read(Path::new(config_path)),
+        None => Config::find(Path::new(file_path)),
+    };
+    config
+        .and_then(|config| linter::lint(&source, &config))
+        .map_err(|e| e.to_string())
+}
+
+/// Check if both paths are the same existing file
+fn is_same_file(path: &Path, other: &Path) -> bool {
+    match (fs::canonicalize(path), fs::canonicalize(other)) {
+        (Ok(path), Ok(other)) => path == other,
+        _ => false,
+    }
+}
+
+/// Function to compile and run a SimpleScript program file, returning a failure exit code on any error so that
+/// every back end exits the same way as rvm does
+fn run_file(args: &Cli) -> ExitCode {
+    let filename = file_path(args);
+
     // This reads the whole file into memory, however large the file may be.
     // Alternative is to use https://doc.rust-lang.org/1.39.0/std/io/struct.BufReader.html
     let source = fs::read_to_string(filename).expect("RuntimeError - File not found");
@@ -87,7 +183,7 @@ fn run_file(filename: &String) {
             }
 
             // Break out of the function
-            return;
+            return ExitCode::FAILURE;
         }
     };
 
@@ -107,7 +203,7 @@ fn run_file(filename: &String) {
             }
 
             // Break out of the function
-            return;
+            return ExitCode::FAILURE;
         }
     };
 
@@ -116,21 +212,103 @@ fn run_file(filename: &String) {
     if let Err(e) = Resolver::resolve(&mut ast) {
         eprintln!("-------- Resolver ERROR --------");
         eprintln!("{}", e);
-        return;
+        return ExitCode::FAILURE;
     }
 
     // Typecheck the AST and quit on error
     if let Err(e) = TypeChecker::check(&mut ast) {
         eprintln!("-------- TypeChecker ERROR --------");
         eprintln!("{}", e);
-        return;
+        return ExitCode::FAILURE;
     }
 
-    // @todo Interpreter can return a code, which will be used as the program exit code of the interpreter
-    // Interpret/Run the AST and quit on error
-    if let Some(err) = Interpreter::interpret(ast) {
-        eprintln!("-------- Interpreter ERROR --------");
-        eprintln!("{}", err);
-        return;
+    // Every back end except the interpreter and the vm back end compiles the program into a file instead of running it
+    let source_path = Path::new(filename);
+    let output = args.output.as_deref();
+    match args.backend {
+        Backend::Interpreter => {
+            // @todo Interpreter can return a code, which will be used as the program exit code of the interpreter
+            // Interpret/Run the AST and quit on error
+            if let Some(err) = Interpreter::interpret(ast) {
+                eprintln!("-------- Interpreter ERROR --------");
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+
+        // Generate a chunk from the AST and run it on rvm's VM instead of the interpreter
+        Backend::Vm => {
+            let chunk = match vm::compile(&ast) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    eprintln!("-------- Bytecode Generator ERROR --------");
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            // Runtime errors from rvm are already rendered with their own header and backtrace
+            if let Err(e) = rvm::vm::VM::interpret(chunk, DebugFlags::default(), JitFlags::default()) {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+
+        Backend::Native => {
+            let compile = |output: &Path| aot::compile(&ast, output);
+            write_output(source_path, output, "", "Compiled native executable", "Native Compilation", compile)
+        }
+        Backend::Js => {
+            let compile = |output: &Path| js::compile(&ast, source_path, output);
+            write_output(source_path, output, "js", "Transpiled JavaScript file", "Transpiler", compile)
+        }
+        Backend::Wasm => {
+            let compile = |output: &Path| wasm::compile(&ast, output);
+            write_output(source_path, output, "wasm", "Compiled WebAssembly module", "WebAssembly Compilation", compile)
+        }
+        Backend::C => {
+            let compile = |output: &Path| c::compile(&ast, source_path, output);
+            write_output(source_path, output, "c", "Generated C file", "C Generator", compile)
+        }
+    }
+}
+
+/// Generate the output file of a compiling back end with 'compile', and report the result of the back end,
+/// where 'header' is the name of the back end used for the header of its errors.
+///
+/// Without an output path, the file is written beside the source file with the back end's extension,
+/// or with the '.out' extension if that is the source file's path, e.g. native executables of source files without an extension.
+fn write_output<E: std::fmt::Display>(
+    source: &Path,
+    output: Option<&str>,
+    extension: &str,
+    message: &str,
+    header: &str,
+    compile: impl FnOnce(&Path) -> Result<(), E>,
+) -> ExitCode {
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None if source.with_extension(extension) == source => source.with_extension("out"),
+        None => source.with_extension(extension),
+    };
+
+    // Never overwrite the program with the generated file
+    let result = match is_same_file(source, &output) {
+        true => Err(format!("Output path '{}' is the source file", output.display())),
+        false => compile(&output).map_err(|e| e.to_string()),
+    };
+
+    match result {
+        Ok(()) => {
+            println!("{} '{}'", message, output.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("-------- {} ERROR --------", header);
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
     }
 }
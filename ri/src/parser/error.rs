@@ -14,6 +14,16 @@ pub enum ParsingError {
     UnexpectedEofError(Token),
 }
 
+impl ParsingError {
+    /// Get the token that caused the error, used by tools like the language server to locate the error
+    pub fn token(&self) -> &Token {
+        match self {
+            ParsingError::UnexpectedTokenError(ref token, _) => token,
+            ParsingError::UnexpectedEofError(ref token) => token,
+        }
+    }
+}
+
 impl fmt::Display for ParsingError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
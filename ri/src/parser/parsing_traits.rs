@@ -173,6 +173,7 @@ impl<'lifetime_of_tokens> Parser<'lifetime_of_tokens> {
     }
 
     fn print_statement(&mut self) -> Result<Stmt, ParsingError> {
+        let line_number = self.previous().line;
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after print expression")?;
         // Pass in the expression too to make it easier for user to fix the issue
@@ -180,7 +181,7 @@ impl<'lifetime_of_tokens> Parser<'lifetime_of_tokens> {
         //     TokenType::Semicolon,
         //     format!("Expect ';' after value {}", expr),
         // )?;
-        Ok(Stmt::Print(expr))
+        Ok(Stmt::Print(expr, line_number))
     }
 
     fn block_statement(&mut self) -> Result<Stmt, ParsingError> {
@@ -269,17 +270,17 @@ impl<'lifetime_of_tokens> Parser<'lifetime_of_tokens> {
     }
 
     fn ignore_statement(&mut self) -> Result<Stmt, ParsingError> {
-        // If the line number is needed for debugging purposes, take here and store in Stmt::Ignore
-        // let line_number = self.previous().line;
+        let line_number = self.previous().line;
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after ignore statement.")?;
-        Ok(Stmt::Ignore(expr))
+        Ok(Stmt::Ignore(expr, line_number))
     }
 
     fn expression_statement(&mut self) -> Result<Stmt, ParsingError> {
+        let line_number = self.current().line;
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after expression")?;
-        Ok(Stmt::Expr(expr))
+        Ok(Stmt::Expr(expr, line_number))
     }
 
     /* ==========================  End of statement methods  ========================== */
@@ -9,11 +9,14 @@ use crate::token::Token;
 // Might be able to do away with Clone trait if all use of Stmt is wrapped in Rc in the future
 #[derive(Debug, Clone)]
 pub enum Stmt {
-    Print(Expr),
+    /// Print(expression_to_print, print_keyword_line_number)
+    Print(Expr, usize),
 
+    /// Expr(expression, line_number_of_first_token)
+    ///
     /// A stmt that is just an expression with a semicolon.
     /// Usually evaluated for its side effects, e.g. a function call
-    Expr(Expr),
+    Expr(Expr, usize),
 
     /// Const(identifier_token, value)
     Const(Token, Expr),
@@ -49,20 +52,36 @@ pub enum Stmt {
     // @todo Expr does not need to be boxed here. Change it out
     Return(Box<Expr>, usize),
 
-    /// Ignore(expression_to_ignore)
+    /// Ignore(expression_to_ignore, ignore_keyword_line_number)
     ///
     /// This behaves semantically the same as Stmt::Expr(..) only difference being the evaluated value is discarded.
     /// Used to ignore evaluated value of an expression, be it a function call or literal number.
     /// This ensures that expression values are not forgotten mistakenly.
-    Ignore(Expr),
+    Ignore(Expr, usize),
+}
+
+impl Stmt {
+    /// Get the line that the stmt starts on, where blocks and anonymous functions do not store the line they start on
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Stmt::Print(_, line)
+            | Stmt::Expr(_, line)
+            | Stmt::If(_, _, _, line)
+            | Stmt::While(_, _, line)
+            | Stmt::Return(_, line)
+            | Stmt::Ignore(_, line) => Some(*line),
+            Stmt::Const(token, _) | Stmt::Func(token, _, _) => Some(token.line),
+            Stmt::Block(..) | Stmt::AnonymousFunc(..) => None,
+        }
+    }
 }
 
 // Temporary display trait for debugging
 impl std::fmt::Display for Stmt {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Stmt::Expr(ref expr) => write!(f, "{}", expr),
-            Stmt::Print(ref expr) => write!(f, "(print {})", expr),
+            Stmt::Expr(ref expr, _) => write!(f, "{}", expr),
+            Stmt::Print(ref expr, _) => write!(f, "(print {})", expr),
             Stmt::Const(ref token, ref expr) => {
                 write!(f, "(const {} {})", token.literal.as_ref().unwrap(), expr)
             }
@@ -84,7 +103,7 @@ impl std::fmt::Display for Stmt {
                 write!(f, "(funcall [anonymous] {:?} {})", parameters, body)
             }
             Stmt::Return(ref expr, _) => write!(f, "(return {})", expr),
-            Stmt::Ignore(ref expr) => write!(f, "(ignore {})", expr),
+            Stmt::Ignore(ref expr, _) => write!(f, "(ignore {})", expr),
         }
     }
 }
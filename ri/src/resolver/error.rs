@@ -12,10 +12,34 @@ pub enum ResolvingError {
     /// As a side effect, a "no-op" function cannot be defined in SS therefore if needed, it must be a native function
     EmptyBlockStatement(usize),
 
-    /// UnreachableCode(error_message_on_the_cause_of_unreachable_code)
+    /// UnreachableCode(line_number_of_halting_stmt, error_message_on_the_cause_of_unreachable_code)
     ///
     /// Generic error for any unreachable code caused by any type of halting stmt
-    UnreachableCode(String),
+    UnreachableCode(usize, String),
+}
+
+impl ResolvingError {
+    /// Get the token that caused the error if there is one, used by tools like the language server to locate the error
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            ResolvingError::UndefinedIdentifier(ref token)
+            | ResolvingError::IdentifierAlreadyUsed(ref token, _)
+            | ResolvingError::IdentifierAlreadyUsedGlobally(ref token, _) => Some(token),
+            _ => None,
+        }
+    }
+
+    /// Get the line number of the error
+    pub fn line(&self) -> usize {
+        match self {
+            ResolvingError::UndefinedIdentifier(ref token)
+            | ResolvingError::IdentifierAlreadyUsed(ref token, _)
+            | ResolvingError::IdentifierAlreadyUsedGlobally(ref token, _) => token.line,
+            ResolvingError::ReturnOutsideFunction(line_number)
+            | ResolvingError::EmptyBlockStatement(line_number)
+            | ResolvingError::UnreachableCode(line_number, _) => *line_number,
+        }
+    }
 }
 
 impl std::fmt::Display for ResolvingError {
@@ -42,7 +66,9 @@ impl std::fmt::Display for ResolvingError {
                 "[line {}] Cannot use `return` outside a function",
                 line_number
             ),
-            ResolvingError::UnreachableCode(message) => write!(f, "{}", message),
+            ResolvingError::UnreachableCode(line_number, message) => {
+                write!(f, "[line {}] {}", line_number, message)
+            }
             ResolvingError::EmptyBlockStatement(line_number) => write!(
                 f,
                 "[line {}] Empty block statements are not allowed",
@@ -24,7 +24,7 @@ pub struct Resolver {
 
 impl Resolver {
     // Associated function to resolve a AST
-    pub fn resolve(ast: &Vec<Stmt>) -> Result<(), ResolvingError> {
+    pub fn resolve(ast: &mut [Stmt]) -> Result<(), ResolvingError> {
         // Create resolver instance internally
         let mut resolver = Resolver {
             scopes: Vec::new(),
@@ -40,7 +40,7 @@ impl Resolver {
         // @todo Make it better then.. Cloning it because cannot have ref and mut ref to resolver at the same time....
         resolver.define_globals(resolver.globals.clone());
 
-        for stmt in ast {
+        for stmt in ast.iter_mut() {
             // Since return statements are illegal outside of a function body, statements in global scope can never be halting.
             // @todo Store all the errors and add synchronization point so that multiple errors can be found at once
             resolver.resolve_statement(stmt)?;
@@ -56,8 +56,8 @@ impl Resolver {
     /// Since statements can be halting, this method checks for unreachable statements if a statement is halting,
     /// regardless if function or none function block, make sure only the last stmt of this block is halting.
     /// Errors on unreachable code, else bubbles up the halting status of the last statement.
-    fn resolve_block_statement(&mut self, block_statement: &Stmt) -> Result<bool, ResolvingError> {
-        if let &Stmt::Block(ref stmts, optional_line_number) = block_statement {
+    fn resolve_block_statement(&mut self, block_statement: &mut Stmt) -> Result<bool, ResolvingError> {
+        if let Stmt::Block(ref mut stmts, optional_line_number) = block_statement {
             // Error on empty block statement, checked here instead of parser to avoid false positives. See parser for details.
             if stmts.is_empty() {
                 Err(ResolvingError::EmptyBlockStatement(
@@ -71,7 +71,8 @@ impl Resolver {
             } else {
                 // Loop through all the statements in block statement till the second last one,
                 // The last statement will be resolved and checked seperately as it does not need an unreachable code check
-                for stmt in stmts[..stmts.len() - 1].iter() {
+                let last_index = stmts.len() - 1;
+                for stmt in stmts[..last_index].iter_mut() {
                     let halting = self.resolve_statement(stmt)?;
 
                     // If statement is halting, there is unreachable code in this block statement since this cannot be the last statement
@@ -90,16 +91,16 @@ impl Resolver {
                         };
 
                         // Return error and stop resolving this path
-                        return Err(ResolvingError::UnreachableCode(format!(
-                            "[line {}] Unreachable code found after this {}",
-                            line_number, msg
-                        )));
+                        return Err(ResolvingError::UnreachableCode(
+                            *line_number,
+                            format!("Unreachable code found after this {}", msg),
+                        ));
                     }
                 }
 
                 // Get the last statement and unwrap it directly (it is garunteed to not be empty after parsing),
                 // Resolve the statement and return it's halting status as the halting status of the block statement.
-                self.resolve_statement(stmts.last().unwrap())
+                self.resolve_statement(stmts.last_mut().unwrap())
             }
         } else {
             panic!(
@@ -114,15 +115,15 @@ impl Resolver {
     ///
     /// Halting, refers to whether any other statements can still be executed after this statement.
     /// Halting statements contain return statements either directly or nested within, and all statements after return is unreachable.
-    fn resolve_statement(&mut self, stmt: &Stmt) -> Result<bool, ResolvingError> {
-        match *stmt {
+    fn resolve_statement(&mut self, stmt: &mut Stmt) -> Result<bool, ResolvingError> {
+        match stmt {
             // No expression is halting, so by extension, the expression stmt is not halting
             // Why are unused values caught in type checker instead of resolver?
             // Because although we dont allow Expression statements like, `1 + 1`
             // Expression statements like `call_function()` should be allowed
             // And since resolver have no way of telling if `1 + 1` produces a value or if call_function() does,
             // Type checker is used as it can already get the type back, which can be used to make ignore a part of the type system
-            Stmt::Expr(ref expr) => self.resolve_expression(expr)?,
+            Stmt::Expr(ref mut expr, _) => self.resolve_expression(expr)?,
             // A block stmt can contain nested return statements, therefore a block stmt can be halting
             Stmt::Block(_, _) => {
                 self.begin_scope();
@@ -138,7 +139,7 @@ impl Resolver {
             // Const definitions are not halting, even when used to bind an anonymous function.
             // Because nested return(s) within anonymous functions does not halt the code within the const binding's scope.
             // i.e. a const definition is not halting at its scope depth as it is unaffected by nested halting code.
-            Stmt::Const(ref token, ref expr) => {
+            Stmt::Const(ref token, ref mut expr) => {
                 self.declare(token)?;
                 self.resolve_expression(expr)?;
                 self.define(token);
@@ -146,12 +147,12 @@ impl Resolver {
 
             // Functions are self contained, so they are not halting, even if there is a return statement within it.
             // That return statement means that it is halting at that point in the inner function body, not the outer block.
-            Stmt::Func(ref token, ref params, ref body) => {
+            Stmt::Func(ref token, ref params, ref mut body) => {
                 // Declare and define to allow function to refer to itself recursively
                 self.declare_and_define(token)?;
                 self.resolve_function(params, body)?;
             }
-            Stmt::AnonymousFunc(ref params, ref body) => {
+            Stmt::AnonymousFunc(ref params, ref mut body) => {
                 // Unlike Stmt::Func, dont need to declare and define since Anonymous Functions are nameless, and will be bound to a Const identifier
                 self.resolve_function(params, body)?;
             }
@@ -163,11 +164,11 @@ impl Resolver {
             // since the condition may be evaluated to false.
             // However if both if and else branches are defined, it means that the execution path MUST go down either of the branches.
             // In that case the statement as a whole is halting, if both the if and else branches are halting.
-            Stmt::If(ref condition, ref then_branch, ref else_branch, _) => {
+            Stmt::If(ref mut condition, ref mut then_branch, ref mut else_branch, _) => {
                 self.resolve_expression(condition)?;
                 // Unwrap to get halting status of branch body for comparison
                 let then_branch_is_halting = self.resolve_statement(then_branch)?;
-                if let Some(ref else_branch) = else_branch {
+                if let Some(ref mut else_branch) = else_branch {
                     // Unwrap to get halting status of branch body for comparison
                     let else_branch_is_halting = self.resolve_statement(else_branch)?;
                     // If both branches are halting then this if stmt is halting, where True && True == True
@@ -175,13 +176,13 @@ impl Resolver {
                 }
             }
 
-            Stmt::Print(ref expr) => self.resolve_expression(expr)?,
+            Stmt::Print(ref mut expr, _) => self.resolve_expression(expr)?,
 
             // Return statement is halting by definition
-            Stmt::Return(ref expr, line_number) => {
+            Stmt::Return(ref mut expr, line_number) => {
                 // If not in any function, return statements are not allowed
                 if !self.in_function {
-                    return Err(ResolvingError::ReturnOutsideFunction(line_number));
+                    return Err(ResolvingError::ReturnOutsideFunction(*line_number));
                 }
 
                 self.resolve_expression(expr)?;
@@ -194,10 +195,10 @@ impl Resolver {
             // Ignore statements are used to ignore evaluated values of expressions,
             // And since expressions are not halting by default, a ignore statement cannot be halting too
             // It is resolved the same as Stmt::Expr
-            Stmt::Ignore(ref expr) => self.resolve_expression(expr)?,
+            Stmt::Ignore(ref mut expr, _) => self.resolve_expression(expr)?,
 
             // While loops are halting if the loop body is halting. i.e. if there is a return statement within the loop body
-            Stmt::While(ref condition, ref body, _) => {
+            Stmt::While(ref mut condition, ref mut body, _) => {
                 self.resolve_expression(condition)?;
                 // The returned value does not need to be unwrapped since this nested halting status is bubbled up immediately
                 return self.resolve_statement(body);
@@ -209,57 +210,50 @@ impl Resolver {
     }
 
     // All expressions are none halting, so there is no need for this method to return a halting indicator
-    fn resolve_expression(&mut self, expr: &Expr) -> Result<(), ResolvingError> {
-        match *expr {
-            Expr::Const(ref token, ref distance_value_in_ast_node) => {
-                let distance = self.resolve_identifier_distance(token)?;
-
-                // @todo UNSAFE WAY used temporarily for testing! See alternatives below
-                unsafe {
-                    let mutable_pointer = distance_value_in_ast_node as *const usize as *mut usize;
-                    *mutable_pointer = distance;
-                }
-                // Alternative 1 is to call resolve_expression with mut reference to the expression
-                // *distance_value_in_ast_node = self.resolve_identifier_distance(token.lexeme.as_ref().unwrap().clone());
+    fn resolve_expression(&mut self, expr: &mut Expr) -> Result<(), ResolvingError> {
+        match expr {
+            Expr::Const(ref token, ref mut distance_value_in_ast_node) => {
+                // Save the distance directly into the AST node, since resolve_expression is called with a mut reference to the expression
+                *distance_value_in_ast_node = self.resolve_identifier_distance(token)?;
 
-                // Alternative 2 is to save distance value into a side table instead of saving directly into the AST node
+                // Alternative is to save distance value into a side table instead of saving directly into the AST node
                 // Problem with this is we cannot have identifiers of the same name, even in different scopes if using identifier string as key
                 // Perhaps use the string and line number? But this will prevent minification....
                 // let identifier = token.lexeme.as_ref().unwrap();
                 // side_table.insert(identifier.clone(), self.resolve_identifier_distance(identifier.clone()));
             }
-            Expr::AnonymousFunc(ref stmt) => {
+            Expr::AnonymousFunc(ref mut stmt) => {
                 // Expr::AnonymousFunc is a wrapper for Stmt::AnonymousFunc, thus use resolve_statement to handle Stmt::AnonymousFunc
                 self.resolve_statement(stmt)?;
             }
-            Expr::Binary(ref left, _, ref right) => {
+            Expr::Binary(ref mut left, _, ref mut right) => {
                 self.resolve_expression(left)?;
                 self.resolve_expression(right)?;
             }
-            Expr::Call(ref callee, ref arguments, _) => {
+            Expr::Call(ref mut callee, ref mut arguments, _) => {
                 self.resolve_expression(callee)?;
 
-                for ref arg in arguments {
+                for arg in arguments.iter_mut() {
                     self.resolve_expression(arg)?;
                 }
             }
-            Expr::Grouping(ref expr) => self.resolve_expression(expr)?,
+            Expr::Grouping(ref mut expr) => self.resolve_expression(expr)?,
             Expr::Literal(_) => {}
-            Expr::Array(_, ref elements) => {
+            Expr::Array(_, ref mut elements) => {
                 // Resolve for every single element in the array, where all elements are expressions
                 for element in elements {
                     self.resolve_expression(element)?;
                 }
             }
-            Expr::ArrayAccess(ref array, ref index_expression) => {
+            Expr::ArrayAccess(ref mut array, ref mut index_expression) => {
                 self.resolve_expression(array)?;
                 self.resolve_expression(index_expression)?;
             }
-            Expr::Logical(ref left, _, ref right) => {
+            Expr::Logical(ref mut left, _, ref mut right) => {
                 self.resolve_expression(left)?;
                 self.resolve_expression(right)?;
             }
-            Expr::Unary(_, ref expr) => {
+            Expr::Unary(_, ref mut expr) => {
                 self.resolve_expression(expr)?;
             }
         };
@@ -300,7 +294,7 @@ impl Resolver {
     fn resolve_function(
         &mut self,
         param_tokens: &Vec<Token>,
-        body: &Stmt,
+        body: &mut Stmt,
     ) -> Result<(), ResolvingError> {
         // Save parent status first before assigning in_function as true
         let is_parent_in_function = self.in_function;
@@ -0,0 +1,20 @@
+/// A comment in the source, which is only kept by `Scanner::scan_tokens_and_comments` as comments are not part of the AST
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// Text of the comment including its '//' or '/* */' delimiters, which can span multiple lines for block comments
+    pub text: String,
+
+    /// Line and column of the comment's first character, starting from 1 like the ones of tokens
+    pub line: usize,
+    pub column: usize,
+
+    /// Block comments can be placed between tokens on the same line, while line comments always end their line
+    pub is_block: bool,
+}
+
+impl Comment {
+    /// Line of the comment's last character
+    pub fn end_line(&self) -> usize {
+        self.line + self.text.matches('\n').count()
+    }
+}
@@ -2,6 +2,7 @@ use std;
 
 pub struct ScannerError {
     pub line: usize,
+    pub column: usize,
     pub description: String,
 }
 
@@ -1,3 +1,4 @@
+pub mod comment;
 mod error;
 pub mod scanner;
 pub mod scanner_struct;
@@ -1,5 +1,6 @@
 //! Scanner module to scan source file for a vector of tokens
 
+use super::comment::Comment;
 use super::error::ScannerError;
 use super::scanner_struct::Scanner;
 
@@ -11,6 +12,17 @@ use crate::token_type::TokenType;
 impl Scanner {
     /// Move ownership of source string into Scanner struct here
     pub fn scan_tokens(source: String) -> Result<Vec<Token>, Vec<ScannerError>> {
+        Scanner::scan(source, false).map(|(tokens, _)| tokens)
+    }
+
+    /// Scan the source string for its tokens and also keep its comments, which are discarded by `scan_tokens`.
+    /// Together with the line and column of every token, this is enough to reproduce the source for tools like the formatter.
+    pub fn scan_tokens_and_comments(source: String) -> Result<(Vec<Token>, Vec<Comment>), Vec<ScannerError>> {
+        Scanner::scan(source, true)
+    }
+
+    /// Scan the source string, where the comments are empty if they are not kept
+    fn scan(source: String, keep_comments: bool) -> Result<(Vec<Token>, Vec<Comment>), Vec<ScannerError>> {
         let mut tokens: Vec<Token> = Vec::<Token>::new();
         let mut errors: Vec<ScannerError> = Vec::<ScannerError>::new();
 
@@ -20,6 +32,8 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            comments: if keep_comments { Some(Vec::new()) } else { None },
         };
 
         // Scan token by token
@@ -42,11 +56,15 @@ impl Scanner {
         }
 
         // Add Eof token
-        tokens.push(Token::new_none_literal(TokenType::Eof, scanner.line));
+        tokens.push(Token::new_none_literal(
+            TokenType::Eof,
+            scanner.line,
+            scanner.current - scanner.line_start + 1,
+        ));
 
         // Return token vector only if there are no errors
         if errors.is_empty() {
-            Ok(tokens)
+            Ok((tokens, scanner.comments.unwrap_or_default()))
         } else {
             // Return errors if any and have the caller handle it
             // Might handle it differently depending on how many files are there for the program.
@@ -84,16 +102,21 @@ impl Scanner {
 
                 match keyword_token_type {
                     // If it is a keyword, we use that keyword's token type.
-                    Some(token_type) => Some(Token::new_keyword(token_type, self.line)),
+                    Some(token_type) => Some(Token::new_keyword(token_type, self.line, self.column())),
 
                     // Otherwise, it's a regular user-defined identifier.
-                    None => Some(Token::new_identifier(identifier.to_string(), self.line)),
+                    None => Some(Token::new_identifier(
+                        identifier.to_string(),
+                        self.line,
+                        self.column(),
+                    )),
                 }
             }
 
             // Newline characters causes line number to be incremented before being eaten and discarded
             '\n' => {
                 self.line += 1;
+                self.line_start = self.current;
                 None
             }
 
@@ -132,6 +155,7 @@ impl Scanner {
                 while self.peek() != '\n' && !self.is_at_end() {
                     self.current += 1;
                 }
+                self.save_comment(self.line, self.column(), false);
 
                 /* Optimization:
                    Technically this is not needed, because if the next character is a new line,
@@ -145,6 +169,7 @@ impl Scanner {
                 if self.peek() == '\n' {
                     self.current += 1;
                     self.line += 1;
+                    self.line_start = self.current;
                 }
 
                 None
@@ -152,6 +177,10 @@ impl Scanner {
 
             // Block Comment, comment that can span multiline lines
             '/' if self.conditional_advance('*') => {
+                // Save the line and column of the start of the comment, as the comment can span multiple lines
+                let line = self.line;
+                let column = self.column();
+
                 // Loop till the end of comment block
                 loop {
                     // Stop if the next 2 characters are '*/', OR if it is at EOF
@@ -162,6 +191,7 @@ impl Scanner {
                     // Advance, AND if current char is a newline, increment line count
                     if self.advance() == '\n' {
                         self.line += 1;
+                        self.line_start = self.current;
                     }
                 }
 
@@ -179,6 +209,7 @@ impl Scanner {
                 // Advance current character pointer 2 more times to eat the ending star and slash characters.
                 self.current += 1;
                 self.current += 1;
+                self.save_comment(line, column, true);
 
                 /* Optimization:
                    Technically this is not needed, because if the next character is a new line,
@@ -192,6 +223,7 @@ impl Scanner {
                 if self.peek() == '\n' {
                     self.current += 1;
                     self.line += 1;
+                    self.line_start = self.current;
                 }
 
                 None
@@ -201,11 +233,15 @@ impl Scanner {
 
             // String Literals
             '"' => {
+                // Get the column before scanning, as the string can span multiple lines
+                let column = self.column();
+
                 while self.peek() != '"' && !self.is_at_end() {
                     // Allow multiline strings.
                     // @todo Is extra processing needed to remove the \n from the final string? Or keep as is?
                     if self.peek() == '\n' {
                         self.line += 1;
+                        self.line_start = self.current + 1;
                     }
 
                     self.current += 1;
@@ -223,6 +259,7 @@ impl Scanner {
                 Some(Token::new_string(
                     self.source[self.start + 1..self.current - 1].to_string(),
                     self.line,
+                    column,
                 ))
             }
 
@@ -251,6 +288,7 @@ impl Scanner {
                         .parse::<f64>()
                         .unwrap(),
                     self.line,
+                    self.column(),
                 ))
             }
 
@@ -259,6 +297,7 @@ impl Scanner {
             _ => {
                 return Err(ScannerError {
                     line: self.line,
+                    column: self.column(),
                     description: format!(
                         "Unexpected character '{}' on line {}",
                         current_character, self.line
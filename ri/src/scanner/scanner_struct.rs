@@ -1,3 +1,5 @@
+use super::comment::Comment;
+
 /// Scanner struct data structure holding all the data related to the scanner while scanning.
 /// All integer fields are limited by the size of an unsigned integer of the target system.
 pub struct Scanner {
@@ -12,4 +14,10 @@ pub struct Scanner {
 
     /// This tracks the line scanner is currently on in the source file to produce tokens that know their location and for error reporting
     pub line: usize,
+
+    /// Index of the first character of the current line, used to calculate the column of tokens
+    pub line_start: usize,
+
+    /// Comments scanned so far, only kept when scanning for tools like the formatter that need to reproduce the source
+    pub comments: Option<Vec<Comment>>,
 }
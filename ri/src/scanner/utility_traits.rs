@@ -1,3 +1,4 @@
+use super::comment::Comment;
 use super::scanner_struct::Scanner;
 
 use crate::token::Token;
@@ -57,10 +58,29 @@ impl Scanner {
         }
     }
 
+    /// Column of the first character in the lexeme being scanned, starting from 1
+    pub fn column(&self) -> usize {
+        self.start - self.line_start + 1
+    }
+
+    /// Save the comment being scanned if comments are kept, including its '//' or '/* */' delimiters
+    pub fn save_comment(&mut self, line: usize, column: usize, is_block: bool) {
+        if let Some(ref mut comments) = self.comments {
+            // Unterminated block comments end at the end of the source
+            let end = self.current.min(self.source.len());
+            comments.push(Comment {
+                text: self.source[self.start..end].trim_end_matches('\r').to_string(),
+                line,
+                column,
+                is_block,
+            });
+        }
+    }
+
     /// Simple wrapper around Token::new_none_literal to simplify none literal token creation.
     /// This is a inlined method as alot of places reuse this syntax only with different token types.
     #[inline]
     pub fn new_none_literal(&self, token_type: TokenType) -> Option<Token> {
-        Some(Token::new_none_literal(token_type, self.line))
+        Some(Token::new_none_literal(token_type, self.line, self.column()))
     }
 }
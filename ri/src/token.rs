@@ -14,53 +14,61 @@ pub struct Token {
     // As this is reused very often and it is tedious to extract from Option<Literal::String> type every single time
     pub lexeme: Option<String>,
     pub line: usize,
+
+    // Column of the token's first character in its line, starting from 1, used by tools like the language server
+    pub column: usize,
 }
 
 // For all the methods, should lexeme and line come first since they are always needed
 impl Token {
-    pub fn new_none_literal(token_type: TokenType, line: usize) -> Token {
+    pub fn new_none_literal(token_type: TokenType, line: usize, column: usize) -> Token {
         Token {
             token_type,
             literal: None,
             lexeme: None,
             line,
+            column,
         }
     }
 
-    pub fn new_keyword(token_type: TokenType, line: usize) -> Token {
+    pub fn new_keyword(token_type: TokenType, line: usize, column: usize) -> Token {
         Token {
             token_type,
             literal: None,
             lexeme: None,
             line,
+            column,
         }
     }
 
-    pub fn new_identifier(lexeme: String, line: usize) -> Token {
+    pub fn new_identifier(lexeme: String, line: usize, column: usize) -> Token {
         Token {
             token_type: TokenType::Identifier,
             literal: None,
             lexeme: Some(lexeme),
             line,
+            column,
         }
     }
 
-    pub fn new_string(lexeme: String, line: usize) -> Token {
+    pub fn new_string(lexeme: String, line: usize, column: usize) -> Token {
         Token {
             token_type: TokenType::Str,
             literal: Some(Literal::String(lexeme)),
             lexeme: None,
             line,
+            column,
         }
     }
 
-    pub fn new_number(number: f64, line: usize) -> Token {
+    pub fn new_number(number: f64, line: usize, column: usize) -> Token {
         Token {
             token_type: TokenType::Number,
             // Treating all numbers as f64 type for now
             literal: Some(Literal::Number(number)),
             lexeme: None,
             line,
+            column,
         }
     }
 
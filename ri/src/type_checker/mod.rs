@@ -3,5 +3,6 @@ mod structs;
 mod type_checker;
 mod type_table;
 
+pub use structs::Inference;
 pub use structs::Type;
 pub use structs::TypeChecker;
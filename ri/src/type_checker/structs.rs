@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use super::error::TypeError;
 use super::type_table::TypeTable;
 
 use crate::parser::stmt::Stmt;
@@ -27,6 +28,22 @@ pub struct TypeChecker {
     /// and thus the type checker should return Type::Lazy immediately as the type of the recursive function call,
     /// to make all checks against this recursive function call as valid, until it can actually be type checked with concrete types.
     pub current_function: Option<Token>,
+
+    /// Types inferred for the identifier tokens of definitions and usages, in the order they are type checked.
+    /// Only recorded when requested by tools like the language server, since function types hold a copy of their AST node.
+    pub inferred_types: Option<Vec<(Token, Type)>>,
+
+    /// Line number of the last token type checked, used to locate type errors since they do not store a line number
+    pub line: usize,
+}
+
+/// Result of `TypeChecker::check_and_infer`
+pub struct Inference {
+    /// Type inferred for the identifier token of every definition and usage, in the order they are type checked
+    pub types: Vec<(Token, Type)>,
+
+    /// The type error and the line number it was found on, if type checking failed
+    pub error: Option<(usize, TypeError)>,
 }
 
 /// An enum of all possible types of values in SS
@@ -1,5 +1,6 @@
 use super::error::TypeError;
 use super::type_table::TypeTable;
+use super::Inference;
 use super::Type;
 use super::TypeChecker;
 
@@ -28,6 +29,9 @@ impl TypeChecker {
 
             // Global scope is not a function unlike languages like C where there is a main function as entry point
             current_function: None,
+
+            inferred_types: None,
+            line: 1,
         };
 
         // @todo Add a synchronization method, to prevent type checker from quitting on first error, and instead, check other errors and return all via an array
@@ -36,6 +40,36 @@ impl TypeChecker {
         Ok(())
     }
 
+    /// Type check a AST just like `check`, while recording the inferred type of every identifier definition and usage.
+    ///
+    /// The recorded types are returned even if type checking failed, since the types recorded before the error are still valid.
+    pub fn check_and_infer(ast: &Vec<Stmt>) -> Inference {
+        let mut type_checker = TypeChecker {
+            types: Rc::new(RefCell::new(TypeTable::global())),
+            closure_types: None,
+            current_function: None,
+            inferred_types: Some(Vec::new()),
+            line: 1,
+        };
+
+        let error = type_checker
+            .check_ast(ast)
+            .err()
+            .map(|error| (type_checker.line, error));
+
+        Inference {
+            types: type_checker.inferred_types.unwrap(),
+            error,
+        }
+    }
+
+    /// Record the inferred type of an identifier token if types are being recorded
+    fn record_type(&mut self, token: &Token, value_type: &Type) {
+        if let Some(ref mut inferred_types) = self.inferred_types {
+            inferred_types.push((token.clone(), value_type.clone()));
+        }
+    }
+
     /// Type check statements 1 by 1 by iterating through the vec of statements instead of calling this recursively for efficiency
     fn check_ast(&mut self, ast: &Vec<Stmt>) -> Result<Type, TypeError> {
         for ref stmt in ast {
@@ -65,7 +99,7 @@ impl TypeChecker {
     fn check_statement(&mut self, stmt: &Stmt) -> Result<Type, TypeError> {
         // Any stmt that resolves into a Type, will have to manually return it
         match *stmt {
-            Stmt::Expr(ref expr) => {
+            Stmt::Expr(ref expr, _) => {
                 // @todo Why need to return here? Is it because a return stmt can be nested?
                 return self.check_expression(expr);
             }
@@ -96,8 +130,11 @@ impl TypeChecker {
                 }
             }
             Stmt::Const(ref identifier_token, ref expr) => {
+                self.line = identifier_token.line;
+
                 // Must be split to prevent borrow as mutable when also borrowed as immutable
                 let expr_type = self.check_expression(expr)?;
+                self.record_type(identifier_token, &expr_type);
                 self.types
                     .borrow_mut()
                     .define(identifier_token.lexeme.as_ref().unwrap().clone(), expr_type);
@@ -111,6 +148,8 @@ impl TypeChecker {
                     Type::Func(params.len(), Box::new(stmt.clone()), Rc::clone(&self.types));
 
                 let identifier_string = identifier_token.lexeme.as_ref().unwrap();
+                self.line = identifier_token.line;
+                self.record_type(identifier_token, &function_type);
 
                 // Add function to type table before type checking function body to allow function to refer to itself recursively.
                 self.types
@@ -155,7 +194,8 @@ impl TypeChecker {
                 // Return function_type as the type of this function definition
                 return Ok(function_type);
             }
-            Stmt::If(ref condition, ref then_branch, ref else_branch, _) => {
+            Stmt::If(ref condition, ref then_branch, ref else_branch, line_number) => {
+                self.line = line_number;
                 let type_found = self.check_expression(condition)?;
                 if type_found != Type::Bool {
                     return Err(TypeError::WithDynamicMessage(format!(
@@ -211,23 +251,26 @@ impl TypeChecker {
                     });
                 }
             }
-            Stmt::Print(ref expr) => {
+            Stmt::Print(ref expr, _) => {
                 // This cannot be skipped because even though print accepts all types, the expression needs to be type checked first
                 // E.g. the expression can be a 5 == "string", and this needs to be checked, even if the expression type is unused
                 // The type of the expression is ignored, but the ? operator is used to allow errors to bubble up
                 self.check_expression(expr)?;
             }
-            Stmt::Return(ref expr, _) => {
+            Stmt::Return(ref expr, line_number) => {
+                self.line = line_number;
+
                 // Get the type of the return expression,
                 // Wrap it in a Return type and Ok variant to bubble it up
                 return Ok(Type::Return(Box::new(self.check_expression(expr)?)));
             }
             // Ignore statements are used to ignore evaluated values of expressions,
             // Which is done by type checking the expression and only bubbling up errors if needed.
-            Stmt::Ignore(ref expr) => {
+            Stmt::Ignore(ref expr, _) => {
                 self.check_expression(expr)?;
             }
-            Stmt::While(ref condition, ref body, _) => {
+            Stmt::While(ref condition, ref body, line_number) => {
+                self.line = line_number;
                 return match self.check_expression(condition)? {
                     // If there are any return statements within loop, the type will be bubbled up.
                     Type::Bool => self.check_statement(body),
@@ -248,6 +291,8 @@ impl TypeChecker {
     fn check_expression(&mut self, expr: &Expr) -> Result<Type, TypeError> {
         Ok(match *expr {
             Expr::Const(ref token, _) => {
+                self.line = token.line;
+
                 // Use lexeme from token as identifier
                 let identifier_string = token.lexeme.as_ref().unwrap();
 
@@ -256,7 +301,7 @@ impl TypeChecker {
                 // Therefore if type not found, it is a internal type table programming logic error
 
                 // @todo Look for type in closure first or current env first?
-                if let Some(value_type) = self.types.borrow().get_type(identifier_string) {
+                let value_type = if let Some(value_type) = self.types.borrow().get_type(identifier_string) {
                     value_type
                 } else if let Some(ref closure_types) = self.closure_types {
                     if let Some(value_type) = closure_types.borrow().get_type(identifier_string) {
@@ -272,7 +317,10 @@ impl TypeChecker {
                         "InternalError: Type of '{}' is not found in current environment and there is no closure environment",
                         identifier_string
                     );
-                }
+                };
+
+                self.record_type(token, &value_type);
+                value_type
             }
 
             // Expr::AnonymousFunc is a wrapper for Stmt::AnonymousFunc, thus use check_statement to handle Stmt::AnonymousFunc
@@ -281,6 +329,7 @@ impl TypeChecker {
             // @todo Add new arithmetic expr to split this up, so that the operator check can be skipped
             // Binary expressions holds both equality/inequality checks, and arithmetic operations
             Expr::Binary(ref left, ref operator, ref right) => {
+                self.line = operator.line;
                 let l_type = self.check_expression(left)?;
                 let r_type = self.check_expression(right)?;
 
@@ -360,7 +409,9 @@ impl TypeChecker {
                 check(1, 1);
                 check("s1", "s2");
             */
-            Expr::Call(ref callee_identifier_expr, ref arguments, _) => {
+            Expr::Call(ref callee_identifier_expr, ref arguments, ref closing_paren) => {
+                self.line = closing_paren.line;
+
                 // If this resolves to a valid Type::Func(..), then extract the tuple's value.
                 let (number_of_parameters, function_stmt, closure_types) =
                     match self.check_expression(callee_identifier_expr)? {
@@ -468,7 +519,8 @@ impl TypeChecker {
                     }
                 }
             }
-            Expr::Logical(ref left, _, ref right) => {
+            Expr::Logical(ref left, ref operator, ref right) => {
+                self.line = operator.line;
                 let l_type = self.check_expression(left)?;
                 let r_type = self.check_expression(right)?;
 
@@ -484,6 +536,7 @@ impl TypeChecker {
                 }
             }
             Expr::Unary(ref operator, ref expr) => {
+                self.line = operator.line;
                 let expr_type = self.check_expression(expr)?;
 
                 match &operator.token_type {
@@ -591,6 +644,18 @@ impl TypeChecker {
             }
         }
 
+        // Record the types of the parameters, which are the argument types when type checking a function call
+        if self.inferred_types.is_some() {
+            for param_token in param_tokens {
+                let param_type = self
+                    .types
+                    .borrow()
+                    .get_type(param_token.lexeme.as_ref().unwrap())
+                    .unwrap();
+                self.record_type(param_token, &param_type);
+            }
+        }
+
         // Assuming most functions only have 1 return statement
         let mut return_types = Vec::<Type>::with_capacity(1);
 
@@ -2,7 +2,7 @@ use crate::callables::Callable;
 use crate::environment::environment::Environment;
 use crate::interpreter::error::RuntimeError;
 use crate::parser::stmt::Stmt;
-use crate::Interpreter;
+use crate::interpreter::interpreter::Interpreter;
 
 use super::value::Value;
 
@@ -31,6 +31,14 @@ impl Function {
             closure,
         }
     }
+
+    // Name of the function, used by the debugger for the function's stack frames
+    pub fn name(&self) -> &str {
+        match &self.declaration {
+            Stmt::Func(ref name_token, _, _) => name_token.lexeme.as_deref().unwrap_or_default(),
+            _ => "[anonymous]",
+        }
+    }
 }
 
 impl Callable for Function {
@@ -114,7 +122,13 @@ impl Callable for Function {
         // When there is a return statement, the return arm of interpret_stmt returns a value wrapped in the Value::Return variant.
         // This extra code wrapping interpret_block ensures that function.call of Callable.call trait ALWAYS returns a value,
         // And the value returned will never be a Value::Return variant
-        Ok(match interpreter.interpret_block(body, environment)? {
+        //
+        // The debugger is notified around the call for it to track the call stack, for stepping over, into and out of calls
+        interpreter.enter_function(self.name());
+        let result = interpreter.interpret_block(body, environment)?;
+        interpreter.exit_function();
+
+        Ok(match result {
             Some(result) => match result {
                 Value::Return(value) => *value,
                 _ => Value::Null,
@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+
+use rvm::chunk::{Chunk, Function};
+use rvm::line_table::SourceLocation;
+use rvm::opcode::OpCode;
+use rvm::value::Value;
+
+use super::VmError;
+
+use crate::literal::Literal;
+use crate::parser::expr::Expr;
+use crate::parser::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// Local on the stack, where the index of the local in the generator's locals is its stack index
+struct Local {
+    name: String,
+
+    /// Scope depth that the local is declared in
+    depth: usize,
+
+    /// Constant pool index of the function value, if the local is a function definition.
+    /// Function values do not capture anything, so nested function bodies load them as constants instead of reading the stack.
+    function_constant: Option<u16>,
+}
+
+/// While loop surrounding the statement being generated
+struct Loop {
+    /// Byte offset of the loop condition
+    start: usize,
+
+    /// Number of locals on the stack when the loop body starts
+    number_of_locals: usize,
+}
+
+/// Generates a rvm chunk from the AST, mirroring the stack layout of the chunks generated by rvm's own compiler
+pub struct BytecodeGenerator {
+    chunk: Chunk,
+
+    /// Locals of every scope from the outermost block to the current scope, mirroring what the VM's stack will hold at runtime
+    locals: Vec<Local>,
+
+    /// HashMap<global_identifier, global_slot> of every global that has been defined or referenced so far
+    globals: HashMap<String, u16>,
+
+    /// HashMap<global_identifier, line_number> of globals referenced in function bodies before they are defined.
+    /// The resolver only lets the prelude's native functions be used without being defined, so any global left in here
+    /// at the end of the program is a native function that rvm does not have.
+    undefined_globals: HashMap<String, usize>,
+
+    /// Number of blocks and function bodies surrounding the current statement, where 0 is the global scope
+    scope_depth: usize,
+
+    /// Stack base of the function bodies being generated, where the last one is the innermost function body
+    function_stack_bases: Vec<usize>,
+
+    /// While loops surrounding the current statement in the innermost function body, from the outermost loop
+    loops: Vec<Loop>,
+
+    /// Line of the last statement or token generated, used as the source location of every instruction emitted
+    line: usize,
+}
+
+impl BytecodeGenerator {
+    /// Generate the chunk, where the top level statements run from the start of the chunk till its end
+    pub fn generate(ast: &Vec<Stmt>) -> Result<Chunk, VmError> {
+        let mut generator = BytecodeGenerator {
+            chunk: Chunk::new(),
+            locals: Vec::new(),
+            globals: HashMap::new(),
+            undefined_globals: HashMap::new(),
+            scope_depth: 0,
+            function_stack_bases: Vec::new(),
+            loops: Vec::new(),
+            line: 1,
+        };
+
+        for stmt in ast {
+            generator.statement(stmt)?;
+        }
+
+        // Report the first native function used, by its first usage
+        if let Some((identifier, line)) = generator.undefined_globals.into_iter().min_by_key(|(_, line)| *line) {
+            return Err(VmError::Codegen(
+                line,
+                format!("Native function '{}' of the prelude is not available in rvm", identifier),
+            ));
+        }
+
+        Ok(generator.chunk)
+    }
+
+    /* ==========================  Start of emit helpers  ========================== */
+
+    fn location(&self) -> SourceLocation {
+        SourceLocation {
+            line: self.line,
+            column: 0,
+        }
+    }
+
+    fn error(&self, message: String) -> VmError {
+        VmError::Codegen(self.line, message)
+    }
+
+    fn emit_code(&mut self, code: OpCode) {
+        self.chunk.write_code(code, self.location());
+    }
+
+    /// Emit an opcode followed by its single byte operand
+    fn emit_code_with_byte(&mut self, code: OpCode, operand: u8) {
+        self.emit_code(code);
+        self.chunk.write(operand, self.location());
+    }
+
+    /// Emit an opcode followed by its 2 bytes wide operand
+    fn emit_code_with_u16(&mut self, code: OpCode, operand: u16) {
+        self.emit_code(code);
+        self.chunk.write_u16(operand, self.location());
+    }
+
+    /// Add value to the chunk's constant pool, and return its index, ensuring that it can be encoded as an operand
+    fn make_constant(&mut self, value: Value) -> Result<u16, VmError> {
+        let constant_index = self.chunk.add_constant(value);
+        if constant_index > u16::MAX as usize {
+            return Err(self.error("Too many constants in one chunk".to_string()));
+        }
+
+        Ok(constant_index as u16)
+    }
+
+    /// Emit a CONSTANT opcode to load the constant onto the stack, using CONSTANT_LONG if the index cannot fit in a single byte
+    fn emit_constant_index(&mut self, constant_index: u16) {
+        if constant_index <= u8::MAX as u16 {
+            self.emit_code_with_byte(OpCode::CONSTANT, constant_index as u8);
+        } else {
+            self.emit_code_with_u16(OpCode::CONSTANT_LONG, constant_index);
+        }
+    }
+
+    fn emit_constant(&mut self, value: Value) -> Result<(), VmError> {
+        let constant_index = self.make_constant(value)?;
+        self.emit_constant_index(constant_index);
+        Ok(())
+    }
+
+    /// Emit a JUMP type opcode with a placeholder offset, and return the byte offset of its operand to patch later
+    fn emit_jump(&mut self, code: OpCode) -> usize {
+        self.emit_code_with_u16(code, 0);
+        self.chunk.codes.len() - 2
+    }
+
+    /// Patch the JUMP type opcode whose operand is at the byte offset, to jump to the end of the chunk
+    fn patch_jump(&mut self, operand_offset: usize) -> Result<(), VmError> {
+        // -2 to adjust for the jump offset operand itself, since VM's ip will be after the operand when jumping
+        let jump = self.chunk.codes.len() - operand_offset - 2;
+        if jump > u16::MAX as usize {
+            return Err(self.error("Too much code to jump over".to_string()));
+        }
+
+        self.chunk.codes[operand_offset] = (jump >> 8) as u8;
+        self.chunk.codes[operand_offset + 1] = jump as u8;
+        Ok(())
+    }
+
+    /// Emit a LOOP opcode to jump backwards to the byte offset of the loop start
+    fn emit_loop(&mut self, loop_start: usize) -> Result<(), VmError> {
+        // +3 to also jump back over the LOOP opcode and its 2 byte operand, since VM's ip will be after the operand
+        let offset = self.chunk.codes.len() - loop_start + 3;
+        if offset > u16::MAX as usize {
+            return Err(self.error("Loop body too large".to_string()));
+        }
+
+        self.emit_code_with_u16(OpCode::LOOP, offset as u16);
+        Ok(())
+    }
+
+    /// Emit the opcode to pop the given number of values off the stack, if there are any
+    fn emit_pops(&mut self, number_of_pops: usize) {
+        if number_of_pops == 1 {
+            self.emit_code(OpCode::POP);
+        } else if number_of_pops > 1 {
+            // Number of locals is capped by add_local, so that this will always fit in the single byte operand
+            self.emit_code_with_byte(OpCode::POP_N, number_of_pops as u8);
+        }
+    }
+
+    /* ==========================  End of emit helpers  ========================== */
+
+    /* ==========================  Start of identifiers  ========================== */
+
+    /// Add a local for the value on top of the stack
+    fn add_local(&mut self, name: &str, function_constant: Option<u16>) -> Result<(), VmError> {
+        // Stack index of locals are encoded as a single byte operand
+        if self.locals.len() >= u8::MAX as usize {
+            return Err(self.error("Too many locals in scope".to_string()));
+        }
+
+        self.locals.push(Local {
+            name: name.to_string(),
+            depth: self.scope_depth,
+            function_constant,
+        });
+        Ok(())
+    }
+
+    /// Pop all the locals of the scopes that ended off the stack
+    fn end_scope(&mut self) {
+        let number_of_locals = self.locals.iter().rev().take_while(|local| local.depth > self.scope_depth).count();
+        self.locals.truncate(self.locals.len() - number_of_locals);
+        self.emit_pops(number_of_locals);
+    }
+
+    /// Resolves and return the slot of the global identifier, allocating a new slot if it has never been seen before
+    fn resolve_global(&mut self, name: &str) -> Result<u16, VmError> {
+        if let Some(&global_slot) = self.globals.get(name) {
+            return Ok(global_slot);
+        }
+
+        // Global slots are encoded as 2 bytes wide operands
+        let global_slot = self.chunk.globals.len();
+        if global_slot > u16::MAX as usize {
+            return Err(self.error("Too many globals".to_string()));
+        }
+
+        self.chunk.globals.push(Value::string(name.to_string()));
+        self.globals.insert(name.to_string(), global_slot as u16);
+        Ok(global_slot as u16)
+    }
+
+    /// Bind the value on top of the stack to the identifier, as a global in the global scope and as a local everywhere else
+    fn define(&mut self, token: &Token, function_constant: Option<u16>) -> Result<(), VmError> {
+        let name = token.lexeme.as_ref().unwrap();
+
+        if self.scope_depth > 0 {
+            return self.add_local(name, function_constant);
+        }
+
+        self.undefined_globals.remove(name);
+        let global_slot = self.resolve_global(name)?;
+        self.emit_code_with_u16(OpCode::DEFINE_GLOBAL, global_slot);
+        Ok(())
+    }
+
+    /// Load the value of the identifier onto the stack.
+    /// Identifiers are already resolved, so the innermost local with the same name is the one the resolver found, else it is a global.
+    fn identifier(&mut self, token: &Token) -> Result<(), VmError> {
+        let name = token.lexeme.as_ref().unwrap();
+
+        let stack_index = self.locals.iter().rposition(|local| &local.name == name);
+        if let Some(stack_index) = stack_index {
+            let function_stack_base = self.function_stack_bases.last().copied().unwrap_or(0);
+            if stack_index >= function_stack_base {
+                self.emit_code_with_byte(OpCode::GET_LOCAL, stack_index as u8);
+                return Ok(());
+            }
+
+            // Locals of enclosing scopes are only available to nested function bodies if they are functions
+            return match self.locals[stack_index].function_constant {
+                Some(constant_index) => {
+                    self.emit_constant_index(constant_index);
+                    Ok(())
+                }
+                None => Err(self.error(format!(
+                    "Closures are not supported by rvm, '{}' is a local of an enclosing scope",
+                    name
+                ))),
+            };
+        }
+
+        // Function bodies can use globals defined after the function
+        if !self.globals.contains_key(name) {
+            self.undefined_globals.insert(name.clone(), self.line);
+        }
+        let global_slot = self.resolve_global(name)?;
+        self.emit_code_with_u16(OpCode::GET_GLOBAL, global_slot);
+        Ok(())
+    }
+
+    /* ==========================  End of identifiers  ========================== */
+
+    /* ==========================  Start of statements  ========================== */
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<(), VmError> {
+        match stmt {
+            Stmt::Print(ref expr, _) => {
+                self.expression(expr)?;
+                self.emit_code(OpCode::PRINT);
+            }
+
+            // Values of expression statements are already checked to be Null by the type checker, and are discarded just like ignored values
+            Stmt::Expr(ref expr, _) | Stmt::Ignore(ref expr, _) => {
+                self.expression(expr)?;
+                self.emit_code(OpCode::POP);
+            }
+
+            Stmt::Const(ref token, ref expr) => {
+                self.line = token.line;
+
+                // Anonymous functions bound to consts are constants just like function definitions
+                let function_constant = match expr {
+                    Expr::AnonymousFunc(_) => Some(self.chunk.constants.len() as u16),
+                    _ => None,
+                };
+                self.expression(expr)?;
+                self.define(token, function_constant)?;
+            }
+
+            Stmt::Block(ref stmts, _) => {
+                self.scope_depth += 1;
+                for stmt in stmts {
+                    self.statement(stmt)?;
+                }
+                self.scope_depth -= 1;
+                self.end_scope();
+            }
+
+            Stmt::Func(ref token, ref parameters, ref body) => {
+                self.line = token.line;
+
+                // Function is defined before its body is generated, so that it can call itself recursively
+                let function_constant = self.function_constant()?;
+                self.define(token, Some(function_constant))?;
+                self.function_body(function_constant, token.lexeme.as_ref().unwrap(), parameters, body)?;
+            }
+
+            Stmt::If(ref condition, ref true_branch, ref else_branch, line) => {
+                self.line = *line;
+                self.expression(condition)?;
+
+                let then_jump = self.emit_jump(OpCode::JUMP_IF_FALSE);
+                // POP opcode to discard condition value from stack
+                self.emit_code(OpCode::POP);
+                self.statement(true_branch)?;
+
+                let else_jump = self.emit_jump(OpCode::JUMP);
+                self.patch_jump(then_jump)?;
+                self.emit_code(OpCode::POP);
+                if let Some(ref else_branch) = else_branch {
+                    self.statement(else_branch)?;
+                }
+                self.patch_jump(else_jump)?;
+            }
+
+            Stmt::While(ref condition, ref loop_body, line) => {
+                self.line = *line;
+                let loop_start = self.chunk.codes.len();
+                self.expression(condition)?;
+
+                let exit_jump = self.emit_jump(OpCode::JUMP_IF_FALSE);
+                // POP opcode to discard condition value from stack
+                self.emit_code(OpCode::POP);
+
+                self.loops.push(Loop {
+                    start: loop_start,
+                    number_of_locals: self.locals.len(),
+                });
+                self.statement(loop_body)?;
+                self.loops.pop();
+
+                self.emit_loop(loop_start)?;
+                self.patch_jump(exit_jump)?;
+                self.emit_code(OpCode::POP);
+            }
+
+            Stmt::Return(ref expr, line) => {
+                self.line = *line;
+                self.expression(expr)?;
+
+                // The interpreter's While arm discards the value of its loop body statement,
+                // so a return statement in a loop body only stops the current iteration like ri does, continuing with the next one.
+                // Locals are only counted and not removed, as the rest of the loop body can still use them.
+                if let Some(loop_) = self.loops.last() {
+                    let (loop_start, number_of_pops) = (loop_.start, self.locals.len() - loop_.number_of_locals);
+                    self.emit_pops(number_of_pops + 1);
+                    return self.emit_loop(loop_start);
+                }
+
+                let function_stack_base = match self.function_stack_bases.last() {
+                    Some(&function_stack_base) => function_stack_base,
+                    None => return Err(self.error("Return statement outside of a function body".to_string())),
+                };
+
+                // Pop the locals of the function, including its arguments, before returning the value to the caller
+                let number_of_pops = self.locals.len() - function_stack_base;
+                if number_of_pops == 0 {
+                    self.emit_code(OpCode::RETURN);
+                } else {
+                    self.emit_code_with_byte(OpCode::RETURN_POP, number_of_pops as u8);
+                }
+            }
+
+            Stmt::AnonymousFunc(..) => {
+                return Err(self.error(format!("Unexpected anonymous function statement: {}", stmt)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /* ==========================  End of statements  ========================== */
+
+    /* ==========================  Start of functions  ========================== */
+
+    /// Emit the function value onto the stack, with a placeholder byte offset that function_body patches once it is known.
+    /// Returns the constant pool index of the function value.
+    fn function_constant(&mut self) -> Result<u16, VmError> {
+        let function_constant = self.make_constant(Value::function(0))?;
+        self.emit_constant_index(function_constant);
+        Ok(function_constant)
+    }
+
+    /// Generate the function body right where the function is defined, with a JUMP over it as it is not called yet
+    fn function_body(
+        &mut self,
+        function_constant: u16,
+        name: &str,
+        parameters: &Vec<Token>,
+        body: &Stmt,
+    ) -> Result<(), VmError> {
+        let stmts = match body {
+            Stmt::Block(ref stmts, _) => stmts,
+            unmatched_stmt_variant => {
+                return Err(self.error(format!(
+                    "Function body must be a Block Statement, found: {}",
+                    unmatched_stmt_variant
+                )))
+            }
+        };
+
+        let jump_over_body = self.emit_jump(OpCode::JUMP);
+
+        let start = self.chunk.codes.len();
+        self.chunk.constants[function_constant as usize] = Value::function(start);
+
+        // Arguments are pushed onto the stack right after the locals before the function definition, in the order of the parameters
+        let stack_base = self.locals.len();
+        self.chunk.functions.push(Function {
+            name: name.to_string(),
+            start,
+            arity: parameters.len(),
+            stack_base,
+        });
+
+        // Loops of the enclosing function body do not surround the statements in this function body
+        let enclosing_loops = std::mem::take(&mut self.loops);
+        self.function_stack_bases.push(stack_base);
+
+        // Body statements are in the same scope as the parameters, without a new scope for the block statement
+        self.scope_depth += 1;
+        for parameter in parameters {
+            self.add_local(parameter.lexeme.as_ref().unwrap(), None)?;
+        }
+        for stmt in stmts {
+            self.statement(stmt)?;
+        }
+        self.scope_depth -= 1;
+
+        // Functions that end without a return statement return Null
+        if matches!(stmts.last(), Some(Stmt::Return(..))) {
+            self.locals.truncate(stack_base);
+        } else {
+            self.end_scope();
+            self.emit_constant(Value::NULL)?;
+            self.emit_code(OpCode::RETURN);
+        }
+
+        self.function_stack_bases.pop();
+        self.loops = enclosing_loops;
+
+        self.patch_jump(jump_over_body)
+    }
+
+    /* ==========================  End of functions  ========================== */
+
+    /* ==========================  Start of expressions  ========================== */
+
+    /// Generate the expression, leaving exactly one value on the stack
+    fn expression(&mut self, expr: &Expr) -> Result<(), VmError> {
+        match expr {
+            Expr::Literal(ref literal) => {
+                let value = match literal {
+                    Literal::Number(number) => Value::number(*number),
+                    Literal::String(ref string) => Value::string(string.clone()),
+                    Literal::Bool(boolean) => Value::bool(*boolean),
+                    Literal::Null => Value::NULL,
+                };
+                self.emit_constant(value)?;
+            }
+
+            Expr::Const(ref token, _) => {
+                self.line = token.line;
+                self.identifier(token)?;
+            }
+
+            Expr::AnonymousFunc(ref stmt) => match **stmt {
+                Stmt::AnonymousFunc(ref parameters, ref body) => {
+                    let function_constant = self.function_constant()?;
+                    self.function_body(function_constant, "<anonymous>", parameters, body)?;
+                }
+                ref unmatched_stmt_variant => {
+                    return Err(self.error(format!("Expected anonymous function, found: {}", unmatched_stmt_variant)))
+                }
+            },
+
+            Expr::Call(ref callee, ref arguments, ref token) => {
+                self.expression(callee)?;
+                for argument in arguments {
+                    self.expression(argument)?;
+                }
+
+                self.line = token.line;
+                if arguments.len() > u8::MAX as usize {
+                    return Err(self.error("Too many arguments in function call".to_string()));
+                }
+                self.emit_code_with_byte(OpCode::CALL, arguments.len() as u8);
+            }
+
+            Expr::Array(ref token, _) => {
+                self.line = token.line;
+                return Err(self.error("Arrays are not supported by rvm".to_string()));
+            }
+
+            Expr::ArrayAccess(..) => return Err(self.error("Arrays are not supported by rvm".to_string())),
+
+            Expr::Grouping(ref expr) => self.expression(expr)?,
+
+            Expr::Unary(ref operator, ref expr) => {
+                self.expression(expr)?;
+
+                self.line = operator.line;
+                match operator.token_type {
+                    TokenType::Minus => self.emit_code(OpCode::NEGATE),
+                    TokenType::Bang => self.emit_code(OpCode::NOT),
+                    ref operator => return Err(self.error(format!("Invalid unary operator: {:?}", operator))),
+                }
+            }
+
+            Expr::Binary(ref left, ref operator, ref right) => {
+                self.expression(left)?;
+                self.expression(right)?;
+
+                self.line = operator.line;
+                let opcode = match operator.token_type {
+                    TokenType::Plus => OpCode::ADD,
+                    TokenType::Minus => OpCode::SUBTRACT,
+                    TokenType::Star => OpCode::MULTIPLY,
+                    TokenType::Slash => OpCode::DIVIDE,
+                    TokenType::EqualEqual => OpCode::EQUAL,
+                    TokenType::BangEqual => OpCode::NOT_EQUAL,
+                    TokenType::Greater => OpCode::GREATER,
+                    TokenType::GreaterEqual => OpCode::GREATER_EQUAL,
+                    TokenType::Less => OpCode::LESS,
+                    TokenType::LessEqual => OpCode::LESS_EQUAL,
+                    ref operator => return Err(self.error(format!("Invalid binary operator: {:?}", operator))),
+                };
+                self.emit_code(opcode);
+            }
+
+            // Short circuiting operators, where the left operand is left on the stack as the value if it decides the result
+            Expr::Logical(ref left, ref operator, ref right) => {
+                self.expression(left)?;
+
+                self.line = operator.line;
+                match operator.token_type {
+                    TokenType::And => {
+                        let end_jump = self.emit_jump(OpCode::JUMP_IF_FALSE);
+                        self.emit_code(OpCode::POP);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump)?;
+                    }
+                    TokenType::Or => {
+                        let else_jump = self.emit_jump(OpCode::JUMP_IF_FALSE);
+                        let end_jump = self.emit_jump(OpCode::JUMP);
+                        self.patch_jump(else_jump)?;
+                        self.emit_code(OpCode::POP);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump)?;
+                    }
+                    ref operator => {
+                        return Err(self.error(format!("Invalid Token Type for logical expr -> {:?}", operator)))
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /* ==========================  End of expressions  ========================== */
+}
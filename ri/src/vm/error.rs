@@ -0,0 +1,16 @@
+/// Errors from generating rvm bytecode from a SS program
+#[derive(Debug)]
+pub enum VmError {
+    /// AST node that cannot be lowered to rvm bytecode, either because rvm has no instructions for it or because a limit of
+    /// the bytecode encoding is exceeded
+    /// Codegen(line number, error message)
+    Codegen(usize, String),
+}
+
+impl std::fmt::Display for VmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VmError::Codegen(line, error) => write!(f, "[line {}] Unable to generate bytecode: {}", line, error),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+mod codegen;
+mod error;
+
+use rvm::chunk::Chunk;
+
+use crate::parser::stmt::Stmt;
+
+use self::codegen::BytecodeGenerator;
+pub use self::error::VmError;
+
+/*
+    VM back end, enabled with '--backend vm', which lowers the resolved and type checked AST into a chunk for rvm's stack VM,
+    so that programs get all of ri's static checks while running on the bytecode VM, with a single front end for both.
+
+    The generated chunk uses the same stack layout as the chunks of rvm's own compiler:
+    - Top level consts and functions are globals, resolved to global slots at generation time.
+    - Every other const, function and parameter is a local, which stays on the stack until its scope ends,
+      and is read with its stack index, where the first parameter of a function is at the function's stack base.
+    - Functions are function values pointing to their body, which is jumped over where the function is defined.
+
+    rvm has no closures, arrays or native functions, so the generator rejects programs that use them:
+    - Function bodies can only use their own parameters and locals, and globals, since locals of enclosing functions are not captured.
+    - Array literals and array access.
+    - The prelude's 'clock' function.
+
+    Stack indices of locals are relative to the stack base of the function they are in, which the VM offsets
+    by where the arguments of a call actually are, so functions can be called in the middle of expressions and recursively.
+
+    Source locations only have line numbers, as most AST nodes only record the line of their tokens, and their column is always 0.
+*/
+
+/// Generate a chunk from the resolved and type checked AST, to run on rvm's VM
+pub fn compile(ast: &Vec<Stmt>) -> Result<Chunk, VmError> {
+    BytecodeGenerator::generate(ast)
+}
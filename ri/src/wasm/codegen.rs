@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+
+use wasm_encoder::{BlockType, Instruction as I, ValType};
+
+use super::module::*;
+use super::runtime::Runtime;
+use super::WasmError;
+
+use crate::literal::Literal;
+use crate::parser::expr::Expr;
+use crate::parser::stmt::Stmt;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// Identifiers of a single scope, mapped to their slot in the scope's environment
+type Scope = HashMap<String, usize>;
+
+const P: ValType = ValType::I32;
+
+/// Compile the resolved and type checked AST into a binary module exporting the 'main' function that runs the program
+pub fn compile_module(ast: &Vec<Stmt>) -> Result<Vec<u8>, WasmError> {
+    let mut module = ModuleBuilder::new();
+    let runtime = Runtime::define(&mut module);
+    let mut compiler = Compiler { module, runtime };
+
+    let main = compiler.compile_main(ast)?;
+    compiler.module.export_function("main", main);
+
+    Ok(compiler.module.finish())
+}
+
+/// Number of slots the environment of a scope needs, which is one for every identifier the scope's statements declare
+fn number_of_slots(stmts: &[Stmt]) -> usize {
+    stmts.iter().filter(|stmt| matches!(stmt, Stmt::Const(..) | Stmt::Func(..))).count()
+}
+
+/// State shared by the compilation of every function in the program
+struct Compiler {
+    module: ModuleBuilder,
+    runtime: Runtime,
+}
+
+impl Compiler {
+    /// Compile the top level statements into the 'main' function, which runs them in the global environment
+    fn compile_main(&mut self, ast: &Vec<Stmt>) -> Result<u32, WasmError> {
+        let function_index = self.module.declare_function(&[], &[]);
+
+        // Define the prelude's clock native function as a static function object in the global environment
+        let clock_table_index = self.module.table_index(self.runtime.clock);
+        let clock_name = self.module.data.string_object("clock");
+        let clock = self.module.data.object(
+            &[TAG_FUNCTION, clock_table_index as i32, 0, 0, clock_name as i32, 1]
+                .map(i32::to_le_bytes)
+                .concat(),
+        );
+
+        let mut translator = FunctionTranslator::new(self, "main", 0, Vec::new());
+        translator.scopes.push(Scope::new());
+        let clock_slot = translator.declare("clock");
+        translator.body.extend([
+            I::I32Const(0),
+            I::I32Const((number_of_slots(ast) + 1) as i32),
+            I::Call(translator.compiler.runtime.environment),
+            I::LocalSet(translator.environment),
+        ]);
+        translator.store_slot(clock_slot, |translator| {
+            translator.body.push(I::I32Const(clock as i32));
+            Ok(())
+        })?;
+
+        for stmt in ast {
+            translator.translate_statement(stmt)?;
+        }
+
+        let body = translator.body;
+        self.module.define_function(function_index, body);
+        Ok(function_index)
+    }
+
+    /// Compile a SS function, which takes its closure environment followed by its arguments, and returns its return value.
+    /// Returns the function's table index, as SS functions are only called indirectly.
+    ///
+    /// 'scopes' are the scopes surrounding the function definition, which the function body's identifiers are resolved against.
+    fn compile_function(
+        &mut self,
+        name: Option<&String>,
+        parameters: &[Token],
+        body: &Stmt,
+        scopes: Vec<Scope>,
+    ) -> Result<u32, WasmError> {
+        let function_index = self.module.declare_function(&vec![P; parameters.len() + 1], &[P]);
+        let table_index = self.module.table_index(function_index);
+
+        let name = name.map(|name| name.as_str()).unwrap_or("[anonymous]").to_string();
+        let mut translator = FunctionTranslator::new(self, &name, parameters.len() as u32 + 1, scopes);
+        translator.scopes.push(Scope::new());
+
+        let stmts = match body {
+            Stmt::Block(ref stmts, _) => stmts,
+            unmatched_stmt_variant => {
+                return Err(WasmError::Codegen(
+                    name,
+                    format!("Function body must be a Block Statement, found: {}", unmatched_stmt_variant),
+                ))
+            }
+        };
+
+        // Like Function's call method, a new environment enclosed by the closure holds the arguments and the body's values
+        translator.body.extend([
+            I::LocalGet(0),
+            I::I32Const((parameters.len() + number_of_slots(stmts)) as i32),
+            I::Call(translator.compiler.runtime.environment),
+            I::LocalSet(translator.environment),
+        ]);
+        for (argument, parameter) in parameters.iter().enumerate() {
+            let slot = translator.declare(parameter.lexeme.as_ref().unwrap());
+            translator.store_slot(slot, |translator| {
+                translator.body.push(I::LocalGet(argument as u32 + 1));
+                Ok(())
+            })?;
+        }
+
+        // Body statements run in the same scope as the parameters, without a new scope for the block statement
+        for stmt in stmts {
+            translator.translate_statement(stmt)?;
+        }
+
+        // Functions that end without a return statement return Null
+        translator.body.push(I::I32Const(NULL_OBJECT));
+
+        let body = translator.body;
+        self.module.define_function(function_index, body);
+        Ok(table_index)
+    }
+}
+
+/// Translates the statements of a single function into WebAssembly instructions
+struct FunctionTranslator<'a> {
+    compiler: &'a mut Compiler,
+    body: FunctionBody,
+
+    /// Name of the function, used in error messages
+    name: String,
+
+    /// Local holding the environment of the current scope, which changes as the function enters and exits block statements
+    environment: u32,
+
+    /// Identifiers of every scope from the global scope to the current scope, mirroring the resolver's scopes
+    /// so that the resolver's scope distance values find the scope of an identifier to get its slot from
+    scopes: Vec<Scope>,
+
+    /// Number of control frames (block, loop and if) enclosing the current instruction, used to compute branch depths
+    depth: u32,
+
+    /// Control frame depth of the loop instruction and the local holding the environment of every while loop
+    /// the current statement is in, from the outermost loop
+    loops: Vec<(u32, u32)>,
+}
+
+impl<'a> FunctionTranslator<'a> {
+    fn new(compiler: &'a mut Compiler, name: &str, parameters: u32, scopes: Vec<Scope>) -> FunctionTranslator<'a> {
+        let mut body = FunctionBody::new(parameters);
+        let environment = body.local(P);
+        FunctionTranslator {
+            compiler,
+            body,
+            name: name.to_string(),
+            environment,
+            scopes,
+            depth: 0,
+            loops: Vec::new(),
+        }
+    }
+
+    fn translate_statement(&mut self, stmt: &Stmt) -> Result<(), WasmError> {
+        match stmt {
+            Stmt::Print(ref expr, _) => {
+                self.body.push(I::I32Const(STDOUT));
+                self.translate_expression(expr)?;
+                self.body.push(I::Call(self.compiler.runtime.print));
+                self.body.write_text(&mut self.compiler.module.data, I::I32Const(STDOUT), "\n");
+            }
+
+            Stmt::Expr(ref expr, _) | Stmt::Ignore(ref expr, _) => {
+                self.translate_expression(expr)?;
+                self.body.push(I::Drop);
+            }
+
+            Stmt::Const(ref token, ref expr) => {
+                // Identifier is declared before its expression is resolved, so the expression can reference the identifier itself
+                let slot = self.declare(token.lexeme.as_ref().unwrap());
+                self.store_slot(slot, |translator| translator.translate_expression(expr))?;
+            }
+
+            Stmt::Block(ref stmts, _) => {
+                let parent_environment = self.body.local(P);
+                self.body.extend([
+                    I::LocalGet(self.environment),
+                    I::LocalTee(parent_environment),
+                    I::I32Const(number_of_slots(stmts) as i32),
+                    I::Call(self.compiler.runtime.environment),
+                    I::LocalSet(self.environment),
+                ]);
+                self.scopes.push(Scope::new());
+
+                for stmt in stmts {
+                    self.translate_statement(stmt)?;
+                }
+
+                self.scopes.pop();
+                self.body.extend([I::LocalGet(parent_environment), I::LocalSet(self.environment)]);
+            }
+
+            Stmt::Func(ref token, ref parameters, ref body) => {
+                // Function identifier is defined before its body is resolved, so that functions can call themselves recursively
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self.declare(name);
+                self.store_slot(slot, |translator| translator.translate_function(Some(name), parameters, body))?;
+            }
+
+            Stmt::If(ref condition, ref true_branch, ref else_branch, _) => {
+                self.translate_condition(
+                    condition,
+                    "Invalid condition value type, only Boolean values can be used as conditionals!",
+                )?;
+
+                self.body.push(I::If(BlockType::Empty));
+                self.depth += 1;
+                self.translate_statement(true_branch)?;
+                if let Some(ref else_branch) = else_branch {
+                    self.body.push(I::Else);
+                    self.translate_statement(else_branch)?;
+                }
+                self.depth -= 1;
+                self.body.push(I::End);
+            }
+
+            Stmt::While(ref condition, ref loop_body, _) => {
+                let environment = self.body.local(P);
+                self.body.extend([
+                    I::LocalGet(self.environment),
+                    I::LocalSet(environment),
+                    I::Block(BlockType::Empty),
+                    I::Loop(BlockType::Empty),
+                ]);
+                self.depth += 2;
+
+                self.translate_condition(condition, "Expected Boolean from While loop expression")?;
+                self.body.extend([I::I32Eqz, I::BrIf(1)]);
+
+                self.loops.push((self.depth, environment));
+                self.translate_statement(loop_body)?;
+                self.loops.pop();
+
+                self.body.extend([I::Br(0), I::End, I::End]);
+                self.depth -= 2;
+            }
+
+            Stmt::Return(ref expr, _) => {
+                self.translate_expression(expr)?;
+
+                // The interpreter's While arm discards the value of its loop body statement,
+                // so a return statement in a loop body only stops the current iteration like ri does, continuing with the next one
+                match self.loops.last() {
+                    Some(&(loop_depth, environment)) => {
+                        self.body.extend([
+                            I::Drop,
+                            I::LocalGet(environment),
+                            I::LocalSet(self.environment),
+                            I::Br(self.depth - loop_depth),
+                        ]);
+                    }
+                    None => self.body.push(I::Return),
+                }
+            }
+
+            unmatched_stmt_variant => {
+                return Err(WasmError::Codegen(
+                    self.name.clone(),
+                    format!("Unimplemented Stmt variant: {}", unmatched_stmt_variant),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate the instructions evaluating the expression, which leave a pointer to its value on the stack
+    fn translate_expression(&mut self, expr: &Expr) -> Result<(), WasmError> {
+        match expr {
+            Expr::Literal(ref literal) => {
+                let object = match literal {
+                    Literal::Number(number) => self.compiler.module.data.number_object(*number) as i32,
+                    Literal::String(ref string) => self.compiler.module.data.string_object(string) as i32,
+                    Literal::Bool(true) => TRUE_OBJECT,
+                    Literal::Bool(false) => FALSE_OBJECT,
+                    Literal::Null => NULL_OBJECT,
+                };
+                self.body.push(I::I32Const(object));
+            }
+
+            Expr::Const(ref token, distance) => {
+                let name = token.lexeme.as_ref().unwrap();
+                let slot = self
+                    .scopes
+                    .iter()
+                    .rev()
+                    .nth(*distance)
+                    .and_then(|scope| scope.get(name))
+                    .copied()
+                    .ok_or_else(|| {
+                        WasmError::Codegen(self.name.clone(), format!("Identifier '{}' is not resolved", name))
+                    })?;
+
+                let name = self.compiler.module.data.string_object(name);
+                self.body.extend([
+                    I::LocalGet(self.environment),
+                    I::I32Const(*distance as i32),
+                    I::I32Const(slot as i32),
+                    I::I32Const(token.line as i32),
+                    I::I32Const(name as i32),
+                    I::Call(self.compiler.runtime.environment_get),
+                ]);
+            }
+
+            Expr::AnonymousFunc(ref stmt) => match **stmt {
+                Stmt::AnonymousFunc(ref parameters, ref body) => self.translate_function(None, parameters, body)?,
+                ref unmatched_stmt_variant => {
+                    return Err(WasmError::Codegen(
+                        self.name.clone(),
+                        format!("Expected anonymous function, found: {}", unmatched_stmt_variant),
+                    ))
+                }
+            },
+
+            Expr::Call(ref callee, ref arguments, ref token) => {
+                // Callee is checked to be a function before the arguments are evaluated, like the interpreter does
+                let function = self.body.local(P);
+                let table_index = self.body.local(P);
+                self.translate_expression(callee)?;
+                self.body.extend([
+                    I::LocalTee(function),
+                    I::I32Const(arguments.len() as i32),
+                    I::I32Const(token.line as i32),
+                    I::Call(self.compiler.runtime.function_code),
+                    I::LocalSet(table_index),
+                    I::LocalGet(function),
+                    I::I32Load(memory(FUNCTION_CLOSURE)),
+                ]);
+
+                for argument in arguments {
+                    self.translate_expression(argument)?;
+                }
+
+                let type_index = self.compiler.module.type_index(&vec![P; arguments.len() + 1], &[P]);
+                self.body.extend([I::LocalGet(table_index), I::CallIndirect { type_index, table_index: 0 }]);
+            }
+
+            Expr::Array(_, ref elements) => {
+                let array = self.body.local(P);
+                self.body.extend([
+                    I::I32Const(elements.len() as i32),
+                    I::Call(self.compiler.runtime.array),
+                    I::LocalSet(array),
+                ]);
+                for (index, element) in elements.iter().enumerate() {
+                    self.body.push(I::LocalGet(array));
+                    self.translate_expression(element)?;
+                    self.body.push(I::I32Store(memory(ELEMENTS + 4 * index as u64)));
+                }
+                self.body.push(I::LocalGet(array));
+            }
+
+            Expr::ArrayAccess(ref array, ref index) => {
+                self.translate_expression(array)?;
+                self.translate_expression(index)?;
+                self.body.push(I::Call(self.compiler.runtime.array_get));
+            }
+
+            Expr::Grouping(ref expr) => self.translate_expression(expr)?,
+
+            Expr::Unary(ref operator, ref expr) => {
+                self.translate_expression(expr)?;
+                let runtime_function = match operator.token_type {
+                    TokenType::Minus => self.compiler.runtime.negate,
+                    TokenType::Bang => self.compiler.runtime.not,
+                    ref operator => {
+                        return Err(WasmError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid unary operator: {:?}", operator),
+                        ))
+                    }
+                };
+                self.body.push(I::Call(runtime_function));
+            }
+
+            Expr::Binary(ref left, ref operator, ref right) => {
+                self.translate_expression(left)?;
+                self.translate_expression(right)?;
+                let runtime = &self.compiler.runtime;
+                let instructions = match operator.token_type {
+                    TokenType::Plus => vec![I::Call(runtime.add)],
+                    TokenType::Minus => vec![I::Call(runtime.subtract)],
+                    TokenType::Star => vec![I::Call(runtime.multiply)],
+                    TokenType::Slash => vec![I::Call(runtime.divide)],
+                    TokenType::EqualEqual => vec![I::Call(runtime.equal), I::Call(runtime.boolean)],
+                    TokenType::BangEqual => vec![I::Call(runtime.equal), I::I32Eqz, I::Call(runtime.boolean)],
+                    TokenType::Greater => vec![I::Call(runtime.greater)],
+                    TokenType::GreaterEqual => vec![I::Call(runtime.greater_equal)],
+                    TokenType::Less => vec![I::Call(runtime.less)],
+                    TokenType::LessEqual => vec![I::Call(runtime.less_equal)],
+                    ref operator => {
+                        return Err(WasmError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid binary operator: {:?}", operator),
+                        ))
+                    }
+                };
+                self.body.extend(instructions);
+            }
+
+            // Strict boolean operators that short circuit, evaluating to a Bool value
+            Expr::Logical(ref left, ref operator, ref right) => {
+                let message = "Logical operations only work with Bool Types";
+                self.translate_condition(left, message)?;
+                self.body.push(I::If(BlockType::Result(P)));
+                match operator.token_type {
+                    TokenType::Or => {
+                        self.body.extend([I::I32Const(1), I::Else]);
+                        self.translate_condition(right, message)?;
+                    }
+                    TokenType::And => {
+                        self.translate_condition(right, message)?;
+                        self.body.extend([I::Else, I::I32Const(0)]);
+                    }
+                    ref operator => {
+                        return Err(WasmError::Codegen(
+                            self.name.clone(),
+                            format!("Invalid Token Type for logical expr -> {:?}", operator),
+                        ))
+                    }
+                }
+                self.body.extend([I::End, I::Call(self.compiler.runtime.boolean)]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluate the expression into an i32 boolean, which fails at runtime with the message if the value is not a Bool
+    fn translate_condition(&mut self, expr: &Expr, message: &str) -> Result<(), WasmError> {
+        self.translate_expression(expr)?;
+        let (message, message_length) = self.compiler.module.data.bytes(message);
+        self.body.extend([
+            I::I32Const(message as i32),
+            I::I32Const(message_length as i32),
+            I::Call(self.compiler.runtime.condition),
+        ]);
+        Ok(())
+    }
+
+    /// Compile the function and create its function value, with the current environment as its closure
+    fn translate_function(
+        &mut self,
+        name: Option<&String>,
+        parameters: &[Token],
+        body: &Stmt,
+    ) -> Result<(), WasmError> {
+        let table_index = self.compiler.compile_function(name, parameters, body, self.scopes.clone())?;
+        let name = match name {
+            Some(name) => self.compiler.module.data.string_object(name),
+            None => 0,
+        };
+        self.body.extend([
+            I::I32Const(table_index as i32),
+            I::I32Const(parameters.len() as i32),
+            I::LocalGet(self.environment),
+            I::I32Const(name as i32),
+            I::Call(self.compiler.runtime.function),
+        ]);
+        Ok(())
+    }
+
+    /// Declare the identifier in the current scope, returning its slot in the scope's environment
+    fn declare(&mut self, name: &str) -> usize {
+        let scope = self.scopes.last_mut().unwrap();
+        let slot = scope.len();
+        *scope.entry(name.to_string()).or_insert(slot)
+    }
+
+    /// Store the value generated by 'value' into the slot of the current environment
+    fn store_slot(
+        &mut self,
+        slot: usize,
+        value: impl FnOnce(&mut Self) -> Result<(), WasmError>,
+    ) -> Result<(), WasmError> {
+        self.body.push(I::LocalGet(self.environment));
+        value(self)?;
+        self.body.push(I::I32Store(memory(ENVIRONMENT_SLOTS + 4 * slot as u64)));
+        Ok(())
+    }
+}
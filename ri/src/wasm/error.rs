@@ -0,0 +1,19 @@
+/// Errors from compiling a SS program into a WebAssembly module
+#[derive(Debug)]
+pub enum WasmError {
+    /// AST node that cannot be compiled, which should have been rejected by the earlier passes
+    /// Codegen(function name, error message)
+    Codegen(String, String),
+
+    /// Module file could not be written
+    Io(String),
+}
+
+impl std::fmt::Display for WasmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WasmError::Codegen(name, error) => write!(f, "Unable to compile function '{}': {}", name, error),
+            WasmError::Io(error) => write!(f, "Unable to write module file: {}", error),
+        }
+    }
+}
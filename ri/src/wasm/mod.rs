@@ -0,0 +1,39 @@
+mod codegen;
+mod error;
+mod module;
+mod runtime;
+
+use std::fs;
+use std::path::Path;
+
+use crate::parser::stmt::Stmt;
+
+pub use self::error::WasmError;
+
+/*
+    WebAssembly back end, enabled with '--backend wasm', which compiles a SS program into a standalone binary module.
+
+    Every SS value is a pointer to an object in the module's linear memory (see module.rs for the object layout).
+    Literals are static objects in the module's data segment, and every other object is allocated by a bump allocator
+    that never frees memory, as the module only runs a single program once.
+    Like the native back end, the operations on values are implemented by runtime functions in the module (see runtime.rs),
+    so compiled code only decides the control flow, and environments are arrays of slots selected by the resolver's scope distance.
+
+    The module imports a small host interface from the "ss" module:
+        write(stream: i32, pointer: i32, length: i32)       Write the bytes in linear memory to stdout (1) or stderr (2)
+        write_number(stream: i32, number: f64, debug: i32)  Write the number formatted like Rust's Display, or Debug if debug is 1
+        clock() -> f64                                      Current time in milliseconds, for the prelude's clock function
+
+    And exports:
+        memory          Linear memory, for the host to read the bytes written by 'write'
+        main() -> ()    Runs the program, which traps with 'unreachable' after writing a runtime error's message to stderr
+
+    SS functions are called indirectly through the module's table, with the same calling convention as the native back end:
+        (closure: i32, arguments: i32...) -> i32
+*/
+
+/// Compile the resolved and type checked AST into a WebAssembly module at the output path
+pub fn compile(ast: &Vec<Stmt>, output: &Path) -> Result<(), WasmError> {
+    let module = codegen::compile_module(ast)?;
+    fs::write(output, module).map_err(|error| WasmError::Io(format!("{}: {}", output.display(), error)))
+}
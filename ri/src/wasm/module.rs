@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use wasm_encoder::{
+    CodeSection, ConstExpr, DataSection, ElementSection, Elements, EntityType, ExportKind, ExportSection, Function,
+    FunctionSection, GlobalSection, GlobalType, ImportSection, Instruction, MemArg, MemorySection, MemoryType, RefType,
+    TableSection, TableType, TypeSection, ValType,
+};
+
+/* ==========================  Start of object layout  ========================== */
+
+// Every SS value is a pointer to an object in linear memory, which starts with its type tag as an i32
+pub const TAG_NUMBER: i32 = 0;
+pub const TAG_STRING: i32 = 1;
+pub const TAG_BOOL: i32 = 2;
+pub const TAG_NULL: i32 = 3;
+pub const TAG_ARRAY: i32 = 4;
+pub const TAG_FUNCTION: i32 = 5;
+pub const TAG_ENVIRONMENT: i32 = 6;
+
+/// Number objects store their f64 value 8 byte aligned
+pub const NUMBER_VALUE: u64 = 8;
+
+/// Bool objects store their value as an i32 of 0 or 1
+pub const BOOL_VALUE: u64 = 4;
+
+/// String and Array objects store their length, followed by their bytes or element pointers
+pub const LENGTH: u64 = 4;
+pub const ELEMENTS: u64 = 8;
+
+pub const FUNCTION_TABLE_INDEX: u64 = 4;
+pub const FUNCTION_ARITY: u64 = 8;
+pub const FUNCTION_CLOSURE: u64 = 12;
+/// Pointer to the String object of the function's name, which is 0 for anonymous functions
+pub const FUNCTION_NAME: u64 = 16;
+pub const FUNCTION_NATIVE: u64 = 20;
+pub const FUNCTION_SIZE: i32 = 24;
+
+pub const ENVIRONMENT_ENCLOSING: u64 = 4;
+pub const ENVIRONMENT_SLOTS: u64 = 8;
+
+/// Bool and Null values are static objects at the start of linear memory, after the 8 bytes reserved so that 0 is never an object
+pub const NULL_OBJECT: i32 = 8;
+pub const TRUE_OBJECT: i32 = 16;
+pub const FALSE_OBJECT: i32 = 24;
+
+/* ==========================  End of object layout  ========================== */
+
+/* ==========================  Start of host interface  ========================== */
+
+/// Module name of the functions the host must provide
+pub const HOST_MODULE: &str = "ss";
+
+/// write(stream: i32, pointer: i32, length: i32), writes the bytes to stdout (1) or stderr (2)
+pub const HOST_WRITE: u32 = 0;
+
+/// write_number(stream: i32, number: f64, debug: i32), writes the number formatted like ri, with Rust's Debug format if debug is 1
+pub const HOST_WRITE_NUMBER: u32 = 1;
+
+/// clock() -> f64, returns the current time in milliseconds
+pub const HOST_CLOCK: u32 = 2;
+
+/// Host functions are imported before every function defined in the module, so the first defined function has this index
+const HOST_FUNCTION_COUNT: u32 = 3;
+
+pub const STDOUT: i32 = 1;
+pub const STDERR: i32 = 2;
+
+/// Index of the global holding the address of the next object to allocate
+pub const HEAP_POINTER: u32 = 0;
+
+/* ==========================  End of host interface  ========================== */
+
+/// Memory argument of a load or store instruction, where objects are always 4 byte aligned
+pub fn memory(offset: u64) -> MemArg {
+    MemArg { offset, align: 2, memory_index: 0 }
+}
+
+/// Memory argument for the f64 value of Number objects
+pub fn memory_f64(offset: u64) -> MemArg {
+    MemArg { offset, align: 3, memory_index: 0 }
+}
+
+/// Body of a function defined in the module, with its locals allocated as the body is generated
+pub struct FunctionBody {
+    parameters: u32,
+    locals: Vec<ValType>,
+    instructions: Vec<Instruction<'static>>,
+}
+
+impl FunctionBody {
+    pub fn new(parameters: u32) -> FunctionBody {
+        FunctionBody { parameters, locals: Vec::new(), instructions: Vec::new() }
+    }
+
+    /// Allocate a new local, returning its index
+    pub fn local(&mut self, value_type: ValType) -> u32 {
+        self.locals.push(value_type);
+        self.parameters + self.locals.len() as u32 - 1
+    }
+
+    pub fn push(&mut self, instruction: Instruction<'static>) {
+        self.instructions.push(instruction);
+    }
+
+    pub fn extend(&mut self, instructions: impl IntoIterator<Item = Instruction<'static>>) {
+        self.instructions.extend(instructions);
+    }
+
+    /// Write the text stored in the module's static data to the stream, where the stream is pushed by the given instruction
+    pub fn write_text(&mut self, data: &mut StaticData, stream: Instruction<'static>, text: &str) {
+        let (pointer, length) = data.bytes(text);
+        self.extend([
+            stream,
+            Instruction::I32Const(pointer as i32),
+            Instruction::I32Const(length as i32),
+            Instruction::Call(HOST_WRITE),
+        ]);
+    }
+
+    fn into_function(self) -> Function {
+        let mut function = Function::new_with_locals_types(self.locals);
+        for instruction in self.instructions.iter() {
+            function.instruction(instruction);
+        }
+        function.instruction(&Instruction::End);
+        function
+    }
+}
+
+/// Read only data stored at the start of linear memory, which holds the static objects and the bytes of messages
+pub struct StaticData {
+    bytes: Vec<u8>,
+    texts: HashMap<String, u32>,
+    string_objects: HashMap<String, u32>,
+    number_objects: HashMap<u64, u32>,
+}
+
+impl StaticData {
+    fn new() -> StaticData {
+        let mut data = StaticData {
+            bytes: vec![0; NULL_OBJECT as usize],
+            texts: HashMap::new(),
+            string_objects: HashMap::new(),
+            number_objects: HashMap::new(),
+        };
+
+        data.object(&[TAG_NULL.to_le_bytes(), 0i32.to_le_bytes()].concat());
+        data.object(&[TAG_BOOL.to_le_bytes(), 1i32.to_le_bytes()].concat());
+        data.object(&[TAG_BOOL.to_le_bytes(), 0i32.to_le_bytes()].concat());
+        data
+    }
+
+    /// Store the object 8 byte aligned, returning its address
+    pub fn object(&mut self, object: &[u8]) -> u32 {
+        self.align();
+        let address = self.bytes.len() as u32;
+        self.bytes.extend_from_slice(object);
+        address
+    }
+
+    /// Store the text's bytes once, returning their address and length
+    pub fn bytes(&mut self, text: &str) -> (u32, u32) {
+        if let Some(&address) = self.texts.get(text) {
+            return (address, text.len() as u32);
+        }
+
+        let address = self.bytes.len() as u32;
+        self.bytes.extend_from_slice(text.as_bytes());
+        self.texts.insert(text.to_string(), address);
+        (address, text.len() as u32)
+    }
+
+    /// Static String object for string literals and names, which are never modified as strings are immutable
+    pub fn string_object(&mut self, string: &str) -> u32 {
+        if let Some(&address) = self.string_objects.get(string) {
+            return address;
+        }
+
+        let object = [&TAG_STRING.to_le_bytes()[..], &(string.len() as u32).to_le_bytes(), string.as_bytes()].concat();
+        let address = self.object(&object);
+        self.string_objects.insert(string.to_string(), address);
+        address
+    }
+
+    /// Static Number object for number literals
+    pub fn number_object(&mut self, number: f64) -> u32 {
+        if let Some(&address) = self.number_objects.get(&number.to_bits()) {
+            return address;
+        }
+
+        let object = [&TAG_NUMBER.to_le_bytes()[..], &[0; 4], &number.to_le_bytes()].concat();
+        let address = self.object(&object);
+        self.number_objects.insert(number.to_bits(), address);
+        address
+    }
+
+    fn align(&mut self) {
+        while !self.bytes.len().is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+    }
+}
+
+/// Builds the module's sections, where functions are declared before they are defined so that they can call each other
+pub struct ModuleBuilder {
+    types: Vec<(Vec<ValType>, Vec<ValType>)>,
+
+    /// Type index and body of every function defined in the module, in function index order
+    functions: Vec<(u32, Option<Function>)>,
+
+    /// Function index of every function in the table, so that it can be called indirectly with its table index
+    table: Vec<u32>,
+
+    exports: Vec<(String, u32)>,
+
+    pub data: StaticData,
+}
+
+impl ModuleBuilder {
+    pub fn new() -> ModuleBuilder {
+        ModuleBuilder {
+            types: Vec::new(),
+            functions: Vec::new(),
+            table: Vec::new(),
+            exports: Vec::new(),
+            data: StaticData::new(),
+        }
+    }
+
+    pub fn type_index(&mut self, parameters: &[ValType], results: &[ValType]) -> u32 {
+        let signature = (parameters.to_vec(), results.to_vec());
+        match self.types.iter().position(|existing| *existing == signature) {
+            Some(index) => index as u32,
+            None => {
+                self.types.push(signature);
+                self.types.len() as u32 - 1
+            }
+        }
+    }
+
+    /// Declare a function without its body, returning its function index
+    pub fn declare_function(&mut self, parameters: &[ValType], results: &[ValType]) -> u32 {
+        let type_index = self.type_index(parameters, results);
+        self.functions.push((type_index, None));
+        HOST_FUNCTION_COUNT + self.functions.len() as u32 - 1
+    }
+
+    pub fn define_function(&mut self, function_index: u32, body: FunctionBody) {
+        self.functions[(function_index - HOST_FUNCTION_COUNT) as usize].1 = Some(body.into_function());
+    }
+
+    /// Add the function to the table, returning its table index for indirect calls
+    pub fn table_index(&mut self, function_index: u32) -> u32 {
+        self.table.push(function_index);
+        self.table.len() as u32 - 1
+    }
+
+    pub fn export_function(&mut self, name: &str, function_index: u32) {
+        self.exports.push((name.to_string(), function_index));
+    }
+
+    /// Encode the binary module, where the heap starts after the static data
+    pub fn finish(mut self) -> Vec<u8> {
+        let host_types = [
+            self.type_index(&[ValType::I32, ValType::I32, ValType::I32], &[]),
+            self.type_index(&[ValType::I32, ValType::F64, ValType::I32], &[]),
+            self.type_index(&[], &[ValType::F64]),
+        ];
+
+        let mut types = TypeSection::new();
+        for (parameters, results) in self.types.iter() {
+            types.ty().function(parameters.iter().copied(), results.iter().copied());
+        }
+
+        let mut imports = ImportSection::new();
+        for (name, type_index) in ["write", "write_number", "clock"].into_iter().zip(host_types) {
+            imports.import(HOST_MODULE, name, EntityType::Function(type_index));
+        }
+
+        let mut functions = FunctionSection::new();
+        let mut code = CodeSection::new();
+        for (type_index, body) in self.functions.iter() {
+            functions.function(*type_index);
+            code.function(body.as_ref().expect("Internal Error: Function is declared but never defined"));
+        }
+
+        let mut tables = TableSection::new();
+        tables.table(TableType {
+            element_type: RefType::FUNCREF,
+            table64: false,
+            minimum: self.table.len() as u64,
+            maximum: Some(self.table.len() as u64),
+            shared: false,
+        });
+
+        self.data.align();
+        let heap_start = self.data.bytes.len() as u64;
+        let mut memories = MemorySection::new();
+        memories.memory(MemoryType {
+            minimum: heap_start / 65536 + 1,
+            maximum: None,
+            memory64: false,
+            shared: false,
+            page_size_log2: None,
+        });
+
+        let mut globals = GlobalSection::new();
+        globals.global(
+            GlobalType { val_type: ValType::I32, mutable: true, shared: false },
+            &ConstExpr::i32_const(heap_start as i32),
+        );
+
+        let mut exports = ExportSection::new();
+        exports.export("memory", ExportKind::Memory, 0);
+        for (name, function_index) in self.exports.iter() {
+            exports.export(name, ExportKind::Func, *function_index);
+        }
+
+        let mut elements = ElementSection::new();
+        elements.active(None, &ConstExpr::i32_const(0), Elements::Functions(self.table.as_slice().into()));
+
+        let mut data = DataSection::new();
+        data.active(0, &ConstExpr::i32_const(0), self.data.bytes.iter().copied());
+
+        let mut module = wasm_encoder::Module::new();
+        module
+            .section(&types)
+            .section(&imports)
+            .section(&functions)
+            .section(&tables)
+            .section(&memories)
+            .section(&globals)
+            .section(&exports)
+            .section(&elements)
+            .section(&code)
+            .section(&data);
+        module.finish()
+    }
+}
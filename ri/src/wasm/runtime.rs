@@ -0,0 +1,845 @@
+use wasm_encoder::{BlockType, Instruction as I, ValType};
+
+use super::module::*;
+
+const P: ValType = ValType::I32;
+
+/// Function index of every runtime function, which are defined in the module before the compiled SS functions.
+///
+/// The runtime implements every operation on values like ri does, as compiled code only decides the control flow of the program.
+/// Objects are allocated from a bump allocator that grows the linear memory as needed, and are never freed,
+/// as every module runs a single program once.
+pub struct Runtime {
+    pub alloc: u32,
+    pub number: u32,
+    pub boolean: u32,
+    pub array: u32,
+    pub function: u32,
+    pub environment: u32,
+    pub environment_get: u32,
+    pub function_code: u32,
+    pub add: u32,
+    pub subtract: u32,
+    pub multiply: u32,
+    pub divide: u32,
+    pub greater: u32,
+    pub greater_equal: u32,
+    pub less: u32,
+    pub less_equal: u32,
+    pub equal: u32,
+    pub string_equal: u32,
+    pub negate: u32,
+    pub not: u32,
+    pub condition: u32,
+    pub array_get: u32,
+    pub print: u32,
+    pub debug: u32,
+    pub error_header: u32,
+
+    /// Native clock function of the prelude, which is called indirectly like SS functions
+    pub clock: u32,
+}
+
+impl Runtime {
+    /// Declare and define every runtime function in the module
+    pub fn define(module: &mut ModuleBuilder) -> Runtime {
+        let runtime = Runtime {
+            alloc: module.declare_function(&[P], &[P]),
+            number: module.declare_function(&[ValType::F64], &[P]),
+            boolean: module.declare_function(&[P], &[P]),
+            array: module.declare_function(&[P], &[P]),
+            function: module.declare_function(&[P, P, P, P], &[P]),
+            environment: module.declare_function(&[P, P], &[P]),
+            environment_get: module.declare_function(&[P, P, P, P, P], &[P]),
+            function_code: module.declare_function(&[P, P, P], &[P]),
+            add: module.declare_function(&[P, P], &[P]),
+            subtract: module.declare_function(&[P, P], &[P]),
+            multiply: module.declare_function(&[P, P], &[P]),
+            divide: module.declare_function(&[P, P], &[P]),
+            greater: module.declare_function(&[P, P], &[P]),
+            greater_equal: module.declare_function(&[P, P], &[P]),
+            less: module.declare_function(&[P, P], &[P]),
+            less_equal: module.declare_function(&[P, P], &[P]),
+            equal: module.declare_function(&[P, P], &[P]),
+            string_equal: module.declare_function(&[P, P], &[P]),
+            negate: module.declare_function(&[P], &[P]),
+            not: module.declare_function(&[P], &[P]),
+            condition: module.declare_function(&[P, P, P], &[P]),
+            array_get: module.declare_function(&[P, P], &[P]),
+            print: module.declare_function(&[P, P], &[]),
+            debug: module.declare_function(&[P, P], &[]),
+            error_header: module.declare_function(&[], &[]),
+            clock: module.declare_function(&[P], &[P]),
+        };
+
+        let definitions = [
+            (runtime.alloc, runtime.define_alloc(&mut module.data)),
+            (runtime.number, runtime.define_number()),
+            (runtime.boolean, runtime.define_boolean()),
+            (runtime.array, runtime.define_array()),
+            (runtime.function, runtime.define_function()),
+            (runtime.environment, runtime.define_environment()),
+            (runtime.environment_get, runtime.define_environment_get(&mut module.data)),
+            (runtime.function_code, runtime.define_function_code(&mut module.data)),
+            (runtime.add, runtime.define_add(&mut module.data)),
+            (runtime.subtract, runtime.define_numeric(&mut module.data, I::F64Sub, runtime.number, "subtraction")),
+            (runtime.multiply, runtime.define_numeric(&mut module.data, I::F64Mul, runtime.number, "multiplication")),
+            (runtime.divide, runtime.define_numeric(&mut module.data, I::F64Div, runtime.number, "division")),
+            (runtime.greater, runtime.define_numeric(&mut module.data, I::F64Gt, runtime.boolean, "Greater")),
+            (runtime.greater_equal, runtime.define_numeric(&mut module.data, I::F64Ge, runtime.boolean, "Greater Equal")),
+            (runtime.less, runtime.define_numeric(&mut module.data, I::F64Lt, runtime.boolean, "Less")),
+            (runtime.less_equal, runtime.define_numeric(&mut module.data, I::F64Le, runtime.boolean, "Less Equal")),
+            (runtime.equal, runtime.define_equal()),
+            (runtime.string_equal, runtime.define_string_equal()),
+            (runtime.negate, runtime.define_negate(&mut module.data)),
+            (runtime.not, runtime.define_not(&mut module.data)),
+            (runtime.condition, runtime.define_condition(&mut module.data)),
+            (runtime.array_get, runtime.define_array_get(&mut module.data)),
+            (runtime.print, runtime.define_print(&mut module.data)),
+            (runtime.debug, runtime.define_debug(&mut module.data)),
+            (runtime.error_header, runtime.define_error_header(&mut module.data)),
+            (runtime.clock, runtime.define_clock()),
+        ];
+        for (function_index, body) in definitions {
+            module.define_function(function_index, body);
+        }
+
+        runtime
+    }
+
+    /* ==========================  Start of helpers  ========================== */
+
+    /// Report a runtime error the same way ri does, where 'message' generates the message written to stderr,
+    /// and trap since runtime errors always end the program
+    fn error(
+        &self,
+        body: &mut FunctionBody,
+        data: &mut StaticData,
+        message: impl FnOnce(&mut FunctionBody, &mut StaticData),
+    ) {
+        body.push(I::Call(self.error_header));
+        message(body, data);
+        body.write_text(data, I::I32Const(STDERR), "\n");
+        body.push(I::Unreachable);
+    }
+
+    /// Push 1 if the object in the local has the tag, else 0
+    fn has_tag(body: &mut FunctionBody, local: u32, tag: i32) {
+        body.extend([I::LocalGet(local), I::I32Load(memory(0)), I::I32Const(tag), I::I32Eq]);
+    }
+
+    /// Write the bytes of the String object in the local
+    fn write_string(body: &mut FunctionBody, stream: I<'static>, local: u32) {
+        body.extend([
+            stream,
+            I::LocalGet(local),
+            I::I32Const(ELEMENTS as i32),
+            I::I32Add,
+            I::LocalGet(local),
+            I::I32Load(memory(LENGTH)),
+            I::Call(HOST_WRITE),
+        ]);
+    }
+
+    /// Write the integer in the local as a number
+    fn write_integer(body: &mut FunctionBody, local: u32) {
+        body.extend([
+            I::I32Const(STDERR),
+            I::LocalGet(local),
+            I::F64ConvertI32S,
+            I::I32Const(0),
+            I::Call(HOST_WRITE_NUMBER),
+        ]);
+    }
+
+    /* ==========================  End of helpers  ========================== */
+
+    /* ==========================  Start of values  ========================== */
+
+    /// alloc(size) -> address, allocating 8 byte aligned memory by bumping the heap pointer, and growing the memory when it runs out
+    fn define_alloc(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(1);
+        let address = body.local(P);
+        body.extend([
+            I::GlobalGet(HEAP_POINTER),
+            I::LocalSet(address),
+            I::GlobalGet(HEAP_POINTER),
+            I::LocalGet(0),
+            I::I32Add,
+            I::I32Const(7),
+            I::I32Add,
+            I::I32Const(-8),
+            I::I32And,
+            I::GlobalSet(HEAP_POINTER),
+            I::Block(BlockType::Empty),
+            // Memory is large enough
+            I::GlobalGet(HEAP_POINTER),
+            I::MemorySize(0),
+            I::I32Const(16),
+            I::I32Shl,
+            I::I32LeU,
+            I::BrIf(0),
+            // Grow by the number of pages missing, plus 1 page to round up
+            I::GlobalGet(HEAP_POINTER),
+            I::MemorySize(0),
+            I::I32Const(16),
+            I::I32Shl,
+            I::I32Sub,
+            I::I32Const(16),
+            I::I32ShrU,
+            I::I32Const(1),
+            I::I32Add,
+            I::MemoryGrow(0),
+            I::I32Const(-1),
+            I::I32Ne,
+            I::BrIf(0),
+        ]);
+        self.error(&mut body, data, |body, data| body.write_text(data, I::I32Const(STDERR), "Out of memory"));
+        body.extend([I::End, I::LocalGet(address)]);
+        body
+    }
+
+    /// number(value) -> Number
+    fn define_number(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(1);
+        let object = body.local(P);
+        body.extend([
+            I::I32Const(16),
+            I::Call(self.alloc),
+            I::LocalTee(object),
+            I::I32Const(TAG_NUMBER),
+            I::I32Store(memory(0)),
+            I::LocalGet(object),
+            I::LocalGet(0),
+            I::F64Store(memory_f64(NUMBER_VALUE)),
+            I::LocalGet(object),
+        ]);
+        body
+    }
+
+    /// boolean(value) -> Bool, which returns the static Bool objects
+    fn define_boolean(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(1);
+        body.extend([I::I32Const(TRUE_OBJECT), I::I32Const(FALSE_OBJECT), I::LocalGet(0), I::Select]);
+        body
+    }
+
+    /// array(length) -> Array, where compiled code stores the elements
+    fn define_array(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(1);
+        let object = body.local(P);
+        body.extend([
+            I::LocalGet(0),
+            I::I32Const(2),
+            I::I32Shl,
+            I::I32Const(ELEMENTS as i32),
+            I::I32Add,
+            I::Call(self.alloc),
+            I::LocalTee(object),
+            I::I32Const(TAG_ARRAY),
+            I::I32Store(memory(0)),
+            I::LocalGet(object),
+            I::LocalGet(0),
+            I::I32Store(memory(LENGTH)),
+            I::LocalGet(object),
+        ]);
+        body
+    }
+
+    /// function(table_index, arity, closure, name) -> Function
+    fn define_function(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(4);
+        let object = body.local(P);
+        body.extend([
+            I::I32Const(FUNCTION_SIZE),
+            I::Call(self.alloc),
+            I::LocalTee(object),
+            I::I32Const(TAG_FUNCTION),
+            I::I32Store(memory(0)),
+        ]);
+        for (parameter, offset) in [FUNCTION_TABLE_INDEX, FUNCTION_ARITY, FUNCTION_CLOSURE, FUNCTION_NAME]
+            .into_iter()
+            .enumerate()
+        {
+            body.extend([I::LocalGet(object), I::LocalGet(parameter as u32), I::I32Store(memory(offset))]);
+        }
+        body.push(I::LocalGet(object));
+        body
+    }
+
+    /// environment(enclosing, number_of_slots) -> Environment, where every slot starts as 0 for an undefined identifier
+    fn define_environment(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+        let object = body.local(P);
+        body.extend([
+            I::LocalGet(1),
+            I::I32Const(2),
+            I::I32Shl,
+            I::I32Const(ENVIRONMENT_SLOTS as i32),
+            I::I32Add,
+            I::Call(self.alloc),
+            I::LocalTee(object),
+            I::I32Const(TAG_ENVIRONMENT),
+            I::I32Store(memory(0)),
+            I::LocalGet(object),
+            I::LocalGet(0),
+            I::I32Store(memory(ENVIRONMENT_ENCLOSING)),
+            I::LocalGet(object),
+        ]);
+        body
+    }
+
+    /// environment_get(environment, distance, slot, line, name) -> value,
+    /// using the scope distance value from the resolver and the slot assigned by the compiler
+    fn define_environment_get(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(5);
+        let value = body.local(P);
+        body.extend([
+            I::Block(BlockType::Empty),
+            I::Loop(BlockType::Empty),
+            I::LocalGet(1),
+            I::I32Eqz,
+            I::BrIf(1),
+            I::LocalGet(0),
+            I::I32Load(memory(ENVIRONMENT_ENCLOSING)),
+            I::LocalSet(0),
+            I::LocalGet(1),
+            I::I32Const(1),
+            I::I32Sub,
+            I::LocalSet(1),
+            I::Br(0),
+            I::End,
+            I::End,
+            I::LocalGet(0),
+            I::LocalGet(2),
+            I::I32Const(2),
+            I::I32Shl,
+            I::I32Add,
+            I::I32Load(memory(ENVIRONMENT_SLOTS)),
+            I::LocalTee(value),
+            I::I32Eqz,
+            I::If(BlockType::Empty),
+        ]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "[line ");
+            Self::write_integer(body, 3);
+            body.write_text(data, I::I32Const(STDERR), "] ReferenceError: Tried to use undefined identifier '");
+            Self::write_string(body, I::I32Const(STDERR), 4);
+            body.write_text(data, I::I32Const(STDERR), "'");
+        });
+        body.extend([I::End, I::LocalGet(value)]);
+        body
+    }
+
+    /// function_code(callee, number_of_arguments, line) -> table_index, which fails if the callee is not a function
+    fn define_function_code(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(3);
+        let arity = body.local(P);
+
+        Self::has_tag(&mut body, 0, TAG_FUNCTION);
+        body.extend([I::I32Eqz, I::If(BlockType::Empty)]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "[line ");
+            Self::write_integer(body, 2);
+            body.write_text(data, I::I32Const(STDERR), "] Attempted to call non-callable: ");
+            body.extend([I::I32Const(STDERR), I::LocalGet(0), I::Call(self.print)]);
+        });
+        body.push(I::End);
+
+        // Type checker ensures that the number of arguments match, and functions can only be called indirectly with their arity
+        body.extend([
+            I::LocalGet(0),
+            I::I32Load(memory(FUNCTION_ARITY)),
+            I::LocalTee(arity),
+            I::LocalGet(1),
+            I::I32Ne,
+            I::If(BlockType::Empty),
+        ]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "Internal interpreter error: Expected ");
+            Self::write_integer(body, arity);
+            body.write_text(data, I::I32Const(STDERR), " arguments but got ");
+            Self::write_integer(body, 1);
+            body.write_text(data, I::I32Const(STDERR), " for ");
+            body.extend([I::I32Const(STDERR), I::LocalGet(0), I::Call(self.print)]);
+        });
+        body.extend([I::End, I::LocalGet(0), I::I32Load(memory(FUNCTION_TABLE_INDEX))]);
+        body
+    }
+
+    /* ==========================  End of values  ========================== */
+
+    /* ==========================  Start of operators  ========================== */
+
+    /// add(left, right) -> value, which is overloaded to support string concatenation
+    fn define_add(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+        let left_length = body.local(P);
+        let right_length = body.local(P);
+        let object = body.local(P);
+
+        Self::has_tag(&mut body, 0, TAG_NUMBER);
+        Self::has_tag(&mut body, 1, TAG_NUMBER);
+        body.extend([
+            I::I32And,
+            I::If(BlockType::Empty),
+            I::LocalGet(0),
+            I::F64Load(memory_f64(NUMBER_VALUE)),
+            I::LocalGet(1),
+            I::F64Load(memory_f64(NUMBER_VALUE)),
+            I::F64Add,
+            I::Call(self.number),
+            I::Return,
+            I::End,
+        ]);
+
+        Self::has_tag(&mut body, 0, TAG_STRING);
+        Self::has_tag(&mut body, 1, TAG_STRING);
+        body.extend([
+            I::I32And,
+            I::If(BlockType::Empty),
+            I::LocalGet(0),
+            I::I32Load(memory(LENGTH)),
+            I::LocalSet(left_length),
+            I::LocalGet(1),
+            I::I32Load(memory(LENGTH)),
+            I::LocalSet(right_length),
+            I::LocalGet(left_length),
+            I::LocalGet(right_length),
+            I::I32Add,
+            I::I32Const(ELEMENTS as i32),
+            I::I32Add,
+            I::Call(self.alloc),
+            I::LocalTee(object),
+            I::I32Const(TAG_STRING),
+            I::I32Store(memory(0)),
+            I::LocalGet(object),
+            I::LocalGet(left_length),
+            I::LocalGet(right_length),
+            I::I32Add,
+            I::I32Store(memory(LENGTH)),
+            // Copy the left string's bytes followed by the right string's bytes
+            I::LocalGet(object),
+            I::I32Const(ELEMENTS as i32),
+            I::I32Add,
+            I::LocalGet(0),
+            I::I32Const(ELEMENTS as i32),
+            I::I32Add,
+            I::LocalGet(left_length),
+            I::MemoryCopy { src_mem: 0, dst_mem: 0 },
+            I::LocalGet(object),
+            I::I32Const(ELEMENTS as i32),
+            I::I32Add,
+            I::LocalGet(left_length),
+            I::I32Add,
+            I::LocalGet(1),
+            I::I32Const(ELEMENTS as i32),
+            I::I32Add,
+            I::LocalGet(right_length),
+            I::MemoryCopy { src_mem: 0, dst_mem: 0 },
+            I::LocalGet(object),
+            I::Return,
+            I::End,
+        ]);
+
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "Type Error: Invalid types used for addition!")
+        });
+        body
+    }
+
+    /// Binary operator on 2 Numbers, where 'result' is the runtime function creating the result value from the operator's result
+    fn define_numeric(&self, data: &mut StaticData, operator: I<'static>, result: u32, name: &str) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+
+        Self::has_tag(&mut body, 0, TAG_NUMBER);
+        Self::has_tag(&mut body, 1, TAG_NUMBER);
+        body.extend([I::I32And, I::I32Eqz, I::If(BlockType::Empty)]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), &format!("Type Error: Invalid types used for {}!", name))
+        });
+        body.extend([
+            I::End,
+            I::LocalGet(0),
+            I::F64Load(memory_f64(NUMBER_VALUE)),
+            I::LocalGet(1),
+            I::F64Load(memory_f64(NUMBER_VALUE)),
+            operator,
+            I::Call(result),
+        ]);
+        body
+    }
+
+    /// equal(left, right) -> i32, where arrays are never equal, and functions are compared by identity like ri does
+    fn define_equal(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+        let tag = body.local(P);
+
+        body.extend([
+            I::LocalGet(0),
+            I::I32Load(memory(0)),
+            I::LocalTee(tag),
+            I::LocalGet(1),
+            I::I32Load(memory(0)),
+            I::I32Ne,
+            I::If(BlockType::Empty),
+            I::I32Const(0),
+            I::Return,
+            I::End,
+        ]);
+
+        let comparisons: [(i32, Vec<I<'static>>); 5] = [
+            (
+                TAG_NUMBER,
+                vec![
+                    I::LocalGet(0),
+                    I::F64Load(memory_f64(NUMBER_VALUE)),
+                    I::LocalGet(1),
+                    I::F64Load(memory_f64(NUMBER_VALUE)),
+                    I::F64Eq,
+                ],
+            ),
+            (TAG_STRING, vec![I::LocalGet(0), I::LocalGet(1), I::Call(self.string_equal)]),
+            (
+                TAG_BOOL,
+                vec![I::LocalGet(0), I::I32Load(memory(BOOL_VALUE)), I::LocalGet(1), I::I32Load(memory(BOOL_VALUE)), I::I32Eq],
+            ),
+            (TAG_NULL, vec![I::I32Const(1)]),
+            (TAG_ARRAY, vec![I::I32Const(0)]),
+        ];
+        for (value_tag, comparison) in comparisons {
+            body.extend([I::LocalGet(tag), I::I32Const(value_tag), I::I32Eq, I::If(BlockType::Empty)]);
+            body.extend(comparison);
+            body.extend([I::Return, I::End]);
+        }
+
+        body.extend([I::LocalGet(0), I::LocalGet(1), I::I32Eq]);
+        body
+    }
+
+    /// string_equal(left, right) -> i32, comparing the bytes of 2 String objects
+    fn define_string_equal(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+        let index = body.local(P);
+        let length = body.local(P);
+        body.extend([
+            I::LocalGet(0),
+            I::I32Load(memory(LENGTH)),
+            I::LocalTee(length),
+            I::LocalGet(1),
+            I::I32Load(memory(LENGTH)),
+            I::I32Ne,
+            I::If(BlockType::Empty),
+            I::I32Const(0),
+            I::Return,
+            I::End,
+            I::Block(BlockType::Empty),
+            I::Loop(BlockType::Empty),
+            I::LocalGet(index),
+            I::LocalGet(length),
+            I::I32GeU,
+            I::BrIf(1),
+            I::LocalGet(0),
+            I::LocalGet(index),
+            I::I32Add,
+            I::I32Load8U(wasm_encoder::MemArg { offset: ELEMENTS, align: 0, memory_index: 0 }),
+            I::LocalGet(1),
+            I::LocalGet(index),
+            I::I32Add,
+            I::I32Load8U(wasm_encoder::MemArg { offset: ELEMENTS, align: 0, memory_index: 0 }),
+            I::I32Ne,
+            I::If(BlockType::Empty),
+            I::I32Const(0),
+            I::Return,
+            I::End,
+            I::LocalGet(index),
+            I::I32Const(1),
+            I::I32Add,
+            I::LocalSet(index),
+            I::Br(0),
+            I::End,
+            I::End,
+            I::I32Const(1),
+        ]);
+        body
+    }
+
+    fn define_negate(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(1);
+        Self::has_tag(&mut body, 0, TAG_NUMBER);
+        body.extend([
+            I::If(BlockType::Empty),
+            I::LocalGet(0),
+            I::F64Load(memory_f64(NUMBER_VALUE)),
+            I::F64Neg,
+            I::Call(self.number),
+            I::Return,
+            I::End,
+        ]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "Type Error: Invalid types used for number negation!")
+        });
+        body
+    }
+
+    fn define_not(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(1);
+        Self::has_tag(&mut body, 0, TAG_BOOL);
+        body.extend([
+            I::If(BlockType::Empty),
+            I::LocalGet(0),
+            I::I32Load(memory(BOOL_VALUE)),
+            I::I32Eqz,
+            I::Call(self.boolean),
+            I::Return,
+            I::End,
+        ]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "Type Error: Invalid types used for boolean negation!")
+        });
+        body
+    }
+
+    /// condition(value, message, message_length) -> i32, with the same strict checking as the interpreter's bool_or_err method
+    fn define_condition(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(3);
+        Self::has_tag(&mut body, 0, TAG_BOOL);
+        body.extend([I::If(BlockType::Empty), I::LocalGet(0), I::I32Load(memory(BOOL_VALUE)), I::Return, I::End]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "Type Error: Expected Bool but found type and value: ");
+            body.extend([I::I32Const(STDERR), I::LocalGet(0), I::Call(self.debug)]);
+            body.write_text(data, I::I32Const(STDERR), "\n");
+            body.extend([I::I32Const(STDERR), I::LocalGet(1), I::LocalGet(2), I::Call(HOST_WRITE)]);
+        });
+        body
+    }
+
+    /// array_get(array, index) -> value, with the same bounds check as the interpreter
+    fn define_array_get(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+        let index = body.local(ValType::F64);
+        let last_index = body.local(P);
+
+        Self::has_tag(&mut body, 0, TAG_ARRAY);
+        body.extend([I::I32Eqz, I::If(BlockType::Empty)]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(
+                data,
+                I::I32Const(STDERR),
+                "Type Error: Array element access failed, expect array to be of type Value::Array, found -> ",
+            );
+            body.extend([I::I32Const(STDERR), I::LocalGet(0), I::Call(self.debug)]);
+        });
+        body.push(I::End);
+
+        Self::has_tag(&mut body, 1, TAG_NUMBER);
+        body.extend([I::I32Eqz, I::If(BlockType::Empty)]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(
+                data,
+                I::I32Const(STDERR),
+                "Type Error: Array element access failed, expect index to be of type Value::Number, found -> ",
+            );
+            body.extend([I::I32Const(STDERR), I::LocalGet(1), I::Call(self.debug)]);
+        });
+        body.push(I::End);
+
+        // Negative indexes are checked before the index is truncated, like casting the index to usize in the interpreter
+        body.extend([
+            I::LocalGet(1),
+            I::F64Load(memory_f64(NUMBER_VALUE)),
+            I::LocalTee(index),
+            I::F64Const(0.0),
+            I::F64Ge,
+            I::LocalGet(index),
+            I::LocalGet(0),
+            I::I32Load(memory(LENGTH)),
+            I::F64ConvertI32U,
+            I::F64Lt,
+            I::I32And,
+            I::If(BlockType::Empty),
+            I::LocalGet(0),
+            I::LocalGet(index),
+            I::I32TruncF64U,
+            I::I32Const(2),
+            I::I32Shl,
+            I::I32Add,
+            I::I32Load(memory(ELEMENTS)),
+            I::Return,
+            I::End,
+            I::LocalGet(0),
+            I::I32Load(memory(LENGTH)),
+            I::I32Const(1),
+            I::I32Sub,
+            I::LocalSet(last_index),
+        ]);
+        self.error(&mut body, data, |body, data| {
+            body.write_text(data, I::I32Const(STDERR), "Array Index Out Of Bounds Error: Expect index to be 0 to ");
+            Self::write_integer(body, last_index);
+            body.write_text(data, I::I32Const(STDERR), ", found -> ");
+            body.extend([I::I32Const(STDERR), I::LocalGet(index), I::I32Const(0), I::Call(HOST_WRITE_NUMBER)]);
+        });
+        body
+    }
+
+    /* ==========================  End of operators  ========================== */
+
+    /* ==========================  Start of printing  ========================== */
+
+    /// Generate the code writing every element of the Array object in the local, separated by ", ",
+    /// where every element is written with the given runtime function
+    fn write_elements(body: &mut FunctionBody, data: &mut StaticData, array: u32, write_element: u32) {
+        let index = body.local(P);
+        body.extend([
+            I::Block(BlockType::Empty),
+            I::Loop(BlockType::Empty),
+            I::LocalGet(index),
+            I::LocalGet(array),
+            I::I32Load(memory(LENGTH)),
+            I::I32GeU,
+            I::BrIf(1),
+            I::LocalGet(index),
+            I::If(BlockType::Empty),
+        ]);
+        body.write_text(data, I::LocalGet(0), ", ");
+        body.extend([
+            I::End,
+            I::LocalGet(0),
+            I::LocalGet(array),
+            I::LocalGet(index),
+            I::I32Const(2),
+            I::I32Shl,
+            I::I32Add,
+            I::I32Load(memory(ELEMENTS)),
+            I::Call(write_element),
+            I::LocalGet(index),
+            I::I32Const(1),
+            I::I32Add,
+            I::LocalSet(index),
+            I::Br(0),
+            I::End,
+            I::End,
+        ]);
+    }
+
+    /// Generate a branch that runs the code generated by 'write' and returns, if the value in local 1 has the tag
+    fn tag_branch(body: &mut FunctionBody, data: &mut StaticData, tag: i32, write: impl FnOnce(&mut FunctionBody, &mut StaticData)) {
+        Self::has_tag(body, 1, tag);
+        body.push(I::If(BlockType::Empty));
+        write(body, data);
+        body.extend([I::Return, I::End]);
+    }
+
+    /// print(stream, value), the pretty printer of values, which is the same as ri's Display trait for values
+    fn define_print(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+        let stream = || I::LocalGet(0);
+
+        Self::tag_branch(&mut body, data, TAG_NUMBER, |body, _| {
+            body.extend([
+                stream(),
+                I::LocalGet(1),
+                I::F64Load(memory_f64(NUMBER_VALUE)),
+                I::I32Const(0),
+                I::Call(HOST_WRITE_NUMBER),
+            ]);
+        });
+        Self::tag_branch(&mut body, data, TAG_STRING, |body, data| {
+            body.write_text(data, stream(), "'");
+            Self::write_string(body, stream(), 1);
+            body.write_text(data, stream(), "'");
+        });
+        Self::tag_branch(&mut body, data, TAG_BOOL, |body, data| {
+            body.extend([I::LocalGet(1), I::I32Load(memory(BOOL_VALUE)), I::If(BlockType::Empty)]);
+            body.write_text(data, stream(), "true");
+            body.push(I::Else);
+            body.write_text(data, stream(), "false");
+            body.push(I::End);
+        });
+        Self::tag_branch(&mut body, data, TAG_NULL, |body, data| body.write_text(data, stream(), "NULL"));
+        Self::tag_branch(&mut body, data, TAG_ARRAY, |body, data| {
+            body.write_text(data, stream(), "[");
+            Self::write_elements(body, data, 1, self.print);
+            body.write_text(data, stream(), "]");
+        });
+        Self::tag_branch(&mut body, data, TAG_FUNCTION, |body, data| {
+            let name = body.local(P);
+            body.extend([
+                I::LocalGet(1),
+                I::I32Load(memory(FUNCTION_NAME)),
+                I::LocalSet(name),
+                I::LocalGet(1),
+                I::I32Load(memory(FUNCTION_NATIVE)),
+                I::If(BlockType::Empty),
+            ]);
+            body.write_text(data, stream(), "<function-native: ");
+            Self::write_string(body, stream(), name);
+            body.write_text(data, stream(), ">");
+            body.extend([I::Return, I::End, I::LocalGet(name), I::I32Eqz, I::If(BlockType::Empty)]);
+            body.write_text(data, stream(), "<function-ss: [anonymous]>");
+            body.extend([I::Return, I::End]);
+            body.write_text(data, stream(), "<function-ss: ");
+            Self::write_string(body, stream(), name);
+            body.write_text(data, stream(), ">");
+        });
+        body
+    }
+
+    /// debug(stream, value), which is the same as ri's derived Debug trait for values that is used in error messages,
+    /// except for functions, which are written with their pretty printed value
+    fn define_debug(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(2);
+        let stream = || I::LocalGet(0);
+
+        Self::tag_branch(&mut body, data, TAG_NUMBER, |body, data| {
+            body.write_text(data, stream(), "Number(");
+            body.extend([
+                stream(),
+                I::LocalGet(1),
+                I::F64Load(memory_f64(NUMBER_VALUE)),
+                I::I32Const(1),
+                I::Call(HOST_WRITE_NUMBER),
+            ]);
+            body.write_text(data, stream(), ")");
+        });
+        Self::tag_branch(&mut body, data, TAG_STRING, |body, data| {
+            body.write_text(data, stream(), "String(\"");
+            Self::write_string(body, stream(), 1);
+            body.write_text(data, stream(), "\")");
+        });
+        Self::tag_branch(&mut body, data, TAG_BOOL, |body, data| {
+            body.extend([I::LocalGet(1), I::I32Load(memory(BOOL_VALUE)), I::If(BlockType::Empty)]);
+            body.write_text(data, stream(), "Bool(true)");
+            body.push(I::Else);
+            body.write_text(data, stream(), "Bool(false)");
+            body.push(I::End);
+        });
+        Self::tag_branch(&mut body, data, TAG_NULL, |body, data| body.write_text(data, stream(), "Null"));
+        Self::tag_branch(&mut body, data, TAG_ARRAY, |body, data| {
+            body.write_text(data, stream(), "Array([");
+            Self::write_elements(body, data, 1, self.debug);
+            body.write_text(data, stream(), "])");
+        });
+        Self::tag_branch(&mut body, data, TAG_FUNCTION, |body, data| {
+            body.write_text(data, stream(), "Func(");
+            body.extend([stream(), I::LocalGet(1), I::Call(self.print)]);
+            body.write_text(data, stream(), ")");
+        });
+        body
+    }
+
+    fn define_error_header(&self, data: &mut StaticData) -> FunctionBody {
+        let mut body = FunctionBody::new(0);
+        body.write_text(data, I::I32Const(STDERR), "-------- Interpreter ERROR --------\n");
+        body
+    }
+
+    /* ==========================  End of printing  ========================== */
+
+    /// Same as the interpreter's clock native function, taking the closure parameter that every indirectly called function takes
+    fn define_clock(&self) -> FunctionBody {
+        let mut body = FunctionBody::new(1);
+        body.extend([I::Call(HOST_CLOCK), I::Call(self.number)]);
+        body
+    }
+}
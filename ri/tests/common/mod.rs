@@ -0,0 +1,60 @@
+// Helpers shared by the test harnesses that run ri's binaries as separate processes.
+// Every harness only uses some of them, and every test file compiles its own copy of this module.
+#![allow(dead_code)]
+
+use std::io::BufReader;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::Value;
+use simple_script::lsp::rpc;
+
+pub fn ri() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_simple_script"))
+}
+
+/// Remove the lines that ri and rvm print around the program's own output
+pub fn program_lines(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with("Entering file") && !line.starts_with("Completed in"))
+        .map(String::from)
+        .collect()
+}
+
+/// Connection to a server spoken to over stdio with 'Content-Length' framed JSON messages, like an editor would,
+/// which is how both the language server and the debug adapter talk
+pub struct Connection {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Connection {
+    /// Start the server binary, where 'name' is used in the panic message if it fails to start
+    pub fn start(binary: &str, name: &str) -> Connection {
+        let mut server = Command::new(binary)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|error| panic!("Failed to start the {}: {}", name, error));
+        let stdin = server.stdin.take().unwrap();
+        let stdout = BufReader::new(server.stdout.take().unwrap());
+        Connection { server, stdin, stdout }
+    }
+
+    pub fn send(&mut self, message: Value) {
+        rpc::write_message(&mut self.stdin, &message).unwrap();
+    }
+
+    pub fn receive(&mut self) -> Value {
+        let content = rpc::read_message(&mut self.stdout)
+            .unwrap()
+            .expect("Server closed its stdout");
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    /// Wait for the server to exit, returning true if it exited successfully
+    pub fn wait(mut self) -> bool {
+        self.server.wait().unwrap().success()
+    }
+}
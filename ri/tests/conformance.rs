@@ -0,0 +1,374 @@
+// Differential conformance runner.
+//
+// Every SS program in the conformance corpus is run on every engine, which are ri's interpreter, ri's compiling back
+// ends and rvm, and each engine's stdout, stderr and exit code is checked against the expectations annotated in the
+// program's comments. Engines whose external tools (like node or a C compiler) are not installed are skipped.
+//
+// Annotations are line comments anywhere in the program:
+//      // expect: <line>                   A line that must be printed to stdout, in the order they are written
+//      // expect stderr: <text>            Text that must be printed to stderr, in the order they are written
+//      // expect exit: <code>              Exit code of the program, defaults to 0
+//      // expect stderr (rvm): <text>      Expectation for a single engine, which replaces the unqualified ones of the
+//                                          same kind for that engine, used for error messages that differ by engine
+//      // skip <engine>: <reason>          Do not run the program on the engine, e.g. if it does not support a feature
+//
+// Variants of an engine, like rvm's execution engines, are named '<engine> <variant>' and use the engine's annotations.
+//
+// Programs that do not expect anything on stderr must not print anything to stderr, and every run must end within a
+// timeout, so that an engine that hangs fails the test instead of hanging it.
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod common;
+mod wasm_host;
+use common::{program_lines, ri};
+use wasm_host::Exit;
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../SS code for testing/conformance");
+const RVM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../rvm");
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Output of a program run by an engine
+#[derive(PartialEq)]
+struct Output {
+    stdout: Vec<String>,
+    stderr: String,
+    exit_code: i32,
+}
+
+/// An engine that programs are run on, where run returns None if the engine's external tools are not available
+struct Engine {
+    name: &'static str,
+    run: fn(&Path, &Path) -> Option<Output>,
+}
+
+const ENGINES: [Engine; 10] = [
+    Engine { name: "ri", run: run_ri },
+    Engine { name: "vm", run: run_vm },
+    Engine { name: "rvm", run: run_rvm },
+    Engine { name: "rvm register", run: run_rvm_register },
+    Engine { name: "rvm -O2", run: run_rvm_optimized },
+    Engine { name: "rvm jit", run: run_rvm_jit },
+    Engine { name: "js", run: run_js },
+    Engine { name: "wasm", run: run_wasm },
+    Engine { name: "c", run: run_c },
+    Engine { name: "native", run: run_native },
+];
+
+/// Read the whole stream on another thread, so that a child filling up a pipe can not block it while waiting on it
+fn read_to_end(stream: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut stream) = stream {
+            stream.read_to_end(&mut bytes).ok();
+        }
+        String::from_utf8_lossy(&bytes).to_string()
+    })
+}
+
+/// Wait for the child until the timeout, killing it if it is still running, returning None if it was killed
+fn wait(child: &mut Child) -> Option<i32> {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait().expect("Failed to wait on child") {
+            return Some(status.code().unwrap_or(-1));
+        }
+        if Instant::now() >= deadline {
+            child.kill().ok();
+            child.wait().ok();
+            return None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Run the command, failing the run if it does not end within the timeout.
+/// Returns None if the command's program cannot be found.
+fn execute(command: &mut Command) -> Option<Output> {
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().ok()?;
+    let stdout = read_to_end(child.stdout.take());
+    let stderr = read_to_end(child.stderr.take());
+    let exit_code = wait(&mut child);
+
+    let mut stderr = stderr.join().unwrap();
+    if exit_code.is_none() {
+        stderr.push_str(&format!("\nKilled after a timeout of {} seconds", TIMEOUT.as_secs()));
+    }
+    Some(Output {
+        stdout: program_lines(&stdout.join().unwrap()),
+        stderr,
+        exit_code: exit_code.unwrap_or(-1),
+    })
+}
+
+/// Compile the program with one of ri's compiling back ends into the output path, returning the output of ri
+/// instead if the program failed to compile, so that compile time errors are checked like any other output
+fn compile(backend: &str, source: &Path, output: &Path) -> Result<(), Output> {
+    let result = execute(ri().args(["--backend", backend, "--output"]).arg(output).arg(source)).expect("Failed to run ri");
+    match result.exit_code {
+        0 => Ok(()),
+        _ => Err(result),
+    }
+}
+
+fn run_ri(source: &Path, _: &Path) -> Option<Output> {
+    execute(ri().arg(source))
+}
+
+fn run_vm(source: &Path, _: &Path) -> Option<Output> {
+    execute(ri().args(["--backend", "vm"]).arg(source))
+}
+
+/// Build rvm once in its own target directory, as it is a separate crate whose binary is not built for ri's tests
+fn rvm() -> &'static Path {
+    static RVM_BINARY: OnceLock<PathBuf> = OnceLock::new();
+    RVM_BINARY.get_or_init(|| {
+        let status = Command::new(std::env::var("CARGO").unwrap_or_else(|_| String::from("cargo")))
+            .args(["build", "--quiet", "--manifest-path"])
+            .arg(Path::new(RVM).join("Cargo.toml"))
+            .status()
+            .expect("Failed to run cargo");
+        assert!(status.success(), "Failed to build rvm");
+        Path::new(RVM).join("target").join("debug").join("rvm")
+    })
+}
+
+fn run_rvm(source: &Path, _: &Path) -> Option<Output> {
+    execute(Command::new(rvm()).arg("run").arg(source))
+}
+
+fn run_rvm_register(source: &Path, _: &Path) -> Option<Output> {
+    execute(Command::new(rvm()).args(["--engine", "register", "run"]).arg(source))
+}
+
+fn run_rvm_optimized(source: &Path, _: &Path) -> Option<Output> {
+    execute(Command::new(rvm()).args(["-O2", "run"]).arg(source))
+}
+
+/// Compile every function the first time it is called, so that the programs run on the JIT compiled code
+fn run_rvm_jit(source: &Path, _: &Path) -> Option<Output> {
+    execute(Command::new(rvm()).args(["--jit", "--jit-threshold", "1", "run"]).arg(source))
+}
+
+fn run_js(source: &Path, output_directory: &Path) -> Option<Output> {
+    Command::new("node").arg("--version").output().ok()?;
+    let output = output_directory.join(source.with_extension("js").file_name().unwrap());
+    match compile("js", source, &output) {
+        Ok(()) => execute(Command::new("node").arg(output)),
+        Err(result) => Some(result),
+    }
+}
+
+/// Run the module in wasmi, where a trap means that the program ended with a runtime error,
+/// and running out of fuel is the timeout of a module
+fn run_wasm(source: &Path, output_directory: &Path) -> Option<Output> {
+    let output = output_directory.join(source.with_extension("wasm").file_name().unwrap());
+    if let Err(result) = compile("wasm", source, &output) {
+        return Some(result);
+    }
+
+    let ((stdout, mut stderr), exit) = wasm_host::run(&output);
+    let exit_code = match exit {
+        Exit::Returned => 0,
+        Exit::Trapped => 1,
+        Exit::OutOfFuel => {
+            stderr.push_str("\nKilled after running out of fuel");
+            -1
+        }
+    };
+    Some(Output { stdout: program_lines(&stdout), stderr, exit_code })
+}
+
+fn run_c(source: &Path, output_directory: &Path) -> Option<Output> {
+    Command::new("cc").arg("--version").output().ok()?;
+    let output = output_directory.join(source.with_extension("c").file_name().unwrap());
+    if let Err(result) = compile("c", source, &output) {
+        return Some(result);
+    }
+
+    let executable = output.with_extension("c.out");
+    let result = Command::new("cc")
+        .args(["-std=c99", "-o"])
+        .arg(&executable)
+        .arg(&output)
+        .arg("-lm")
+        .output()
+        .ok()?;
+    assert!(
+        result.status.success(),
+        "Failed to compile the generated C for '{}':\n{}",
+        source.display(),
+        String::from_utf8_lossy(&result.stderr)
+    );
+    execute(&mut Command::new(executable))
+}
+
+fn run_native(source: &Path, output_directory: &Path) -> Option<Output> {
+    // The system linker is used to link the native executables
+    Command::new("cc").arg("--version").output().ok()?;
+    let output = output_directory.join(source.with_extension("native").file_name().unwrap());
+    match compile("native", source, &output) {
+        Ok(()) => execute(&mut Command::new(output)),
+        Err(result) => Some(result),
+    }
+}
+
+/// Expectations annotated in a program, where engine is None for expectations that apply to every engine
+#[derive(Default)]
+struct Expectations {
+    stdout: Vec<(Option<String>, String)>,
+    stderr: Vec<(Option<String>, String)>,
+    exit_code: Vec<(Option<String>, i32)>,
+    skip: Vec<(String, String)>,
+}
+
+impl Expectations {
+    fn parse(source: &str) -> Self {
+        let mut expectations = Expectations::default();
+        for comment in source.lines().filter_map(|line| line.trim().strip_prefix("//")) {
+            let comment = comment.trim();
+            let Some((annotation, value)) = comment.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().to_string();
+
+            if let Some(engine) = annotation.strip_prefix("skip ") {
+                expectations.skip.push((engine.trim().to_string(), value));
+                continue;
+            }
+
+            let Some(annotation) = annotation.strip_prefix("expect") else {
+                continue;
+            };
+            let (kind, engine) = match annotation.split_once('(') {
+                Some((kind, engine)) => (kind.trim(), Some(engine.trim_end_matches(')').trim().to_string())),
+                None => (annotation.trim(), None),
+            };
+            match kind {
+                "" => expectations.stdout.push((engine, value)),
+                "stderr" => expectations.stderr.push((engine, value)),
+                "exit" => expectations
+                    .exit_code
+                    .push((engine, value.parse().expect("Exit code annotation must be a number"))),
+                _ => panic!("Unknown annotation '// expect{}'", annotation),
+            }
+        }
+        expectations
+    }
+
+    /// Get the expectations for the engine, where the engine's own expectations replace the unqualified ones
+    fn for_engine<T: Clone>(expectations: &[(Option<String>, T)], engine: &str) -> Vec<T> {
+        let engine = base_engine(engine);
+        let qualified: Vec<T> = expectations
+            .iter()
+            .filter(|(name, _)| name.as_deref() == Some(engine))
+            .map(|(_, value)| value.clone())
+            .collect();
+        if !qualified.is_empty() {
+            return qualified;
+        }
+        expectations
+            .iter()
+            .filter(|(name, _)| name.is_none())
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    fn skip_reason(&self, engine: &str) -> Option<&str> {
+        self.skip
+            .iter()
+            .find(|(name, _)| name == base_engine(engine))
+            .map(|(_, reason)| reason.as_str())
+    }
+
+    /// Check the output of an engine, returning a description of every divergence from the expectations
+    fn check(&self, engine: &str, output: &Output) -> Vec<String> {
+        let mut divergences = Vec::new();
+
+        let stdout = Self::for_engine(&self.stdout, engine);
+        if output.stdout != stdout {
+            divergences.push(format!(
+                "stdout differs\n-- expected --\n{}\n-- actual --\n{}",
+                stdout.join("\n"),
+                output.stdout.join("\n")
+            ));
+        }
+
+        let stderr = Self::for_engine(&self.stderr, engine);
+        if stderr.is_empty() && !output.stderr.is_empty() {
+            divergences.push(format!("unexpected stderr\n{}", output.stderr));
+        }
+        // Every expected text must be found after the previous one
+        let mut remaining = output.stderr.as_str();
+        for text in stderr.iter() {
+            match remaining.find(text.as_str()) {
+                Some(index) => remaining = &remaining[index + text.len()..],
+                None => {
+                    divergences.push(format!("stderr is missing '{}'\n{}", text, output.stderr));
+                    break;
+                }
+            }
+        }
+
+        let exit_code = Self::for_engine(&self.exit_code, engine).last().copied().unwrap_or(0);
+        if output.exit_code != exit_code {
+            divergences.push(format!("expected exit code {} but found {}", exit_code, output.exit_code));
+        }
+
+        divergences
+    }
+}
+
+/// Get the name of the engine that a variant of it is annotated as
+fn base_engine(engine: &str) -> &str {
+    engine.split(' ').next().unwrap()
+}
+
+#[test]
+fn engines_conform_to_annotated_programs() {
+    let output_directory = std::env::temp_dir().join(format!("ss_conformance_{}", std::process::id()));
+    fs::create_dir_all(&output_directory).unwrap();
+
+    let mut sources: Vec<PathBuf> = fs::read_dir(CORPUS)
+        .expect("Failed to read the conformance corpus")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ss"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty(), "Conformance corpus is empty");
+
+    let mut failures = Vec::new();
+    for source in sources.iter() {
+        let expectations = Expectations::parse(&fs::read_to_string(source).unwrap());
+        let name = source.file_name().unwrap().to_string_lossy();
+
+        for engine in ENGINES.iter() {
+            if let Some(reason) = expectations.skip_reason(engine.name) {
+                println!("SKIP {} on {}: {}", name, engine.name, reason);
+                continue;
+            }
+            let Some(output) = (engine.run)(source, &output_directory) else {
+                println!("SKIP {} on {}: engine is not available", name, engine.name);
+                continue;
+            };
+
+            let divergences = expectations.check(engine.name, &output);
+            if divergences.is_empty() {
+                println!("PASS {} on {}", name, engine.name);
+            } else {
+                println!("FAIL {} on {}", name, engine.name);
+                for divergence in divergences {
+                    failures.push(format!("{} on {}: {}", name, engine.name, divergence));
+                }
+            }
+        }
+    }
+
+    fs::remove_dir_all(&output_directory).ok();
+    assert!(failures.is_empty(), "Engines diverge from the conformance corpus:\n\n{}", failures.join("\n\n"));
+}
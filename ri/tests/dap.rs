@@ -0,0 +1,251 @@
+// Tests for the debug adapter, which is run as a separate process and spoken to over stdio like an editor would.
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+use common::Connection;
+
+use serde_json::{json, Value};
+
+const PROGRAM: &str = "\
+fn add(a, b) {
+  const sum = a + b;
+  return sum;
+}
+
+const numbers = [1, 2];
+const total = add(numbers[0], numbers[1]);
+print total;
+print add(total, 10);
+";
+
+struct Client {
+    connection: Connection,
+    seq: u64,
+
+    /// Events received while waiting for responses
+    events: Vec<Value>,
+
+    /// Everything that the program printed so far
+    output: String,
+    program: PathBuf,
+}
+
+impl Client {
+    /// Start the debug adapter and launch the program, which is written to a file named after the test
+    fn launch(test: &str, program: &str, stop_on_entry: bool) -> (Client, Value) {
+        let path = std::env::temp_dir().join(format!("ss-dap-{}-{}.ss", std::process::id(), test));
+        fs::write(&path, program).unwrap();
+
+        let mut client = Client {
+            connection: Connection::start(env!("CARGO_BIN_EXE_ss-dap"), "debug adapter"),
+            seq: 0,
+            events: Vec::new(),
+            output: String::new(),
+            program: path,
+        };
+        client.request("initialize", json!({ "adapterID": "simplescript" }));
+        let program = client.program.to_string_lossy().to_string();
+        let response = client.request("launch", json!({ "program": program, "stopOnEntry": stop_on_entry }));
+        (client, response)
+    }
+
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let seq = self.seq;
+        self.connection.send(json!({ "seq": seq, "type": "request", "command": command, "arguments": arguments }));
+
+        loop {
+            let message = self.connection.receive();
+            if message["type"] == "response" && message["request_seq"] == seq {
+                return message;
+            }
+            self.events.push(message);
+        }
+    }
+
+    /// Get the body of a request that must succeed
+    fn body(&mut self, command: &str, arguments: Value) -> Value {
+        let response = self.request(command, arguments);
+        assert_eq!(response["success"], true, "{} failed: {}", command, response);
+        response["body"].clone()
+    }
+
+    /// Wait for the event, skipping other events before it
+    fn event(&mut self, event: &str) -> Value {
+        loop {
+            let message = match self.events.is_empty() {
+                true => self.connection.receive(),
+                false => self.events.remove(0),
+            };
+            if message["event"] == "output" && message["body"]["category"] == "stdout" {
+                self.output += message["body"]["output"].as_str().unwrap();
+            }
+            if message["event"] == event {
+                return message["body"].clone();
+            }
+        }
+    }
+
+    fn set_breakpoints(&mut self, lines: &[u64]) -> Value {
+        let breakpoints: Vec<Value> = lines.iter().map(|line| json!({ "line": line })).collect();
+        let path = self.program.to_string_lossy().to_string();
+        self.body("setBreakpoints", json!({ "source": { "path": path }, "breakpoints": breakpoints }))
+    }
+
+    /// Get the (name, line) of every stack frame, from the top frame
+    fn stack(&mut self) -> Vec<(String, u64)> {
+        let body = self.body("stackTrace", json!({ "threadId": 1 }));
+        body["stackFrames"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|frame| (frame["name"].as_str().unwrap().to_string(), frame["line"].as_u64().unwrap()))
+            .collect()
+    }
+
+    /// Resume with the request and get the line of the top frame once the program stops again, with the stop reason
+    fn resume(&mut self, command: &str) -> (String, u64) {
+        self.body(command, json!({ "threadId": 1 }));
+        let stopped = self.event("stopped");
+        (stopped["reason"].as_str().unwrap().to_string(), self.stack()[0].1)
+    }
+
+    /// Get the (name, value) of every variable of the reference
+    fn variables(&mut self, reference: &Value) -> Vec<(String, String)> {
+        let body = self.body("variables", json!({ "variablesReference": reference }));
+        body["variables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variable| {
+                let name = variable["name"].as_str().unwrap().to_string();
+                (name, variable["value"].as_str().unwrap().to_string())
+            })
+            .collect()
+    }
+
+    fn evaluate(&mut self, expression: &str, frame_id: Value) -> Value {
+        self.request("evaluate", json!({ "expression": expression, "frameId": frame_id, "context": "watch" }))
+    }
+
+    /// Wait for the program to exit, and get its exit code
+    fn exit_code(&mut self) -> u64 {
+        let exit_code = self.event("exited")["exitCode"].as_u64().unwrap();
+        self.event("terminated");
+        exit_code
+    }
+
+    fn disconnect(mut self) -> bool {
+        self.request("disconnect", json!({}));
+        fs::remove_file(&self.program).unwrap();
+        self.connection.wait()
+    }
+}
+
+#[test]
+fn breakpoints_scopes_and_evaluation() {
+    let (mut client, response) = Client::launch("breakpoints", PROGRAM, false);
+    assert_eq!(response["success"], true);
+    client.event("initialized");
+
+    // Breakpoints on lines without a stmt are moved to the next stmt
+    let breakpoints = client.set_breakpoints(&[2, 5, 20]);
+    assert_eq!(breakpoints["breakpoints"][0], json!({ "verified": true, "line": 2 }));
+    assert_eq!(breakpoints["breakpoints"][1], json!({ "verified": true, "line": 6 }));
+    assert_eq!(breakpoints["breakpoints"][2]["verified"], false);
+    client.body("configurationDone", json!({}));
+
+    assert_eq!(client.event("stopped")["reason"], "breakpoint");
+    assert_eq!(client.stack(), vec![(String::from("main"), 6)]);
+
+    assert_eq!(client.resume("continue"), (String::from("breakpoint"), 2));
+    assert_eq!(client.stack(), vec![(String::from("add"), 2), (String::from("main"), 7)]);
+
+    // Frames are numbered from the bottom of the call stack, and the function's scope is enclosed by its closure
+    let scopes = client.body("scopes", json!({ "frameId": 1 }))["scopes"].clone();
+    let names: Vec<&str> = scopes.as_array().unwrap().iter().map(|scope| scope["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Local", "Global"]);
+    let locals = client.variables(&scopes[0]["variablesReference"]);
+    assert_eq!(locals, vec![(String::from("a"), String::from("1")), (String::from("b"), String::from("2"))]);
+
+    let globals = client.variables(&scopes[1]["variablesReference"]);
+    let names: Vec<&str> = globals.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["add", "clock", "numbers"]);
+
+    // Expressions are evaluated in the scope of the frame, and arrays can be expanded into their elements
+    let result = client.evaluate("a + b * 10", json!(1));
+    assert_eq!(result["body"]["result"], "21");
+    let result = client.evaluate("numbers", json!(0));
+    assert_eq!(result["body"]["result"], "[1, 2]");
+    let elements = client.variables(&result["body"]["variablesReference"]);
+    assert_eq!(elements, vec![(String::from("[0]"), String::from("1")), (String::from("[1]"), String::from("2"))]);
+    assert_eq!(client.evaluate("add(a, 100)", json!(1))["body"]["result"], "101");
+
+    let result = client.evaluate("sum", json!(1));
+    assert_eq!(result["success"], false);
+    assert_eq!(result["message"], "'sum' is not defined in this scope");
+
+    assert_eq!(client.resume("continue"), (String::from("breakpoint"), 2));
+    assert_eq!(client.evaluate("a", Value::Null)["body"]["result"], "3");
+
+    client.body("continue", json!({ "threadId": 1 }));
+    assert_eq!(client.exit_code(), 0);
+    assert_eq!(client.output, "3\n13\n");
+    assert!(client.disconnect());
+}
+
+#[test]
+fn steps_over_into_and_out_of_calls() {
+    let (mut client, _) = Client::launch("steps", PROGRAM, true);
+    client.event("initialized");
+    client.body("configurationDone", json!({}));
+
+    assert_eq!(client.event("stopped")["reason"], "entry");
+    assert_eq!(client.stack(), vec![(String::from("main"), 1)]);
+
+    assert_eq!(client.resume("next"), (String::from("step"), 6));
+    assert_eq!(client.resume("next"), (String::from("step"), 7));
+    assert_eq!(client.resume("stepIn"), (String::from("step"), 2));
+    assert_eq!(client.stack().len(), 2);
+    assert_eq!(client.resume("next"), (String::from("step"), 3));
+
+    // Stepping out pauses after the call, at the stmt after the one that called the function
+    assert_eq!(client.resume("stepOut"), (String::from("step"), 8));
+    assert_eq!(client.evaluate("total", Value::Null)["body"]["result"], "3");
+
+    // Stepping over the call runs the whole function, and stepping in at the last stmt runs until the program exits
+    assert_eq!(client.resume("next"), (String::from("step"), 9));
+    assert_eq!(client.resume("stepIn"), (String::from("step"), 2));
+    client.body("stepOut", json!({ "threadId": 1 }));
+    assert_eq!(client.exit_code(), 0);
+    assert_eq!(client.output, "3\n13\n");
+    assert!(client.disconnect());
+}
+
+#[test]
+fn reports_invalid_programs_and_runtime_errors() {
+    let (client, response) = Client::launch("invalid", "const a = 1;\nprint b;\n", false);
+    assert_eq!(response["success"], false);
+    assert!(response["message"].as_str().unwrap().contains("Resolver error"));
+    assert!(client.disconnect());
+
+    let (mut client, _) = Client::launch("runtime_error", "const a = [1];\nprint a[0];\nprint a[5];\n", false);
+    client.event("initialized");
+    client.body("configurationDone", json!({}));
+    assert_eq!(client.exit_code(), 1);
+    assert_eq!(client.output, "1\n");
+    assert!(client.disconnect());
+}
+
+#[test]
+fn disconnecting_terminates_the_paused_program() {
+    let (mut client, _) = Client::launch("disconnect", "while (true) {\n  print 1;\n}\n", false);
+    client.event("initialized");
+    client.set_breakpoints(&[2]);
+    client.body("configurationDone", json!({}));
+
+    assert_eq!(client.event("stopped")["reason"], "breakpoint");
+    assert_eq!(client.resume("continue"), (String::from("breakpoint"), 2));
+    assert!(client.disconnect());
+}
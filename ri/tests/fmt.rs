@@ -0,0 +1,159 @@
+// Tests for the formatter, run through 'simple_script fmt' like it is used in CI, and through the library for the corpus.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use simple_script::formatter;
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../SS code for testing");
+
+const UNFORMATTED: &str = "   const a=1;   // trailing
+const b = [ 1, // one
+  2 /* two */ ,3];
+
+
+/* block */ print a+b[0];
+fn f(x,y) { // after brace
+  return; }
+const g = (x) => x*2;
+if (a == 1) { print 0; } else if (a == 2) print 1; else { print 2; }
+{
+  print -a;
+
+  // end of block
+}
+ignore f(1, 2) /* inline */;
+// end of program
+";
+
+const FORMATTED: &str = "const a = 1; // trailing
+const b = [1, // one
+\t2 /* two */, 3];
+
+/* block */
+print a + b[0];
+fn f(x, y) { // after brace
+\treturn;
+}
+const g = (x) => x * 2;
+if (a == 1) {
+\tprint 0;
+} else if (a == 2)
+\tprint 1;
+else {
+\tprint 2;
+}
+{
+\tprint -a;
+
+\t// end of block
+}
+ignore f(1, 2) /* inline */;
+// end of program
+";
+
+// Comments that would separate an opening brace from its statement, or 'else' from the closing brace before it
+const COMMENTS_AROUND_BRACES: &str = "const a = 1;
+if (a == 1) // condition
+{
+  print 1;
+} // after branch
+else {
+  print 2;
+}
+if (a == 1) print 1;
+// before else
+else print 2;
+fn f() /* block */ // line
+{}
+";
+
+const COMMENTS_AROUND_BRACES_FORMATTED: &str = "const a = 1;
+if (a == 1) {
+\t// condition
+\tprint 1;
+\t// after branch
+} else {
+\tprint 2;
+}
+if (a == 1)
+\tprint 1;
+// before else
+else
+\tprint 2;
+fn f() {
+\t/* block */
+\t// line
+}
+";
+
+fn ss_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            ss_files(&path, files);
+        } else if path.extension().is_some_and(|extension| extension == "ss") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn formats_into_canonical_layout_keeping_comments() {
+    assert_eq!(formatter::format(UNFORMATTED).unwrap(), FORMATTED);
+    assert_eq!(formatter::format(FORMATTED).unwrap(), FORMATTED);
+}
+
+#[test]
+fn comments_around_braces_are_moved_into_blocks() {
+    assert_eq!(formatter::format(COMMENTS_AROUND_BRACES).unwrap(), COMMENTS_AROUND_BRACES_FORMATTED);
+    assert_eq!(
+        formatter::format(COMMENTS_AROUND_BRACES_FORMATTED).unwrap(),
+        COMMENTS_AROUND_BRACES_FORMATTED
+    );
+}
+
+#[test]
+fn formatting_the_corpus_is_idempotent() {
+    let mut files = Vec::new();
+    ss_files(Path::new(CORPUS), &mut files);
+    assert!(!files.is_empty());
+
+    for file in files.iter() {
+        let formatted = formatter::format(&fs::read_to_string(file).unwrap())
+            .unwrap_or_else(|error| panic!("Failed to format '{}': {}", file.display(), error));
+        assert_eq!(
+            formatter::format(&formatted).unwrap(),
+            formatted,
+            "Formatting '{}' again changed it",
+            file.display()
+        );
+    }
+}
+
+#[test]
+fn check_fails_on_unformatted_files_without_changing_them() {
+    let file = std::env::temp_dir().join(format!("ss_fmt_{}.ss", std::process::id()));
+    fs::write(&file, UNFORMATTED).unwrap();
+    let fmt = |check: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_simple_script"));
+        command.arg("fmt");
+        if check {
+            command.arg("--check");
+        }
+        command.arg(&file).output().expect("Failed to run ri").status.success()
+    };
+
+    assert!(!fmt(true));
+    assert_eq!(fs::read_to_string(&file).unwrap(), UNFORMATTED);
+
+    assert!(fmt(false));
+    assert_eq!(fs::read_to_string(&file).unwrap(), FORMATTED);
+    assert!(fmt(true));
+
+    // Programs that do not parse are not formatted
+    fs::write(&file, "const a = ;").unwrap();
+    assert!(!fmt(false));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "const a = ;");
+    fs::remove_file(&file).ok();
+}
@@ -0,0 +1,122 @@
+// Tests for the linter, run through the library for the rules and through 'simple_script lint' for config files.
+use std::fs;
+use std::process::Command;
+
+use simple_script::linter::{self, Config, Rule, Severity, CONFIG_FILE_NAME};
+
+const PROGRAM: &str = "\
+const MyValue = 1;
+fn add(a, b, _c) {
+  return (1 * a);
+}
+fn log(x) {
+  print x;
+}
+const f = fn (unused) { print 1; };
+const g = (x) => (1 + x);
+if (true) print (1 + 2) * 3;
+while (1 < 2 and false) print MyValue;
+const _private = 2; // lint-ignore naming-style
+// lint-ignore
+const Other = add(1, 2, 3) + f(1) + g(2);
+ignore log(((5)));
+print [-(2)][0];
+";
+
+/// Get the (line, column, rule) of every diagnostic
+fn broken_rules(config: &Config) -> Vec<(usize, usize, &'static str)> {
+    linter::lint(PROGRAM, config)
+        .unwrap()
+        .iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.rule.name()))
+        .collect()
+}
+
+#[test]
+fn rules_are_checked_at_their_positions() {
+    assert_eq!(
+        broken_rules(&Config::default()),
+        vec![
+            (1, 7, "naming-style"),
+            (2, 11, "unused-parameter"),
+            (3, 10, "redundant-grouping"),
+            (5, 4, "missing-return"),
+            (8, 11, "missing-return"),
+            (8, 15, "unused-parameter"),
+            (9, 18, "redundant-grouping"),
+            (10, 5, "constant-condition"),
+            (11, 8, "constant-condition"),
+            (15, 12, "redundant-grouping"),
+            (15, 13, "redundant-grouping"),
+            (16, 9, "redundant-grouping"),
+        ]
+    );
+}
+
+#[test]
+fn rules_are_configurable() {
+    let config = Config::parse(r#"{ "rules": { "naming-style": "error", "redundant-grouping": "off" } }"#).unwrap();
+    assert_eq!(config.severity(Rule::NamingStyle), Severity::Error);
+    assert_eq!(config.severity(Rule::MissingReturn), Severity::Warning);
+
+    let diagnostics = linter::lint(PROGRAM, &config).unwrap();
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.rule != Rule::RedundantGrouping));
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+
+    assert!(Config::parse(r#"{ "rules": { "no-such-rule": "error" } }"#).is_err());
+    assert!(Config::parse(r#"{ "rules": { "naming-style": "loud" } }"#).is_err());
+}
+
+#[test]
+fn corpus_can_be_linted() {
+    let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/../SS code for testing");
+    let mut directories = vec![std::path::PathBuf::from(corpus)];
+    while let Some(directory) = directories.pop() {
+        for path in fs::read_dir(directory).unwrap().map(|entry| entry.unwrap().path()) {
+            if path.is_dir() {
+                directories.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "ss") {
+                // Programs with resolver errors are part of the corpus too, which cannot be linted
+                let result = linter::lint(&fs::read_to_string(&path).unwrap(), &Config::default());
+                if let Err(error) = result {
+                    assert!(error.to_string().contains("resolver"), "{}: {}", path.display(), error);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn programs_must_resolve_to_be_linted() {
+    assert!(linter::lint("print a;", &Config::default()).is_err());
+    assert!(linter::lint("const a = ;", &Config::default()).is_err());
+}
+
+#[test]
+fn config_file_decides_exit_code() {
+    let directory = std::env::temp_dir().join(format!("ss_lint_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let file = directory.join("program.ss");
+    fs::write(&file, PROGRAM).unwrap();
+
+    let lint = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_simple_script"))
+            .arg("lint")
+            .arg(&file)
+            .output()
+            .expect("Failed to run ri");
+        (output.status.success(), String::from_utf8_lossy(&output.stdout).to_string())
+    };
+
+    // Warnings do not fail linting
+    let (success, stdout) = lint();
+    assert!(success);
+    assert!(stdout.contains("program.ss:1:7: warning [naming-style] Const 'MyValue' should be named in camelCase"));
+
+    fs::write(directory.join(CONFIG_FILE_NAME), r#"{ "rules": { "naming-style": "error" } }"#).unwrap();
+    let (success, stdout) = lint();
+    assert!(!success);
+    assert!(stdout.contains("program.ss:1:7: error [naming-style]"));
+
+    fs::remove_dir_all(&directory).ok();
+}
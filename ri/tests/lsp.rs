@@ -0,0 +1,271 @@
+// Tests for the language server, which is run as a separate process and spoken to over stdio like an editor would.
+mod common;
+use common::Connection;
+
+use serde_json::{json, Value};
+
+const URI: &str = "file:///test.ss";
+
+struct Client {
+    connection: Connection,
+    next_id: u64,
+
+    /// Notifications received while waiting for responses
+    notifications: Vec<Value>,
+}
+
+impl Client {
+    fn start() -> Client {
+        let mut client = Client {
+            connection: Connection::start(env!("CARGO_BIN_EXE_ss-lsp"), "language server"),
+            next_id: 1,
+            notifications: Vec::new(),
+        };
+        client.request("initialize", json!({ "capabilities": {} }));
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.connection.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+
+        loop {
+            let message = self.connection.receive();
+            if message["id"] == id {
+                return message;
+            }
+            self.notifications.push(message);
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.connection.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Open the document and wait for its diagnostics
+    fn open(&mut self, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "simplescript", "version": 1, "text": text } }),
+        );
+        self.diagnostics()
+    }
+
+    fn change(&mut self, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didChange",
+            json!({ "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": text }] }),
+        );
+        self.diagnostics()
+    }
+
+    fn diagnostics(&mut self) -> Vec<Value> {
+        let notification = match self.notifications.pop() {
+            Some(notification) => notification,
+            None => self.connection.receive(),
+        };
+        assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+        notification["params"]["diagnostics"].as_array().unwrap().clone()
+    }
+
+    /// Send a request for the position in the document, and get its result
+    fn at(&mut self, method: &str, line: u64, character: u64) -> Value {
+        let response = self.request(
+            method,
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+                "context": { "includeDeclaration": true },
+            }),
+        );
+        response["result"].clone()
+    }
+
+    fn exit(mut self) -> bool {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        self.connection.wait()
+    }
+}
+
+/// Get the (line, character) of the start of every location
+fn starts(locations: &Value) -> Vec<(u64, u64)> {
+    let mut starts: Vec<(u64, u64)> = locations
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| {
+            let start = &location["range"]["start"];
+            (start["line"].as_u64().unwrap(), start["character"].as_u64().unwrap())
+        })
+        .collect();
+    starts.sort();
+    starts
+}
+
+const PROGRAM: &str = "\
+fn add(a, b) {
+  return a + b;
+}
+const x = add(1, 2);
+print x;
+{
+  const x = \"shadow\";
+  print x;
+}
+";
+
+#[test]
+fn publishes_diagnostics_on_change() {
+    let mut client = Client::start();
+    assert!(client.open(PROGRAM).is_empty());
+
+    let diagnostics = client.change("const a = 1;\nprint b;\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["source"], "Resolver");
+    assert_eq!(diagnostics[0]["range"]["start"], json!({ "line": 1, "character": 6 }));
+    assert_eq!(diagnostics[0]["range"]["end"], json!({ "line": 1, "character": 7 }));
+
+    let diagnostics = client.change("const a = 1;\nprint a + \"s\";\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["source"], "TypeChecker");
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+    let diagnostics = client.change("const a = 1\nprint a;\n");
+    assert_eq!(diagnostics[0]["source"], "Parser");
+
+    assert!(client.change(PROGRAM).is_empty());
+    assert!(client.exit());
+}
+
+#[test]
+fn hover_shows_inferred_types() {
+    let mut client = Client::start();
+    client.open(PROGRAM);
+
+    let hover = client.at("textDocument/hover", 3, 6);
+    assert_eq!(hover["contents"]["value"], "```simplescript\nx: Type::Number\n```");
+
+    // Parameters are shown with the types of the arguments they are called with
+    let hover = client.at("textDocument/hover", 0, 7);
+    assert_eq!(hover["contents"]["value"], "```simplescript\na: Type::Number\n```");
+
+    let hover = client.at("textDocument/hover", 0, 3);
+    assert_eq!(hover["contents"]["value"], "```simplescript\nadd: Type::Func(2 params)\n```");
+
+    let hover = client.at("textDocument/hover", 7, 8);
+    assert_eq!(hover["contents"]["value"], "```simplescript\nx: Type::String\n```");
+
+    // Nothing to show for keywords
+    assert_eq!(client.at("textDocument/hover", 4, 1), Value::Null);
+    assert!(client.exit());
+}
+
+#[test]
+fn definitions_and_references_follow_scopes() {
+    let mut client = Client::start();
+    client.open(PROGRAM);
+
+    let definition = client.at("textDocument/definition", 4, 6);
+    assert_eq!(definition["range"]["start"], json!({ "line": 3, "character": 6 }));
+
+    // The shadowing const in the block is a different identifier
+    let definition = client.at("textDocument/definition", 7, 8);
+    assert_eq!(definition["range"]["start"], json!({ "line": 6, "character": 8 }));
+
+    let references = client.at("textDocument/references", 3, 6);
+    assert_eq!(starts(&references), vec![(3, 6), (4, 6)]);
+
+    let references = client.at("textDocument/references", 1, 9);
+    assert_eq!(starts(&references), vec![(0, 7), (1, 9)]);
+    assert!(client.exit());
+}
+
+/// Get the labels of the completion items
+fn labels(items: &Value) -> Vec<&str> {
+    items.as_array().unwrap().iter().map(|item| item["label"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn completes_identifiers_in_scope_natives_and_keywords() {
+    let mut client = Client::start();
+    client.open(PROGRAM);
+
+    // Inside the function, its parameters and the function itself are in scope, but not the const defined after it
+    let items = client.at("textDocument/completion", 1, 9);
+    let names = labels(&items);
+    assert!(names.contains(&"a") && names.contains(&"b") && names.contains(&"add"));
+    assert!(!names.contains(&"x"));
+    assert!(names.contains(&"clock") && names.contains(&"const") && names.contains(&"print"));
+
+    let clock = items.as_array().unwrap().iter().find(|item| item["label"] == "clock").unwrap();
+    assert_eq!(clock["detail"], "fn clock() -> Type::Number");
+
+    // Only the shadowing x is offered in the block
+    let items = client.at("textDocument/completion", 7, 8);
+    let x: Vec<&Value> = items.as_array().unwrap().iter().filter(|item| item["label"] == "x").collect();
+    assert_eq!(x.len(), 1);
+    assert_eq!(x[0]["detail"], "Type::String");
+
+    // Identifiers are still completed while the document does not parse, as it usually does not while typing
+    client.change(&format!("{}print ad", PROGRAM));
+    let items = client.at("textDocument/completion", 9, 8);
+    assert!(labels(&items).contains(&"add"));
+
+    // SS has no records, so there are no fields to complete
+    client.change(&format!("{}print x.", PROGRAM));
+    assert_eq!(client.at("textDocument/completion", 9, 8), json!([]));
+    assert!(client.exit());
+}
+
+const SCOPING: &str = include_str!("../../SS code for testing/type checker/type table scoping.ss");
+
+#[test]
+fn rename_leaves_shadowed_identifiers_alone() {
+    let mut client = Client::start();
+    client.open(SCOPING);
+
+    // Rename the outer test on the last line, which must not rename the inner test in the block
+    let response = client.request(
+        "textDocument/rename",
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 16, "character": 7 },
+            "newName": "outer",
+        }),
+    );
+    let edits = &response["result"]["changes"][URI];
+    assert_eq!(starts(edits), vec![(3, 6), (16, 6)]);
+    assert!(edits.as_array().unwrap().iter().all(|edit| edit["newText"] == "outer"));
+
+    let prepared = client.at("textDocument/prepareRename", 8, 9);
+    assert_eq!(prepared["start"], json!({ "line": 8, "character": 8 }));
+
+    // Keywords and invalid identifiers are rejected
+    for new_name in ["const", "7up"] {
+        let response = client.request(
+            "textDocument/rename",
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": 3, "character": 6 },
+                "newName": new_name,
+            }),
+        );
+        assert!(response["error"]["message"].is_string(), "'{}' must be rejected", new_name);
+    }
+
+    // Renaming parameter a to b would make the parameters conflict
+    client.change(PROGRAM);
+    let response = client.request(
+        "textDocument/rename",
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 0, "character": 7 },
+            "newName": "b",
+        }),
+    );
+    assert!(response["error"]["message"].is_string());
+    assert!(client.exit());
+}
@@ -0,0 +1,77 @@
+// Test harness for the WebAssembly back end.
+//
+// Every SS program in the backends corpus is run with ri's interpreter, and compiled with '--backend wasm' into a module
+// that is run in wasmi, a pure Rust WebAssembly interpreter, with the host interface implemented in wasm_host.
+// The program's stdout and stderr must be the same for both.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod common;
+mod wasm_host;
+use common::{program_lines, ri};
+use wasm_host::{Exit, Output};
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../SS code for testing/backends");
+
+/// Run the program with the interpreter, removing the lines that ri prints around the program's own output
+fn interpret(source: &Path) -> Output {
+    let output = ri().arg(source).output().expect("Failed to run ri");
+    let stdout = program_lines(&String::from_utf8_lossy(&output.stdout))
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect();
+    (stdout, String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+/// Compile the program into a module in the output directory
+fn compile(source: &Path, output_directory: &Path) -> PathBuf {
+    let output = output_directory.join(source.with_extension("wasm").file_name().unwrap());
+    let result = ri()
+        .args(["--backend", "wasm", "--output"])
+        .arg(&output)
+        .arg(source)
+        .output()
+        .expect("Failed to run ri");
+    assert!(
+        output.exists(),
+        "Failed to compile '{}':\n{}",
+        source.display(),
+        String::from_utf8_lossy(&result.stderr)
+    );
+    output
+}
+
+#[test]
+fn wasm_output_matches_interpreter() {
+    let output_directory = std::env::temp_dir().join(format!("ss_wasm_harness_{}", std::process::id()));
+    fs::create_dir_all(&output_directory).unwrap();
+
+    let mut sources: Vec<PathBuf> = fs::read_dir(CORPUS)
+        .expect("Failed to read the backends corpus")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ss"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty(), "Backends corpus is empty");
+
+    let mut failures = Vec::new();
+    for source in sources.iter() {
+        let expected = interpret(source);
+        let (actual, exit) = wasm_host::run(&compile(source, &output_directory));
+        if exit == Exit::OutOfFuel {
+            failures.push(format!("{}\n-- wasm ran out of fuel --", source.display()));
+        } else if actual != expected {
+            failures.push(format!(
+                "{}\n-- ri stdout --\n{}-- wasm stdout --\n{}-- ri stderr --\n{}-- wasm stderr --\n{}",
+                source.display(),
+                expected.0,
+                actual.0,
+                expected.1,
+                actual.1
+            ));
+        }
+    }
+
+    fs::remove_dir_all(&output_directory).ok();
+    assert!(failures.is_empty(), "Output differs from ri for:\n{}", failures.join("\n"));
+}
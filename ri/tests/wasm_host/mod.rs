@@ -0,0 +1,78 @@
+// Host interface of the modules emitted by the WebAssembly back end, shared by the test harnesses that run them in
+// wasmi, a pure Rust WebAssembly interpreter.
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use wasmi::core::TrapCode;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Module, Store};
+
+/// Output of a program, as (stdout, stderr)
+pub type Output = (String, String);
+
+/// Fuel given to a module, which is roughly the number of instructions it may execute before it is stopped,
+/// so that a module that never returns fails the test instead of hanging it
+const FUEL: u64 = 1_000_000_000;
+
+/// How a module's main function ended
+#[derive(PartialEq, Debug)]
+pub enum Exit {
+    Returned,
+    /// Runtime errors end the program with a trap
+    Trapped,
+    /// The module used up its fuel
+    OutOfFuel,
+}
+
+/// Write the bytes in the module's memory to the stream, where 1 is stdout and 2 is stderr
+fn write(caller: &mut Caller<'_, Output>, stream: i32, text: String) {
+    match stream {
+        1 => caller.data_mut().0.push_str(&text),
+        _ => caller.data_mut().1.push_str(&text),
+    }
+}
+
+/// Run the module in wasmi, returning its output and how it ended
+pub fn run(module_path: &Path) -> (Output, Exit) {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, &fs::read(module_path).unwrap()[..]).expect("Emitted module is invalid");
+    let mut store = Store::new(&engine, (String::new(), String::new()));
+    store.set_fuel(FUEL).unwrap();
+
+    let mut linker = <Linker<Output>>::new(&engine);
+    linker
+        .func_wrap("ss", "write", |mut caller: Caller<'_, Output>, stream: i32, pointer: i32, length: i32| {
+            let memory = caller.get_export("memory").and_then(Extern::into_memory).unwrap();
+            let bytes = &memory.data(&caller)[pointer as usize..(pointer + length) as usize];
+            let text = String::from_utf8_lossy(bytes).to_string();
+            write(&mut caller, stream, text);
+        })
+        .unwrap();
+    linker
+        .func_wrap("ss", "write_number", |mut caller: Caller<'_, Output>, stream: i32, number: f64, debug: i32| {
+            let text = match debug {
+                1 => format!("{:?}", number),
+                _ => format!("{}", number),
+            };
+            write(&mut caller, stream, text);
+        })
+        .unwrap();
+    linker
+        .func_wrap("ss", "clock", || SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as f64)
+        .unwrap();
+
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .and_then(|instance| instance.start(&mut store))
+        .expect("Failed to instantiate module");
+    let main = instance.get_typed_func::<(), ()>(&store, "main").expect("Module does not export main");
+    let exit = match main.call(&mut store, ()) {
+        Ok(()) => Exit::Returned,
+        Err(error) if error.as_trap_code() == Some(TrapCode::OutOfFuel) => Exit::OutOfFuel,
+        Err(_) => Exit::Trapped,
+    };
+
+    (store.into_data(), exit)
+}
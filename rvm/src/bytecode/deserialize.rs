@@ -0,0 +1,126 @@
+use super::{BytecodeError, ConstantTag, BYTECODE_VERSION, MAGIC};
+
+use crate::chunk::{Chunk, Function};
+use crate::line_table::LineRun;
+use crate::value::Value;
+
+/// Cursor over the bytes of a bytecode file, with helper methods to read numbers and strings in the file's little endian encoding
+struct BytecodeReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BytecodeReader<'a> {
+    /// Take the next 'length' bytes, failing if the file does not have enough bytes left
+    fn take(&mut self, length: usize) -> Result<&'a [u8], BytecodeError> {
+        if self.bytes.len() - self.position < length {
+            return Err(BytecodeError::UnexpectedEof);
+        }
+
+        let slice = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, BytecodeError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, BytecodeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    #[inline]
+    fn read_usize(&mut self) -> Result<usize, BytecodeError> {
+        Ok(self.read_u32()? as usize)
+    }
+
+    fn read_f64(&mut self) -> Result<f64, BytecodeError> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn read_string(&mut self) -> Result<String, BytecodeError> {
+        let length = self.read_usize()?;
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|_| BytecodeError::InvalidString)
+    }
+
+    fn read_constant(&mut self) -> Result<Value, BytecodeError> {
+        let tag = self.read_u8()?;
+
+        Ok(match tag {
+            tag if tag == ConstantTag::Number as u8 => Value::number(self.read_f64()?),
+            tag if tag == ConstantTag::String as u8 => Value::string(self.read_string()?),
+            tag if tag == ConstantTag::Bool as u8 => Value::bool(self.read_u8()? != 0),
+            tag if tag == ConstantTag::Null as u8 => Value::NULL,
+            tag if tag == ConstantTag::Fn as u8 => Value::function(self.read_usize()?),
+            invalid_tag => return Err(BytecodeError::InvalidConstantTag(invalid_tag)),
+        })
+    }
+}
+
+/// Deserialize a bytecode file back into a chunk. The chunk is NOT verified, and should be verified before it is executed.
+pub fn deserialize(bytes: &[u8]) -> Result<Chunk, BytecodeError> {
+    let mut reader = BytecodeReader { bytes, position: 0 };
+
+    /* Header */
+    if reader.take(MAGIC.len()).map_err(|_| BytecodeError::InvalidMagic)? != MAGIC {
+        return Err(BytecodeError::InvalidMagic);
+    }
+    let version = reader.read_u16()?;
+    if version != BYTECODE_VERSION {
+        return Err(BytecodeError::UnsupportedVersion(version));
+    }
+    // Flags are reserved for now
+    reader.read_u16()?;
+
+    let mut chunk = Chunk::new();
+
+    /* Constant pool */
+    // Counts are not used to preallocate, so that a corrupted count cannot cause a huge allocation
+    let number_of_constants = reader.read_usize()?;
+    for _ in 0..number_of_constants {
+        chunk.constants.push(reader.read_constant()?);
+    }
+
+    /* Globals */
+    let number_of_globals = reader.read_usize()?;
+    for _ in 0..number_of_globals {
+        chunk.globals.push(Value::string(reader.read_string()?));
+    }
+
+    /* Code */
+    let code_length = reader.read_usize()?;
+    chunk.codes = reader.take(code_length)?.to_vec();
+
+    /* Line table */
+    let number_of_runs = reader.read_usize()?;
+    for _ in 0..number_of_runs {
+        chunk.lines.runs.push(LineRun {
+            start: reader.read_u32()?,
+            length: reader.read_u32()?,
+            line: reader.read_u32()?,
+            column: reader.read_u32()?,
+        });
+    }
+
+    /* Function table */
+    let number_of_functions = reader.read_usize()?;
+    for _ in 0..number_of_functions {
+        chunk.functions.push(Function {
+            name: reader.read_string()?,
+            start: reader.read_usize()?,
+            arity: reader.read_usize()?,
+            stack_base: reader.read_usize()?,
+        });
+    }
+
+    if reader.position != bytes.len() {
+        return Err(BytecodeError::TrailingBytes(bytes.len() - reader.position));
+    }
+
+    Ok(chunk)
+}
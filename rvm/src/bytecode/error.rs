@@ -0,0 +1,106 @@
+/// Errors from reading a bytecode file, and from verifying that the bytecode is safe to execute
+#[derive(Debug)]
+pub enum BytecodeError {
+    /// File does not start with the bytecode file magic bytes
+    InvalidMagic,
+
+    /// File was written with a different version of the bytecode format
+    UnsupportedVersion(u16),
+
+    /// File ended before the section being read was complete
+    UnexpectedEof,
+
+    /// Extra bytes found after the end of the last section
+    TrailingBytes(usize),
+
+    /// Unknown tag byte for a value in the constant pool
+    InvalidConstantTag(u8),
+
+    /// String in the file is not valid UTF8
+    InvalidString,
+
+    /* Verifier errors, where the first usize is always the byte offset of the offending instruction */
+    /// Byte is not a valid opcode
+    InvalidOpcode(usize, u8),
+
+    /// Instruction's operand bytes goes past the end of the code
+    TruncatedInstruction(usize),
+
+    /// Operand indexes past the end of the constant pool
+    InvalidConstant(usize, usize),
+
+    /// Operand indexes past the end of the globals table
+    InvalidGlobal(usize, usize),
+
+    /// Jump or function start does not land on the start of an instruction
+    InvalidJumpTarget(usize, usize),
+
+    /// Function value in the constant pool does not have an entry in the function table
+    /// UnknownFunction(byte offset of the function body, constant index)
+    UnknownFunction(usize, usize),
+
+    /// Local opcode's stack index is below the stack base of the function body, i.e. a value of the caller
+    /// InvalidLocal(byte offset, stack index)
+    InvalidLocal(usize, usize),
+
+    /// Instruction pops more values than there are on the stack
+    StackUnderflow(usize),
+
+    /// Different execution paths reach the same instruction with different stack sizes
+    /// StackImbalance(byte offset, stack size of first path, stack size of other path)
+    StackImbalance(usize, usize, usize),
+
+    /// Return leaves a different number of values on the stack than the function started with
+    InvalidReturn(usize),
+
+    /// Execution can run past the end of the code from inside a function body, or RETURN used outside of a function
+    /// usize is the byte offset where execution ends up
+    InvalidControlFlow(usize),
+
+    /// Line table does not cover exactly every byte of the code
+    LineTableMismatch,
+}
+
+impl std::fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BytecodeError::InvalidMagic => write!(f, "Not a SimpleScript bytecode file"),
+            BytecodeError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported bytecode version {}, expected version {}", version, super::BYTECODE_VERSION)
+            }
+            BytecodeError::UnexpectedEof => write!(f, "Bytecode file ended unexpectedly"),
+            BytecodeError::TrailingBytes(count) => write!(f, "Bytecode file has {} unexpected trailing bytes", count),
+            BytecodeError::InvalidConstantTag(tag) => write!(f, "Invalid constant tag {}", tag),
+            BytecodeError::InvalidString => write!(f, "Bytecode file contains an invalid UTF8 string"),
+
+            BytecodeError::InvalidOpcode(offset, byte) => write!(f, "[byte {}] Invalid opcode {}", offset, byte),
+            BytecodeError::TruncatedInstruction(offset) => write!(f, "[byte {}] Instruction operand is truncated", offset),
+            BytecodeError::InvalidConstant(offset, index) => {
+                write!(f, "[byte {}] Invalid constant pool index {}", offset, index)
+            }
+            BytecodeError::InvalidGlobal(offset, global_slot) => {
+                write!(f, "[byte {}] Invalid global slot {}", offset, global_slot)
+            }
+            BytecodeError::InvalidJumpTarget(offset, target) => {
+                write!(f, "[byte {}] Jump target {} is not the start of an instruction", offset, target)
+            }
+            BytecodeError::UnknownFunction(start, index) => {
+                write!(f, "Function {} at constant pool index {} is not in the function table", start, index)
+            }
+            BytecodeError::InvalidLocal(offset, stack_index) => {
+                write!(f, "[byte {}] Stack index {} is below the function's stack base", offset, stack_index)
+            }
+            BytecodeError::StackUnderflow(offset) => write!(f, "[byte {}] Stack underflow", offset),
+            BytecodeError::StackImbalance(offset, expected, found) => write!(
+                f,
+                "[byte {}] Stack imbalance, reached with {} values on stack on one path and {} on another",
+                offset, expected, found
+            ),
+            BytecodeError::InvalidReturn(offset) => {
+                write!(f, "[byte {}] Return leaves an invalid number of values on the stack", offset)
+            }
+            BytecodeError::InvalidControlFlow(offset) => write!(f, "[byte {}] Invalid control flow", offset),
+            BytecodeError::LineTableMismatch => write!(f, "Line table does not match the code"),
+        }
+    }
+}
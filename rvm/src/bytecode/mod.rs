@@ -0,0 +1,57 @@
+mod deserialize;
+mod error;
+mod serialize;
+mod verifier;
+
+pub use deserialize::deserialize;
+pub use error::BytecodeError;
+pub use serialize::serialize;
+pub use verifier::{function_stack_sizes, stack_sizes, verify};
+
+/*
+    Binary format of a compiled SimpleScript bytecode file (.ssc)
+    All multi byte numbers are stored in little endian order.
+
+    Header:
+        magic           4 bytes     "SSBC"
+        version         u16         BYTECODE_VERSION, files of any other version are rejected
+        flags           u16         Reserved, always 0 for now
+
+    Constant pool:
+        count           u32
+        constants       count * (tag u8 + value), see ConstantTag for the encoding of every value type
+
+    Globals:
+        count           u32
+        identifiers     count * string, where the index of the identifier is the global's slot
+
+    Code:
+        length          u32
+        codes           length bytes
+
+    Line table:
+        count           u32
+        runs            count * (start u32 + length u32 + line u32 + column u32)
+
+    Function table:
+        count           u32
+        functions       count * (name string + start u32 + arity u32 + stack_base u32)
+
+    Strings are stored as a u32 byte length followed by their UTF8 bytes.
+*/
+
+/// Magic bytes at the start of every bytecode file, used to reject files that are not bytecode files
+pub const MAGIC: [u8; 4] = *b"SSBC";
+
+/// Version of the bytecode format, to be incremented whenever the format or the instruction set changes
+pub const BYTECODE_VERSION: u16 = 5;
+
+/// Tags used to mark the type of every value in the serialized constant pool
+#[repr(u8)]
+pub enum ConstantTag {
+    Number,
+    String,
+    Bool,
+    Null,
+    Fn,
+}
@@ -0,0 +1,109 @@
+use super::{ConstantTag, BYTECODE_VERSION, MAGIC};
+
+use crate::chunk::Chunk;
+use crate::value::{UnpackedValue, Value};
+
+/// Byte buffer with helper methods to write numbers and strings in the bytecode file's little endian encoding
+struct BytecodeWriter {
+    bytes: Vec<u8>,
+}
+
+impl BytecodeWriter {
+    #[inline]
+    fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u32(&mut self, value: usize) {
+        // Chunk sizes are bounded by the operand widths, so these will always fit in a u32
+        self.bytes.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    #[inline]
+    fn write_f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_string(&mut self, string: &str) {
+        self.write_u32(string.len());
+        self.bytes.extend_from_slice(string.as_bytes());
+    }
+
+    fn write_constant(&mut self, value: &Value) {
+        match value.unpack() {
+            UnpackedValue::Number(number) => {
+                self.write_u8(ConstantTag::Number as u8);
+                self.write_f64(number);
+            }
+            UnpackedValue::String(string) => {
+                self.write_u8(ConstantTag::String as u8);
+                self.write_string(string);
+            }
+            UnpackedValue::Bool(bool) => {
+                self.write_u8(ConstantTag::Bool as u8);
+                self.write_u8(bool as u8);
+            }
+            UnpackedValue::Null => self.write_u8(ConstantTag::Null as u8),
+            UnpackedValue::Fn(start) => {
+                self.write_u8(ConstantTag::Fn as u8);
+                self.write_u32(start);
+            }
+            // Objects holding values are only created by running programs, so they are never in the constant pool
+            UnpackedValue::Array(_) | UnpackedValue::Closure(..) | UnpackedValue::Record(_) => {
+                panic!("Bytecode Error: {:?} can not be serialized as a constant", value)
+            }
+        }
+    }
+}
+
+/// Serialize a compiled chunk into the binary bytecode file format, see the bytecode module for the format
+pub fn serialize(chunk: &Chunk) -> Vec<u8> {
+    let mut writer = BytecodeWriter { bytes: Vec::new() };
+
+    /* Header */
+    writer.bytes.extend_from_slice(&MAGIC);
+    writer.write_u16(BYTECODE_VERSION);
+    writer.write_u16(0);
+
+    /* Constant pool */
+    writer.write_u32(chunk.constants.len());
+    for constant in &chunk.constants {
+        writer.write_constant(constant);
+    }
+
+    /* Globals */
+    writer.write_u32(chunk.globals.len());
+    for global_slot in 0..chunk.globals.len() {
+        writer.write_string(chunk.global_identifier(global_slot));
+    }
+
+    /* Code */
+    writer.write_u32(chunk.codes.len());
+    writer.bytes.extend_from_slice(&chunk.codes);
+
+    /* Line table */
+    writer.write_u32(chunk.lines.runs.len());
+    for run in &chunk.lines.runs {
+        writer.bytes.extend_from_slice(&run.start.to_le_bytes());
+        writer.bytes.extend_from_slice(&run.length.to_le_bytes());
+        writer.bytes.extend_from_slice(&run.line.to_le_bytes());
+        writer.bytes.extend_from_slice(&run.column.to_le_bytes());
+    }
+
+    /* Function table */
+    writer.write_u32(chunk.functions.len());
+    for function in &chunk.functions {
+        writer.write_string(&function.name);
+        writer.write_u32(function.start);
+        writer.write_u32(function.arity);
+        writer.write_u32(function.stack_base);
+    }
+
+    writer.bytes
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use super::BytecodeError;
+
+use crate::chunk::{Chunk, Function};
+use crate::opcode::{decode, OpCode};
+use crate::value::UnpackedValue;
+
+/// Bytecode verifier, to reject malformed chunks before execution, since the VM assumes that the code it runs is well formed.
+///
+/// Verification is done in 2 passes:
+/// 1. A linear pass to decode every instruction, checking that operands are within bounds and jumps land on instruction boundaries
+/// 2. A data flow pass over every reachable instruction, starting from the top level script and every function in the function table,
+///    to ensure that the stack never underflows and that every instruction is always reached with the same stack size.
+///    Function bodies must not read or pop values below their stack base, as the VM runs them at any stack offset.
+pub fn verify(chunk: &Chunk) -> Result<(), BytecodeError> {
+    let instruction_starts = verify_instructions(chunk)?;
+
+    verify_line_table(chunk)?;
+
+    // Every function value and function table entry must point to the start of an instruction
+    let is_valid_start = |start: usize| start < chunk.codes.len() && instruction_starts[start];
+    for (index, constant) in chunk.constants.iter().enumerate() {
+        match constant.unpack() {
+            UnpackedValue::Fn(start) if !is_valid_start(start) => {
+                return Err(BytecodeError::InvalidConstant(start, index))
+            }
+            // Function values can only be called if their body is in the function table, which is the only way its body is verified
+            UnpackedValue::Fn(start) if !chunk.functions.iter().any(|function| function.start == start) => {
+                return Err(BytecodeError::UnknownFunction(start, index))
+            }
+            _ => {}
+        }
+    }
+    for function in &chunk.functions {
+        if !is_valid_start(function.start) {
+            return Err(BytecodeError::InvalidJumpTarget(function.start, function.start));
+        }
+    }
+
+    stack_sizes(chunk)?;
+
+    Ok(())
+}
+
+/// Get the stack size on entering every reachable instruction, keyed by the byte offset of the instruction,
+/// which also ensures that the stack never underflows and that every instruction is always reached with the same stack size.
+///
+/// The chunk's instructions must already be valid, i.e. generated by the compiler or checked by 'verify'.
+pub fn stack_sizes(chunk: &Chunk) -> Result<HashMap<usize, usize>, BytecodeError> {
+    // Top level script starts with an empty stack, and function bodies start with their locals and arguments on the stack
+    let mut stack_sizes = function_stack_sizes(chunk, None)?;
+    for function in &chunk.functions {
+        stack_sizes.extend(function_stack_sizes(chunk, Some(function))?);
+    }
+
+    Ok(stack_sizes)
+}
+
+/// Same as 'stack_sizes', but only for the instructions reachable from the function's body,
+/// or from the top level script if 'function' is None.
+pub fn function_stack_sizes(chunk: &Chunk, function: Option<&Function>) -> Result<HashMap<usize, usize>, BytecodeError> {
+    verify_stack(chunk, function.map_or(0, |function| function.start), function)
+}
+
+/// Decode every instruction linearly, and return a vector marking which byte offsets are the start of an instruction.
+/// The vector has 1 more element than the code, as jumping to the end of the code is valid.
+fn verify_instructions(chunk: &Chunk) -> Result<Vec<bool>, BytecodeError> {
+    let codes = &chunk.codes;
+    let mut instruction_starts = vec![false; codes.len() + 1];
+    instruction_starts[codes.len()] = true;
+
+    // Jump targets are only checked after all instruction starts are known, since jumps can go forward
+    let mut jump_targets = Vec::<(usize, usize)>::new();
+
+    let mut offset = 0;
+    while offset < codes.len() {
+        instruction_starts[offset] = true;
+
+        let opcode = match decode(codes[offset]) {
+            Some(opcode) => opcode,
+            None => return Err(BytecodeError::InvalidOpcode(offset, codes[offset])),
+        };
+
+        let next_instruction = offset + 1 + opcode.operand_length();
+        if next_instruction > codes.len() {
+            return Err(BytecodeError::TruncatedInstruction(offset));
+        }
+
+        match opcode {
+            OpCode::CONSTANT => verify_constant(chunk, offset, codes[offset + 1] as usize)?,
+            OpCode::CONSTANT_LONG => verify_constant(chunk, offset, chunk.read_u16(offset + 1) as usize)?,
+            OpCode::DEFINE_GLOBAL | OpCode::GET_GLOBAL => {
+                let global_slot = chunk.read_u16(offset + 1) as usize;
+                if global_slot >= chunk.globals.len() {
+                    return Err(BytecodeError::InvalidGlobal(offset, global_slot));
+                }
+            }
+
+            OpCode::JUMP | OpCode::JUMP_IF_FALSE => {
+                jump_targets.push((offset, next_instruction + chunk.read_u16(offset + 1) as usize))
+            }
+            OpCode::LOOP => match next_instruction.checked_sub(chunk.read_u16(offset + 1) as usize) {
+                Some(target) => jump_targets.push((offset, target)),
+                None => return Err(BytecodeError::InvalidJumpTarget(offset, 0)),
+            },
+
+            _ => {}
+        }
+
+        offset = next_instruction;
+    }
+
+    for (offset, target) in jump_targets {
+        if target > codes.len() || !instruction_starts[target] {
+            return Err(BytecodeError::InvalidJumpTarget(offset, target));
+        }
+    }
+
+    Ok(instruction_starts)
+}
+
+/// Ensure constant index is within the constant pool
+fn verify_constant(chunk: &Chunk, offset: usize, constant_index: usize) -> Result<(), BytecodeError> {
+    if constant_index >= chunk.constants.len() {
+        return Err(BytecodeError::InvalidConstant(offset, constant_index));
+    }
+
+    Ok(())
+}
+
+/// Ensure that the line table runs are contiguous and cover exactly every byte of the code
+fn verify_line_table(chunk: &Chunk) -> Result<(), BytecodeError> {
+    let mut expected_start: usize = 0;
+    for run in &chunk.lines.runs {
+        if run.start as usize != expected_start || run.length == 0 {
+            return Err(BytecodeError::LineTableMismatch);
+        }
+        expected_start += run.length as usize;
+    }
+
+    if expected_start != chunk.codes.len() {
+        return Err(BytecodeError::LineTableMismatch);
+    }
+
+    Ok(())
+}
+
+/// Simulate the stack size of every instruction reachable from 'entry'.
+///
+/// 'function' is None for the top level script, which starts with an empty stack.
+/// Function bodies start with the locals before its stack base and its arguments on the stack.
+fn verify_stack(
+    chunk: &Chunk,
+    entry: usize,
+    function: Option<&Function>,
+) -> Result<HashMap<usize, usize>, BytecodeError> {
+    // Stack size on entering every instruction that has been reached so far
+    let mut stack_sizes = HashMap::<usize, usize>::new();
+
+    // Values below the stack base belong to the caller, which the function body cannot read or pop off
+    let stack_base = function.map_or(0, |function| function.stack_base);
+
+    // Work list of (byte offset, stack size) of instructions still to be checked
+    let initial_stack_size = function.map_or(0, |function| function.stack_base + function.arity);
+    let mut work_list = vec![(entry, initial_stack_size)];
+
+    while let Some((offset, stack_size)) = work_list.pop() {
+        // Reaching the end of the code is only valid for the top level script, and only if all values on the stack were popped
+        if offset == chunk.codes.len() {
+            match function {
+                None if stack_size == 0 => continue,
+                None => return Err(BytecodeError::StackImbalance(offset, 0, stack_size)),
+                Some(_) => return Err(BytecodeError::InvalidControlFlow(offset)),
+            }
+        }
+
+        // Skip instructions that have already been checked, as long as they are reached with the same stack size
+        match stack_sizes.get(&offset) {
+            Some(&expected) if expected == stack_size => continue,
+            Some(&expected) => return Err(BytecodeError::StackImbalance(offset, expected, stack_size)),
+            None => {
+                stack_sizes.insert(offset, stack_size);
+            }
+        }
+
+        // Instructions are already decoded once by verify_instructions, so decoding and operand reads cannot fail here
+        let opcode = decode(chunk.codes[offset]).unwrap();
+        let next_instruction = offset + 1 + opcode.operand_length();
+        let byte_operand = || chunk.codes[offset + 1] as usize;
+        let jump_operand = || chunk.read_u16(offset + 1) as usize;
+
+        // Number of values popped and pushed by the instruction
+        let (pops, pushes) = match opcode {
+            OpCode::POP => (1, 0),
+            OpCode::POP_N => (byte_operand(), 0),
+
+            OpCode::RETURN | OpCode::RETURN_POP => {
+                let number_of_locals = if opcode == OpCode::RETURN { 0 } else { byte_operand() };
+
+                // A function must return with only its return value left above its stack base,
+                // after popping off all of its arguments and locals
+                match function {
+                    Some(function) if stack_size == function.stack_base + number_of_locals + 1 => {}
+                    Some(_) => return Err(BytecodeError::InvalidReturn(offset)),
+                    None => return Err(BytecodeError::InvalidControlFlow(offset)),
+                }
+
+                // Return does not continue on to the next instruction
+                continue;
+            }
+
+            OpCode::CONSTANT | OpCode::CONSTANT_LONG | OpCode::GET_GLOBAL => (0, 1),
+            OpCode::DEFINE_GLOBAL => (1, 0),
+
+            OpCode::GET_LOCAL | OpCode::SET_LOCAL => {
+                // Local must be a value that is already on the stack
+                if byte_operand() >= stack_size {
+                    return Err(BytecodeError::StackUnderflow(offset));
+                }
+                if byte_operand() < stack_base {
+                    return Err(BytecodeError::InvalidLocal(offset, byte_operand()));
+                }
+
+                if opcode == OpCode::GET_LOCAL { (0, 1) } else { (1, 0) }
+            }
+            OpCode::GET_LOCAL_ADD | OpCode::GET_LOCAL_SUBTRACT => {
+                // Local must be a value that is already on the stack, which is added to the last value on the stack
+                if byte_operand() >= stack_size {
+                    return Err(BytecodeError::StackUnderflow(offset));
+                }
+                if byte_operand() < stack_base {
+                    return Err(BytecodeError::InvalidLocal(offset, byte_operand()));
+                }
+
+                (1, 1)
+            }
+
+            OpCode::JUMP => {
+                work_list.push((next_instruction + jump_operand(), stack_size));
+                continue;
+            }
+            OpCode::JUMP_IF_FALSE => {
+                // Condition value is peeked and not popped, so both branches continue with the same stack size
+                if stack_size == 0 {
+                    return Err(BytecodeError::StackUnderflow(offset));
+                }
+                work_list.push((next_instruction + jump_operand(), stack_size));
+                (0, 0)
+            }
+            OpCode::LOOP => {
+                work_list.push((next_instruction - jump_operand(), stack_size));
+                continue;
+            }
+
+            // Function value and arguments are taken off the stack, and the arguments are only popped by the callee's return.
+            // So after the call, only the return value is left in place of the function value and its arguments.
+            OpCode::CALL => (byte_operand() + 1, 1),
+
+            OpCode::ADD
+            | OpCode::SUBTRACT
+            | OpCode::MULTIPLY
+            | OpCode::DIVIDE
+            | OpCode::EQUAL
+            | OpCode::NOT_EQUAL
+            | OpCode::GREATER
+            | OpCode::GREATER_EQUAL
+            | OpCode::LESS
+            | OpCode::LESS_EQUAL => (2, 1),
+
+            OpCode::NOT | OpCode::NEGATE => (1, 1),
+
+            OpCode::PRINT => (1, 0),
+        };
+
+        if pops > stack_size - stack_base {
+            return Err(BytecodeError::StackUnderflow(offset));
+        }
+
+        work_list.push((next_instruction, stack_size - pops + pushes));
+    }
+
+    Ok(stack_sizes)
+}
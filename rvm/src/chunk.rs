@@ -1,26 +1,104 @@
+use crate::line_table::{LineTable, SourceLocation};
 use crate::opcode::OpCode;
+use crate::value::{UnpackedValue, Value};
 
-#[derive(Debug)]
+/// Entry in the chunk's function table, describing a function body compiled into the chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    /// Byte offset of the first instruction of the function body
+    pub start: usize,
+    /// Number of parameters of the function
+    pub arity: usize,
+    /// Number of locals already on the stack when the function was defined, a.k.a stack index of the first parameter
+    pub stack_base: usize,
+}
+
+#[derive(Debug, Clone)]
 pub struct Chunk {
-    pub codes: Vec<OpCode>,
+    /// Byte encoded instruction stream, where every opcode is followed by its operand bytes (if any)
+    pub codes: Vec<u8>,
+
+    /// Constant pool, holding all the values loaded by CONSTANT type opcodes
+    /// Opcodes reference these values by their index instead of holding the values directly
+    pub constants: Vec<Value>,
+
+    /// Identifiers of all the global values, where the index of the identifier is the global's slot number.
+    /// Globals are resolved to slots at compile time, so these are only used to allocate the VM's globals and for error messages.
+    /// Identifiers are interned string values, sharing the same string objects as string constants with the same content.
+    pub globals: Vec<Value>,
+
+    /// Run length encoded source locations of every byte in codes, used for runtime errors and the disassembler
+    pub lines: LineTable,
 
-    // Optimize storing line info: https://en.wikipedia.org/wiki/Run-length_encoding
-    pub lines: Vec<usize>,
+    /// Function table of all the functions defined in this chunk, in the order that they are defined
+    pub functions: Vec<Function>,
 }
 
 impl Chunk {
     // Associated function to create a new Chunk
     pub fn new() -> Chunk {
         Chunk {
-            // codes: Vec::<OpCode>::new(),
-            codes: Vec::<OpCode>::with_capacity(6),
-            lines: Vec::new(),
+            // codes: Vec::<u8>::new(),
+            codes: Vec::<u8>::with_capacity(8),
+            constants: Vec::new(),
+            globals: Vec::new(),
+            lines: LineTable::new(),
+            functions: Vec::new(),
         }
     }
 
-    // Method to write a new OpCode
-    pub fn write(&mut self, code: OpCode, line_number: usize) {
-        self.codes.push(code);
-        self.lines.push(line_number);
+    // Method to write a single byte, which can either be an opcode or an operand of an opcode
+    pub fn write(&mut self, byte: u8, location: SourceLocation) {
+        self.codes.push(byte);
+        self.lines.push(location);
+    }
+
+    // Method to write a new OpCode without any operands
+    #[inline]
+    pub fn write_code(&mut self, code: OpCode, location: SourceLocation) {
+        self.write(code as u8, location);
+    }
+
+    // Method to write a 2 byte wide operand in big endian order
+    pub fn write_u16(&mut self, operand: u16, location: SourceLocation) {
+        self.write((operand >> 8) as u8, location);
+        self.write(operand as u8, location);
+    }
+
+    /// Get the source line number of the instruction at byte offset 'ip'
+    #[inline]
+    pub fn line_for(&self, ip: usize) -> usize {
+        self.lines.line_for(ip)
+    }
+
+    /// Add value to the constant pool and return its index in the pool
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    /// Get the identifier of the global in the given slot
+    pub fn global_identifier(&self, global_slot: usize) -> &str {
+        // Global identifiers are always string values
+        self.globals[global_slot].as_str().unwrap_or_default()
+    }
+
+    /// Read a 2 byte wide big endian operand starting at the given offset of the code vector
+    #[inline]
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        ((self.codes[offset] as u16) << 8) | self.codes[offset + 1] as u16
+    }
+
+    /// Format the value in the canonical format that SS programs print values in, the same as ri,
+    /// where functions are printed with their name from the function table
+    pub fn format_value(&self, value: Value) -> String {
+        match value.unpack() {
+            UnpackedValue::Fn(start) => match self.functions.iter().find(|function| function.start == start) {
+                Some(function) if function.name != "<anonymous>" => format!("<function-ss: {}>", function.name),
+                _ => "<function-ss: [anonymous]>".to_string(),
+            },
+            _ => value.to_string(),
+        }
     }
 }
@@ -0,0 +1,104 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::optimizer::MAX_OPTIMIZATION_LEVEL;
+
+/// CLI data structure
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)] // Read from `Cargo.toml`
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+
+    #[command(flatten)]
+    pub debug_flags: DebugFlags,
+
+    #[command(flatten)]
+    pub jit_flags: JitFlags,
+
+    /// Optimization level of the compiled bytecode, from 0 for no optimizations up to 2. Not used by the REPL
+    #[arg(short = 'O', long, global = true, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=MAX_OPTIMIZATION_LEVEL as i64))]
+    pub optimization_level: u8,
+
+    /// Execution engine used to run and disassemble programs. Not used by the REPL
+    #[arg(long, global = true, value_enum, default_value_t = Engine::Stack)]
+    pub engine: Engine,
+}
+
+/// Execution engines that programs can be run with, which run the same compiled programs so that they can be benchmarked against each other
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum Engine {
+    /// Stack based VM, executing the compiled bytecode directly
+    Stack,
+
+    /// Register based VM, executing three address instructions translated from the compiled bytecode
+    Register,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run a SimpleScript source file or a precompiled bytecode file
+    Run {
+        /// The path of the SimpleScript source or bytecode file to execute
+        file_path: String,
+    },
+
+    /// Compile a SimpleScript source file into a bytecode file
+    Compile {
+        /// The path of the SimpleScript source file to compile
+        file_path: String,
+
+        /// The path to write the bytecode file to, defaults to the source file path with the .ssc extension
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Print the disassembled bytecode of a SimpleScript source or bytecode file without running it
+    Disasm {
+        /// The path of the SimpleScript source or bytecode file to disassemble
+        file_path: String,
+    },
+
+    /// Start an interactive Read Eval Print Loop
+    Repl,
+}
+
+/// Flags of the baseline JIT compiler, which compiles hot functions into native code.
+/// Only used by the stack based engine when running a file, and not by the REPL
+#[derive(Args, Clone, Copy, Default)]
+pub struct JitFlags {
+    /// Compile functions into native x86-64 code once they are called enough times
+    #[arg(long, global = true)]
+    pub jit: bool,
+
+    /// Number of calls before a function is compiled by the JIT
+    #[arg(long, global = true, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    pub jit_threshold: u32,
+
+    /// Print every function compiled by the JIT, and every exit from compiled code back to the interpreter
+    #[arg(long, global = true)]
+    pub trace_jit: bool,
+}
+
+/// Debugging flags to trace the VM at runtime, which can be used with any subcommand and on both debug and release builds
+#[derive(Args, Clone, Copy, Default)]
+pub struct DebugFlags {
+    /// Print every instruction before it is executed
+    #[arg(long, global = true)]
+    pub trace_exec: bool,
+
+    /// Print the stack after every instruction is executed
+    #[arg(long, global = true)]
+    pub print_stack: bool,
+
+    /// Print the disassembled chunk before it is executed
+    #[arg(long, global = true)]
+    pub dump_chunk: bool,
+
+    /// Run a garbage collection at every GC safepoint instead of only when the heap has grown enough, to find objects freed too early
+    #[arg(long, global = true)]
+    pub gc_stress: bool,
+
+    /// Print the heap statistics after the program is executed, or after every line in the REPL
+    #[arg(long, global = true)]
+    pub heap_stats: bool,
+}
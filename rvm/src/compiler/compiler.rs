@@ -1,12 +1,14 @@
 use std::collections::hash_map::HashMap;
 
 use super::parse_rule::{get_rule, Precedence, USIZE_TO_PRECEDENCE};
+use super::types::{Type, TypeTable};
 use super::CompileError;
 
-use crate::chunk::Chunk;
+use crate::chunk::{Chunk, Function};
 use crate::compiler::Parser;
 use crate::error::SSError;
-use crate::opcode::OpCode;
+use crate::line_table::SourceLocation;
+use crate::opcode::{decode, OpCode};
 use crate::scanner::Scanner;
 use crate::token::Token;
 use crate::token::TokenType;
@@ -17,6 +19,11 @@ use crate::value::Value;
 pub struct Local {
     pub name: String,
     pub depth: usize,
+    pub value_type: Type,
+
+    /// Constant pool index of the function value if the local is a function, so that nested function bodies can load it
+    /// as a constant instead, since locals of enclosing functions are not in their call frame
+    pub function_constant: Option<u16>,
 }
 
 // @todo Refactor this out into its own module
@@ -28,16 +35,40 @@ pub struct Compiler {
     /// Generated opcodes are emitted into this chunk
     pub chunk: Chunk,
 
+    /// Byte offset of the last opcode emitted into the chunk, used to look back at the last opcode since opcodes have variable lengths
+    pub previous_code: usize,
+
     /// Hold a parser so that it can be passed along to the methods easily instead of relying on global state like clox
     pub parser: Parser,
 
+    /// All errors found so far, since the compiler recovers from errors to keep compiling and find as many errors as possible
+    pub errors: Vec<CompileError>,
+
     /// Vector of Locals to get at from the stack
     pub locals: Vec<Local>,
 
-    /// HashMap<function_string_identifier, parameter_count>
-    ///
-    /// Used to ensure number of arguments match number of parameters defined
-    pub functions_parameter_count: HashMap<String, usize>,
+    /// HashMap<global_identifier, global_slot> of every global that has been defined or referenced so far
+    /// Globals are resolved to slots at compile time, so that the VM can access them by index instead of hashing identifiers
+    pub globals: HashMap<String, usize>,
+
+    /// HashMap<global_identifier, location> of globals referenced in function bodies before they are defined
+    /// Any global still left in here at the end of compilation is never defined, and is a compile error
+    pub undefined_globals: HashMap<String, SourceLocation>,
+
+    /// Type variables and the types they are bound to, used to infer the types of all values at compile time
+    pub types: TypeTable,
+
+    /// Types of the values that compiled expressions leave on the stack, mirroring what the VM's stack will hold at runtime
+    pub expression_types: Vec<Type>,
+
+    /// Type of every global, using the global slot as index
+    pub global_types: Vec<Type>,
+
+    /// Return types of the function bodies being compiled, where the last one is the innermost function body
+    pub function_return_types: Vec<Type>,
+
+    /// Whether every execution path of the code compiled so far in the current function body ends with a return statement
+    pub returns: bool,
 
     /// scope depth is the number of blocks surrounding the current bit of code being compiling.
     pub scope_depth: usize,
@@ -45,58 +76,163 @@ pub struct Compiler {
     /// function scopes is the number of function bodies surrounding the current bit of code being compiling.
     /// Used to track if compiler is currently compiling a function body
     pub function_scopes: usize,
+
+    /// Scope depth of the innermost function body being compiled.
+    /// Used by return statements to find all the locals of the function, including those defined in nested blocks
+    pub function_scope_depth: usize,
+
+    /// Whether the source being compiled is a line of REPL input
+    pub repl: bool,
+
+    /// Whether the value of the last top level expression statement is still on the stack, to be echoed by the REPL.
+    /// Only the value of the last statement in a line is echoed, so the value is popped if any other declaration follows it.
+    pub echo_value: bool,
 }
 
 impl Compiler {
-    /// Returns a 'Chunk' that can be run immediately
-    pub fn compile(source: String, chunk: Chunk) -> Result<Chunk, SSError> {
-        // Create compiler struct internally instead of having a seperate method to create and compile.
-        let mut compiler = Compiler {
+    /// Create a compiler that emits opcodes into the given chunk, before any source is given to it
+    pub fn new(chunk: Chunk) -> Compiler {
+        Compiler {
             // Move chunk into the compiler struct, so that the methods can access it
             chunk,
+            previous_code: 0,
 
             // Create default token structs using the derived default trait, since at the start current and previous tokens does not exists yet
             parser: Parser::new(
-                // Create scanner using the source string
-                Scanner::new(source),
+                // Placeholder scanner without any source, replaced with a new scanner for every source compiled
+                Scanner::new(String::new()),
                 // Since parser current and previous fields hold tokens instead of Option<Token>, generate 2 default Tokens
                 Token::default(),
                 Token::default(),
             ),
 
             locals: Vec::<Local>::new(),
-            functions_parameter_count: HashMap::<String, usize>::new(),
+            globals: HashMap::<String, usize>::new(),
+            errors: Vec::<CompileError>::new(),
+            undefined_globals: HashMap::<String, SourceLocation>::new(),
+            types: TypeTable::new(),
+            expression_types: Vec::<Type>::new(),
+            global_types: Vec::<Type>::new(),
+            function_return_types: Vec::<Type>::new(),
+            returns: false,
             scope_depth: 0,
             function_scopes: 0,
-        };
+            function_scope_depth: 0,
+            repl: false,
+            echo_value: false,
+        }
+    }
+
+    /// Returns a 'Chunk' that can be run immediately
+    pub fn compile(source: String, chunk: Chunk) -> Result<Chunk, SSError> {
+        // Create compiler struct internally instead of having a seperate method to create and compile.
+        let mut compiler = Compiler::new(chunk);
+        compiler.compile_source(source)?;
+
+        // Now that the chunk is filled with OpCodes after compilation, return it from Compiler struct to use with the VM
+        Ok(compiler.chunk)
+    }
+
+    /// Compile a line of REPL input, appending its opcodes to the chunk after the opcodes of all the previous lines.
+    /// Globals, functions and their types are kept between lines, so that later lines can use them.
+    ///
+    /// Returns whether the value of the last expression statement is left on the stack for the REPL to echo.
+    /// If the line fails to compile, it is discarded entirely so that the chunk only holds the opcodes of valid lines.
+    pub fn compile_line(&mut self, source: String) -> Result<bool, SSError> {
+        self.repl = true;
+
+        // Save everything that is kept between lines, to restore it if the line fails to compile.
+        // Locals and the other per declaration state are already restored by declaration.
+        let chunk = self.chunk.clone();
+        let globals = self.globals.clone();
+        let types = self.types.clone();
+        let global_types = self.global_types.clone();
+
+        if let Err(error) = self.compile_source(source) {
+            self.chunk = chunk;
+            self.globals = globals;
+            self.types = types;
+            self.global_types = global_types;
+            self.echo_value = false;
+            return Err(error);
+        }
+
+        // The echoed value is popped off the stack by the VM, so the next line starts with an empty stack
+        let echo_value = self.echo_value;
+        self.echo_value = false;
+        Ok(echo_value)
+    }
+
+    /// Compile the given source into the chunk, returning all the errors found if there are any
+    fn compile_source(&mut self, source: String) -> Result<(), SSError> {
+        self.parser = Parser::new(Scanner::new(source), Token::default(), Token::default());
 
         // Start by advancing the parser first, since Parser is created with default placeholder tokens
-        compiler.parser.advance()?;
+        if let Err(error) = self.parser.advance() {
+            self.errors.push(error.into());
+            self.synchronize();
+        }
 
-        // Keep parsing and compiling until EOF or error
-        while !compiler.parser.match_next(TokenType::Eof)? {
-            // CompileError will be automatically converted to SSError using From<CompileError> trait for SSError before bubbling up
-            compiler.declaration()?;
+        // Keep parsing and compiling until EOF, where errors are collected by declaration instead of stopping compilation
+        while !self.parser.check(TokenType::Eof) {
+            self.declaration();
         }
 
-        // @todo Fix the error message
-        compiler
-            .parser
-            .consume(TokenType::Eof, "Expect end of expression".to_string())?;
+        // Globals used in function bodies must be defined by the end of the program, report the first usage of every undefined global
+        let mut undefined_globals: Vec<(String, SourceLocation)> = self.undefined_globals.drain().collect();
+        undefined_globals.sort_by_key(|(_, location)| (location.line, location.column));
+        for (identifier, location) in undefined_globals {
+            self.errors
+                .push(CompileError::UndefinedIdentifier(location, identifier));
+        }
 
-        // Now that the chunk is filled with OpCodes after compilation, return it from Compiler struct to use with the VM
-        Ok(compiler.chunk)
+        if !self.errors.is_empty() {
+            return Err(SSError::CompileErrors(std::mem::take(&mut self.errors)));
+        }
+
+        Ok(())
     }
 
     /* ================== Declaration compiler methods ================== */
 
-    fn declaration(&mut self) -> Result<(), CompileError> {
-        match &self.parser.current.token_type {
+    /// Compile a single declaration, which is where the compiler recovers from any errors like clox's panic mode.
+    /// Errors are collected instead of bubbled up, before skipping to the next statement to continue compiling.
+    fn declaration(&mut self) {
+        // Save the compiler state, to restore it if the declaration fails halfway, e.g. in the middle of a block or function body
+        let number_of_locals = self.locals.len();
+        let number_of_expression_types = self.expression_types.len();
+        let number_of_function_return_types = self.function_return_types.len();
+        let scope_depth = self.scope_depth;
+        let function_scopes = self.function_scopes;
+        let function_scope_depth = self.function_scope_depth;
+        let returns = self.returns;
+
+        // Discard the value of the previous REPL expression statement, since it is no longer the last statement of the line
+        if self.echo_value {
+            self.emit_code(OpCode::POP);
+            self.echo_value = false;
+        }
+
+        let result = match &self.parser.current.token_type {
             TokenType::Const => self.advance_and_call(Compiler::const_declaration),
             TokenType::Function => self.advance_and_call(Compiler::function_declaration),
 
             // it is as a statement if it did not match any declaration tokens
             _ => self.statement(),
+        };
+
+        if let Err(error) = result {
+            self.errors.push(error);
+
+            self.locals.truncate(number_of_locals);
+            self.expression_types.truncate(number_of_expression_types);
+            self.function_return_types.truncate(number_of_function_return_types);
+            self.scope_depth = scope_depth;
+            self.function_scopes = function_scopes;
+            self.function_scope_depth = function_scope_depth;
+            self.returns = returns;
+
+            self.synchronize();
         }
     }
 
@@ -109,6 +245,7 @@ impl Compiler {
 
         // Get name/identifier string of function
         let function_name = self.parse_identifier_string();
+        let location = self.parser.previous.location();
 
         self.parser.consume(
             TokenType::LeftParen,
@@ -140,9 +277,13 @@ impl Compiler {
             _parameters
         };
 
-        // Store the function name and its parameter count into hashmap, to check if arg count is matching
-        self.functions_parameter_count
-            .insert(function_name.clone(), parameter_identifiers.len());
+        // Types of the parameters and return value are only inferred from how they are used in the function body and function calls
+        let parameter_types: Vec<Type> = parameter_identifiers
+            .iter()
+            .map(|_| self.types.new_variable())
+            .collect();
+        let return_type = self.types.new_variable();
+        let function_type = Type::Function(parameter_types.clone(), Box::new(return_type.clone()));
 
         self.parser.consume(
             TokenType::RightParen,
@@ -150,29 +291,26 @@ impl Compiler {
         )?;
 
         // Emit function as a constant value
-        // Here the opcode index of the chunk pointing to this function body will be calculated
-        // The opcode index can be calculated using the current opcode index, and a few skips for the comming instructions like JUMP
-        //
-        // A -1 is NOT NEEDED even though the base value uses the length of the vector rather than the index,
-        // because this particular constant loading code, has not been generated yet.
-        //
-        // +1 Skip this constant opcode that loads the function onto stack
-        // +1 OR +0 Only add a skip if function is defined in global scope, which means an additional opcode to set value to identifier
-        // +1 to skip the jump_over_fn_body, since now we actually want to execute the fn body code
-        //
-        // So the final value is 'current_opcode_index + 3' if function defined in global scope, else it is 'current_opcode_index + 2'
-        self.emit_constant(Value::Fn(
-            self.chunk.codes.len() + if self.scope_depth == 0 { 3 } else { 2 },
-        ));
+        // The byte offset of the function body is not known yet as the instructions before it have variable lengths,
+        // so a placeholder function value is stored into the constant pool first, to be patched once the JUMP below is emitted
+        let function_constant = self.chunk.constants.len();
+        self.emit_constant(Value::function(0))?;
 
+        // Function is declared before its body is compiled, so that it can be called recursively
         // Only works for local scope
-        self.declare_const(&function_name)?;
+        self.declare_const(&function_name, function_type.clone())?;
+        if self.scope_depth != 0 {
+            self.locals.last_mut().unwrap().function_constant = Some(function_constant as u16);
+        }
 
         // Only works for global scope
-        self.define_const(function_name);
+        self.define_const(function_name.clone(), function_type, location)?;
 
         // Add JUMP to jump over codes of the function body, as the function is being defined and not being executed/called yet
-        let jump_over_fn_body: usize = self.emit_jump(OpCode::JUMP(0));
+        let jump_over_fn_body: usize = self.emit_jump(OpCode::JUMP);
+
+        // Function body starts right after the jump, patch the function value in the constant pool to point to it
+        self.chunk.constants[function_constant] = Value::function(self.chunk.codes.len());
 
         // Need to consume the LeftBrace before calling block_statement method as it assumes that it is already consumed
         self.parser.consume(
@@ -183,11 +321,29 @@ impl Compiler {
         // Increment function scopes before compiling the function body
         self.function_scopes += 1;
 
+        // Return statements in the function body are checked against the function's return type
+        self.function_return_types.push(return_type);
+
+        // Save whether the enclosing function body always returns, as this function body tracks its own returns
+        let enclosing_returns = std::mem::replace(&mut self.returns, false);
+
         /* Function body is compiled like a block statement with locals definition for its parameters */
 
         // Create a new scope by incrementing compiler's scope depth
         self.scope_depth += 1;
 
+        // Save the enclosing function's body scope depth, so that it can be restored after this function body is compiled
+        let enclosing_function_scope_depth =
+            std::mem::replace(&mut self.function_scope_depth, self.scope_depth);
+
+        // Record the function in the chunk's function table, where its stack base is the number of locals before its parameters
+        self.chunk.functions.push(Function {
+            name: function_name,
+            start: self.chunk.codes.len(),
+            arity: parameter_identifiers.len(),
+            stack_base: self.locals.len(),
+        });
+
         // Use the parameter identifiers to call declare_const and register all of the parameters as locals of the function body scope
         // Where the first parameter will be the first local of the function body scope
         //
@@ -195,13 +351,13 @@ impl Compiler {
         //
         // When compiling a function call, its arguments will be compiled into codes that will push values onto the stack,
         // in the order that arguments are used, which will align with the defined stack index created here
-        for parameter_identifier in parameter_identifiers {
-            self.declare_const(&parameter_identifier)?;
+        for (parameter_identifier, parameter_type) in parameter_identifiers.iter().zip(parameter_types) {
+            self.declare_const(parameter_identifier, parameter_type)?;
         }
 
         // Keep parsing/compiling as long as it is not the closing right brace or an unexpected EOF yet
         while !self.parser.check(TokenType::RightBrace) && !self.parser.check(TokenType::Eof) {
-            self.declaration()?;
+            self.declaration();
         }
 
         self.parser
@@ -216,27 +372,29 @@ impl Compiler {
 
         // Decrement number of function scopes once function body is compiled
         self.function_scopes -= 1;
+        self.function_scope_depth = enclosing_function_scope_depth;
 
-        // Check if there is a return type opcode at the end of function body, and add a default return if there isn't
-        // NOTE:
-        // 1) This is just an optimization since adding an extra default return does not actually change the semantics of the code
-        // 2) This also does not account for the fact that a function can have all execution paths covered by ending them in return statements,
-        //    without having a return statement at the end of the function body. See example function definition below.
+        // Add a default return to mark the end of the function body, unless every execution path already ends with a return statement.
+        // Including function bodies where all execution paths are covered by return statements without one at the end of the body, e.g.
         //    function test(condition) { if (condition) { return 1; } else { return 2; } }
-        //    In this case, due to the single pass nature of this compiler, there is no way to know and no way to optimize away the default return
-        match self.chunk.codes.last() {
-            // Do nothing if the last opcode is a type of RETURN opcode
-            Some(OpCode::RETURN) | Some(OpCode::RETURN_POP(_)) => {}
-
-            // For any other opcode, Add a default return to mark the end of the function body
-            // Usually there will be a return, but in case the function does not have any, this will break out of the function
+        // This is not just an optimization, as the default return affects the function's return type.
+        if !self.returns {
             // Default return is a Null, since a function call is an expression and always expects a value to be left on stack
-            _ => {
-                self.emit_constant(Value::Null);
-                self.emit_code(OpCode::RETURN);
-            }
+            let return_type = self.function_return_types.last().unwrap().clone();
+            self.expect_type(
+                &return_type,
+                &Type::Null,
+                self.parser.previous.location(),
+                "Function body without a return statement at the end",
+            );
+
+            self.emit_constant(Value::NULL)?;
+            self.emit_code(OpCode::RETURN);
         }
 
+        self.function_return_types.pop();
+        self.returns = enclosing_returns;
+
         // Patch the jump over function body once it has been compiled
         self.patch_jump(jump_over_fn_body)?;
 
@@ -249,16 +407,23 @@ impl Compiler {
             .consume(TokenType::Identifier, "Expect const name".to_string())?;
 
         let const_name = self.parse_identifier_string();
+        let location = self.parser.previous.location();
+
+        // Type of the const is only known after its value is compiled
+        let value_type = self.types.new_variable();
 
         // Only works for local scope
-        self.declare_const(&const_name)?;
+        self.declare_const(&const_name, value_type.clone())?;
 
         // @todo Should not have this right, all const must be initialized
-        if self.parser.match_next(TokenType::Equal)? {
+        let expression_type = if self.parser.match_next(TokenType::Equal)? {
             self.expression()?;
+            self.pop_type()
         } else {
-            self.emit_constant(Value::Null);
-        }
+            self.emit_constant(Value::NULL)?;
+            Type::Null
+        };
+        self.expect_type(&value_type, &expression_type, location, "Const declaration");
 
         self.parser.consume(
             TokenType::Semicolon,
@@ -266,23 +431,44 @@ impl Compiler {
         )?;
 
         // Only works for global scope
-        self.define_const(const_name);
+        self.define_const(const_name, value_type, location)?;
 
         Ok(())
     }
 
-    /// Generate a identifier/value pair if code is in global scope
-    fn define_const(&mut self, const_name: String) {
+    /// Generate a identifier/value pair if code is in global scope, where location is the location of the identifier for error messages
+    fn define_const(
+        &mut self,
+        const_name: String,
+        value_type: Type,
+        location: SourceLocation,
+    ) -> Result<(), CompileError> {
         // @todo Skip if none global scope
         if self.scope_depth > 0 {
-            return;
+            return Ok(());
         }
 
-        self.emit_code(OpCode::IDENTIFIER(const_name));
+        // Globals are const too, so a global identifier can only be defined once.
+        // Unless it has only been referenced in a function body before its definition, which this definition now fulfills
+        if self.globals.contains_key(&const_name) && self.undefined_globals.remove(&const_name).is_none() {
+            self.error(CompileError::IdentifierAlreadyUsed(location, const_name));
+            return Ok(());
+        }
+
+        let usage = format!("Definition of '{}'", const_name);
+        let global_slot = self.resolve_global(const_name)?;
+
+        // Function bodies that use the global before it is defined might have already constrained its type
+        let global_type = self.global_types[global_slot as usize].clone();
+        self.expect_type(&global_type, &value_type, location, &usage);
+
+        self.emit_code_with_u16(OpCode::DEFINE_GLOBAL, global_slot);
+
+        Ok(())
     }
 
     /// Generate a identifier/value pair if code is in local scope
-    fn declare_const(&mut self, identifier: &String) -> Result<(), CompileError> {
+    fn declare_const(&mut self, identifier: &String, value_type: Type) -> Result<(), CompileError> {
         // @todo Skip if global scope
         if self.scope_depth != 0 {
             // Run identifier check to make sure it is unused in current scope, if there are local identifiers already
@@ -295,12 +481,15 @@ impl Compiler {
 
                 // Ensure that the identifier name is unique in current scope
                 if identifier == &local.name {
-                    // @todo Include line info
-                    return Err(CompileError::IdentifierAlreadyUsed(identifier.clone()));
+                    self.error(CompileError::IdentifierAlreadyUsed(
+                        self.parser.previous.location(),
+                        identifier.clone(),
+                    ));
+                    break;
                 }
             }
 
-            self.add_local(identifier.clone());
+            self.add_local(identifier.clone(), value_type)?;
         }
 
         // Return Ok variant with unit type
@@ -318,6 +507,7 @@ impl Compiler {
             TokenType::Return => self.advance_and_call(Compiler::return_statement),
             TokenType::If => self.advance_and_call(Compiler::if_statement),
             TokenType::While => self.advance_and_call(Compiler::while_statement),
+            TokenType::Ignore => self.advance_and_call(Compiler::ignore_statement),
 
             // it is as an expression statement if it did not match any statement tokens
             _ => self.expression_statement(),
@@ -327,6 +517,9 @@ impl Compiler {
     fn print_statement(&mut self) -> Result<(), CompileError> {
         self.expression()?;
 
+        // Values of any type can be printed
+        self.pop_type();
+
         self.parser.consume(
             TokenType::Semicolon,
             "Expect ';' after print statement".to_string(),
@@ -344,7 +537,7 @@ impl Compiler {
 
         // Keep parsing/compiling as long as it is not the closing right brace or an unexpected EOF yet
         while !self.parser.check(TokenType::RightBrace) && !self.parser.check(TokenType::Eof) {
-            self.declaration()?;
+            self.declaration();
         }
 
         self.parser
@@ -363,16 +556,25 @@ impl Compiler {
         // Error if return is found but compiler is not enclosed by any function scope, regardless of how many level up is that function scope
         // Cannot just check scope_depth == 0, because code might be in a scope but not necessarily in the scope of a function body
         if self.function_scopes == 0 {
-            return Err(CompileError::ReturnOutsideFunction(
-                self.parser.current.line,
+            self.error(CompileError::ReturnOutsideFunction(
+                self.parser.previous.location(),
             ));
         }
 
+        let location = self.parser.previous.location();
+
         // If semicolon read a.k.a no return expression, compile "return;" as shorthand for "return null;"
-        if self.parser.check(TokenType::Semicolon) {
-            self.emit_constant(Value::Null);
+        let value_type = if self.parser.check(TokenType::Semicolon) {
+            self.emit_constant(Value::NULL)?;
+            Type::Null
         } else {
             self.expression()?;
+            self.pop_type()
+        };
+
+        // Every return statement in a function body must return values of the same type
+        if let Some(return_type) = self.function_return_types.last().cloned() {
+            self.expect_type(&return_type, &value_type, location, "Return statement");
         }
 
         self.parser.consume(
@@ -380,22 +582,18 @@ impl Compiler {
             "Expect ';' after return statement".to_string(),
         )?;
 
-        // Temporarily reduce scope depth to find all locals to be popped off by the RETURN_POP statement
-        // Temporarily only, as there can still be function code after this statement, so scope depth needs to be restored for them to work
-        // Destroy the current block scope by decrementing compiler's scope depth
-        self.scope_depth -= 1;
-
         // The POP(s) generated are for the locals, including function arguments and new locals defined between the start of the function
-        // and this return statement in the body. Does not account for other locals in function body that are not created before this return.
-        let mut number_of_pops = 0;
-        while self.locals.len() > 0 && self.locals.last().unwrap().depth > self.scope_depth {
-            // Remove the local from compiler's locals vector too
-            self.locals.pop();
-            number_of_pops += 1;
-        }
-
-        // Restore the function's scope depth
-        self.scope_depth += 1;
+        // and this return statement in the body, even if the return statement is in a nested block of the function body.
+        // Does not account for other locals in function body that are not created before this return.
+        //
+        // Locals are only counted and not removed from the compiler's locals vector,
+        // as there can still be function code after this statement that uses them.
+        let number_of_pops = self
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth >= self.function_scope_depth)
+            .count();
 
         // If there are no POP(s) needed, e.g. no function argument and no locals created, optimize and use an empty return
         if number_of_pops == 0 {
@@ -403,13 +601,17 @@ impl Compiler {
             self.emit_code(OpCode::RETURN);
         } else if number_of_pops > 0 {
             // Use RETURN_POP if there are local(s) to pop off the stack before returning to caller
-            self.emit_code(OpCode::RETURN_POP(number_of_pops));
+            self.emit_code_with_byte(OpCode::RETURN_POP, number_of_pops as u8);
         }
 
+        self.returns = true;
+
         Ok(())
     }
 
     fn if_statement(&mut self) -> Result<(), CompileError> {
+        let location = self.parser.previous.location();
+
         self.parser.consume(
             TokenType::LeftParen,
             "Expect '(' after 'if' keyword".to_string(),
@@ -421,12 +623,20 @@ impl Compiler {
             "Expect ')' after 'if' condition".to_string(),
         )?;
 
-        let then_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE(0));
+        let condition_type = self.pop_type();
+        self.expect_type(&Type::Bool, &condition_type, location, "'if' condition");
+
+        // The if statement only always returns if both of its branches always return
+        let enclosing_returns = std::mem::replace(&mut self.returns, false);
+
+        let then_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE);
         // POP opcode to discard condition value from stack
         self.emit_code(OpCode::POP);
         self.statement()?;
 
-        let else_jump: usize = self.emit_jump(OpCode::JUMP(0));
+        let then_returns = std::mem::replace(&mut self.returns, false);
+
+        let else_jump: usize = self.emit_jump(OpCode::JUMP);
 
         self.patch_jump(then_jump)?;
 
@@ -438,11 +648,16 @@ impl Compiler {
         }
         self.patch_jump(else_jump)?;
 
+        // Without an else branch, self.returns stays false as the condition can be false
+        self.returns = enclosing_returns || (then_returns && self.returns);
+
         Ok(())
     }
 
     fn while_statement(&mut self) -> Result<(), CompileError> {
-        // Store the chunk’s current opcode count to record the opcodes offset right before compiling the condition expression
+        let location = self.parser.previous.location();
+
+        // Store the chunk’s current byte count to record the byte offset right before compiling the condition expression
         let loop_start: usize = self.chunk.codes.len();
 
         self.parser.consume(
@@ -456,17 +671,22 @@ impl Compiler {
             "Expect ')' after 'while' condition".to_string(),
         )?;
 
-        let exit_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE(0));
+        let condition_type = self.pop_type();
+        self.expect_type(&Type::Bool, &condition_type, location, "'while' condition");
+
+        // Loop body might never run, so it does not make the enclosing code always return even if the body always returns
+        let enclosing_returns = self.returns;
+
+        let exit_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE);
         // POP opcode to discard condition value from stack
         self.emit_code(OpCode::POP);
         self.statement()?;
 
-        // Calculate the opcode count difference between current length after compiling loop body and start of loop
-        let offset = self.chunk.codes.len() - loop_start;
+        self.returns = enclosing_returns;
 
         // Although this can be implemented with JUMP(-offset), alot more work needs to be done in the VM to support negative offsets
         // This is because most offset calculation and things like the VM's Instruction Pointer are all usize
-        self.emit_code(OpCode::LOOP(offset));
+        self.emit_loop(loop_start)?;
 
         self.patch_jump(exit_jump)?;
 
@@ -481,6 +701,8 @@ impl Compiler {
     /// Usually, it’s so that you can call a function or evaluate an assignment for its side effect.
     /// An expression statement evaluates the expression and discards the result from the stack.
     fn expression_statement(&mut self) -> Result<(), CompileError> {
+        let location = self.parser.current.location();
+
         self.expression()?;
 
         self.parser.consume(
@@ -488,6 +710,27 @@ impl Compiler {
             "Expect ';' after expression".to_string(),
         )?;
 
+        let expression_type = self.pop_type();
+
+        // Values of top level REPL expression statements are left on the stack for the REPL to echo instead of discarded
+        if self.repl
+            && self.scope_depth == 0
+            && self.function_scopes == 0
+            && self.types.resolve(&expression_type) != Type::Null
+        {
+            self.echo_value = true;
+            return Ok(());
+        }
+
+        // All values must be used, so expression statements can only discard Null values, like the return value of a function without returns.
+        // Other values must be explicitly discarded with an ignore statement.
+        self.expect_type(
+            &Type::Null,
+            &expression_type,
+            location,
+            "Expression statement (use 'ignore' to discard unused values)",
+        );
+
         // POP opcode to discard result from the stack
         // This assumes every single type of expression will always leave exactly one value on the stack once executed
         // And since this is a single expression statement, the value on stack is not needed, thus discarded
@@ -496,6 +739,25 @@ impl Compiler {
         Ok(())
     }
 
+    /// Ignore statements are used to explicitly ignore the evaluated value of an expression, e.g. return value of a function call.
+    /// Compiled just like an expression statement, where the value is discarded from the stack.
+    fn ignore_statement(&mut self) -> Result<(), CompileError> {
+        self.expression()?;
+
+        self.parser.consume(
+            TokenType::Semicolon,
+            "Expect ';' after ignore statement".to_string(),
+        )?;
+
+        // Values of any type can be ignored
+        self.pop_type();
+
+        // POP opcode to discard the ignored value from the stack
+        self.emit_code(OpCode::POP);
+
+        Ok(())
+    }
+
     /* ============= End of Statement compiler methods ============= */
 
     /*
@@ -517,11 +779,45 @@ impl Compiler {
         // Handling identifiers in local scopes differently from global scope identifiers
         // @todo Merge these
         match self.resolve_local(&identifier) {
-            Ok(stack_index) => self.emit_code(OpCode::GET_LOCAL(stack_index)),
+            // Stack index is guaranteed to fit in a byte, as the number of locals is capped by add_local
+            Ok(stack_index) => {
+                let local = &self.locals[stack_index];
+                let value_type = local.value_type.clone();
+
+                // Locals declared outside of the current function body are below its stack base
+                if self.function_scopes == 0 || local.depth >= self.function_scope_depth {
+                    self.emit_code_with_byte(OpCode::GET_LOCAL, stack_index as u8);
+                } else if let Some(function_constant) = local.function_constant {
+                    self.emit_constant_index(function_constant);
+                } else {
+                    self.error(CompileError::CapturedLocal(self.parser.previous.location(), identifier));
+                }
+
+                self.push_type(value_type);
+            }
 
-            // @todo Add compile time error to ensure that the identifier must exist
             Err(CompileError::IdentifierNotInAnyLocalScope(_)) => {
-                self.emit_code(OpCode::IDENTIFIER_LOOKUP(identifier))
+                let is_defined = self.globals.contains_key(&identifier)
+                    && !self.undefined_globals.contains_key(&identifier);
+
+                if !is_defined {
+                    // Code outside of function bodies runs in the order it is written, so the global must already be defined.
+                    // But function bodies can use globals defined after the function, as long as it is defined by the end of the program.
+                    if self.function_scopes == 0 {
+                        self.error(CompileError::UndefinedIdentifier(
+                            self.parser.previous.location(),
+                            identifier.clone(),
+                        ));
+                    } else {
+                        self.undefined_globals
+                            .entry(identifier.clone())
+                            .or_insert(self.parser.previous.location());
+                    }
+                }
+
+                let global_slot = self.resolve_global(identifier)?;
+                self.emit_code_with_u16(OpCode::GET_GLOBAL, global_slot);
+                self.push_type(self.global_types[global_slot as usize].clone());
             }
 
             Err(_) => panic!("Compiler Debug Error: 'Resolve Local' threw unknown error"),
@@ -530,26 +826,34 @@ impl Compiler {
         Ok(())
     }
 
-    // @todo Handle calls with arguments
     /// Method to compile function calls
     pub fn call(&mut self) -> Result<(), CompileError> {
-        // Immediately before 'call compiler method' is called, the opcode in front of it is assumed to hold info to get the function's identifier
+        let location = self.parser.previous.location();
+
+        // Name of the function for error messages, which is only known if the function is called directly through its identifier.
         // Need to clone the string out to not hold onto self immutably
-        let function_name = match self.chunk.codes.last() {
-            // The identifier is stored directly for global scope identifier lookups
-            Some(OpCode::IDENTIFIER_LOOKUP(identifier)) => identifier,
+        let function_name = match decode(self.chunk.codes[self.previous_code]) {
+            // The identifier of global scope identifier lookups is stored in the chunk using the global slot as index
+            Some(OpCode::GET_GLOBAL) => Some(
+                self.chunk
+                    .global_identifier(self.chunk.read_u16(self.previous_code + 1) as usize)
+                    .to_string(),
+            ),
 
             // For locals, get the function name by looking into the locals vector in compiler
-            Some(OpCode::GET_LOCAL(stack_index)) => &self.locals[*stack_index].name,
-
-            // There will be no function name, and this method of arity checking will not work if:
-            // 1) It is an anonymous function
-            // 2) The previous opcode is something else that is able to push a Function onto stack
-            // 3) Potentially not work, if the function is imported, since they are compiled seperately and have no access to the other hashmap
-            // @todo Add method to check arity in cases like above
-            _ => panic!("Compiler Debug Error: Unable to get function identifier from codes"),
-        }
-        .clone();
+            Some(OpCode::GET_LOCAL) => Some(self.locals[self.chunk.codes[self.previous_code + 1] as usize].name.clone()),
+
+            // Anonymous function values, e.g. functions returned by calls
+            _ => None,
+        };
+
+        // Number of parameters of the called function, taken from the type inferred for the called value, which is on the top of the type stack.
+        // Parameter count is not known for values whose type is not inferred yet, e.g. functions called through a parameter or a global defined later,
+        // in which case the number of arguments is still checked by the function type check below
+        let parameter_count = match self.expression_types.last().map(|value_type| self.types.resolve(value_type)) {
+            Some(Type::Function(parameter_types, _)) => Some(parameter_types.len()),
+            _ => None,
+        };
 
         // number_of_args here means, 'number of arguments used for this function call'
         let number_of_args: usize = if self.parser.check(TokenType::RightParen) {
@@ -571,25 +875,17 @@ impl Compiler {
             _number_of_args
         };
 
-        // @todo To handle anonymous functions...
-        // Get the function parameters count stored in the hashmap
-        let functions_parameter_count = self.functions_parameter_count.get(&function_name);
-
-        // Runtime check on debug builds to ensure function parameter count is actually stored
-        #[cfg(debug_assertions)]
-        if functions_parameter_count.is_none() {
-            panic!(
-                "Compiler Debug Error: '{}' function parameter count not stored",
-                function_name
-            );
-        }
-
-        // Ensure that the number of arguments match the number of parameters defined
-        if *functions_parameter_count.unwrap() != number_of_args {
-            return Err(CompileError::MismatchedArgumentCount(
-                *functions_parameter_count.unwrap(),
-                number_of_args,
-            ));
+        // Ensure that the number of arguments match the number of parameters of the function
+        let mut is_argument_count_matched = true;
+        if let Some(parameter_count) = parameter_count {
+            if parameter_count != number_of_args {
+                self.error(CompileError::MismatchedArgumentCount(
+                    location,
+                    parameter_count,
+                    number_of_args,
+                ));
+                is_argument_count_matched = false;
+            }
         }
 
         self.parser.consume(
@@ -597,9 +893,34 @@ impl Compiler {
             "Expected ')' after function arguments".to_string(),
         )?;
 
+        if number_of_args > u8::MAX as usize {
+            return Err(CompileError::TooManyArguments(location));
+        }
+
+        // Argument types are popped in reverse, since the last argument's type is at the top of the type stack
+        let mut argument_types: Vec<Type> = (0..number_of_args).map(|_| self.pop_type()).collect();
+        argument_types.reverse();
+        let function_type = self.pop_type();
+
+        // Called value must be a function that accepts the argument types, and the call evaluates to the function's return type
+        // Skipped if the argument count is already known to be wrong, as the function type will not match either
+        let return_type = self.types.new_variable();
+        if is_argument_count_matched {
+            self.expect_type(
+                &function_type,
+                &Type::Function(argument_types, Box::new(return_type.clone())),
+                location,
+                &match function_name {
+                    Some(function_name) => format!("Call to '{}'", function_name),
+                    None => "Call".to_string(),
+                },
+            );
+        }
+        self.push_type(return_type);
+
         // Once the argument count has been checked to match the number of parameters defined,
         // The use of number_of_args here means, 'number of arguments on stack'
-        self.emit_code(OpCode::CALL(number_of_args));
+        self.emit_code_with_byte(OpCode::CALL, number_of_args as u8);
 
         Ok(())
     }
@@ -610,7 +931,8 @@ impl Compiler {
             [self.parser.previous.start..self.parser.previous.start + self.parser.previous.length]
             .parse::<f64>()
             .unwrap();
-        self.emit_constant(Value::Number(value));
+        self.emit_constant(Value::number(value))?;
+        self.push_type(Type::Number);
 
         Ok(())
     }
@@ -625,7 +947,8 @@ impl Compiler {
             ]
             .parse::<String>()
             .unwrap();
-        self.emit_constant(Value::String(value));
+        self.emit_constant(Value::string(value))?;
+        self.push_type(Type::String);
 
         Ok(())
     }
@@ -644,24 +967,38 @@ impl Compiler {
         // Remember the operator because the next call to parse_precedence moves the parser forward
         // Need to clone here instead of taking a immutable ref because self.parse_precedence needs a mutable ref to self
         let operator_type: TokenType = self.parser.previous.token_type.clone();
+        let location = self.parser.previous.location();
 
         // Compile the operand
         self.parse_precedence(Precedence::Unary)?;
 
-        // Emit the operator instruction.
-        Ok(match operator_type {
-            TokenType::Bang => self.emit_code(OpCode::NOT),
-            TokenType::Minus => self.emit_code(OpCode::NEGATE),
+        // Emit the operator instruction, where the operand and the result have the same type
+        let operand_type = match operator_type {
+            TokenType::Bang => {
+                self.emit_code(OpCode::NOT);
+                Type::Bool
+            }
+            TokenType::Minus => {
+                self.emit_code(OpCode::NEGATE);
+                Type::Number
+            }
 
             // Unreachable
             _ => return Err(CompileError::InvalidOperatorType(operator_type)),
-        })
+        };
+
+        let found_type = self.pop_type();
+        self.expect_type(&operand_type, &found_type, location, "Unary operator");
+        self.push_type(operand_type);
+
+        Ok(())
     }
 
     pub fn binary(&mut self) -> Result<(), CompileError> {
         // Remember the operator because the next call to parse_precedence moves the parser forward
         // Need to clone here instead of taking a immutable ref because self.parse_precedence needs a mutable ref to self
         let operator_type: TokenType = self.parser.previous.token_type.clone();
+        let location = self.parser.previous.location();
 
         // Parse/Compile right operand first, so that opcode will execute before operator code,
         // which for binary arithmetic operators needs the values on the stack already.
@@ -675,32 +1012,52 @@ impl Compiler {
         //     get_rule(&operator_type).precedence as usize + 1,
         // ));
 
-        // Emit the operator's OpCode
-        Ok(match operator_type {
-            TokenType::Plus => self.emit_code(OpCode::ADD),
-            TokenType::Minus => self.emit_code(OpCode::SUBTRACT),
-            TokenType::Star => self.emit_code(OpCode::MULTIPLY),
-            TokenType::Slash => self.emit_code(OpCode::DIVIDE),
-
-            TokenType::BangEqual => self.emit_code(OpCode::NOT_EQUAL),
-            TokenType::EqualEqual => self.emit_code(OpCode::EQUAL),
-            TokenType::Greater => self.emit_code(OpCode::GREATER),
-            TokenType::GreaterEqual => self.emit_code(OpCode::GREATER_EQUAL),
-            TokenType::Less => self.emit_code(OpCode::LESS),
-            TokenType::LessEqual => self.emit_code(OpCode::LESS_EQUAL),
+        // Get the operator's OpCode, the type of operands expected by the operator and the type of the result,
+        // where an operand type of None means that the operator accepts operands of any type.
+        let (opcode, operand_type, result_type) = match operator_type {
+            TokenType::Plus => (OpCode::ADD, Some(Type::Number), Type::Number),
+            TokenType::Minus => (OpCode::SUBTRACT, Some(Type::Number), Type::Number),
+            TokenType::Star => (OpCode::MULTIPLY, Some(Type::Number), Type::Number),
+            TokenType::Slash => (OpCode::DIVIDE, Some(Type::Number), Type::Number),
+
+            TokenType::BangEqual => (OpCode::NOT_EQUAL, None, Type::Bool),
+            TokenType::EqualEqual => (OpCode::EQUAL, None, Type::Bool),
+            TokenType::Greater => (OpCode::GREATER, Some(Type::Number), Type::Bool),
+            TokenType::GreaterEqual => (OpCode::GREATER_EQUAL, Some(Type::Number), Type::Bool),
+            TokenType::Less => (OpCode::LESS, Some(Type::Number), Type::Bool),
+            TokenType::LessEqual => (OpCode::LESS_EQUAL, Some(Type::Number), Type::Bool),
 
             // Unreachable
             _ => return Err(CompileError::InvalidOperatorType(operator_type)),
-        })
+        };
+
+        // Emit the operator's OpCode
+        self.emit_code(opcode);
+
+        // Regardless of the operator, both operands must always have the same type
+        let right_type = self.pop_type();
+        let left_type = self.pop_type();
+        match operand_type {
+            Some(operand_type) => {
+                self.expect_type(&operand_type, &left_type, location, "Binary operator");
+                self.expect_type(&operand_type, &right_type, location, "Binary operator");
+            }
+            None => self.expect_type(&left_type, &right_type, location, "Binary operator"),
+        }
+        self.push_type(result_type);
+
+        Ok(())
     }
 
-    /// The 2 operands of 'and' will be typed check to be bool.
-    ///
-    /// The first operand on the left hand side, is checked by JUMP_IF_FALSE opcode, which requires bool conditionals.
-    ///
-    /// The second operand on the right hand side, is checked by TYPE_CHECK_BOOL opcode, which throws runtime error if last value on stack is not bool.
+    /// The 2 operands of 'and' are type checked at compile time to be bool, as the 'and' expression evaluates to either of them.
     pub fn and(&mut self) -> Result<(), CompileError> {
-        let end_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE(0));
+        let location = self.parser.previous.location();
+
+        // Left hand side operand is already compiled, and is used as the JUMP_IF_FALSE condition
+        let left_type = self.pop_type();
+        self.expect_type(&Type::Bool, &left_type, location, "Operator 'and'");
+
+        let end_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE);
 
         // POP opcode to discard condition value from stack, which will be the left hand side expression of the 'and' keyword
         self.emit_code(OpCode::POP);
@@ -711,22 +1068,24 @@ impl Compiler {
         // Patch jump to jump/skip the right hand side operand expression opcodes if the left hand side operand is Bool(false)
         self.patch_jump(end_jump)?;
 
-        // Add TYPE_CHECK_BOOL opcode to type check if the right hand side operand expression evaluates to a Bool(_)
-        self.emit_code(OpCode::TYPE_CHECK_BOOL);
+        let right_type = self.pop_type();
+        self.expect_type(&Type::Bool, &right_type, location, "Operator 'and'");
+        self.push_type(Type::Bool);
 
         Ok(())
     }
 
-    /// The 2 operands of 'or' will be typed check to be bool.
-    ///
-    /// The first operand on the left hand side, is checked by JUMP_IF_FALSE opcode, which requires bool conditionals.
-    ///
-    /// The second operand on the right hand side, is checked by TYPE_CHECK_BOOL opcode, which throws runtime error if last value on stack is not bool.
-    ///
-    /// @todo Because of how we compile this, if the first value is true, the second value will not be type checked
+    /// The 2 operands of 'or' are type checked at compile time to be bool, as the 'or' expression evaluates to either of them.
+    /// Unlike a runtime check, the right hand side operand is type checked even if it is skipped because the first value is true.
     pub fn or(&mut self) -> Result<(), CompileError> {
-        let else_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE(0));
-        let end_jump: usize = self.emit_jump(OpCode::JUMP(0));
+        let location = self.parser.previous.location();
+
+        // Left hand side operand is already compiled, and is used as the JUMP_IF_FALSE condition
+        let left_type = self.pop_type();
+        self.expect_type(&Type::Bool, &left_type, location, "Operator 'or'");
+
+        let else_jump: usize = self.emit_jump(OpCode::JUMP_IF_FALSE);
+        let end_jump: usize = self.emit_jump(OpCode::JUMP);
 
         // If left hand side expression is Bool(false), jump to the POP instruction emitted below to pop it from stack.
         self.patch_jump(else_jump)?;
@@ -741,25 +1100,37 @@ impl Compiler {
         // Patch jump to jump/skip the right hand side operand expression opcodes if the left hand side operand is Bool(true)
         self.patch_jump(end_jump)?;
 
-        // Add TYPE_CHECK_BOOL opcode to type check if the right hand side operand expression evaluates to a Bool(_)
-        self.emit_code(OpCode::TYPE_CHECK_BOOL);
+        let right_type = self.pop_type();
+        self.expect_type(&Type::Bool, &right_type, location, "Operator 'or'");
+        self.push_type(Type::Bool);
 
         Ok(())
     }
 
     pub fn literal(&mut self) -> Result<(), CompileError> {
-        Ok(match &self.parser.previous.token_type {
-            // Optimize by using special opcodes, like OpCode::True to load True onto stack directly instead of reading from CONSTANT(val)
-            TokenType::True => self.emit_constant(Value::Bool(true)),
-            TokenType::False => self.emit_constant(Value::Bool(false)),
-            TokenType::Null => self.emit_constant(Value::Null),
+        match &self.parser.previous.token_type {
+            // Optimize by using special opcodes, like OpCode::True to load True onto stack directly instead of reading from the constant pool
+            TokenType::True => {
+                self.emit_constant(Value::bool(true))?;
+                self.push_type(Type::Bool);
+            }
+            TokenType::False => {
+                self.emit_constant(Value::bool(false))?;
+                self.push_type(Type::Bool);
+            }
+            TokenType::Null => {
+                self.emit_constant(Value::NULL)?;
+                self.push_type(Type::Null);
+            }
 
             _ => {
                 return Err(CompileError::InvalidOperatorType(
                     self.parser.previous.token_type.clone(),
                 ))
             }
-        })
+        }
+
+        Ok(())
     }
 
     /* ============= End of Expression compiler methods ============= */
@@ -781,9 +1152,10 @@ impl Compiler {
 
             // If there is no prefix parser, then the token must be a syntax error
             None => {
-                return Err(CompileError::MissingParser(
-                    "Expect expression. No prefix parser".to_string(),
-                ))
+                return Err(self
+                    .parser
+                    .error_at_previous("Expect expression".to_string())
+                    .into())
             }
         };
 
@@ -1,5 +1,6 @@
 use super::parser::ParsingError;
 
+use crate::line_table::SourceLocation;
 use crate::opcode::OpCode;
 use crate::token::TokenType;
 
@@ -9,20 +10,44 @@ pub enum CompileError {
     // Wrapper type over ParsingError to allow it to be bubbled up into CompileError eventually
     ParsingError(ParsingError),
 
-    IdentifierAlreadyUsed(String),
+    /// IdentifierAlreadyUsed(location, identifier)
+    IdentifierAlreadyUsed(SourceLocation, String),
 
     IdentifierNotInAnyLocalScope(String),
 
+    /// Identifier is not defined in any local scope or the global scope
+    /// UndefinedIdentifier(location, identifier)
+    UndefinedIdentifier(SourceLocation, String),
+
     /// Missing compiler/parser method in compiler struct to parse given expression
     MissingParser(String),
 
-    /// 'return' can only be used in a function body, store line number with error for error message
-    // ReturnOutsideFunction(Token),
-    ReturnOutsideFunction(usize),
+    /// 'return' can only be used in a function body, store location of the return keyword with error for error message
+    ReturnOutsideFunction(SourceLocation),
 
     /// If the number of arguments does not matched the number of parameters defined
-    /// MismatchedArgumentCount(number_of_parameters, number_of_args),
-    MismatchedArgumentCount(usize, usize),
+    /// MismatchedArgumentCount(location, number_of_parameters, number_of_args),
+    MismatchedArgumentCount(SourceLocation, usize, usize),
+
+    /// Local of an enclosing function used in a nested function body, which needs closures that are not supported yet
+    /// CapturedLocal(location, identifier)
+    CapturedLocal(SourceLocation, String),
+
+    /// Value is used as a type that is different from the type it is inferred to be
+    /// TypeError(location, message)
+    TypeError(SourceLocation, String),
+
+    /// Limits imposed by the width of the operands in the byte encoded instructions, with the location of the code that hit the limit
+    /// Constant pool can only be indexed with up to 2 bytes
+    TooManyConstants(SourceLocation),
+    /// Local stack slots are indexed with a single byte
+    TooManyLocals(SourceLocation),
+    /// Global slots are indexed with up to 2 bytes
+    TooManyGlobals(SourceLocation),
+    /// Argument count of a function call is stored in a single byte
+    TooManyArguments(SourceLocation),
+    /// JUMP and LOOP offsets are stored in 2 bytes
+    JumpTooLarge(SourceLocation),
 
     // Internal Compiler Errors
     // @todo Change these to be panics instead in the code directly, as these should not happen if compiler is bug free
@@ -34,6 +59,62 @@ pub enum CompileError {
     InvalidOperatorType(TokenType),
 }
 
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CompileError::ParsingError(parsing_error) => write!(f, "{}", parsing_error),
+
+            CompileError::IdentifierAlreadyUsed(location, identifier) => write!(
+                f,
+                "[line {}] ReferenceError: Identifier '{}' already used in current scope",
+                location.line, identifier
+            ),
+            CompileError::UndefinedIdentifier(location, identifier) => write!(
+                f,
+                "[line {}] ReferenceError: Tried to use undefined identifier '{}'",
+                location.line, identifier
+            ),
+            CompileError::ReturnOutsideFunction(location) => write!(
+                f,
+                "[line {}] Error at 'return': Can only return from within a function body",
+                location.line
+            ),
+            CompileError::MismatchedArgumentCount(location, number_of_parameters, number_of_args) => write!(
+                f,
+                "[line {}] Function arity error, expected {} arguments but got {}",
+                location.line, number_of_parameters, number_of_args
+            ),
+            CompileError::CapturedLocal(location, identifier) => write!(
+                f,
+                "[line {}] Closures are not supported yet, '{}' is a local of an enclosing function",
+                location.line, identifier
+            ),
+            CompileError::TypeError(location, message) => {
+                write!(f, "[line {}] TypeError: {}", location.line, message)
+            }
+
+            CompileError::TooManyConstants(location) => {
+                write!(f, "[line {}] Too many constants in one chunk", location.line)
+            }
+            CompileError::TooManyLocals(location) => {
+                write!(f, "[line {}] Too many local values in scope", location.line)
+            }
+            CompileError::TooManyGlobals(location) => {
+                write!(f, "[line {}] Too many global values", location.line)
+            }
+            CompileError::TooManyArguments(location) => {
+                write!(f, "[line {}] Cannot have more than 255 arguments", location.line)
+            }
+            CompileError::JumpTooLarge(location) => {
+                write!(f, "[line {}] Too much code to jump over", location.line)
+            }
+
+            // Internal compiler errors that should not happen if the compiler is bug free, printed with debug symbol
+            internal_error => write!(f, "Internal compiler error: {:?}", internal_error),
+        }
+    }
+}
+
 // Convert ParsingError to CompileError automatically
 impl From<ParsingError> for CompileError {
     fn from(error: ParsingError) -> Self {
@@ -2,6 +2,7 @@ pub mod compiler;
 mod error;
 mod parse_rule;
 pub mod parser;
+mod types;
 mod utility;
 
 pub use compiler::Compiler;
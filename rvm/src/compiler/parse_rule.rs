@@ -89,7 +89,7 @@ pub struct ParseRule {
 // Converts ParseFnBase to ParseFn
 macro_rules! wrap_parse_fn {
     ($parse_fn:expr) => {
-        |compiler: &mut Compiler, _| $parse_fn(compiler);
+        |compiler: &mut Compiler, _| $parse_fn(compiler)
     };
 
     // Alternative wrapper using a inline function instead of a closure
@@ -273,6 +273,7 @@ static RULES_TABLE: [ParseRule; NUM_OF_TOKENTYPE_VARIANTS] = {
     rules_array[TokenType::Else as usize] = new_parse_rule!(Precedence::None);
 
     rules_array[TokenType::Print as usize] = new_parse_rule!(Precedence::None);
+    rules_array[TokenType::Ignore as usize] = new_parse_rule!(Precedence::None);
 
     rules_array[TokenType::True as usize] =
         new_parse_rule!(Compiler::literal, None, Precedence::None);
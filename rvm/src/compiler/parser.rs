@@ -1,3 +1,4 @@
+use crate::line_table::SourceLocation;
 use crate::scanner::Scanner;
 use crate::scanner::ScannerError;
 use crate::token::Token;
@@ -14,8 +15,20 @@ pub struct Parser {
 pub enum ParsingError {
     ScannerError(ScannerError),
 
-    /// @todo Change out the blanket error variant used temporarily for now
-    error,
+    /// Token found is not the token expected by the parser
+    /// UnexpectedToken(location, message), where the message starts with the lexeme of the unexpected token
+    UnexpectedToken(SourceLocation, String),
+}
+
+impl std::fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParsingError::ScannerError(scanner_error) => write!(f, "{}", scanner_error),
+            ParsingError::UnexpectedToken(location, message) => {
+                write!(f, "[line {}] Error {}", location.line, message)
+            }
+        }
+    }
 }
 
 // Convert ScannerError to ParsingError automatically
@@ -73,47 +86,47 @@ impl Parser {
                 // self.error_at_current(self.current.start);
                 // self.error_at_current("");
 
-                // @todo Fix this error
-                // Should this break out or?
-                return Err(ParsingError::error);
+                return Err(self.error_at_current("Invalid token".to_string()));
             }
         }
     }
 
-    // @todo Should bubble error up to be handled instead of internally here
-    fn error_at(&self, token: &Token, message: String) {
-        eprint!("[line {}] Error ", token.line);
-
-        if token.token_type == TokenType::Eof {
-            eprint!("at end");
+    /// Create a ParsingError for the given token, which is bubbled up for the compiler to collect before recovering from it
+    pub fn error_at(&self, token: &Token, message: String) -> ParsingError {
+        let lexeme = if token.token_type == TokenType::Eof {
+            "at end".to_string()
         } else if token.token_type == TokenType::Error {
             // Nothing.
+            "".to_string()
         } else {
             // Get slice from source and convert to String to print
-            eprint!(
+            format!(
                 "at '{}'",
                 self.scanner.source[token.start..token.start + token.length].to_string()
-            );
-        }
+            )
+        };
 
-        eprintln!(": {}", message);
-        // parser.hadError = true;
+        ParsingError::UnexpectedToken(token.location(), format!("{}: {}", lexeme, message))
     }
 
     // error_at for the current token.
     #[inline]
-    fn error_at_current(&self, message: String) {
+    pub fn error_at_current(&self, message: String) -> ParsingError {
         self.error_at(&self.current, message)
     }
 
+    // error_at for the previous token.
+    #[inline]
+    pub fn error_at_previous(&self, message: String) -> ParsingError {
+        self.error_at(&self.previous, message)
+    }
+
     /// Self needs to be parser
     pub fn consume(&mut self, token_type: TokenType, message: String) -> Result<(), ParsingError> {
         if self.current.token_type == token_type {
-            self.advance()?;
+            self.advance()
         } else {
-            self.error_at_current(message);
+            Err(self.error_at_current(message))
         }
-
-        Ok(())
     }
 }
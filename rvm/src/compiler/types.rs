@@ -0,0 +1,131 @@
+/// Static type of a value in SS, inferred at compile time since SS has no type annotations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Number,
+    String,
+    Bool,
+    Null,
+
+    /// Function(parameter_types, return_type)
+    Function(Vec<Type>, Box<Type>),
+
+    /// Type variable of a value whose type is not known yet, e.g. function parameters and return values.
+    /// The usize is the variable's index in the TypeTable, where it is bound to a type once it is used in a way that constrains its type.
+    Variable(usize),
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Type::Number => write!(f, "Number"),
+            Type::String => write!(f, "String"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Null => write!(f, "Null"),
+
+            Type::Function(parameter_types, return_type) => {
+                write!(f, "fn(")?;
+                for (index, parameter_type) in parameter_types.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", parameter_type)?;
+                }
+                write!(f, ") -> {}", return_type)
+            }
+
+            // Unbound type variables can be of any type
+            Type::Variable(variable) => write!(f, "T{}", variable),
+        }
+    }
+}
+
+/// Type table of all the type variables created while compiling, and the types they are bound to.
+///
+/// Types are inferred by unifying the type a value is used as with the type it is expected to be,
+/// like a simplified Hindley Milner type inference without let polymorphism,
+/// so every function has a single type, which its parameter types are inferred from how the function body and its callers use them.
+/// https://en.wikipedia.org/wiki/Hindley%E2%80%93Milner_type_system
+#[derive(Clone)]
+pub struct TypeTable {
+    /// Type bound to every type variable, using the variable as index, where None means the variable is not bound yet
+    bindings: Vec<Option<Type>>,
+}
+
+impl TypeTable {
+    pub fn new() -> TypeTable {
+        TypeTable {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Create a new unbound type variable
+    pub fn new_variable(&mut self) -> Type {
+        self.bindings.push(None);
+        Type::Variable(self.bindings.len() - 1)
+    }
+
+    /// Resolve a type by replacing all bound type variables in it with their bound types
+    pub fn resolve(&self, value_type: &Type) -> Type {
+        match value_type {
+            Type::Variable(variable) => match &self.bindings[*variable] {
+                Some(bound_type) => self.resolve(bound_type),
+                None => value_type.clone(),
+            },
+
+            Type::Function(parameter_types, return_type) => Type::Function(
+                parameter_types
+                    .iter()
+                    .map(|parameter_type| self.resolve(parameter_type))
+                    .collect(),
+                Box::new(self.resolve(return_type)),
+            ),
+
+            _ => value_type.clone(),
+        }
+    }
+
+    /// Unify 2 types by binding type variables so that both types are the same, returns false if the types cannot be the same
+    pub fn unify(&mut self, type_1: &Type, type_2: &Type) -> bool {
+        match (self.resolve(type_1), self.resolve(type_2)) {
+            (Type::Variable(variable_1), Type::Variable(variable_2)) if variable_1 == variable_2 => true,
+
+            // A type variable cannot be bound to a type containing itself, as that type would be infinitely long
+            (Type::Variable(variable), other_type) | (other_type, Type::Variable(variable)) => {
+                if self.occurs(variable, &other_type) {
+                    return false;
+                }
+
+                self.bindings[variable] = Some(other_type);
+                true
+            }
+
+            (
+                Type::Function(parameter_types_1, return_type_1),
+                Type::Function(parameter_types_2, return_type_2),
+            ) => {
+                parameter_types_1.len() == parameter_types_2.len()
+                    && parameter_types_1
+                        .iter()
+                        .zip(parameter_types_2.iter())
+                        .all(|(parameter_type_1, parameter_type_2)| self.unify(parameter_type_1, parameter_type_2))
+                    && self.unify(&return_type_1, &return_type_2)
+            }
+
+            (type_1, type_2) => type_1 == type_2,
+        }
+    }
+
+    /// Check if type variable occurs in the given resolved type
+    fn occurs(&self, variable: usize, value_type: &Type) -> bool {
+        match value_type {
+            Type::Variable(other_variable) => *other_variable == variable,
+            Type::Function(parameter_types, return_type) => {
+                parameter_types
+                    .iter()
+                    .any(|parameter_type| self.occurs(variable, parameter_type))
+                    || self.occurs(variable, return_type)
+            }
+            _ => false,
+        }
+    }
+}
@@ -1,42 +1,161 @@
 use super::compiler::Local;
+use super::types::Type;
 use super::CompileError;
 use super::Compiler;
 
-use crate::opcode::OpCode;
+use crate::line_table::SourceLocation;
+use crate::opcode::{decode, OpCode};
+use crate::token::TokenType;
 use crate::value::Value;
 
 impl Compiler {
     // This should be under compiler instead
     pub fn emit_code(&mut self, code: OpCode) {
-        self.chunk.write(code, self.parser.previous.line);
+        // Save the byte offset of this opcode, so that the compiler can look back at the last opcode emitted
+        self.previous_code = self.chunk.codes.len();
+        self.chunk.write_code(code, self.parser.previous.location());
     }
 
-    pub fn emit_constant(&mut self, value: Value) {
-        self.emit_code(OpCode::CONSTANT(value));
+    /// Emit an opcode followed by its single byte operand
+    pub fn emit_code_with_byte(&mut self, code: OpCode, operand: u8) {
+        self.emit_code(code);
+        self.chunk.write(operand, self.parser.previous.location());
     }
 
-    // Utility method to emit a JUMP type opcode and return the current code position
+    /// Emit an opcode followed by its 2 bytes wide operand
+    pub fn emit_code_with_u16(&mut self, code: OpCode, operand: u16) {
+        self.emit_code(code);
+        self.chunk.write_u16(operand, self.parser.previous.location());
+    }
+
+    /// Add value to the chunk's constant pool, and return its index, ensuring that it can be encoded as an operand
+    pub fn make_constant(&mut self, value: Value) -> Result<u16, CompileError> {
+        let constant_index = self.chunk.add_constant(value);
+
+        if constant_index > u16::MAX as usize {
+            return Err(CompileError::TooManyConstants(self.parser.previous.location()));
+        }
+
+        Ok(constant_index as u16)
+    }
+
+    /// Emit a CONSTANT opcode to load value onto the stack, using CONSTANT_LONG if the index cannot fit in a single byte
+    pub fn emit_constant(&mut self, value: Value) -> Result<(), CompileError> {
+        let constant_index = self.make_constant(value)?;
+        self.emit_constant_index(constant_index);
+
+        Ok(())
+    }
+
+    /// Emit the instruction to load the value at the index of the constant pool
+    pub fn emit_constant_index(&mut self, constant_index: u16) {
+        if constant_index <= u8::MAX as u16 {
+            self.emit_code_with_byte(OpCode::CONSTANT, constant_index as u8);
+        } else {
+            self.emit_code_with_u16(OpCode::CONSTANT_LONG, constant_index);
+        }
+    }
+
+    /// Resolves and return the slot of the given global identifier, allocating a new slot if it has never been seen before
+    pub fn resolve_global(&mut self, identifier: String) -> Result<u16, CompileError> {
+        if let Some(global_slot) = self.globals.get(&identifier) {
+            return Ok(*global_slot as u16);
+        }
+
+        // Global slots are encoded as 2 bytes wide operands
+        let global_slot = self.chunk.globals.len();
+        if global_slot > u16::MAX as usize {
+            return Err(CompileError::TooManyGlobals(self.parser.previous.location()));
+        }
+
+        // Identifiers are interned just like string literals
+        self.chunk.globals.push(Value::string(identifier.clone()));
+        self.globals.insert(identifier, global_slot);
+
+        // Type of the global is only known once it is defined, which might be after it is referenced in a function body
+        let global_type = self.types.new_variable();
+        self.global_types.push(global_type);
+
+        Ok(global_slot as u16)
+    }
+
+    // Utility method to emit a JUMP type opcode and return the byte offset of its operand
     pub fn emit_jump(&mut self, instruction: OpCode) -> usize {
-        self.emit_code(instruction);
+        // Emit placeholder offset of 0 which will be back patched once the jump target is known
+        self.emit_code_with_u16(instruction, 0);
 
-        // Return code vec length after appending jump opcode, to use for calculating JUMP offset to back patch into the above instruction later
-        self.chunk.codes.len() - 1
+        // Return byte offset of the jump operand, to use for calculating JUMP offset to back patch into the above instruction later
+        self.chunk.codes.len() - 2
     }
 
     // Utility method to patch a JUMP type opcode with the actual jump offset value
     // JUMP instructions are first emitted with a 0 offset, before the offset is calculated and patched back in with this method
-    pub fn patch_jump(&mut self, original_offset: usize) -> Result<(), CompileError> {
-        let jump: usize = self.chunk.codes.len() - original_offset;
-
-        // Create new opcode of the same JUMP type, but with the newly calculated jump offset value, and save it back into chunk
-        self.chunk.codes[original_offset] = match &self.chunk.codes[original_offset] {
-            OpCode::JUMP(0) => OpCode::JUMP(jump),
-            OpCode::JUMP_IF_FALSE(0) => OpCode::JUMP_IF_FALSE(jump),
-            invalid_opcode => return Err(CompileError::InvalidJumpOpcode(invalid_opcode.clone())),
-        };
+    pub fn patch_jump(&mut self, operand_offset: usize) -> Result<(), CompileError> {
+        // Ensure that the byte before the operand is indeed a forward JUMP type opcode
+        match decode(self.chunk.codes[operand_offset - 1]) {
+            Some(OpCode::JUMP) | Some(OpCode::JUMP_IF_FALSE) => {}
+            Some(invalid_opcode) => return Err(CompileError::InvalidJumpOpcode(invalid_opcode)),
+            None => panic!("Compiler Debug Error: Patching jump of an invalid opcode"),
+        }
+
+        // -2 to adjust for the jump offset operand itself, since VM's ip will be after the operand when jumping
+        let jump: usize = self.chunk.codes.len() - operand_offset - 2;
+
+        if jump > u16::MAX as usize {
+            return Err(CompileError::JumpTooLarge(self.parser.previous.location()));
+        }
+
+        // Write the newly calculated jump offset value back into chunk in big endian order
+        self.chunk.codes[operand_offset] = (jump >> 8) as u8;
+        self.chunk.codes[operand_offset + 1] = jump as u8;
         Ok(())
     }
 
+    /// Emit a LOOP opcode to jump backwards to the given byte offset of the loop start
+    pub fn emit_loop(&mut self, loop_start: usize) -> Result<(), CompileError> {
+        // +3 to also jump back over the LOOP opcode and its 2 byte operand, since VM's ip will be after the operand
+        let offset = self.chunk.codes.len() - loop_start + 3;
+
+        if offset > u16::MAX as usize {
+            return Err(CompileError::JumpTooLarge(self.parser.previous.location()));
+        }
+
+        self.emit_code_with_u16(OpCode::LOOP, offset as u16);
+        Ok(())
+    }
+
+    /// Skip tokens until a statement boundary after an error, like clox's panic mode recovery,
+    /// so that compilation can continue from the next statement to find more errors instead of stopping at the first one
+    pub fn synchronize(&mut self) {
+        while self.parser.current.token_type != TokenType::Eof {
+            // Current token is only a placeholder if advancing failed because of a scanner error, so it is skipped too
+            if self.parser.previous.token_type == TokenType::Semicolon
+                && self.parser.current.token_type != TokenType::NOTHING
+            {
+                return;
+            }
+
+            match self.parser.current.token_type {
+                TokenType::Const
+                | TokenType::Function
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Ignore => return,
+
+                // Stop at the end of the block, so that the enclosing block or function body can be closed properly
+                TokenType::RightBrace => return,
+
+                _ => {}
+            }
+
+            if let Err(error) = self.parser.advance() {
+                self.errors.push(error.into());
+            }
+        }
+    }
+
     // Indirection for all declaration and statement methods, to advance parser before calling the method
     // Inlined to remove runtime method call overhead
     #[inline]
@@ -54,7 +173,7 @@ impl Compiler {
         // Can unwrap last() value directly because len has already been checked to be bigger than 0
         //
         // Instead of popping values of stack 1 by 1 using multiple pop opcodes,
-        // Use POP_N(usize) opcode, to pop N number of values of the stack with a single opcode to make runtime faster
+        // Use POP_N opcode, to pop N number of values of the stack with a single opcode to make runtime faster
         let mut number_of_pops = 0;
         while self.locals.len() > 0 && self.locals.last().unwrap().depth > self.scope_depth {
             // Remove the local from compiler's locals vector too
@@ -67,7 +186,8 @@ impl Compiler {
             self.emit_code(OpCode::POP);
         } else if number_of_pops > 0 {
             // Use POP_N if there are more than 1 local to pop off the stack
-            self.emit_code(OpCode::POP_N(number_of_pops));
+            // Number of locals is capped by add_local, so that this will always fit in the single byte operand
+            self.emit_code_with_byte(OpCode::POP_N, number_of_pops as u8);
         }
     }
 
@@ -100,10 +220,55 @@ impl Compiler {
     }
 
     /// Add identifier to self.locals vector, which will be used for resolving stack index for identifier lookups
-    pub fn add_local(&mut self, identifier: String) {
+    pub fn add_local(&mut self, identifier: String, value_type: Type) -> Result<(), CompileError> {
+        // Stack index of locals are encoded as a single byte operand
+        if self.locals.len() >= u8::MAX as usize {
+            return Err(CompileError::TooManyLocals(self.parser.previous.location()));
+        }
+
         self.locals.push(Local {
             name: identifier,
             depth: self.scope_depth,
+            value_type,
+            function_constant: None,
         });
+        Ok(())
+    }
+
+    /// Push the type of the value that the last compiled expression leaves on the stack
+    #[inline]
+    pub fn push_type(&mut self, value_type: Type) {
+        self.expression_types.push(value_type);
+    }
+
+    /// Pop the type of the value that the last compiled expression leaves on the stack
+    pub fn pop_type(&mut self) -> Type {
+        // Every expression compiler method pushes exactly one type, just like how every expression leaves exactly one value on the stack
+        match self.expression_types.pop() {
+            Some(value_type) => value_type,
+            None => panic!("Compiler Debug Error: Type stack missing type of expression"),
+        }
+    }
+
+    /// Ensure that the type found is the type expected, by unifying them, where 'usage' describes how the value is used for the error message
+    pub fn expect_type(&mut self, expected: &Type, found: &Type, location: SourceLocation, usage: &str) {
+        if self.types.unify(expected, found) {
+            return;
+        }
+
+        let message = format!(
+            "{} expects {} but found {}",
+            usage,
+            self.types.resolve(expected),
+            self.types.resolve(found)
+        );
+        self.error(CompileError::TypeError(location, message));
+    }
+
+    /// Record an error that does not stop the current declaration from being compiled, e.g. type errors,
+    /// since the code is still syntactically valid, so there is nothing for the compiler to recover from
+    #[inline]
+    pub fn error(&mut self, error: CompileError) {
+        self.errors.push(error);
     }
 }
@@ -1,32 +1,98 @@
 use crate::chunk::Chunk;
+use crate::opcode::{decode, OpCode};
 use crate::value::Value;
 
 pub fn disassemble_chunk(chunk: &Chunk, name: &str) {
     println!("====== Start of chunk: {} ======", name);
-    for offset in 0..chunk.codes.len() {
-        disassemble_instruction(&chunk, offset);
+    // Instructions have variable lengths, so let each instruction tell us where the next one starts
+    let mut offset = 0;
+    while offset < chunk.codes.len() {
+        offset = disassemble_instruction(chunk, offset);
     }
     println!("====== End of chunk:   {} ======", name);
 }
 
-pub fn disassemble_instruction(chunk: &Chunk, offset: usize) {
-    // Prints the offset (bytecode index of a chunk) with 0 padding up to 3 digits
+/// Print out the instruction at the given byte offset, and return the byte offset of the next instruction
+pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> usize {
+    // Prints the offset (bytecode index of a chunk) with 0 padding up to 4 digits
     // https://stackoverflow.com/a/41821049
-    print!("{:0width$}", offset, width = 3);
+    print!("{:0width$} ", offset, width = 4);
 
     /*
         Printing should be of the format
-        <Line number>   <OpCode index>    <OpCode string representation>    <add. data if any>
+        <Byte offset>   <Line number>    <OpCode string representation>    <operands if any>
 
         Print line number or | for bytecodes on the same line
     */
-    if offset > 0 && chunk.lines[offset] == chunk.lines[offset - 1] {
+    if offset > 0 && chunk.line_for(offset) == chunk.line_for(offset - 1) {
         print!("   | ");
     } else {
-        print!("{:4} ", chunk.lines[offset]);
+        print!("{:4} ", chunk.line_for(offset));
     }
 
-    println!("{:?}", chunk.codes[offset]);
+    let opcode = match decode(chunk.codes[offset]) {
+        Some(opcode) => opcode,
+        None => {
+            println!("Unknown opcode {}", chunk.codes[offset]);
+            return offset + 1;
+        }
+    };
+
+    match opcode {
+        OpCode::CONSTANT => constant_instruction(chunk, opcode, chunk.codes[offset + 1] as usize),
+        OpCode::CONSTANT_LONG => constant_instruction(chunk, opcode, chunk.read_u16(offset + 1) as usize),
+        OpCode::DEFINE_GLOBAL | OpCode::GET_GLOBAL => {
+            global_instruction(chunk, opcode, chunk.read_u16(offset + 1) as usize)
+        }
+
+        OpCode::POP_N
+        | OpCode::RETURN_POP
+        | OpCode::GET_LOCAL
+        | OpCode::SET_LOCAL
+        | OpCode::CALL
+        | OpCode::GET_LOCAL_ADD
+        | OpCode::GET_LOCAL_SUBTRACT => println!("{:<18} {:4}", format!("{:?}", opcode), chunk.codes[offset + 1]),
+
+        OpCode::JUMP | OpCode::JUMP_IF_FALSE => jump_instruction(chunk, opcode, offset, true),
+        OpCode::LOOP => jump_instruction(chunk, opcode, offset, false),
+
+        // Simple instructions without any operands
+        _ => println!("{:?}", opcode),
+    }
+
+    offset + 1 + opcode.operand_length()
+}
+
+// Print instruction together with the index and value of the constant it references
+fn constant_instruction(chunk: &Chunk, opcode: OpCode, constant_index: usize) {
+    println!(
+        "{:<18} {:4} '{:?}'",
+        format!("{:?}", opcode),
+        constant_index,
+        chunk.constants[constant_index]
+    );
+}
+
+// Print instruction together with the slot and identifier of the global it references
+fn global_instruction(chunk: &Chunk, opcode: OpCode, global_slot: usize) {
+    println!(
+        "{:<18} {:4} '{}'",
+        format!("{:?}", opcode),
+        global_slot,
+        chunk.global_identifier(global_slot)
+    );
+}
+
+// Print jump instructions with both the jump offset and the absolute byte offset that it jumps to
+fn jump_instruction(chunk: &Chunk, opcode: OpCode, offset: usize, forward: bool) {
+    let jump = chunk.read_u16(offset + 1) as usize;
+    let next_instruction = offset + 1 + opcode.operand_length();
+    let target = if forward {
+        next_instruction + jump
+    } else {
+        next_instruction - jump
+    };
+    println!("{:<18} {:4} -> {}", format!("{:?}", opcode), jump, target);
 }
 
 pub fn print_stack(stack: &Vec<Value>) {
@@ -1,46 +1,104 @@
-use crate::compiler::parser::ParsingError;
+use crate::bytecode::BytecodeError;
 use crate::compiler::CompileError;
-use crate::scanner::ScannerError;
+use crate::line_table::SourceLocation;
 
 // Error enum type that encapsulates all other error enum types that can happen and bubble up to main SS program.
 #[derive(Debug)]
 pub enum SSError {
-    ScannerError(ScannerError),
-    ParsingError(ParsingError),
-    CompileError(CompileError),
-    RuntimeError(RuntimeError),
+    /// All errors found while compiling, in the order they are found.
+    /// Compiler recovers from errors at statement boundaries to report as many errors as possible in a single run,
+    /// where CompileError also wraps scanner and parsing errors.
+    CompileErrors(Vec<CompileError>),
+
+    /// Runtime error that escaped the VM, with the backtrace of the call stack when it happened, where the first frame is where the error happened
+    RuntimeError(RuntimeError, Vec<StackFrame>),
+    BytecodeError(BytecodeError),
+
+    /// File could not be read or written, with the path of the file
+    FileError(String, std::io::Error),
+}
+
+// Errors are rendered the same way as ri, with a header for the stage that failed
+impl std::fmt::Display for SSError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SSError::CompileErrors(errors) => {
+                writeln!(f, "-------- Compile ERROR --------")?;
+
+                if let Some(error) = errors.first() {
+                    writeln!(f, "{}\n", error)?;
+                }
+                if errors.len() > 1 {
+                    // Errors after the first might be caused by the first error, as the compiler might not recover perfectly from it
+                    writeln!(f, "---- These might be false positives ----\n")?;
+                    for error in errors.iter().skip(1) {
+                        writeln!(f, "{}\n", error)?;
+                    }
+                }
+
+                Ok(())
+            }
+
+            SSError::RuntimeError(runtime_error, backtrace) => {
+                writeln!(f, "-------- Runtime ERROR --------")?;
+                match backtrace.first() {
+                    Some(frame) => writeln!(f, "[line {}] {}", frame.location.line, runtime_error)?,
+                    None => writeln!(f, "{}", runtime_error)?,
+                }
+
+                writeln!(f, "\nBacktrace (most recent call first):")?;
+                for frame in backtrace {
+                    writeln!(f, "    at {} [line {}]", frame.function_name, frame.location.line)?;
+                }
+
+                Ok(())
+            }
+
+            SSError::BytecodeError(bytecode_error) => {
+                writeln!(f, "-------- Bytecode ERROR --------")?;
+                write!(f, "{}", bytecode_error)
+            }
+
+            SSError::FileError(path, io_error) => {
+                writeln!(f, "-------- File ERROR --------")?;
+                write!(f, "Unable to access file '{}': {}", path, io_error)
+            }
+        }
+    }
 }
 
-// Convert ParsingError to SSError automatically
-impl From<ParsingError> for SSError {
-    fn from(error: ParsingError) -> Self {
-        SSError::ParsingError(error)
+// Convert BytecodeError to SSError automatically
+impl From<BytecodeError> for SSError {
+    fn from(error: BytecodeError) -> Self {
+        SSError::BytecodeError(error)
     }
 }
 
-// Convert CompileError to SSError automatically
+// Convert a single CompileError to SSError automatically
 impl From<CompileError> for SSError {
     fn from(error: CompileError) -> Self {
-        // CompileError is able to wrap ParsingError, so this will unwrap it, and wrap it in SSError::ParsingError directly
-        // Instead of having a SSError::CompileError::ParsingError(parsing_error)
-        // Same with SSError::CompileError::ParsingError(ParsingError::ScannerError(scanner_error))
-        if let CompileError::ParsingError(ParsingError::ScannerError(scanner_error)) = error {
-            SSError::ScannerError(scanner_error)
-        } else if let CompileError::ParsingError(parsing_error) = error {
-            SSError::ParsingError(parsing_error)
-        } else {
-            SSError::CompileError(error)
-        }
+        SSError::CompileErrors(vec![error])
     }
 }
 
+/// A function call on the VM's call stack when a runtime error happened
+#[derive(Debug)]
+pub struct StackFrame {
+    /// Name of the function called, or <script> for the top level code
+    pub function_name: String,
+
+    /// Location of the instruction being executed in this function when the error happened,
+    /// which is the call site of the next frame for every frame except the first
+    pub location: SourceLocation,
+}
+
 /**
  * WIP Scaffolded from rust interpreter.
  *
  * Enum of all possible Runtime Errors
  * String is used instead of &str, as some of the strings are formatted on the fly using format!()
  *
- * @todo Should line number be included here for logging?
+ * Line numbers are not included here, as the VM attaches the location of the failing instruction with a backtrace when the error escapes
  * @todo Remove debug trait once Display trait is implemented
  */
 #[derive(Debug)]
@@ -62,7 +120,7 @@ pub enum RuntimeError {
 
     // Undefined values and variables, 1 for const and 1 for variables
     // @todo Undefined variable will not be used since it will always be parsed as Expr::Const for now, thus always UndefinedIdentifier
-    UndefinedIdentifier(usize, String),
+    UndefinedIdentifier(String),
     UndefinedVariable(String),
 
     // @todo Should be a SyntaxError or ParsingError instead, basically should not be RuntimeError as this error should be found before runtime
@@ -73,11 +131,20 @@ pub enum RuntimeError {
     ArrayOutOfBounds(String),
 
     // Tried using a none callable Value type as a function identifier and calling it as a function
-    // usize holds the line number of the call site
     // String is the string representation of Value object that the user tried to call
-    CallOnNonCallable(usize, String),
+    CallOnNonCallable(String),
+
+    // Function called with a different number of arguments than its parameters, which the compiler rejects,
+    // so it can only happen with bytecode that is not generated by the compiler
+    // WrongArity(function name, number of parameters, number of arguments)
+    WrongArity(String, usize, usize),
+
+    // Byte that does not decode to any OpCode, which can only happen if the chunk is generated wrongly
+    UnknownOpCode(u8),
+
+    // Instruction expected more values on the stack than there are, which can only happen if the chunk is generated wrongly
+    StackUnderflow(String),
     // DivideByZeroError(Token),
-    // WrongArity(Token, usize, usize),
     // UndefinedProperty(Token),
 }
 
@@ -93,8 +160,8 @@ impl std::fmt::Display for RuntimeError {
             RuntimeError::ConditionTypeError(ref message) => write!(f, "{}", message),
 
             // @todo DELETE THIS, should be caught by resolver already?? Would there be cases where this is not caught?
-            RuntimeError::UndefinedIdentifier(ref line_number, identifier) => {
-                write!(f, "[line {}] ReferenceError: Tried to use undefined identifier '{}'", line_number, identifier)
+            RuntimeError::UndefinedIdentifier(identifier) => {
+                write!(f, "ReferenceError: Tried to use undefined identifier '{}'", identifier)
             }
 
             RuntimeError::ValueAlreadyDefined(ref identifier) => {
@@ -103,8 +170,19 @@ impl std::fmt::Display for RuntimeError {
 
             RuntimeError::ArrayOutOfBounds(ref message) => write!(f, "{}", message),
 
-            RuntimeError::CallOnNonCallable(ref line_number, ref value) => {
-                write!(f, "[line {}] Attempted to call non-callable: {}", line_number, value)
+            RuntimeError::CallOnNonCallable(ref value) => {
+                write!(f, "Attempted to call non-callable: {}", value)
+            }
+
+            RuntimeError::WrongArity(ref name, arity, number_of_arguments) => write!(
+                f,
+                "Function '{}' expects {} arguments but got {}",
+                name, arity, number_of_arguments
+            ),
+
+            RuntimeError::UnknownOpCode(byte) => write!(f, "Internal interpreter error: Unknown OpCode {}", byte),
+            RuntimeError::StackUnderflow(ref message) => {
+                write!(f, "Internal interpreter error: Stack missing value for {}", message)
             }
 
             // If unimplemented yet print with debug symbol to prevent infinite recursive loop to calling the display trait
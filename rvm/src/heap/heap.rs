@@ -0,0 +1,193 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use super::HeapStats;
+use crate::value::{Object, Value};
+
+/// Heap size to reach before the first collection, just like Clox's initial 'nextGC'
+const INITIAL_NEXT_GC: usize = 1024 * 1024;
+
+/// After every collection, the next collection happens once the heap grows to this multiple of the bytes still alive
+const HEAP_GROW_FACTOR: usize = 2;
+
+/// Object allocated on the heap, together with the mark bit used by the collector
+pub struct HeapObject {
+    /// Set for every object reachable from the roots during a collection, and cleared again after the sweep.
+    /// Cell is used as objects are marked through the shared references held by values.
+    marked: Cell<bool>,
+
+    pub object: Object,
+}
+
+impl HeapObject {
+    /// Number of bytes used by the object, including the bytes it owns outside of its own allocation
+    fn size(&self) -> usize {
+        std::mem::size_of::<HeapObject>()
+            + match &self.object {
+                Object::String(string) => string.capacity(),
+                Object::Array(values) | Object::Closure(_, values) => values.capacity() * std::mem::size_of::<Value>(),
+                Object::Record(fields) => {
+                    fields.capacity() * std::mem::size_of::<(String, Value)>()
+                        + fields.iter().map(|(name, _)| name.capacity()).sum::<usize>()
+                }
+            }
+    }
+}
+
+/// Mark and sweep garbage collected heap, owning every object that values point to.
+///
+/// Objects are only freed by collections, which can only happen when every live value is reachable from the roots given.
+/// So collections are only triggered by the VM, which knows all the roots, while objects can be allocated anywhere,
+/// e.g. by the compiler when it creates string constants.
+struct Heap {
+    /// Every object allocated and not freed yet. Objects are boxed so that their addresses stay the same as the vector grows.
+    #[allow(clippy::vec_box)]
+    objects: Vec<Box<HeapObject>>,
+
+    /// Interning table of every string allocated, so that every unique string is only allocated once,
+    /// which also allows strings to be compared by pointer instead of by content.
+    /// Strings are not kept alive by this table, as freed strings are removed from it during collection.
+    strings: HashMap<String, *const HeapObject>,
+
+    /// Number of bytes used by all the objects still on the heap
+    bytes_allocated: usize,
+
+    /// Number of bytes allocated that triggers the next collection
+    next_gc: usize,
+
+    stats: HeapStats,
+}
+
+// Just like Clox's global VM struct, there is a single heap that all values point into
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        objects: Vec::new(),
+        strings: HashMap::new(),
+        bytes_allocated: 0,
+        next_gc: INITIAL_NEXT_GC,
+        stats: HeapStats::default(),
+    });
+}
+
+impl Heap {
+    fn allocate(&mut self, object: Object) -> *const HeapObject {
+        let heap_object = Box::new(HeapObject {
+            marked: Cell::new(false),
+            object,
+        });
+
+        let size = heap_object.size();
+        self.bytes_allocated += size;
+        self.stats.objects_allocated += 1;
+        self.stats.bytes_allocated += size;
+
+        let pointer: *const HeapObject = &*heap_object;
+        self.objects.push(heap_object);
+        pointer
+    }
+
+    fn intern(&mut self, string: String) -> *const HeapObject {
+        if let Some(pointer) = self.strings.get(&string) {
+            return *pointer;
+        }
+
+        let pointer = self.allocate(Object::String(string.clone()));
+        self.strings.insert(string, pointer);
+        pointer
+    }
+
+    fn collect<'a>(&mut self, roots: impl Iterator<Item = &'a Value>) {
+        // Mark every object reachable from the roots, with a work list of marked objects whose values are not traced yet
+        let mut gray_objects = Vec::<&HeapObject>::new();
+        for root in roots {
+            mark(root, &mut gray_objects);
+        }
+        while let Some(heap_object) = gray_objects.pop() {
+            match &heap_object.object {
+                Object::String(_) => {}
+                Object::Array(values) | Object::Closure(_, values) => {
+                    for value in values {
+                        mark(value, &mut gray_objects);
+                    }
+                }
+                Object::Record(fields) => {
+                    for (_, value) in fields {
+                        mark(value, &mut gray_objects);
+                    }
+                }
+            }
+        }
+
+        // Interned strings that are not marked are about to be freed, so they must be removed from the interning table
+        // Safety: Objects in the interning table are only freed below after they are removed from the table
+        self.strings
+            .retain(|_, pointer| unsafe { (**pointer).marked.get() });
+
+        // Sweep by freeing every object not marked, and clear the marks of the objects still alive for the next collection
+        let mut bytes_freed = 0;
+        let mut objects_freed = 0;
+        self.objects.retain(|heap_object| {
+            if heap_object.marked.replace(false) {
+                return true;
+            }
+
+            bytes_freed += heap_object.size();
+            objects_freed += 1;
+            false
+        });
+
+        self.bytes_allocated -= bytes_freed;
+        self.next_gc = (self.bytes_allocated * HEAP_GROW_FACTOR).max(INITIAL_NEXT_GC);
+
+        self.stats.collections += 1;
+        self.stats.objects_freed += objects_freed;
+        self.stats.bytes_freed += bytes_freed;
+    }
+}
+
+/// Mark the object of the value if it is not marked yet, and push it onto the work list to trace the values it holds
+fn mark<'a>(value: &'a Value, gray_objects: &mut Vec<&'a HeapObject>) {
+    if let Some(heap_object) = value.as_heap_object() {
+        if !heap_object.marked.replace(true) {
+            gray_objects.push(heap_object);
+        }
+    }
+}
+
+/// Allocate object on the heap and return a pointer to it, which stays valid until a collection where it is not reachable
+pub fn allocate(object: Object) -> *const HeapObject {
+    HEAP.with(|heap| heap.borrow_mut().allocate(object))
+}
+
+/// Get the interned string object of the given string, allocating it if the string has not been interned yet
+pub fn intern(string: String) -> *const HeapObject {
+    HEAP.with(|heap| heap.borrow_mut().intern(string))
+}
+
+/// Check if the heap has grown enough since the last collection for a new collection
+pub fn should_collect() -> bool {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        heap.bytes_allocated > heap.next_gc
+    })
+}
+
+/// Run a mark and sweep collection, freeing every object not reachable from the given roots.
+///
+/// Every value still in use must be reachable from the roots, as values pointing to freed objects are dangling pointers.
+pub fn collect<'a>(roots: impl Iterator<Item = &'a Value>) {
+    HEAP.with(|heap| heap.borrow_mut().collect(roots))
+}
+
+/// Get the statistics of the heap, including the objects and bytes that are still on the heap
+pub fn stats() -> HeapStats {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        HeapStats {
+            live_objects: heap.objects.len(),
+            live_bytes: heap.bytes_allocated,
+            interned_strings: heap.strings.len(),
+            ..heap.stats
+        }
+    })
+}
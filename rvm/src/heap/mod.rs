@@ -0,0 +1,5 @@
+mod heap;
+mod stats;
+
+pub use self::heap::{allocate, collect, intern, should_collect, stats, HeapObject};
+pub use self::stats::HeapStats;
@@ -0,0 +1,32 @@
+/// Statistics of the heap, used to test and tune the garbage collector
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HeapStats {
+    /// Total number of objects and bytes ever allocated
+    pub objects_allocated: usize,
+    pub bytes_allocated: usize,
+
+    /// Total number of objects and bytes freed by all collections
+    pub objects_freed: usize,
+    pub bytes_freed: usize,
+
+    /// Number of collections ran
+    pub collections: usize,
+
+    /// Number of objects and bytes still on the heap
+    pub live_objects: usize,
+    pub live_bytes: usize,
+
+    /// Number of strings in the interning table
+    pub interned_strings: usize,
+}
+
+impl std::fmt::Display for HeapStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "-------- Heap statistics --------")?;
+        writeln!(f, "Collections:        {}", self.collections)?;
+        writeln!(f, "Objects allocated:  {} ({} bytes)", self.objects_allocated, self.bytes_allocated)?;
+        writeln!(f, "Objects freed:      {} ({} bytes)", self.objects_freed, self.bytes_freed)?;
+        writeln!(f, "Objects live:       {} ({} bytes)", self.live_objects, self.live_bytes)?;
+        write!(f, "Interned strings:   {}", self.interned_strings)
+    }
+}
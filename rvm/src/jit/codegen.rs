@@ -0,0 +1,601 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use cranelift_codegen::entity::EntityRef;
+use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
+use cranelift_codegen::ir::{self, types, AbiParam, Block, InstBuilder, MemFlags, SigRef, Signature};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::JITModule;
+use cranelift_module::{Linkage, Module};
+
+use super::runtime::{self, JitContext, CALL_NOT_MADE, CALL_RETURNED, NESTED_EXIT, UNDEFINED_BITS};
+use super::JitError;
+
+use crate::chunk::{Chunk, Function};
+use crate::opcode::{decode, OpCode};
+use crate::value::{FALSE_BITS, NAN_BITS, QNAN, SIGN_BIT, TRUE_BITS};
+
+/// Signature of JIT compiled functions, see the JIT module for the calling convention
+pub type NativeFunction =
+    for<'a> unsafe extern "C" fn(context: *mut JitContext<'a>, frame_start: usize, frame_length: *mut usize) -> usize;
+
+/// Value returned by a JIT compiled function when it returns normally instead of exiting back to the interpreter
+pub const RETURNED: usize = usize::MAX;
+
+/// Function body compiled into native code
+pub struct CompiledFunction {
+    pub code: NativeFunction,
+
+    /// Number of values in the call frame when the function is called, which are its arguments
+    pub arity: usize,
+
+    /// Stack index that the function body's first argument is compiled to be at
+    pub stack_base: usize,
+
+    /// Maximum number of values that the compiled code can write into the call frame, when returning or exiting back to the interpreter
+    pub max_frame_size: usize,
+}
+
+/// Compile a function body into native code, where every instruction that cannot be compiled becomes an exit back to the interpreter
+pub fn compile_function(
+    module: &mut JITModule,
+    chunk: &Chunk,
+    function: &Function,
+    stack_sizes: &HashMap<usize, usize>,
+) -> Result<CompiledFunction, JitError> {
+    let offsets = reachable_instructions(chunk, function.start);
+    let entry_stack_size = function.stack_base + function.arity;
+    let max_stack_size = offsets
+        .iter()
+        .map(|offset| stack_sizes[offset] + 1)
+        .max()
+        .unwrap_or(entry_stack_size);
+
+    let pointer_type = module.target_config().pointer_type();
+    let call_conv = module.target_config().default_call_conv;
+    let signature = |params: usize| {
+        let mut signature = Signature::new(call_conv);
+        signature.params.extend((0..params).map(|_| AbiParam::new(pointer_type)));
+        signature.returns.push(AbiParam::new(pointer_type));
+        signature
+    };
+
+    let mut context = module.make_context();
+    context.func.signature = signature(3);
+
+    let mut builder_context = FunctionBuilderContext::new();
+    let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+
+    // Every stack slot the function body pushes values into is a variable, so that Cranelift keeps them in registers
+    for slot in entry_stack_size..max_stack_size {
+        builder.declare_var(Variable::new(slot), types::I64);
+    }
+    let frame = Variable::new(max_stack_size);
+    builder.declare_var(frame, pointer_type);
+
+    let runtime_signatures = RuntimeSignatures {
+        frame: builder.import_signature(signature(2)),
+        get_global: builder.import_signature(signature(2)),
+        call: builder.import_signature(signature(5)),
+    };
+
+    let entry_block = builder.create_block();
+    builder.append_block_params_for_function_params(entry_block);
+    builder.switch_to_block(entry_block);
+    let context_pointer = builder.block_params(entry_block)[0];
+    let frame_start = builder.block_params(entry_block)[1];
+    let frame_length = builder.block_params(entry_block)[2];
+
+    // Every instruction that can be jumped to starts a new block, including the instruction after a conditional jump
+    let mut blocks = HashMap::<usize, Block>::new();
+    blocks.insert(function.start, builder.create_block());
+    for &offset in &offsets {
+        let opcode = decode(chunk.codes[offset]).unwrap();
+        if let OpCode::JUMP | OpCode::JUMP_IF_FALSE | OpCode::LOOP = opcode {
+            blocks.entry(jump_target(chunk, offset)).or_insert_with(|| builder.create_block());
+        }
+        if opcode == OpCode::JUMP_IF_FALSE {
+            blocks.entry(offset + 3).or_insert_with(|| builder.create_block());
+        }
+    }
+
+    let mut translator = FunctionTranslator {
+        builder,
+        chunk,
+        stack_sizes,
+        pointer_type,
+        runtime_signatures,
+        context: context_pointer,
+        frame_start,
+        frame,
+        frame_length,
+        entry_stack_size,
+        stack_base: function.stack_base,
+    };
+    translator.load_frame();
+    translator.builder.ins().jump(blocks[&function.start], &[]);
+
+    // Instructions are translated in the order of their byte offsets, so an instruction that is not the start of a block
+    // is always translated right after the instruction before it, which is the only instruction that can continue on to it
+    let mut terminated = true;
+    for offset in offsets {
+        if let Some(&block) = blocks.get(&offset) {
+            if !terminated {
+                translator.builder.ins().jump(block, &[]);
+            }
+            translator.builder.switch_to_block(block);
+        }
+
+        terminated = translator.translate_instruction(offset, &blocks);
+    }
+
+    translator.builder.seal_all_blocks();
+    translator.builder.finalize();
+
+    let name = format!("{}@{}", function.name, function.start);
+    let function_id = module
+        .declare_function(&name, Linkage::Local, &context.func.signature)
+        .map_err(|error| JitError::Codegen(name.clone(), error.to_string()))?;
+    module
+        .define_function(function_id, &mut context)
+        .map_err(|error| JitError::Codegen(name.clone(), error.to_string()))?;
+    module.clear_context(&mut context);
+    module
+        .finalize_definitions()
+        .map_err(|error| JitError::Codegen(name.clone(), error.to_string()))?;
+
+    // Safety: The function is defined with the same signature as NativeFunction
+    let code = unsafe { std::mem::transmute::<*const u8, NativeFunction>(module.get_finalized_function(function_id)) };
+
+    Ok(CompiledFunction {
+        code,
+        arity: function.arity,
+        stack_base: function.stack_base,
+        max_frame_size: max_stack_size - function.stack_base,
+    })
+}
+
+/// Check if the opcode can be compiled into native code, all other opcodes exit back to the interpreter.
+/// Defining globals and printing are left to the interpreter, so that compiled code has no side effects.
+fn is_supported(opcode: OpCode) -> bool {
+    !matches!(opcode, OpCode::DEFINE_GLOBAL | OpCode::SET_LOCAL | OpCode::PRINT)
+}
+
+/// Byte offset that the jump type instruction at the byte offset jumps to
+fn jump_target(chunk: &Chunk, offset: usize) -> usize {
+    let next_instruction = offset + 3;
+    match decode(chunk.codes[offset]) {
+        Some(OpCode::LOOP) => next_instruction - chunk.read_u16(offset + 1) as usize,
+        _ => next_instruction + chunk.read_u16(offset + 1) as usize,
+    }
+}
+
+/// Byte offsets of every instruction of the function body that can be reached in native code, in order.
+/// Instructions after an unsupported instruction are executed by the interpreter, so they are only reachable through jumps.
+fn reachable_instructions(chunk: &Chunk, start: usize) -> BTreeSet<usize> {
+    let mut reachable = BTreeSet::<usize>::new();
+    let mut visited = HashSet::<usize>::new();
+    let mut work_list = vec![start];
+
+    while let Some(offset) = work_list.pop() {
+        if !visited.insert(offset) {
+            continue;
+        }
+        reachable.insert(offset);
+
+        let opcode = decode(chunk.codes[offset]).unwrap();
+        match opcode {
+            OpCode::RETURN | OpCode::RETURN_POP => {}
+            OpCode::JUMP | OpCode::LOOP => work_list.push(jump_target(chunk, offset)),
+            OpCode::JUMP_IF_FALSE => {
+                work_list.push(jump_target(chunk, offset));
+                work_list.push(offset + 3);
+            }
+            _ if !is_supported(opcode) => {}
+            _ => work_list.push(offset + 1 + opcode.operand_length()),
+        }
+    }
+
+    reachable
+}
+
+/// Signatures of the runtime functions called by compiled code, imported into the function being compiled
+struct RuntimeSignatures {
+    frame: SigRef,
+    get_global: SigRef,
+    call: SigRef,
+}
+
+/// Translates the instructions of a function body into Cranelift IR, keeping track of the stack using the stack sizes from the verifier.
+///
+/// Stack slots are numbered with the stack indices that the function body is compiled with, just like the local opcodes' operands,
+/// and are offset by the function's stack base when they are read from or stored into the call frame.
+struct FunctionTranslator<'a> {
+    builder: FunctionBuilder<'a>,
+    chunk: &'a Chunk,
+    stack_sizes: &'a HashMap<usize, usize>,
+
+    pointer_type: ir::Type,
+    runtime_signatures: RuntimeSignatures,
+
+    /// Pointer to the JitContext, passed on to every runtime function
+    context: ir::Value,
+    /// Stack index of the first value of the call frame, which is the function's first argument
+    frame_start: ir::Value,
+    /// Pointer to the first value of the call frame on the VM's stack,
+    /// which is loaded again after every call, as calls can grow the stack and move it
+    frame: Variable,
+    /// Pointer to the number of values in the call frame, which is written when the compiled code returns or exits
+    frame_length: ir::Value,
+
+    entry_stack_size: usize,
+    stack_base: usize,
+}
+
+impl<'a> FunctionTranslator<'a> {
+    /// Translate the instruction at the byte offset, returning true if the instruction ends the current block
+    fn translate_instruction(&mut self, offset: usize, blocks: &HashMap<usize, Block>) -> bool {
+        let opcode = decode(self.chunk.codes[offset]).unwrap();
+        let stack_size = self.stack_sizes[&offset];
+        let byte_operand = self.chunk.codes.get(offset + 1).copied().unwrap_or(0) as usize;
+
+        match opcode {
+            // Stack sizes are known for every instruction, so popping values does not need any code
+            OpCode::POP | OpCode::POP_N => {}
+
+            OpCode::RETURN | OpCode::RETURN_POP => {
+                // Function returns with only its return value left above its stack base, just like the interpreter
+                let return_value = self.read_slot(stack_size - 1);
+                self.store(return_value, self.stack_base);
+                self.return_with(self.stack_base + 1, RETURNED);
+                return true;
+            }
+
+            OpCode::CONSTANT | OpCode::CONSTANT_LONG => {
+                let constant_index = match opcode {
+                    OpCode::CONSTANT => byte_operand,
+                    _ => self.chunk.read_u16(offset + 1) as usize,
+                };
+
+                // Objects of constants are kept alive by the chunk, so their pointers can be embedded in the code
+                let bits = self.chunk.constants[constant_index].to_bits();
+                let value = self.builder.ins().iconst(types::I64, bits as i64);
+                self.write_slot(stack_size, value);
+            }
+
+            OpCode::GET_LOCAL => {
+                let value = self.read_slot(byte_operand);
+                self.write_slot(stack_size, value);
+            }
+
+            OpCode::JUMP | OpCode::LOOP => {
+                self.builder.ins().jump(blocks[&jump_target(self.chunk, offset)], &[]);
+                return true;
+            }
+            OpCode::JUMP_IF_FALSE => {
+                // Condition is only peeked, and any value that is not a bool exits to the interpreter to raise the error
+                let condition = self.read_slot(stack_size - 1);
+                let is_false = self.builder.ins().icmp_imm(IntCC::Equal, condition, FALSE_BITS as i64);
+                let check_true = self.builder.create_block();
+                self.builder
+                    .ins()
+                    .brif(is_false, blocks[&jump_target(self.chunk, offset)], &[], check_true, &[]);
+
+                self.builder.switch_to_block(check_true);
+                let is_true = self.builder.ins().icmp_imm(IntCC::Equal, condition, TRUE_BITS as i64);
+                let exit = self.builder.create_block();
+                self.builder.ins().brif(is_true, blocks[&(offset + 3)], &[], exit, &[]);
+
+                self.builder.switch_to_block(exit);
+                self.exit_to_interpreter(offset);
+                return true;
+            }
+
+            OpCode::ADD | OpCode::SUBTRACT | OpCode::MULTIPLY | OpCode::DIVIDE => {
+                let a = self.read_slot(stack_size - 2);
+                let b = self.read_slot(stack_size - 1);
+                let result = self.arithmetic(opcode, a, b, offset);
+                self.write_slot(stack_size - 2, result);
+            }
+            OpCode::GET_LOCAL_ADD | OpCode::GET_LOCAL_SUBTRACT => {
+                let a = self.read_slot(stack_size - 1);
+                let b = self.read_slot(byte_operand);
+                let opcode = match opcode {
+                    OpCode::GET_LOCAL_ADD => OpCode::ADD,
+                    _ => OpCode::SUBTRACT,
+                };
+                let result = self.arithmetic(opcode, a, b, offset);
+                self.write_slot(stack_size - 1, result);
+            }
+
+            OpCode::GREATER | OpCode::GREATER_EQUAL | OpCode::LESS | OpCode::LESS_EQUAL => {
+                let a = self.read_slot(stack_size - 2);
+                let b = self.read_slot(stack_size - 1);
+                let both_numbers = self.both_numbers(a, b);
+                self.guard(both_numbers, offset);
+
+                let condition = match opcode {
+                    OpCode::GREATER => FloatCC::GreaterThan,
+                    OpCode::GREATER_EQUAL => FloatCC::GreaterThanOrEqual,
+                    OpCode::LESS => FloatCC::LessThan,
+                    _ => FloatCC::LessThanOrEqual,
+                };
+                let (a, b) = (self.as_float(a), self.as_float(b));
+                let result = self.builder.ins().fcmp(condition, a, b);
+                let result = self.box_bool(result);
+                self.write_slot(stack_size - 2, result);
+            }
+
+            OpCode::EQUAL | OpCode::NOT_EQUAL => {
+                let a = self.read_slot(stack_size - 2);
+                let b = self.read_slot(stack_size - 1);
+
+                // Different objects are compared by their contents, which is left to the interpreter
+                let both_objects = {
+                    let a = self.is_object(a);
+                    let b = self.is_object(b);
+                    self.builder.ins().band(a, b)
+                };
+                let same_bits = self.builder.ins().icmp(IntCC::Equal, a, b);
+                let needs_interpreter = self.builder.ins().band_not(both_objects, same_bits);
+                let can_compare = self.builder.ins().bxor_imm(needs_interpreter, 1);
+                self.guard(can_compare, offset);
+
+                // Numbers are compared as floats, and all other values are equal only if they have the same bits
+                let both_numbers = self.both_numbers(a, b);
+                let (float_a, float_b) = (self.as_float(a), self.as_float(b));
+                let equal_numbers = self.builder.ins().fcmp(FloatCC::Equal, float_a, float_b);
+                let equal = self.builder.ins().select(both_numbers, equal_numbers, same_bits);
+
+                let result = match opcode {
+                    OpCode::EQUAL => equal,
+                    _ => self.builder.ins().bxor_imm(equal, 1),
+                };
+                let result = self.box_bool(result);
+                self.write_slot(stack_size - 2, result);
+            }
+
+            OpCode::NOT => {
+                let value = self.read_slot(stack_size - 1);
+                let is_bool = self.builder.ins().bor_imm(value, 1);
+                let is_bool = self.builder.ins().icmp_imm(IntCC::Equal, is_bool, TRUE_BITS as i64);
+                self.guard(is_bool, offset);
+
+                // true and false only differ in the lowest bit
+                let result = self.builder.ins().bxor_imm(value, 1);
+                self.write_slot(stack_size - 1, result);
+            }
+            OpCode::NEGATE => {
+                let value = self.read_slot(stack_size - 1);
+                let is_number = self.is_number(value);
+                self.guard(is_number, offset);
+
+                let value = self.as_float(value);
+                let result = self.builder.ins().fneg(value);
+                let result = self.box_float(result);
+                self.write_slot(stack_size - 1, result);
+            }
+
+            OpCode::GET_GLOBAL => {
+                // Globals that are not defined yet exit to the interpreter to raise the error
+                let slot = self.builder.ins().iconst(self.pointer_type, self.chunk.read_u16(offset + 1) as i64);
+                let value = self.call_runtime(self.runtime_signatures.get_global, runtime::get_global as *const u8, &[slot]);
+                let is_defined = self
+                    .builder
+                    .ins()
+                    .icmp_imm(IntCC::NotEqual, value, UNDEFINED_BITS as i64);
+                self.guard(is_defined, offset);
+                self.write_slot(stack_size, value);
+            }
+
+            OpCode::CALL => {
+                self.call(offset, stack_size, byte_operand);
+            }
+
+            // Unsupported instructions are executed by the interpreter, starting from the instruction itself
+            OpCode::DEFINE_GLOBAL | OpCode::SET_LOCAL | OpCode::PRINT => {
+                self.exit_to_interpreter(offset);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Read the value of a stack slot, where the arguments are read from the call frame on the VM's stack,
+    /// since they are never changed by the function body
+    fn read_slot(&mut self, slot: usize) -> ir::Value {
+        if slot < self.entry_stack_size {
+            self.load(slot)
+        } else {
+            self.builder.use_var(Variable::new(slot))
+        }
+    }
+
+    fn write_slot(&mut self, slot: usize, value: ir::Value) {
+        self.builder.def_var(Variable::new(slot), value);
+    }
+
+    /// Byte offset of the stack slot from the start of the call frame.
+    /// Function bodies never use slots below their stack base, which is checked by the verifier
+    fn frame_offset(&self, slot: usize) -> i32 {
+        ((slot - self.stack_base) * 8) as i32
+    }
+
+    /// Load the value of the VM's stack slot
+    fn load(&mut self, slot: usize) -> ir::Value {
+        let offset = self.frame_offset(slot);
+        let frame = self.builder.use_var(self.frame);
+        self.builder.ins().load(types::I64, MemFlags::trusted(), frame, offset)
+    }
+
+    /// Store value into the VM's stack slot
+    fn store(&mut self, value: ir::Value, slot: usize) {
+        let offset = self.frame_offset(slot);
+        let frame = self.builder.use_var(self.frame);
+        self.builder.ins().store(MemFlags::trusted(), value, frame, offset);
+    }
+
+    /// Call the runtime function at the address with the context and the arguments, returning its result
+    fn call_runtime(&mut self, signature: SigRef, address: *const u8, arguments: &[ir::Value]) -> ir::Value {
+        let address = self.builder.ins().iconst(self.pointer_type, address as i64);
+        let arguments: Vec<ir::Value> = std::iter::once(self.context).chain(arguments.iter().copied()).collect();
+        let call = self.builder.ins().call_indirect(signature, address, &arguments);
+        self.builder.inst_results(call)[0]
+    }
+
+    fn load_frame(&mut self) {
+        let frame = self.call_runtime(self.runtime_signatures.frame, runtime::frame as *const u8, &[self.frame_start]);
+        self.builder.def_var(self.frame, frame);
+    }
+
+    /// Call the function value below the arguments natively through the runtime, which makes the call only if the callee
+    /// is compiled and the call would not fail, otherwise the caller exits to the interpreter to make the call.
+    ///
+    /// The call frame is laid out just like the interpreter's while the callee runs, where the values below the function
+    /// value are stored onto the VM's stack and the arguments are moved down to where the function value was,
+    /// so that the callee's call frame starts right after the caller's values.
+    fn call(&mut self, offset: usize, stack_size: usize, number_of_arguments: usize) {
+        let function_slot = stack_size - 1 - number_of_arguments;
+        let function = self.read_slot(function_slot);
+
+        for slot in self.entry_stack_size..function_slot {
+            let value = self.builder.use_var(Variable::new(slot));
+            self.store(value, slot);
+        }
+        for slot in function_slot + 1..stack_size {
+            let argument = self.read_slot(slot);
+            self.store(argument, slot - 1);
+        }
+
+        let callee_frame_start = self
+            .builder
+            .ins()
+            .iadd_imm(self.frame_start, (function_slot - self.stack_base) as i64);
+        let number_of_arguments = self.builder.ins().iconst(self.pointer_type, number_of_arguments as i64);
+        let return_ip = self.builder.ins().iconst(self.pointer_type, (offset + 2) as i64);
+        let result = self.call_runtime(
+            self.runtime_signatures.call,
+            runtime::call as *const u8,
+            &[function, callee_frame_start, number_of_arguments, return_ip],
+        );
+        self.load_frame();
+
+        let returned_block = self.builder.create_block();
+        let not_returned_block = self.builder.create_block();
+        let not_made_block = self.builder.create_block();
+        let exited_block = self.builder.create_block();
+
+        let returned = self.builder.ins().icmp_imm(IntCC::Equal, result, CALL_RETURNED as i64);
+        self.builder
+            .ins()
+            .brif(returned, returned_block, &[], not_returned_block, &[]);
+
+        self.builder.switch_to_block(not_returned_block);
+        let not_made = self.builder.ins().icmp_imm(IntCC::Equal, result, CALL_NOT_MADE as i64);
+        self.builder
+            .ins()
+            .brif(not_made, not_made_block, &[], exited_block, &[]);
+
+        // Values stored by the call are all still in variables, so the interpreter makes the call just as if it was never tried
+        self.builder.switch_to_block(not_made_block);
+        self.exit_to_interpreter(offset);
+
+        // Callee's call frame is the innermost one now, so the caller stops without changing the VM's stack
+        self.builder.switch_to_block(exited_block);
+        let nested_exit = self.builder.ins().iconst(types::I64, NESTED_EXIT as i64);
+        self.builder.ins().return_(&[nested_exit]);
+
+        // Callee left only its return value, right where the function value was
+        self.builder.switch_to_block(returned_block);
+        let return_value = self.load(function_slot);
+        self.write_slot(function_slot, return_value);
+    }
+
+    /// Write the new length of the call frame for the stack size and return from the compiled code
+    fn return_with(&mut self, stack_size: usize, result: usize) {
+        let frame_length = self.builder.ins().iconst(types::I64, (stack_size - self.stack_base) as i64);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), frame_length, self.frame_length, 0);
+
+        let result = self.builder.ins().iconst(types::I64, result as i64);
+        self.builder.ins().return_(&[result]);
+    }
+
+    /// Exit back to the interpreter before executing the instruction at the byte offset,
+    /// by storing the values pushed by the function body onto the VM's stack, and returning the byte offset to resume from
+    fn exit_to_interpreter(&mut self, offset: usize) {
+        let stack_size = self.stack_sizes[&offset];
+        for slot in self.entry_stack_size..stack_size {
+            let value = self.builder.use_var(Variable::new(slot));
+            self.store(value, slot);
+        }
+
+        self.return_with(stack_size, offset);
+    }
+
+    /// Continue in a new block if the condition holds, otherwise exit back to the interpreter at the instruction,
+    /// which executes the instruction again to raise the same runtime error as when the function is interpreted
+    fn guard(&mut self, condition: ir::Value, offset: usize) {
+        let continue_block = self.builder.create_block();
+        let exit_block = self.builder.create_block();
+        self.builder
+            .ins()
+            .brif(condition, continue_block, &[], exit_block, &[]);
+
+        self.builder.switch_to_block(exit_block);
+        self.exit_to_interpreter(offset);
+
+        self.builder.switch_to_block(continue_block);
+    }
+
+    fn arithmetic(&mut self, opcode: OpCode, a: ir::Value, b: ir::Value, offset: usize) -> ir::Value {
+        let both_numbers = self.both_numbers(a, b);
+        self.guard(both_numbers, offset);
+
+        let (a, b) = (self.as_float(a), self.as_float(b));
+        let result = match opcode {
+            OpCode::ADD => self.builder.ins().fadd(a, b),
+            OpCode::SUBTRACT => self.builder.ins().fsub(a, b),
+            OpCode::MULTIPLY => self.builder.ins().fmul(a, b),
+            _ => self.builder.ins().fdiv(a, b),
+        };
+        self.box_float(result)
+    }
+
+    fn is_number(&mut self, value: ir::Value) -> ir::Value {
+        let bits = self.builder.ins().band_imm(value, QNAN as i64);
+        self.builder.ins().icmp_imm(IntCC::NotEqual, bits, QNAN as i64)
+    }
+
+    fn both_numbers(&mut self, a: ir::Value, b: ir::Value) -> ir::Value {
+        let a = self.is_number(a);
+        let b = self.is_number(b);
+        self.builder.ins().band(a, b)
+    }
+
+    fn is_object(&mut self, value: ir::Value) -> ir::Value {
+        let bits = self.builder.ins().band_imm(value, (SIGN_BIT | QNAN) as i64);
+        self.builder
+            .ins()
+            .icmp_imm(IntCC::Equal, bits, (SIGN_BIT | QNAN) as i64)
+    }
+
+    fn as_float(&mut self, value: ir::Value) -> ir::Value {
+        self.builder.ins().bitcast(types::F64, MemFlags::new(), value)
+    }
+
+    /// Box a float into a value, canonicalizing NaNs just like Value::number
+    fn box_float(&mut self, value: ir::Value) -> ir::Value {
+        let is_nan = self.builder.ins().fcmp(FloatCC::Unordered, value, value);
+        let bits = self.builder.ins().bitcast(types::I64, MemFlags::new(), value);
+        let nan = self.builder.ins().iconst(types::I64, NAN_BITS as i64);
+        self.builder.ins().select(is_nan, nan, bits)
+    }
+
+    /// Box the result of a comparison into a bool value, where true and false only differ in the lowest bit
+    fn box_bool(&mut self, condition: ir::Value) -> ir::Value {
+        let bit = self.builder.ins().uextend(types::I64, condition);
+        self.builder.ins().bor_imm(bit, FALSE_BITS as i64)
+    }
+}
@@ -0,0 +1,31 @@
+/// Errors from compiling a function with the JIT, which are never fatal since the function is interpreted instead
+#[derive(Debug)]
+pub enum JitError {
+    /// JIT only generates code for x86-64 Linux
+    UnsupportedPlatform,
+
+    /// Native code generator could not be created for the host machine
+    UnsupportedHost(String),
+
+    /// Chunk's stack sizes could not be determined, as the chunk did not pass the verifier
+    InvalidChunk(String),
+
+    /// Function value's byte offset is not in the chunk's function table
+    UnknownFunction(usize),
+
+    /// Cranelift failed to compile the function
+    /// Codegen(function name, error message)
+    Codegen(String, String),
+}
+
+impl std::fmt::Display for JitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JitError::UnsupportedPlatform => write!(f, "JIT is only supported on x86-64 Linux"),
+            JitError::UnsupportedHost(error) => write!(f, "Unable to generate native code for this machine: {}", error),
+            JitError::InvalidChunk(error) => write!(f, "Unable to JIT compile invalid chunk: {}", error),
+            JitError::UnknownFunction(start) => write!(f, "No function starts at byte {}", start),
+            JitError::Codegen(name, error) => write!(f, "Unable to compile function '{}': {}", name, error),
+        }
+    }
+}
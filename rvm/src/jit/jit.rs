@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::default_libcall_names;
+
+use super::codegen::{compile_function, CompiledFunction, RETURNED};
+use super::runtime::{self, JitContext, NativeCall, NESTED_EXIT};
+use super::JitError;
+
+use crate::bytecode;
+use crate::chunk::Chunk;
+use crate::cli::JitFlags;
+use crate::value::Value;
+
+/// How a call to a JIT compiled function ended
+pub enum JitExit {
+    /// Function returned, leaving only its return value above its stack base
+    Returned,
+
+    /// Compiled code exited back to the interpreter, which should resume from the byte offset.
+    /// If the exit happened in a function called natively by compiled code, the calls that were still running are
+    /// given from the outermost call, which the interpreter pushes onto its call stack before resuming in the innermost callee.
+    Interpret(usize, Vec<NativeCall>),
+}
+
+/// Baseline JIT compiler, compiling hot functions of a single chunk into native code
+pub struct Jit {
+    module: JITModule,
+    flags: JitFlags,
+
+    /// Stack size on entering every reachable instruction of the chunk, from the verifier
+    stack_sizes: HashMap<usize, usize>,
+
+    /// Number of calls of every function that is not compiled yet, keyed by the byte offset of the function body
+    call_counts: HashMap<usize, u32>,
+
+    /// Every function that the JIT tried to compile, where None means that the function could not be compiled
+    functions: HashMap<usize, Option<CompiledFunction>>,
+}
+
+impl Jit {
+    pub fn new(chunk: &Chunk, flags: JitFlags) -> Result<Jit, JitError> {
+        if !cfg!(all(target_arch = "x86_64", target_os = "linux")) {
+            return Err(JitError::UnsupportedPlatform);
+        }
+
+        let stack_sizes = bytecode::stack_sizes(chunk).map_err(|error| JitError::InvalidChunk(error.to_string()))?;
+
+        let mut flag_builder = settings::builder();
+        flag_builder.set("opt_level", "speed").unwrap();
+        let isa = cranelift_native::builder()
+            .map_err(|error| JitError::UnsupportedHost(error.to_string()))?
+            .finish(settings::Flags::new(flag_builder))
+            .map_err(|error| JitError::UnsupportedHost(error.to_string()))?;
+
+        Ok(Jit {
+            module: JITModule::new(JITBuilder::with_isa(isa, default_libcall_names())),
+            flags,
+            stack_sizes,
+            call_counts: HashMap::new(),
+            functions: HashMap::new(),
+        })
+    }
+
+    /// Call the function if it is compiled, with its arguments already on the stack starting at 'frame_start',
+    /// compiling it first if this call makes the function hot.
+    ///
+    /// Compiled code reads and writes the values of the call frame relative to the frame's start, just like the interpreter offsets
+    /// the stack indices of locals, so functions run as native code no matter where they are called from, including recursive calls.
+    ///
+    /// Returns None if the function should be interpreted instead, which is the case for functions that could not be compiled.
+    pub fn call(
+        &mut self,
+        chunk: &Chunk,
+        stack: &mut Vec<Value>,
+        globals: &[Option<Value>],
+        function_start: usize,
+        frame_start: usize,
+    ) -> Option<JitExit> {
+        if !self.functions.contains_key(&function_start) {
+            let call_count = self.call_counts.entry(function_start).or_insert(0);
+            *call_count += 1;
+            if *call_count < self.flags.jit_threshold {
+                return None;
+            }
+
+            self.call_counts.remove(&function_start);
+            let compiled_function = self.compile(chunk, function_start);
+            self.functions.insert(function_start, compiled_function);
+        }
+
+        let function = self.functions[&function_start].as_ref()?;
+
+        // Call frame must only hold the arguments, which the VM checks against the function's arity before calling it
+        if stack.len() - frame_start != function.arity {
+            return None;
+        }
+
+        let mut context = JitContext {
+            stack,
+            globals,
+            functions: &self.functions,
+            depth: 0,
+            exited_calls: Vec::new(),
+            resume_ip: 0,
+        };
+
+        // Safety: The arguments are on the stack, and the compiled code only stores valid values into call frames
+        // reserved by the runtime before setting their new lengths
+        let result = unsafe { runtime::run(&mut context, function, frame_start) };
+
+        if result == RETURNED {
+            return Some(JitExit::Returned);
+        }
+
+        let resume_ip = match result {
+            NESTED_EXIT => context.resume_ip,
+            _ => result,
+        };
+        let mut exited_calls = context.exited_calls;
+        exited_calls.reverse();
+
+        if self.flags.trace_jit {
+            println!("JIT: Exit to interpreter at byte {}", resume_ip);
+        }
+
+        Some(JitExit::Interpret(resume_ip, exited_calls))
+    }
+
+    fn compile(&mut self, chunk: &Chunk, function_start: usize) -> Option<CompiledFunction> {
+        let function = chunk.functions.iter().find(|function| function.start == function_start);
+        let result = match function {
+            Some(function) => compile_function(&mut self.module, chunk, function, &self.stack_sizes),
+            None => Err(JitError::UnknownFunction(function_start)),
+        };
+
+        match result {
+            Ok(compiled_function) => {
+                if self.flags.trace_jit {
+                    println!("JIT: Compiled function '{}'", function.unwrap().name);
+                }
+                Some(compiled_function)
+            }
+            Err(error) => {
+                if self.flags.trace_jit {
+                    println!("JIT: {}", error);
+                }
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+mod codegen;
+mod error;
+mod jit;
+mod runtime;
+
+pub use self::error::JitError;
+pub use self::jit::{Jit, JitExit};
+pub use self::runtime::NativeCall;
+
+/*
+    Baseline JIT compiler for the stack VM, enabled with '--jit'.
+
+    The VM counts the calls of every function, and once a function has been called '--jit-threshold' times,
+    its body is compiled into native code with Cranelift, which is then used for every later call of the function.
+
+    Every instruction is compiled into code that works on the NaN boxed values as plain 64 bit integers,
+    where the values the function body pushes are kept in Cranelift variables instead of on the VM's stack.
+    Globals are read and compiled functions are called through the runtime functions, so hot recursive functions
+    stay in native code. Instructions that cannot be compiled, and operands with types that would fail at runtime,
+    exit back to the interpreter: the values pushed are stored onto the VM's stack, and the interpreter resumes
+    from the instruction that exited, so runtime errors are always raised by the interpreter with the same error,
+    location and backtrace. See the runtime module for how exits from functions called by compiled code work.
+
+    Calling convention of compiled functions:
+        fn(context: *mut JitContext, frame_start: usize, frame_length: *mut usize) -> usize
+
+    context         VM's state used by the runtime functions, which the compiled code passes on to every runtime call
+    frame_start     Stack index of the call frame, starting at the first argument, where the stack is reserved to fit
+                    every value the function can store into the frame. Stack slots are offset by the function's stack base,
+                    so the same code runs for calls at any stack depth, like the interpreter's stack offset.
+    frame_length    Compiled code writes the new number of values in the call frame here before it returns
+    return value    Byte offset of the instruction to resume interpreting from, usize::MAX if the function returned,
+                    or usize::MAX - 1 if a function it called exited to the interpreter
+*/
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use super::codegen::{CompiledFunction, RETURNED};
+
+use crate::value::{Value, QNAN};
+
+/*
+    Runtime functions called by JIT compiled code to access the VM's state, which all take the JitContext of the call.
+
+    Compiled code has no side effects other than writing into the call frames on the VM's stack, as every instruction
+    with side effects exits to the interpreter. So when a function called by compiled code exits to the interpreter,
+    every compiled function in the chain of native calls stops right at its call instruction, and the VM resumes
+    interpreting the innermost function with a call frame pushed for every native call that was still running.
+*/
+
+/// Results of 'call', telling the compiled caller how to continue
+pub const CALL_RETURNED: usize = 0;
+/// Callee cannot be called from compiled code, so the caller exits to the interpreter to make the call
+pub const CALL_NOT_MADE: usize = 1;
+/// Callee exited to the interpreter, so the caller must stop at the call too, see NESTED_EXIT
+pub const CALL_EXITED: usize = 2;
+
+/// Returned by compiled code instead of a byte offset to resume from, when a function it called exited to the interpreter
+pub const NESTED_EXIT: usize = usize::MAX - 1;
+
+/// Bits returned by 'get_global' for globals that are not defined, which are never the bits of a value,
+/// as quiet NaNs without a tag are canonicalized into NAN_BITS
+pub const UNDEFINED_BITS: u64 = QNAN;
+
+/// Maximum number of compiled functions calling each other natively, as every native call uses the native stack.
+/// Deeper calls exit to the interpreter, which calls into compiled code again with a new native stack.
+const MAX_NATIVE_DEPTH: usize = 1000;
+
+/// Call made by compiled code that was still running when its callee exited to the interpreter,
+/// which the VM pushes onto its call stack to resume interpreting the callee
+pub struct NativeCall {
+    /// Byte offset after the call instruction of the caller
+    pub return_ip: usize,
+    pub function: Value,
+    /// Offset added to the stack index operands of locals in the called function's body, just like the interpreter's
+    pub stack_offset: isize,
+}
+
+/// State of the VM that compiled code reads and writes through the runtime functions
+pub struct JitContext<'a> {
+    pub stack: &'a mut Vec<Value>,
+    pub globals: &'a [Option<Value>],
+    pub functions: &'a HashMap<usize, Option<CompiledFunction>>,
+
+    /// Number of native calls made by compiled code that have not returned yet
+    pub depth: usize,
+
+    /// Calls that were running when the innermost call exited to the interpreter, starting from the innermost call
+    pub exited_calls: Vec<NativeCall>,
+
+    /// Byte offset that the innermost call that exited to the interpreter resumes from
+    pub resume_ip: usize,
+}
+
+/// Run the compiled function with its arguments already stored in the call frame starting at 'frame_start',
+/// returning what the compiled code returned.
+///
+/// # Safety
+/// The context must be valid, and its stack must have the function's arguments stored at 'frame_start',
+/// which may be past the stack's length if they are stored by compiled code.
+pub unsafe fn run(context: *mut JitContext, function: &CompiledFunction, frame_start: usize) -> usize {
+    // Reserve every value the compiled code can store into the call frame
+    (*context).stack.set_len(frame_start + function.arity);
+    (*context).stack.reserve(function.max_frame_size - function.arity);
+
+    let mut frame_length = function.arity;
+    let result = (function.code)(context, frame_start, &mut frame_length);
+
+    // Stack of a nested exit ends with the call frame of the innermost call, whose length is already set
+    if result != NESTED_EXIT {
+        (*context).stack.set_len(frame_start + frame_length);
+    }
+    result
+}
+
+/// Get a pointer to the call frame starting at the stack index, which changes whenever the stack grows
+pub unsafe extern "C" fn frame(context: *mut JitContext, frame_start: usize) -> *mut u64 {
+    (*context).stack.as_mut_ptr().add(frame_start) as *mut u64
+}
+
+/// Get the bits of the global's value, or UNDEFINED_BITS if the global is not defined yet
+pub unsafe extern "C" fn get_global(context: *mut JitContext, slot: usize) -> u64 {
+    let globals = (*context).globals;
+    match globals.get(slot) {
+        Some(Some(value)) => value.to_bits(),
+        _ => UNDEFINED_BITS,
+    }
+}
+
+/// Call the function value with the arguments stored in the call frame starting at 'frame_start', if the function
+/// is compiled and the call would not fail, returning one of the CALL_* results
+pub unsafe extern "C" fn call(
+    context: *mut JitContext,
+    function: u64,
+    frame_start: usize,
+    number_of_arguments: usize,
+    return_ip: usize,
+) -> usize {
+    let function = Value::from_bits(function);
+    let functions = (*context).functions;
+    let compiled_function = match functions.get(&function.as_fn()) {
+        Some(Some(compiled_function))
+            if function.is_fn()
+                && compiled_function.arity == number_of_arguments
+                && (*context).depth < MAX_NATIVE_DEPTH =>
+        {
+            compiled_function
+        }
+        _ => return CALL_NOT_MADE,
+    };
+
+    (*context).depth += 1;
+    let result = run(context, compiled_function, frame_start);
+    (*context).depth -= 1;
+
+    if result == RETURNED {
+        return CALL_RETURNED;
+    }
+    if result != NESTED_EXIT {
+        (*context).resume_ip = result;
+    }
+    (*context).exited_calls.push(NativeCall {
+        return_ip,
+        function,
+        stack_offset: frame_start as isize - compiled_function.stack_base as isize,
+    });
+    CALL_EXITED
+}
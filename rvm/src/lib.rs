@@ -0,0 +1,21 @@
+// rvm is both a library and the 'rvm' binary (see main.rs), so that other front ends like ri
+// can generate chunks directly and run them on the VM without going through rvm's own compiler.
+pub mod bytecode;
+pub mod chunk;
+pub mod cli;
+pub mod compiler;
+pub mod debug;
+pub mod error;
+pub mod heap;
+pub mod jit;
+pub mod keywords;
+pub mod line_table;
+pub mod opcode;
+pub mod optimizer;
+pub mod register;
+pub mod scanner;
+pub mod token;
+pub mod value;
+pub mod vm;
+
+pub use error::SSError;
@@ -0,0 +1,78 @@
+/// Location in the source code that an instruction was compiled from
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A single run in the line table, where every byte in the run shares the same source location
+/// u32 is used instead of usize to keep each run as small as possible
+#[derive(Debug, Clone, Copy)]
+pub struct LineRun {
+    /// Byte offset of the first byte in this run
+    pub start: u32,
+    /// Number of bytes in this run
+    pub length: u32,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Run length encoded line table mapping every byte in the chunk back to its source location
+/// https://en.wikipedia.org/wiki/Run-length_encoding
+///
+/// Every opcode and its operand bytes share the same source location, and so do most instructions compiled from the same token,
+/// so instead of storing 1 location per byte, consecutive bytes with the same location are stored as a single run.
+#[derive(Debug, Clone, Default)]
+pub struct LineTable {
+    pub runs: Vec<LineRun>,
+}
+
+impl LineTable {
+    pub fn new() -> LineTable {
+        LineTable { runs: Vec::new() }
+    }
+
+    /// Record the source location of the next byte written into the chunk
+    pub fn push(&mut self, location: SourceLocation) {
+        let line = location.line as u32;
+        let column = location.column as u32;
+
+        // Extend the last run if this byte has the same location, else start a new run right after the last run
+        match self.runs.last_mut() {
+            Some(run) if run.line == line && run.column == column => run.length += 1,
+
+            last_run => {
+                let start = last_run.map_or(0, |run| run.start + run.length);
+                self.runs.push(LineRun {
+                    start,
+                    length: 1,
+                    line,
+                    column,
+                });
+            }
+        }
+    }
+
+    /// Get the source location of the byte at the given offset, or the default location at line 0 if the offset is not
+    /// in the table, like for an empty chunk, so that a location can always be reported for a byte
+    /// Binary search is used since the runs are sorted by their start offset
+    pub fn location_for(&self, offset: usize) -> SourceLocation {
+        let offset = offset as u32;
+
+        // Index of the first run that starts after offset, so the run containing offset is the one before it
+        let run_index = self.runs.partition_point(|run| run.start <= offset);
+        match run_index.checked_sub(1).map(|index| &self.runs[index]) {
+            Some(run) if offset < run.start + run.length => SourceLocation {
+                line: run.line as usize,
+                column: run.column as usize,
+            },
+            _ => SourceLocation::default(),
+        }
+    }
+
+    /// Get the source line number of the byte at the given offset
+    #[inline]
+    pub fn line_for(&self, offset: usize) -> usize {
+        self.location_for(offset).line
+    }
+}
@@ -1,48 +1,184 @@
-use std::env;
 use std::fs;
-
-mod chunk;
-mod compiler;
-mod debug;
-mod error;
-mod keywords;
-mod opcode;
-mod scanner;
-mod token;
-mod value;
-mod vm;
-
-use chunk::Chunk;
-use compiler::Compiler;
-use debug::disassemble_chunk;
-use error::SSError;
-use opcode::OpCode;
-use value::Value;
-use vm::VM;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+
+use clap::Parser;
+
+use rvm::chunk::Chunk;
+use rvm::cli::{Cli, Command, DebugFlags, Engine, JitFlags};
+use rvm::compiler::Compiler;
+use rvm::debug::{disassemble_chunk, disassemble_instruction};
+use rvm::error::SSError;
+use rvm::line_table::SourceLocation;
+use rvm::opcode::OpCode;
+use rvm::register::{self, RegisterVM};
+use rvm::value::Value;
+use rvm::vm::VM;
+use rvm::{bytecode, heap, optimizer};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    // Use Clap lib to parse out CLI arguments
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Run { file_path } => run_file(
+            &file_path,
+            cli.optimization_level,
+            cli.engine,
+            cli.debug_flags,
+            cli.jit_flags,
+        ),
+
+        Command::Compile { file_path, output } => {
+            // Default to writing the bytecode file beside the source file with the .ssc extension
+            let output = output.unwrap_or_else(|| {
+                Path::new(&file_path)
+                    .with_extension("ssc")
+                    .to_string_lossy()
+                    .to_string()
+            });
+            compile_file(&file_path, &output, cli.optimization_level)
+        }
+
+        Command::Disasm { file_path } => disassemble_file(&file_path, cli.optimization_level, cli.engine),
+
+        Command::Repl => repl(cli.debug_flags),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// Compile and optimize a source file and write the chunk out as a bytecode file
+fn compile_file(filename: &str, output: &str, optimization_level: u8) -> Result<(), SSError> {
+    let source = fs::read_to_string(filename).map_err(|err| SSError::FileError(filename.to_string(), err))?;
+
+    let chunk = optimizer::optimize(Compiler::compile(source, Chunk::new())?, optimization_level);
+
+    fs::write(output, bytecode::serialize(&chunk)).map_err(|err| SSError::FileError(output.to_string(), err))?;
+    println!("Compiled '{}' to '{}'", filename, output);
+
+    Ok(())
+}
+
+/// Load a chunk from either a source file or a precompiled bytecode file, where bytecode files are detected using their magic bytes.
+/// Only chunks compiled from source files are optimized, as bytecode files are optimized when they are compiled.
+fn load_file(filename: &str, optimization_level: u8) -> Result<Chunk, SSError> {
+    let file = fs::read(filename).map_err(|err| SSError::FileError(filename.to_string(), err))?;
+
+    if file.starts_with(&bytecode::MAGIC) {
+        // Bytecode files are verified before execution, since they might not have been generated by this compiler
+        let chunk = bytecode::deserialize(&file)?;
+        bytecode::verify(&chunk)?;
+        Ok(chunk)
+    } else {
+        match String::from_utf8(file) {
+            Ok(source) => Ok(optimizer::optimize(
+                Compiler::compile(source, Chunk::new())?,
+                optimization_level,
+            )),
+            // Binary files that are not source files can only be bytecode files, e.g. with corrupted magic bytes
+            Err(_) => Err(bytecode::BytecodeError::InvalidMagic.into()),
+        }
+    }
+}
 
-    let filename = &args[1];
+/// Run either a source file or a precompiled bytecode file with the selected execution engine
+fn run_file(
+    filename: &str,
+    optimization_level: u8,
+    engine: Engine,
+    debug_flags: DebugFlags,
+    jit_flags: JitFlags,
+) -> Result<(), SSError> {
     println!("Entering file '{}'", filename);
 
-    let source = match fs::read_to_string(filename) {
-        Ok(source) => source,
-        Err(err) => panic!("Unable to read file: {}", err), // Bubble error up instead of panic!
+    let chunk = load_file(filename, optimization_level)?;
+    let result = match engine {
+        Engine::Stack => VM::interpret(chunk, debug_flags, jit_flags).map(|_| ()),
+        Engine::Register => RegisterVM::interpret(register::translate(chunk)?, debug_flags),
     };
 
-    if let Err(e) = interpret(source) {
-        println!("{:?}", e)
+    if debug_flags.heap_stats {
+        println!("{}", heap::stats());
+    }
+
+    result?;
+    Ok(())
+}
+
+/// Print the disassembled chunk of either a source file or a precompiled bytecode file, as instructions of the selected execution engine
+fn disassemble_file(filename: &str, optimization_level: u8, engine: Engine) -> Result<(), SSError> {
+    let chunk = load_file(filename, optimization_level)?;
+    match engine {
+        Engine::Stack => disassemble_chunk(&chunk, filename),
+        Engine::Register => register::disassemble_register_chunk(&register::translate(chunk)?, filename),
+    }
+
+    Ok(())
+}
+
+/// Read Eval Print Loop, where every line read is compiled into the same chunk and ran on the same VM,
+/// so that globals and functions defined in earlier lines can be used in later lines.
+fn repl(debug_flags: DebugFlags) -> Result<(), SSError> {
+    let stdin = io::stdin();
+
+    let mut compiler = Compiler::new(Chunk::new());
+    let mut vm = VM::new();
+
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("Unable to flush stdout");
+
+        let mut line = String::new();
+        match stdin.read_line(&mut line) {
+            // Exit the REPL on EOF, e.g. Ctrl+D
+            Ok(0) => {
+                println!();
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(err) => panic!("Unable to read line: {}", err), // Bubble error up instead of panic!
+        }
+
+        // Errors are printed instead of bubbled up, so that the REPL can continue with the next line
+        if let Err(e) = interpret_line(&mut compiler, &mut vm, line, debug_flags) {
+            eprintln!("{}", e);
+        }
     }
 }
 
-fn interpret(source: String) -> Result<(), SSError> {
-    let chunk = Compiler::compile(source, Chunk::new())?;
+/// Compile a line of REPL input into the chunk and run only the newly appended opcodes, echoing the value of the line if any
+fn interpret_line(
+    compiler: &mut Compiler,
+    vm: &mut VM,
+    line: String,
+    debug_flags: DebugFlags,
+) -> Result<(), SSError> {
+    let start = compiler.chunk.codes.len();
+    let echo_value = compiler.compile_line(line)?;
+
+    if debug_flags.dump_chunk {
+        // Only disassemble the opcodes of this line, since earlier lines have already been dumped
+        let mut offset = start;
+        while offset < compiler.chunk.codes.len() {
+            offset = disassemble_instruction(&compiler.chunk, offset);
+        }
+    }
 
-    // disassemble_chunk(&chunk, "test");
+    let result = vm.run(&compiler.chunk, debug_flags);
+    if let Ok(Some(value)) = result.as_ref().map(|value| value.filter(|_| echo_value)) {
+        println!("{}", compiler.chunk.format_value(value));
+    }
 
-    VM::interpret(chunk)?;
+    if debug_flags.heap_stats {
+        println!("{}", heap::stats());
+    }
 
+    result?;
     Ok(())
 }
 
@@ -50,22 +186,27 @@ fn interpret(source: String) -> Result<(), SSError> {
 fn test_vm_with_chunk() {
     let mut chunk = Chunk::new();
 
-    chunk.write(OpCode::CONSTANT(Value::Number(1.2)), 2);
-    chunk.write(OpCode::NEGATE, 2);
+    let constant_index = chunk.add_constant(Value::number(1.2));
+    chunk.write_code(OpCode::CONSTANT, SourceLocation { line: 2, column: 1 });
+    chunk.write(constant_index as u8, SourceLocation { line: 2, column: 1 });
+    chunk.write_code(OpCode::NEGATE, SourceLocation { line: 2, column: 1 });
 
-    // chunk.write(OpCode::CONSTANT(Value::Null), 3);
-    // chunk.write(OpCode::CONSTANT(Value::Number(4.2)), 3);
-    // chunk.write(OpCode::ADD, 3);
+    // let constant_index = chunk.add_constant(Value::NULL);
+    // chunk.write_code(OpCode::CONSTANT, SourceLocation { line: 3, column: 1 });
+    // chunk.write(constant_index as u8, SourceLocation { line: 3, column: 1 });
+    // chunk.write_code(OpCode::ADD, SourceLocation { line: 3, column: 1 });
 
-    chunk.write(OpCode::CONSTANT(Value::Number(1.8)), 3);
-    chunk.write(OpCode::SUBTRACT, 3);
+    let constant_index = chunk.add_constant(Value::number(1.8));
+    chunk.write_code(OpCode::CONSTANT, SourceLocation { line: 3, column: 1 });
+    chunk.write(constant_index as u8, SourceLocation { line: 3, column: 1 });
+    chunk.write_code(OpCode::SUBTRACT, SourceLocation { line: 3, column: 1 });
 
-    chunk.write(OpCode::RETURN, 3);
+    chunk.write_code(OpCode::PRINT, SourceLocation { line: 3, column: 1 });
 
     disassemble_chunk(&chunk, "test");
     // println!("{:?}", chunk);
 
-    if let Err(e) = VM::interpret(chunk) {
+    if let Err(e) = VM::interpret(chunk, DebugFlags::default(), JitFlags::default()) {
         println!("{:?}", e)
     }
 }
@@ -1,52 +1,73 @@
-use crate::value::Value;
-
-// OpCodes variants can optionally contain additional values like 'CONSTANT(Value)' to be executed together with the code
-// Clox differentiate OpCode from Data using OP_ prefix, and also all their data types is just a single byte anyways
+// OpCodes are encoded as a single byte in the chunk's code vector, just like Clox's OP_ prefixed opcodes.
+// Opcodes that need additional data like 'CONSTANT' are followed by their operand bytes directly in the code vector,
+// where the operand is either an index into the chunk's constant pool or a raw number like a stack index or jump offset.
+// Operands that are 2 bytes wide are stored in big endian order, i.e. the high byte comes first.
+//
+// The operand encoding of each opcode is documented as 'OPCODE <operand> (<number of operand bytes>)'
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OpCode {
     /// POP a single value off the stack
     POP,
-    /// POP 'usize' number of values off the stack
-    POP_N(usize),
+    /// POP_N <number of values> (1)
+    ///
+    /// POP N number of values off the stack
+    POP_N,
     /// Return from current function body. A.k.a go back once in the call stack
     RETURN,
+    /// RETURN_POP <number of locals> (1)
+    ///
     /// Return from current function body. A.k.a go back once in the call stack, and POP locals off the stack
-    RETURN_POP(usize),
+    RETURN_POP,
 
     /* Opcodes dealing with values/identifiers/variables */
-    /// Load a Value onto the stack
-    CONSTANT(Value),
-    /// Take and store last value on stack as a value with the given string identifier
-    IDENTIFIER(String),
-    /// Get value using given string identifier from 'global scope storage' (hashmap) and push it onto stack
-    IDENTIFIER_LOOKUP(String),
-    /// GET a local scope value, by cloning the stack value at index 'usize' and pushing it onto stack
-    GET_LOCAL(usize),
-    /// Update an identifier in local scope, by setting the stack value at index 'usize' to the last value on stack
-    SET_LOCAL(usize),
+    /// CONSTANT <constant index> (1)
+    ///
+    /// Load a Value from the constant pool onto the stack
+    CONSTANT,
+    /// CONSTANT_LONG <constant index> (2)
+    ///
+    /// Same as CONSTANT, but used once the constant pool grows past what a single byte index can address
+    CONSTANT_LONG,
+    /// DEFINE_GLOBAL <global slot> (2)
+    ///
+    /// Take and store last value on stack into the given slot of the 'global scope storage'
+    DEFINE_GLOBAL,
+    /// GET_GLOBAL <global slot> (2)
+    ///
+    /// Get value from the given slot of the 'global scope storage' and push it onto stack
+    GET_GLOBAL,
+    /// GET_LOCAL <stack index> (1)
+    ///
+    /// GET a local scope value, by cloning the stack value at the stack index and pushing it onto stack
+    GET_LOCAL,
+    /// SET_LOCAL <stack index> (1)
+    ///
+    /// Update an identifier in local scope, by setting the stack value at the stack index to the last value on stack
+    SET_LOCAL,
 
     /* JUMP type / control flow opcodes */
-    /// JUMP forward by 'usize' number of opcodes. See OpCode::LOOP(usize) for jumping backwards
-    JUMP(usize),
-    JUMP_IF_FALSE(usize),
+    /// JUMP <offset> (2)
+    ///
+    /// JUMP forward by offset number of bytes, counted from the end of this instruction. See OpCode::LOOP for jumping backwards
+    JUMP,
+    /// JUMP_IF_FALSE <offset> (2)
+    JUMP_IF_FALSE,
 
     /* Function call opcodes */
-    /// CALL('number of arguements on stack')
+    /// CALL <number of arguements on stack> (1)
     ///
-    /// Stack: ... --> Value::Function(..) --> arg1 --> arg2 --> argN
+    /// Stack: ... --> Value::Fn(..) --> arg1 --> arg2 --> argN
     ///
     /// Make a function call, where the 'stack.len() - 1 - number_of_args' value on stack is the function 'Value::Fn(..)'
-    CALL(usize),
+    CALL,
 
+    /// LOOP <offset> (2)
+    ///
     /// Special loop opcode, that is basically JUMP, but jumps backwards instead of forward
-    LOOP(usize),
-
-    /* Special opcodes to do type checking of the last value on the stack */
-    /// Check if last value on stack is a Boolean
-    TYPE_CHECK_BOOL,
-    TYPE_CHECK(Value),
+    LOOP,
 
     // Arithmetic Binary operators
     ADD,
@@ -67,4 +88,89 @@ pub enum OpCode {
     LESS_EQUAL,
 
     PRINT,
+
+    /* Superinstructions, generated by the optimizer by fusing common pairs of opcodes to reduce the number of dispatches */
+    /// GET_LOCAL_ADD <stack index> (1)
+    ///
+    /// Same as GET_LOCAL followed by ADD, adding the local to the last value on stack
+    GET_LOCAL_ADD,
+    /// GET_LOCAL_SUBTRACT <stack index> (1)
+    ///
+    /// Same as GET_LOCAL followed by SUBTRACT, subtracting the local from the last value on stack
+    GET_LOCAL_SUBTRACT,
+}
+
+impl OpCode {
+    /// Number of operand bytes that follow this opcode in the code vector
+    pub fn operand_length(self) -> usize {
+        match self {
+            OpCode::POP_N
+            | OpCode::RETURN_POP
+            | OpCode::CONSTANT
+            | OpCode::GET_LOCAL
+            | OpCode::SET_LOCAL
+            | OpCode::CALL
+            | OpCode::GET_LOCAL_ADD
+            | OpCode::GET_LOCAL_SUBTRACT => 1,
+
+            OpCode::CONSTANT_LONG
+            | OpCode::DEFINE_GLOBAL
+            | OpCode::GET_GLOBAL
+            | OpCode::JUMP
+            | OpCode::JUMP_IF_FALSE
+            | OpCode::LOOP => 2,
+
+            _ => 0,
+        }
+    }
+}
+
+// This array is used to decode a byte read from the code vector back into an OpCode enum variant
+// Bytes that do not map to any opcode are None, so that the VM can detect corrupted or invalid code
+// This has no runtime cost as this is a static value evaluted at compile time compared to a match statement from u8 to variant
+pub static U8_TO_OPCODE: [Option<OpCode>; 256] = {
+    let mut opcode_array = [None; 256];
+
+    /*
+        Explicitly insert opcode for each byte 1 by 1 using the opcode itself as index
+        This prevents errors caused with directly instantiating the array in the correct order
+        OpCode enum variants are converted to usize first before using it to index the array
+    */
+    opcode_array[OpCode::POP as usize] = Some(OpCode::POP);
+    opcode_array[OpCode::POP_N as usize] = Some(OpCode::POP_N);
+    opcode_array[OpCode::RETURN as usize] = Some(OpCode::RETURN);
+    opcode_array[OpCode::RETURN_POP as usize] = Some(OpCode::RETURN_POP);
+    opcode_array[OpCode::CONSTANT as usize] = Some(OpCode::CONSTANT);
+    opcode_array[OpCode::CONSTANT_LONG as usize] = Some(OpCode::CONSTANT_LONG);
+    opcode_array[OpCode::DEFINE_GLOBAL as usize] = Some(OpCode::DEFINE_GLOBAL);
+    opcode_array[OpCode::GET_GLOBAL as usize] = Some(OpCode::GET_GLOBAL);
+    opcode_array[OpCode::GET_LOCAL as usize] = Some(OpCode::GET_LOCAL);
+    opcode_array[OpCode::SET_LOCAL as usize] = Some(OpCode::SET_LOCAL);
+    opcode_array[OpCode::JUMP as usize] = Some(OpCode::JUMP);
+    opcode_array[OpCode::JUMP_IF_FALSE as usize] = Some(OpCode::JUMP_IF_FALSE);
+    opcode_array[OpCode::CALL as usize] = Some(OpCode::CALL);
+    opcode_array[OpCode::LOOP as usize] = Some(OpCode::LOOP);
+    opcode_array[OpCode::ADD as usize] = Some(OpCode::ADD);
+    opcode_array[OpCode::SUBTRACT as usize] = Some(OpCode::SUBTRACT);
+    opcode_array[OpCode::MULTIPLY as usize] = Some(OpCode::MULTIPLY);
+    opcode_array[OpCode::DIVIDE as usize] = Some(OpCode::DIVIDE);
+    opcode_array[OpCode::NOT as usize] = Some(OpCode::NOT);
+    opcode_array[OpCode::NEGATE as usize] = Some(OpCode::NEGATE);
+    opcode_array[OpCode::EQUAL as usize] = Some(OpCode::EQUAL);
+    opcode_array[OpCode::NOT_EQUAL as usize] = Some(OpCode::NOT_EQUAL);
+    opcode_array[OpCode::GREATER as usize] = Some(OpCode::GREATER);
+    opcode_array[OpCode::GREATER_EQUAL as usize] = Some(OpCode::GREATER_EQUAL);
+    opcode_array[OpCode::LESS as usize] = Some(OpCode::LESS);
+    opcode_array[OpCode::LESS_EQUAL as usize] = Some(OpCode::LESS_EQUAL);
+    opcode_array[OpCode::PRINT as usize] = Some(OpCode::PRINT);
+    opcode_array[OpCode::GET_LOCAL_ADD as usize] = Some(OpCode::GET_LOCAL_ADD);
+    opcode_array[OpCode::GET_LOCAL_SUBTRACT as usize] = Some(OpCode::GET_LOCAL_SUBTRACT);
+
+    opcode_array
+};
+
+// Inline method to decode a byte from the code vector into an OpCode, returns None if the byte is not a valid opcode
+#[inline]
+pub fn decode(byte: u8) -> Option<OpCode> {
+    U8_TO_OPCODE[byte as usize]
 }
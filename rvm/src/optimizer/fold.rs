@@ -0,0 +1,122 @@
+use super::instruction::Instruction;
+use super::{find_jump_targets, live_instructions};
+
+use crate::opcode::OpCode;
+use crate::value::{UnpackedValue, Value};
+
+/// Fold unary and binary operations on constants into a single constant, e.g. 'CONSTANT 1; CONSTANT 2; ADD' into 'CONSTANT 3'.
+/// Operations are only folded if they behave exactly the same way as the VM, so operations that would fail at runtime are left as is.
+pub fn fold_constants(instructions: &mut [Instruction], constants: &mut Vec<Value>, function_starts: &[usize]) -> bool {
+    let jump_targets = find_jump_targets(instructions, function_starts);
+    let live = live_instructions(instructions);
+    let mut changed = false;
+
+    let mut i = 0;
+    while i < live.len() {
+        let first = instructions[live[i]];
+        if first.opcode != OpCode::CONSTANT {
+            i += 1;
+            continue;
+        }
+
+        // CONSTANT; unary operator
+        if let Some(&operator_index) = live.get(i + 1) {
+            let operator = instructions[operator_index];
+            let folded_value = fold_unary(operator.opcode, &constants[first.operand]);
+
+            if let (Some(value), false) = (folded_value, jump_targets[operator_index]) {
+                instructions[live[i]] = Instruction {
+                    operand: add_constant(constants, value),
+                    location: operator.location,
+                    ..first
+                };
+                instructions[operator_index].removed = true;
+
+                changed = true;
+                i += 2;
+                continue;
+            }
+        }
+
+        // CONSTANT; CONSTANT; binary operator
+        if let (Some(&second_index), Some(&operator_index)) = (live.get(i + 1), live.get(i + 2)) {
+            let second = instructions[second_index];
+            let operator = instructions[operator_index];
+
+            let folded_value = match second.opcode {
+                OpCode::CONSTANT => fold_binary(operator.opcode, &constants[first.operand], &constants[second.operand]),
+                _ => None,
+            };
+
+            if let (Some(value), false, false) = (folded_value, jump_targets[second_index], jump_targets[operator_index]) {
+                instructions[live[i]] = Instruction {
+                    operand: add_constant(constants, value),
+                    location: operator.location,
+                    ..first
+                };
+                instructions[second_index].removed = true;
+                instructions[operator_index].removed = true;
+
+                changed = true;
+                i += 3;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    changed
+}
+
+/// Add folded value to the constant pool, reusing an existing constant with the same bits if there is one.
+/// Values are not compared with PartialEq, which compares numbers as floats, where -0 would reuse a 0 that prints
+/// differently, and NaN would never be reused as it is not equal to itself.
+fn add_constant(constants: &mut Vec<Value>, value: Value) -> usize {
+    match constants.iter().position(|constant| constant.to_bits() == value.to_bits()) {
+        Some(constant_index) => constant_index,
+        None => {
+            constants.push(value);
+            constants.len() - 1
+        }
+    }
+}
+
+/// Evaluate a unary operator on a constant, returns None if it is not a unary operator or if it would fail at runtime
+fn fold_unary(opcode: OpCode, value: &Value) -> Option<Value> {
+    match (opcode, value.unpack()) {
+        (OpCode::NEGATE, UnpackedValue::Number(number)) => Some(Value::number(-number)),
+        (OpCode::NOT, UnpackedValue::Bool(bool)) => Some(Value::bool(!bool)),
+        _ => None,
+    }
+}
+
+/// Evaluate a binary operator on 2 constants, returns None if it is not a binary operator or if it would fail at runtime
+fn fold_binary(opcode: OpCode, a: &Value, b: &Value) -> Option<Value> {
+    // Equality operators work on all values, using Value's PartialEq trait just like the VM
+    match opcode {
+        OpCode::EQUAL => return Some(Value::bool(a == b)),
+        OpCode::NOT_EQUAL => return Some(Value::bool(a != b)),
+        _ => {}
+    }
+
+    // Arithmetic and comparison operators only work on numbers
+    let (a, b) = match (a.unpack(), b.unpack()) {
+        (UnpackedValue::Number(a), UnpackedValue::Number(b)) => (a, b),
+        _ => return None,
+    };
+
+    match opcode {
+        OpCode::ADD => Some(Value::number(a + b)),
+        OpCode::SUBTRACT => Some(Value::number(a - b)),
+        OpCode::MULTIPLY => Some(Value::number(a * b)),
+        OpCode::DIVIDE => Some(Value::number(a / b)),
+
+        OpCode::GREATER => Some(Value::bool(a > b)),
+        OpCode::GREATER_EQUAL => Some(Value::bool(a >= b)),
+        OpCode::LESS => Some(Value::bool(a < b)),
+        OpCode::LESS_EQUAL => Some(Value::bool(a <= b)),
+
+        _ => None,
+    }
+}
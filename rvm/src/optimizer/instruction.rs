@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use crate::chunk::{Chunk, Function};
+use crate::line_table::{LineTable, SourceLocation};
+use crate::opcode::{decode, OpCode};
+use crate::value::{UnpackedValue, Value};
+
+/// Instruction decoded from the chunk's code, with its operand decoded into a plain number,
+/// so that the optimizer can change instructions without worrying about their byte encoding and jump offsets.
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    /// CONSTANT_LONG is decoded as CONSTANT and LOOP is decoded as JUMP,
+    /// as the long or backward form of the opcode is picked again when encoding
+    pub opcode: OpCode,
+
+    /// Operand of the opcode if it has any, where the operand of jump type opcodes is the index of the instruction jumped to
+    pub operand: usize,
+
+    pub location: SourceLocation,
+
+    /// Removed instructions are kept in place until encoding, so that instruction indices used as jump targets stay valid.
+    /// Jumping to a removed instruction continues execution at the next instruction that is not removed.
+    pub removed: bool,
+}
+
+/// Decode the chunk's code into instructions, together with a map of the byte offset of every instruction to its index.
+/// The byte offset of the end of the code is mapped to the number of instructions, since jumping to the end of the code is valid.
+pub fn decode_instructions(chunk: &Chunk) -> (Vec<Instruction>, HashMap<usize, usize>) {
+    let mut instruction_indices = HashMap::<usize, usize>::new();
+
+    // Jump operands are decoded as byte offsets first, and converted to instruction indices once all instructions are decoded
+    let mut instructions = Vec::<Instruction>::new();
+    let mut offset = 0;
+    while offset < chunk.codes.len() {
+        instruction_indices.insert(offset, instructions.len());
+
+        // Chunks are either generated by the compiler or verified, so they can always be decoded
+        let opcode = decode(chunk.codes[offset]).unwrap();
+        let next_instruction = offset + 1 + opcode.operand_length();
+
+        let (opcode, operand) = match opcode {
+            OpCode::CONSTANT_LONG => (OpCode::CONSTANT, chunk.read_u16(offset + 1) as usize),
+            OpCode::JUMP | OpCode::JUMP_IF_FALSE => (opcode, next_instruction + chunk.read_u16(offset + 1) as usize),
+            OpCode::LOOP => (OpCode::JUMP, next_instruction - chunk.read_u16(offset + 1) as usize),
+            _ => match opcode.operand_length() {
+                1 => (opcode, chunk.codes[offset + 1] as usize),
+                2 => (opcode, chunk.read_u16(offset + 1) as usize),
+                _ => (opcode, 0),
+            },
+        };
+
+        instructions.push(Instruction {
+            opcode,
+            operand,
+            location: chunk.lines.location_for(offset),
+            removed: false,
+        });
+
+        offset = next_instruction;
+    }
+    instruction_indices.insert(offset, instructions.len());
+
+    for instruction in instructions.iter_mut() {
+        if instruction.opcode == OpCode::JUMP || instruction.opcode == OpCode::JUMP_IF_FALSE {
+            instruction.operand = instruction_indices[&instruction.operand];
+        }
+    }
+
+    (instructions, instruction_indices)
+}
+
+/// Encode the instructions that are not removed into a new chunk, recalculating all the jump offsets, source locations,
+/// and byte offsets of functions, which are mapped from their original byte offsets using 'instruction_indices'.
+///
+/// Returns None if a jump can no longer be encoded, e.g. if a jump became too long because of CONSTANT_LONG opcodes.
+pub fn encode_instructions(
+    chunk: &Chunk,
+    instructions: &[Instruction],
+    instruction_indices: &HashMap<usize, usize>,
+) -> Option<Chunk> {
+    // Byte offset of every instruction in the new chunk, where removed instructions take the byte offset of the next instruction
+    let mut offsets = Vec::<usize>::with_capacity(instructions.len() + 1);
+    let mut offset = 0;
+    for instruction in instructions {
+        offsets.push(offset);
+        if !instruction.removed {
+            offset += encoded_length(instruction);
+        }
+    }
+    offsets.push(offset);
+
+    let mut codes = Vec::<u8>::with_capacity(offset);
+    let mut lines = LineTable::new();
+    let mut write = |byte: u8, location: SourceLocation| {
+        codes.push(byte);
+        lines.push(location);
+    };
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        if instruction.removed {
+            continue;
+        }
+
+        let location = instruction.location;
+        let next_instruction = offsets[index] + encoded_length(instruction);
+
+        let (opcode, operand) = match instruction.opcode {
+            OpCode::CONSTANT if instruction.operand > u8::MAX as usize => (OpCode::CONSTANT_LONG, instruction.operand),
+
+            // Jumps are encoded as JUMP or LOOP depending on the direction, as their targets might have been changed
+            OpCode::JUMP | OpCode::JUMP_IF_FALSE => {
+                let target = offsets[instruction.operand];
+                if target >= next_instruction {
+                    (instruction.opcode, target - next_instruction)
+                } else if instruction.opcode == OpCode::JUMP {
+                    (OpCode::LOOP, next_instruction - target)
+                } else {
+                    // There is no opcode for a conditional jump backwards
+                    return None;
+                }
+            }
+
+            opcode => (opcode, instruction.operand),
+        };
+
+        write(opcode as u8, location);
+        match opcode.operand_length() {
+            1 => write(operand as u8, location),
+            2 => {
+                if operand > u16::MAX as usize {
+                    return None;
+                }
+                write((operand >> 8) as u8, location);
+                write(operand as u8, location);
+            }
+            _ => {}
+        }
+    }
+
+    // Function values and the function table hold byte offsets of function bodies, which are moved by the optimizer
+    let new_offset = |start: &usize| offsets[instruction_indices[start]];
+    let constants = chunk
+        .constants
+        .iter()
+        .map(|constant| match constant.unpack() {
+            UnpackedValue::Fn(start) => Value::function(new_offset(&start)),
+            _ => *constant,
+        })
+        .collect();
+    let functions = chunk
+        .functions
+        .iter()
+        .map(|function| Function {
+            start: new_offset(&function.start),
+            ..function.clone()
+        })
+        .collect();
+
+    Some(Chunk {
+        codes,
+        constants,
+        globals: chunk.globals.clone(),
+        lines,
+        functions,
+    })
+}
+
+/// Number of bytes the instruction is encoded in
+fn encoded_length(instruction: &Instruction) -> usize {
+    match instruction.opcode {
+        OpCode::CONSTANT if instruction.operand > u8::MAX as usize => 1 + OpCode::CONSTANT_LONG.operand_length(),
+        opcode => 1 + opcode.operand_length(),
+    }
+}
@@ -0,0 +1,98 @@
+mod fold;
+mod instruction;
+mod peephole;
+
+use instruction::{decode_instructions, encode_instructions, Instruction};
+
+use crate::chunk::Chunk;
+use crate::opcode::OpCode;
+
+/// Highest optimization level supported, where every level includes all the optimizations of the levels below it
+/// - 0: No optimizations
+/// - 1: Constant folding, removal of values pushed only to be popped, jump threading, and removal of unreachable code
+/// - 2: Fuse common pairs of opcodes into superinstructions
+pub const MAX_OPTIMIZATION_LEVEL: u8 = 2;
+
+/// Optimize a chunk generated by the compiler, returning the chunk unchanged if it cannot be optimized.
+///
+/// The chunk is decoded into a vector of instructions where jump targets are instruction indices instead of byte offsets,
+/// so that the passes can change and remove instructions without breaking jumps, before it is encoded back into a chunk.
+/// Passes never change or remove an instruction that is jumped to, unless it is the first instruction of the pattern matched,
+/// since the instructions before it are not always executed before it.
+pub fn optimize(mut chunk: Chunk, level: u8) -> Chunk {
+    if level == 0 {
+        return chunk;
+    }
+
+    let (mut instructions, instruction_indices) = decode_instructions(&chunk);
+
+    // Function bodies are jumped to by CALL instructions, whose targets are only known at runtime
+    let function_starts: Vec<usize> = chunk
+        .functions
+        .iter()
+        .map(|function| instruction_indices[&function.start])
+        .collect();
+
+    // Passes are repeated until nothing changes, as every pass can create new opportunities for the other passes,
+    // e.g. folded constants can be folded again with the constants around them
+    loop {
+        let mut changed = fold::fold_constants(&mut instructions, &mut chunk.constants, &function_starts);
+        changed |= peephole::remove_dead_pushes(&mut instructions, &function_starts);
+        changed |= peephole::thread_jumps(&mut instructions);
+        changed |= peephole::remove_unreachable_code(&mut instructions, &function_starts);
+
+        if !changed {
+            break;
+        }
+    }
+
+    if level >= 2 {
+        peephole::fuse_superinstructions(&mut instructions, &function_starts);
+    }
+
+    match encode_instructions(&chunk, &instructions, &instruction_indices) {
+        Some(optimized_chunk) => {
+            // Runtime check on debug builds to ensure that the optimizer generates valid chunks
+            #[cfg(debug_assertions)]
+            if let Err(error) = crate::bytecode::verify(&optimized_chunk) {
+                panic!("Optimizer Debug Error: Generated invalid chunk: {}", error);
+            }
+
+            optimized_chunk
+        }
+        None => chunk,
+    }
+}
+
+/// Index of the first instruction at or after 'index' that is not removed, which is where execution continues from 'index'
+fn next_instruction(instructions: &[Instruction], index: usize) -> usize {
+    let mut index = index;
+    while index < instructions.len() && instructions[index].removed {
+        index += 1;
+    }
+    index
+}
+
+/// Indices of all instructions that are not removed, so that passes can match patterns of consecutive instructions
+fn live_instructions(instructions: &[Instruction]) -> Vec<usize> {
+    (0..instructions.len())
+        .filter(|index| !instructions[*index].removed)
+        .collect()
+}
+
+/// Mark every instruction that execution can jump to, which can be reached without executing the instruction before it.
+/// The vector has 1 more element than the instructions, as jumping to the end of the code is valid.
+fn find_jump_targets(instructions: &[Instruction], function_starts: &[usize]) -> Vec<bool> {
+    let mut jump_targets = vec![false; instructions.len() + 1];
+
+    for &start in function_starts {
+        jump_targets[next_instruction(instructions, start)] = true;
+    }
+    for instruction in instructions {
+        if !instruction.removed && (instruction.opcode == OpCode::JUMP || instruction.opcode == OpCode::JUMP_IF_FALSE) {
+            jump_targets[next_instruction(instructions, instruction.operand)] = true;
+        }
+    }
+
+    jump_targets
+}
@@ -0,0 +1,145 @@
+use super::instruction::Instruction;
+use super::{find_jump_targets, live_instructions, next_instruction};
+
+use crate::opcode::OpCode;
+
+/// Remove values that are pushed onto the stack only to be popped off right after, e.g. 'CONSTANT 1; POP'.
+/// Only values that can be pushed without any side effects or runtime errors are removed.
+pub fn remove_dead_pushes(instructions: &mut [Instruction], function_starts: &[usize]) -> bool {
+    let jump_targets = find_jump_targets(instructions, function_starts);
+    let live = live_instructions(instructions);
+    let mut changed = false;
+
+    let mut i = 0;
+    while i + 1 < live.len() {
+        let push = instructions[live[i]].opcode;
+        let pop = instructions[live[i + 1]].opcode;
+
+        if (push == OpCode::CONSTANT || push == OpCode::GET_LOCAL)
+            && pop == OpCode::POP
+            && !jump_targets[live[i + 1]]
+        {
+            instructions[live[i]].removed = true;
+            instructions[live[i + 1]].removed = true;
+
+            changed = true;
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+
+    changed
+}
+
+/// Thread jumps to their final target, so that jumping to a jump only takes a single jump, and remove jumps to the next instruction.
+///
+/// A JUMP_IF_FALSE can also be threaded through another JUMP_IF_FALSE,
+/// since the condition is only peeked and not popped, the second jump is always taken if the first one is.
+pub fn thread_jumps(instructions: &mut [Instruction]) -> bool {
+    let mut changed = false;
+
+    for index in 0..instructions.len() {
+        let instruction = instructions[index];
+        if instruction.removed || !(instruction.opcode == OpCode::JUMP || instruction.opcode == OpCode::JUMP_IF_FALSE) {
+            continue;
+        }
+
+        let mut target = next_instruction(instructions, instruction.operand);
+
+        // Number of jumps followed is limited by the number of instructions, to stop at infinite loops of jumps
+        for _ in 0..instructions.len() {
+            if target == instructions.len() {
+                break;
+            }
+
+            let target_instruction = instructions[target];
+            let is_threadable = target_instruction.opcode == OpCode::JUMP
+                || (instruction.opcode == OpCode::JUMP_IF_FALSE && target_instruction.opcode == OpCode::JUMP_IF_FALSE);
+
+            let next_target = next_instruction(instructions, target_instruction.operand);
+
+            // There is no opcode for a conditional jump backwards
+            if !is_threadable || (instruction.opcode == OpCode::JUMP_IF_FALSE && next_target <= index) {
+                break;
+            }
+
+            target = next_target;
+        }
+
+        if target != instruction.operand {
+            instructions[index].operand = target;
+            changed = true;
+        }
+
+        // Unconditional jump to the next instruction does nothing
+        if instruction.opcode == OpCode::JUMP && target == next_instruction(instructions, index + 1) {
+            instructions[index].removed = true;
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+/// Remove instructions after an unconditional jump or return, until an instruction that is jumped to
+pub fn remove_unreachable_code(instructions: &mut [Instruction], function_starts: &[usize]) -> bool {
+    let jump_targets = find_jump_targets(instructions, function_starts);
+    let mut changed = false;
+
+    let mut reachable = true;
+    for index in 0..instructions.len() {
+        if instructions[index].removed {
+            continue;
+        }
+
+        if jump_targets[index] {
+            reachable = true;
+        }
+
+        if !reachable {
+            instructions[index].removed = true;
+            changed = true;
+            continue;
+        }
+
+        match instructions[index].opcode {
+            OpCode::JUMP | OpCode::RETURN | OpCode::RETURN_POP => reachable = false,
+            _ => {}
+        }
+    }
+
+    changed
+}
+
+/// Fuse common pairs of opcodes into superinstructions, e.g. 'GET_LOCAL 0; ADD' into 'GET_LOCAL_ADD 0'
+pub fn fuse_superinstructions(instructions: &mut [Instruction], function_starts: &[usize]) {
+    let jump_targets = find_jump_targets(instructions, function_starts);
+    let live = live_instructions(instructions);
+
+    let mut i = 0;
+    while i + 1 < live.len() {
+        let first = instructions[live[i]];
+        let second = instructions[live[i + 1]];
+
+        let superinstruction = match (first.opcode, second.opcode) {
+            (OpCode::GET_LOCAL, OpCode::ADD) => Some(OpCode::GET_LOCAL_ADD),
+            (OpCode::GET_LOCAL, OpCode::SUBTRACT) => Some(OpCode::GET_LOCAL_SUBTRACT),
+            _ => None,
+        };
+
+        match superinstruction {
+            Some(opcode) if !jump_targets[live[i + 1]] => {
+                // Superinstruction takes the location of the operator, since that is where runtime errors can happen
+                instructions[live[i]] = Instruction {
+                    opcode,
+                    location: second.location,
+                    ..first
+                };
+                instructions[live[i + 1]].removed = true;
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::instruction::{Instruction, Operand, RegisterChunk};
+
+pub fn disassemble_register_chunk(chunk: &RegisterChunk, name: &str) {
+    println!("====== Start of register chunk: {} ({} registers) ======", name, chunk.register_count);
+    for pc in 0..chunk.instructions.len() {
+        disassemble_register_instruction(chunk, pc);
+    }
+    println!("====== End of register chunk:   {} ======", name);
+}
+
+/// Print out the instruction at the given instruction index, in the same format as the stack chunk's disassembler,
+/// where registers are printed as R<register> and constants as K<constant index> followed by the constant's value
+pub fn disassemble_register_instruction(chunk: &RegisterChunk, pc: usize) {
+    print!("{:0width$} ", pc, width = 4);
+
+    // Print line number or | for instructions on the same line
+    let line = chunk.locations[pc].line;
+    if pc > 0 && line == chunk.locations[pc - 1].line {
+        print!("   | ");
+    } else {
+        print!("{:4} ", line);
+    }
+
+    let operand = |operand: Operand| match operand {
+        Operand::Register(register) => format!("R{}", register),
+        Operand::Constant(constant_index) => format!(
+            "K{} '{:?}'",
+            constant_index, chunk.chunk.constants[constant_index as usize]
+        ),
+    };
+    let global = |global_slot: u16| format!("G{} '{}'", global_slot, chunk.chunk.global_identifier(global_slot as usize));
+
+    let (name, operands) = match chunk.instructions[pc] {
+        Instruction::MOVE(a, b) => ("MOVE", format!("R{}, R{}", a, b)),
+        Instruction::LOAD_CONSTANT(a, constant_index) => {
+            ("LOAD_CONSTANT", format!("R{}, {}", a, operand(Operand::Constant(constant_index))))
+        }
+
+        Instruction::DEFINE_GLOBAL(global_slot, b) => ("DEFINE_GLOBAL", format!("{}, {}", global(global_slot), operand(b))),
+        Instruction::GET_GLOBAL(a, global_slot) => ("GET_GLOBAL", format!("R{}, {}", a, global(global_slot))),
+
+        Instruction::JUMP(target) => ("JUMP", format!("-> {}", target)),
+        Instruction::JUMP_IF_FALSE(b, target) => ("JUMP_IF_FALSE", format!("{} -> {}", operand(b), target)),
+        Instruction::LOOP(target) => ("LOOP", format!("-> {}", target)),
+
+        Instruction::CALL(a, number_of_arguments) => ("CALL", format!("R{}, {}", a, number_of_arguments)),
+        Instruction::RETURN(b) => ("RETURN", operand(b)),
+
+        Instruction::ADD(a, b, c) => ("ADD", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::SUBTRACT(a, b, c) => ("SUBTRACT", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::MULTIPLY(a, b, c) => ("MULTIPLY", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::DIVIDE(a, b, c) => ("DIVIDE", format!("R{}, {}, {}", a, operand(b), operand(c))),
+
+        Instruction::NOT(a, b) => ("NOT", format!("R{}, {}", a, operand(b))),
+        Instruction::NEGATE(a, b) => ("NEGATE", format!("R{}, {}", a, operand(b))),
+
+        Instruction::EQUAL(a, b, c) => ("EQUAL", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::NOT_EQUAL(a, b, c) => ("NOT_EQUAL", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::GREATER(a, b, c) => ("GREATER", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::GREATER_EQUAL(a, b, c) => ("GREATER_EQUAL", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::LESS(a, b, c) => ("LESS", format!("R{}, {}, {}", a, operand(b), operand(c))),
+        Instruction::LESS_EQUAL(a, b, c) => ("LESS_EQUAL", format!("R{}, {}, {}", a, operand(b), operand(c))),
+
+        Instruction::PRINT(b) => ("PRINT", operand(b)),
+    };
+
+    println!("{:<18} {}", name, operands);
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use crate::chunk::Chunk;
+use crate::line_table::SourceLocation;
+
+/// Index of a register in the register window of the current call, which is the stack index of the value in the stack VM
+/// relative to the function's stack base
+pub type Register = u16;
+
+/// Operand of an instruction that reads a value, which is either a register or a constant, like Lua's RK(B) operands.
+/// Reading constants directly saves the instructions needed to load them into registers first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    /// Value in the register
+    Register(Register),
+    /// Value at the index of the constant pool
+    Constant(u16),
+}
+
+// Lua 5 style three address instructions, where every instruction names the registers it reads from and writes to,
+// instead of implicitly popping its operands off and pushing its result onto the stack.
+//
+// The instruction format of each instruction is documented as 'INSTRUCTION <operands>' followed by what it does,
+// where R(A) is the value in register A, and RK(B) is the value of operand B, which is either a register or a constant.
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// MOVE A B
+    ///
+    /// R(A) := R(B)
+    MOVE(Register, Register),
+    /// LOAD_CONSTANT A <constant index>
+    ///
+    /// R(A) := Constant
+    LOAD_CONSTANT(Register, u16),
+
+    /// DEFINE_GLOBAL <global slot> B
+    ///
+    /// Global := RK(B)
+    DEFINE_GLOBAL(u16, Operand),
+    /// GET_GLOBAL A <global slot>
+    ///
+    /// R(A) := Global, which fails if the global is not defined yet
+    GET_GLOBAL(Register, u16),
+
+    /// JUMP <instruction index>
+    JUMP(u32),
+    /// JUMP_IF_FALSE B <instruction index>
+    ///
+    /// Jump if RK(B) is false, which must be a bool
+    JUMP_IF_FALSE(Operand, u32),
+    /// LOOP <instruction index>
+    ///
+    /// Same as JUMP, but jumps backwards and is a GC safepoint
+    LOOP(u32),
+
+    /// CALL A <number of arguments>
+    ///
+    /// Call the function R(A) with the arguments in the registers after it.
+    /// Arguments are moved down by 1 register before the call, and the register window of the call starts at R(A),
+    /// so the first argument is R0 of the called function. The return value is written to R(A)
+    CALL(Register, u8),
+    /// RETURN B
+    ///
+    /// Return RK(B) to the caller
+    RETURN(Operand),
+
+    /// ADD A B C
+    ///
+    /// R(A) := RK(B) + RK(C)
+    ADD(Register, Operand, Operand),
+    /// SUBTRACT A B C
+    ///
+    /// R(A) := RK(B) - RK(C)
+    SUBTRACT(Register, Operand, Operand),
+    /// MULTIPLY A B C
+    ///
+    /// R(A) := RK(B) * RK(C)
+    MULTIPLY(Register, Operand, Operand),
+    /// DIVIDE A B C
+    ///
+    /// R(A) := RK(B) / RK(C)
+    DIVIDE(Register, Operand, Operand),
+
+    /// NOT A B
+    ///
+    /// R(A) := !RK(B)
+    NOT(Register, Operand),
+    /// NEGATE A B
+    ///
+    /// R(A) := -RK(B)
+    NEGATE(Register, Operand),
+
+    /// EQUAL A B C
+    ///
+    /// R(A) := RK(B) == RK(C)
+    EQUAL(Register, Operand, Operand),
+    /// NOT_EQUAL A B C
+    ///
+    /// R(A) := RK(B) != RK(C)
+    NOT_EQUAL(Register, Operand, Operand),
+    /// GREATER A B C
+    ///
+    /// R(A) := RK(B) > RK(C)
+    GREATER(Register, Operand, Operand),
+    /// GREATER_EQUAL A B C
+    ///
+    /// R(A) := RK(B) >= RK(C)
+    GREATER_EQUAL(Register, Operand, Operand),
+    /// LESS A B C
+    ///
+    /// R(A) := RK(B) < RK(C)
+    LESS(Register, Operand, Operand),
+    /// LESS_EQUAL A B C
+    ///
+    /// R(A) := RK(B) <= RK(C)
+    LESS_EQUAL(Register, Operand, Operand),
+
+    /// PRINT B
+    PRINT(Operand),
+}
+
+/// Register based version of a chunk, translated from the stack based chunk generated by the compiler
+pub struct RegisterChunk {
+    pub instructions: Vec<Instruction>,
+
+    /// Source location of every instruction, taken from the stack instruction that it was translated from
+    pub locations: Vec<SourceLocation>,
+
+    /// Instruction index of every byte offset of the stack chunk's code, so that function values,
+    /// which hold the byte offset of their function body, can be called without changing any values.
+    /// Has 1 more element than the code, as the end of the code is also mapped.
+    pub instruction_indices: Vec<usize>,
+
+    /// Number of registers used by the top level script, which is its maximum stack size
+    pub register_count: usize,
+
+    /// Number of registers used by every function body, keyed by the byte offset of the function body,
+    /// which is the maximum stack size of the body above the function's stack base.
+    /// The register file is grown by this many registers past the register window of every call.
+    pub frame_register_counts: HashMap<usize, usize>,
+
+    /// Stack chunk that was translated, whose constants, globals and function table are used as is
+    pub chunk: Chunk,
+}
@@ -0,0 +1,20 @@
+mod debug;
+mod instruction;
+mod translate;
+mod vm;
+
+pub use debug::disassemble_register_chunk;
+pub use translate::translate;
+pub use vm::RegisterVM;
+
+/*
+    Register based execution engine, an alternative to the stack based VM selected with '--engine register'.
+
+    Instead of compiling source code separately, stack chunks generated by the compiler (or loaded from bytecode files)
+    are translated into register chunks, so that both engines run exactly the same programs from the same front end.
+
+    Registers are the stack slots of the stack VM, so that locals are read from the same registers that they are stored in,
+    and the values that the stack VM pushes and pops are instead named directly as the operands of every instruction.
+    Like Lua, every call runs in its own register window starting at its first argument, where registers of function
+    bodies are numbered from the function's stack base, so that recursive and nested calls never share registers.
+*/
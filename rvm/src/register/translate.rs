@@ -0,0 +1,309 @@
+use std::collections::{HashMap, HashSet};
+
+use super::instruction::{Instruction, Operand, Register, RegisterChunk};
+
+use crate::bytecode::{self, BytecodeError};
+use crate::chunk::Chunk;
+use crate::line_table::SourceLocation;
+use crate::opcode::{decode, OpCode};
+
+/// Translate a stack based chunk into a register based chunk, so that both VMs run the same program from the same front end.
+///
+/// Every stack slot is mapped to a register using the stack size of every instruction from the verifier.
+/// Registers of function bodies are numbered from the function's stack base, i.e. its first parameter is R0,
+/// and the VM offsets them by the register window of the call, so that every call gets its own registers.
+/// Instead of loading every value into its stack slot's register, values that can be read directly as operands,
+/// i.e. constants and locals, are kept on a virtual stack of operands until an instruction uses them,
+/// so that 'CONSTANT 0; GET_LOCAL 1; ADD' is translated into a single 'ADD R2, K0, R1'.
+///
+/// The chunk must be valid, i.e. generated by the compiler or checked by the verifier.
+pub fn translate(chunk: Chunk) -> Result<RegisterChunk, BytecodeError> {
+    // Stack size of every instruction relative to the stack base of the function body it is in, with the stack base,
+    // where the top level script has a stack base of 0
+    let mut stack_sizes = HashMap::<usize, (usize, usize)>::new();
+    let mut register_count = 0;
+    let mut frame_register_counts = HashMap::<usize, usize>::new();
+
+    for function in std::iter::once(None).chain(chunk.functions.iter().map(Some)) {
+        let stack_base = function.map_or(0, |function| function.stack_base);
+
+        let mut max_stack_size = 0;
+        for (offset, stack_size) in bytecode::function_stack_sizes(&chunk, function)? {
+            // Function bodies cannot pop values below their stack base, as those belong to the caller
+            let stack_size = stack_size.checked_sub(stack_base).ok_or(BytecodeError::StackUnderflow(offset))?;
+            max_stack_size = max_stack_size.max(stack_size);
+            stack_sizes.insert(offset, (stack_base, stack_size));
+        }
+
+        match function {
+            Some(function) => {
+                frame_register_counts.insert(function.start, max_stack_size + 1);
+            }
+            None => register_count = max_stack_size + 1,
+        }
+    }
+
+    let jump_targets = find_jump_targets(&chunk);
+
+    let mut translator = Translator {
+        instructions: Vec::new(),
+        locations: Vec::new(),
+        operands: Vec::new(),
+        location: SourceLocation::default(),
+    };
+
+    // Jump targets are stored as byte offsets until every instruction is translated, since jumps can go forward
+    let mut instruction_indices = vec![0; chunk.codes.len() + 1];
+
+    // Whether the previous instruction can continue on to the current instruction, i.e. it is not a jump or return
+    let mut falls_through = false;
+
+    let mut offset = 0;
+    while offset < chunk.codes.len() {
+        let opcode = decode(chunk.codes[offset]).unwrap();
+        let next_instruction = offset + 1 + opcode.operand_length();
+        let byte_operand = || chunk.codes[offset + 1] as usize;
+        let u16_operand = || chunk.read_u16(offset + 1) as usize;
+
+        // Unreachable instructions are not translated, and are mapped to the next instruction translated
+        let (stack_base, stack_size) = match stack_sizes.get(&offset) {
+            Some(&stack_sizes) => stack_sizes,
+            None => {
+                instruction_indices[offset] = translator.instructions.len();
+                offset = next_instruction;
+                continue;
+            }
+        };
+
+        // Register of the local in the stack index operand, relative to the function's stack base
+        let local = || byte_operand().checked_sub(stack_base).ok_or(BytecodeError::StackUnderflow(offset));
+
+        translator.location = chunk.lines.location_for(offset);
+
+        // Values must be in their registers when execution continues from a jump, so the previous instruction flushes them first
+        // Instructions that can only be jumped to start with every value in its register
+        if !falls_through || jump_targets.contains(&offset) {
+            if falls_through {
+                translator.flush();
+            }
+            translator.operands = (0..stack_size).map(|slot| Operand::Register(slot as Register)).collect();
+        }
+        instruction_indices[offset] = translator.instructions.len();
+        falls_through = true;
+
+        // Register of the value on the top of the stack, which instructions that replace their operands write their result to
+        let top = |pops: usize| (stack_size - pops) as Register;
+
+        match opcode {
+            OpCode::POP => {
+                translator.operands.pop();
+            }
+            OpCode::POP_N => translator.operands.truncate(stack_size - byte_operand()),
+
+            OpCode::RETURN | OpCode::RETURN_POP => {
+                // Locals do not have to be popped off, as the VM writes the return value straight into the caller's register
+                let value = translator.pop();
+                translator.emit(Instruction::RETURN(value));
+                falls_through = false;
+            }
+
+            OpCode::CONSTANT => translator.operands.push(Operand::Constant(byte_operand() as u16)),
+            OpCode::CONSTANT_LONG => translator.operands.push(Operand::Constant(u16_operand() as u16)),
+
+            OpCode::DEFINE_GLOBAL => {
+                let value = translator.pop();
+                translator.emit(Instruction::DEFINE_GLOBAL(u16_operand() as u16, value));
+            }
+            // Globals are loaded immediately, since getting a global that is not defined yet is a runtime error
+            OpCode::GET_GLOBAL => translator.emit_push(Instruction::GET_GLOBAL(top(0), u16_operand() as u16)),
+
+            // Locals are never changed in place without SET_LOCAL, which flushes the operands first,
+            // so the local's operand can be read directly as long as the local is on the stack
+            OpCode::GET_LOCAL => {
+                let local = translator.operands[local()?];
+                translator.operands.push(local);
+            }
+            OpCode::SET_LOCAL => {
+                translator.flush();
+                translator.operands.pop();
+                translator.emit(Instruction::MOVE(local()? as Register, top(1)));
+            }
+
+            OpCode::JUMP | OpCode::LOOP | OpCode::JUMP_IF_FALSE => {
+                let target = jump_target(&chunk, offset);
+
+                // Condition value is only peeked, so it is left in its register for the instructions after the jump
+                translator.flush();
+                translator.emit(match opcode {
+                    OpCode::JUMP => Instruction::JUMP(target as u32),
+                    OpCode::LOOP => Instruction::LOOP(target as u32),
+                    _ => Instruction::JUMP_IF_FALSE(Operand::Register(top(1)), target as u32),
+                });
+
+                falls_through = opcode == OpCode::JUMP_IF_FALSE;
+            }
+
+            // Function value and arguments must be in their registers for the call, and so must the locals used by the function body
+            OpCode::CALL => {
+                let number_of_arguments = byte_operand();
+                translator.flush();
+                translator.operands.truncate(stack_size - number_of_arguments - 1);
+                translator.emit_push(Instruction::CALL(top(number_of_arguments + 1), number_of_arguments as u8));
+            }
+
+            OpCode::ADD
+            | OpCode::SUBTRACT
+            | OpCode::MULTIPLY
+            | OpCode::DIVIDE
+            | OpCode::EQUAL
+            | OpCode::NOT_EQUAL
+            | OpCode::GREATER
+            | OpCode::GREATER_EQUAL
+            | OpCode::LESS
+            | OpCode::LESS_EQUAL => {
+                let b = translator.pop();
+                let a = translator.pop();
+                translator.emit_push(binary_instruction(opcode, top(2), a, b));
+            }
+
+            // Superinstructions read the local directly as the second operand
+            OpCode::GET_LOCAL_ADD | OpCode::GET_LOCAL_SUBTRACT => {
+                let a = translator.pop();
+                let b = translator.operands[local()?];
+                let opcode = match opcode {
+                    OpCode::GET_LOCAL_ADD => OpCode::ADD,
+                    _ => OpCode::SUBTRACT,
+                };
+                translator.emit_push(binary_instruction(opcode, top(1), a, b));
+            }
+
+            OpCode::NOT => {
+                let value = translator.pop();
+                translator.emit_push(Instruction::NOT(top(1), value));
+            }
+            OpCode::NEGATE => {
+                let value = translator.pop();
+                translator.emit_push(Instruction::NEGATE(top(1), value));
+            }
+
+            OpCode::PRINT => {
+                let value = translator.pop();
+                translator.emit(Instruction::PRINT(value));
+            }
+        }
+
+        offset = next_instruction;
+    }
+    instruction_indices[chunk.codes.len()] = translator.instructions.len();
+
+    // Convert the byte offsets of jump targets into instruction indices
+    let mut instructions = translator.instructions;
+    for instruction in instructions.iter_mut() {
+        match instruction {
+            Instruction::JUMP(target) | Instruction::LOOP(target) | Instruction::JUMP_IF_FALSE(_, target) => {
+                *target = instruction_indices[*target as usize] as u32
+            }
+            _ => {}
+        }
+    }
+
+    Ok(RegisterChunk {
+        instructions,
+        locations: translator.locations,
+        instruction_indices,
+        register_count,
+        frame_register_counts,
+        chunk,
+    })
+}
+
+/// Byte offset that the jump type instruction at the byte offset jumps to
+fn jump_target(chunk: &Chunk, offset: usize) -> usize {
+    let next_instruction = offset + 3;
+    match decode(chunk.codes[offset]) {
+        Some(OpCode::LOOP) => next_instruction - chunk.read_u16(offset + 1) as usize,
+        _ => next_instruction + chunk.read_u16(offset + 1) as usize,
+    }
+}
+
+/// Find the byte offsets that can be reached without executing the instruction before it,
+/// which are the targets of jumps and the start of function bodies, where every value must be in its register
+fn find_jump_targets(chunk: &Chunk) -> HashSet<usize> {
+    let mut jump_targets: HashSet<usize> = chunk.functions.iter().map(|function| function.start).collect();
+
+    let mut offset = 0;
+    while offset < chunk.codes.len() {
+        let opcode = decode(chunk.codes[offset]).unwrap();
+        if let OpCode::JUMP | OpCode::JUMP_IF_FALSE | OpCode::LOOP = opcode {
+            jump_targets.insert(jump_target(chunk, offset));
+        }
+        offset += 1 + opcode.operand_length();
+    }
+
+    jump_targets
+}
+
+/// State of the translation, where the virtual stack holds the operand to read every stack slot's value from
+struct Translator {
+    instructions: Vec<Instruction>,
+    locations: Vec<SourceLocation>,
+
+    /// Operand of every stack slot, which is the slot's own register once the value is stored in it
+    operands: Vec<Operand>,
+
+    /// Source location of the stack instruction being translated
+    location: SourceLocation,
+}
+
+impl Translator {
+    fn emit(&mut self, instruction: Instruction) {
+        self.instructions.push(instruction);
+        self.locations.push(self.location);
+    }
+
+    /// Emit instruction that writes its result to the register of the next stack slot, and push that register
+    fn emit_push(&mut self, instruction: Instruction) {
+        let register = self.operands.len() as Register;
+        self.emit(instruction);
+        self.operands.push(Operand::Register(register));
+    }
+
+    /// Pop the operand of the value on the top of the stack
+    fn pop(&mut self) -> Operand {
+        // Stack sizes are checked by the verifier, so the virtual stack cannot underflow
+        self.operands.pop().unwrap()
+    }
+
+    /// Store every value that is not in its register yet into its register.
+    ///
+    /// Operands only read constants or registers of values below them on the stack, which are already in their registers,
+    /// so loading them in any order never overwrites a register that is still to be read.
+    fn flush(&mut self) {
+        for slot in 0..self.operands.len() {
+            let register = slot as Register;
+            match self.operands[slot] {
+                Operand::Register(source) if source == register => continue,
+                Operand::Register(source) => self.emit(Instruction::MOVE(register, source)),
+                Operand::Constant(constant_index) => self.emit(Instruction::LOAD_CONSTANT(register, constant_index)),
+            }
+            self.operands[slot] = Operand::Register(register);
+        }
+    }
+}
+
+/// Get the register instruction of a binary operator opcode
+fn binary_instruction(opcode: OpCode, register: Register, a: Operand, b: Operand) -> Instruction {
+    match opcode {
+        OpCode::ADD => Instruction::ADD(register, a, b),
+        OpCode::SUBTRACT => Instruction::SUBTRACT(register, a, b),
+        OpCode::MULTIPLY => Instruction::MULTIPLY(register, a, b),
+        OpCode::DIVIDE => Instruction::DIVIDE(register, a, b),
+        OpCode::EQUAL => Instruction::EQUAL(register, a, b),
+        OpCode::NOT_EQUAL => Instruction::NOT_EQUAL(register, a, b),
+        OpCode::GREATER => Instruction::GREATER(register, a, b),
+        OpCode::GREATER_EQUAL => Instruction::GREATER_EQUAL(register, a, b),
+        OpCode::LESS => Instruction::LESS(register, a, b),
+        OpCode::LESS_EQUAL => Instruction::LESS_EQUAL(register, a, b),
+        _ => unreachable!("{:?} is not a binary operator", opcode),
+    }
+}
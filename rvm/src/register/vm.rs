@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use super::debug::disassemble_register_instruction;
+use super::instruction::{Instruction, Operand, RegisterChunk};
+
+use crate::chunk::Function;
+use crate::cli::DebugFlags;
+use crate::debug;
+use crate::error::{RuntimeError, StackFrame};
+use crate::value::Value;
+use crate::vm::{callee, safepoint};
+use crate::SSError;
+
+// Read the value of an operand, which is either a register in the current register window or a constant
+macro_rules! read_operand {
+    ($registers:ident, $base:ident, $chunk:ident, $operand:expr) => {
+        match $operand {
+            Operand::Register(register) => $registers[$base + register as usize],
+            Operand::Constant(constant_index) => $chunk.constants[constant_index as usize],
+        }
+    };
+}
+
+// Macro to perform a binary operation on 2 operands and write the result into a register,
+// with the same type checks and error messages as the stack VM's binary operation macros
+macro_rules! register_binary_op {
+    ($op_name:literal, $registers:ident, $base:ident, $chunk:ident, $operator:tt, ($a:ident, $b:ident, $c:ident), |$x:ident, $y:ident| $type_check:expr => $logic:expr) => {{
+        let $x = read_operand!($registers, $base, $chunk, $b);
+        let $y = read_operand!($registers, $base, $chunk, $c);
+
+        if $type_check {
+            $registers[$base + $a as usize] = $logic;
+        } else {
+            return Err(RuntimeError::TypeError(format!(
+                "Invalid operand types {:?} and {:?} used for '{}' {} operation",
+                $x, $y, stringify!($operator), $op_name
+            )));
+        }
+    }};
+}
+
+macro_rules! register_arithmetic_op {
+    ($registers:ident, $base:ident, $chunk:ident, $operator:tt, $operands:tt) => {
+        register_binary_op!("Arithmetic", $registers, $base, $chunk, $operator, $operands,
+            |x, y| x.is_number() & y.is_number() => Value::number(x.as_number() $operator y.as_number()))
+    };
+}
+
+macro_rules! register_equality_op {
+    ($registers:ident, $base:ident, $chunk:ident, $operator:tt, $operands:tt) => {
+        register_binary_op!("Equality", $registers, $base, $chunk, $operator, $operands,
+            |x, y| true => Value::bool(x $operator y))
+    };
+}
+
+macro_rules! register_comparison_op {
+    ($registers:ident, $base:ident, $chunk:ident, $operator:tt, $operands:tt) => {
+        register_binary_op!("Numeric Comparison", $registers, $base, $chunk, $operator, $operands,
+            |x, y| x.is_number() & y.is_number() => Value::bool(x.as_number() $operator y.as_number()))
+    };
+}
+
+/// Function call on the call stack
+struct CallFrame {
+    /// Instruction index to return to for execution, which is the instruction after the CALL instruction
+    return_pc: usize,
+
+    /// Register that the caller expects the return value in, which is the register of the function value called
+    return_register: usize,
+
+    /// Index of the called function's R0 in the register file, a.k.a the start of its register window,
+    /// which is where the caller put the first argument, so that every call has its own registers
+    base: usize,
+
+    /// Function value called, which is overwritten by the arguments, so frames are GC roots to keep it alive.
+    /// Its byte offset in the stack chunk is used to find the function's name in the chunk's function table.
+    function: Value,
+}
+
+/// Register based VM, executing chunks translated from the stack based chunks that the stack VM executes,
+/// so that both designs can be benchmarked against each other on the same programs.
+pub struct RegisterVM {
+    // pc: Program Counter, instruction index of the instruction being executed, saved to locate runtime errors
+    pc: usize,
+
+    // Register file, where the registers of every call are in a window starting at the call frame's base,
+    // which grows whenever a call needs more registers than there are
+    registers: Vec<Value>,
+
+    // Global values are stored in slots resolved at compile time, where None means the global is not defined yet
+    globals: Vec<Option<Value>>,
+
+    // Call stack for function calls in SS, which is walked to generate a backtrace on runtime errors
+    call_stack: Vec<CallFrame>,
+}
+
+impl RegisterVM {
+    // Wrapper method to run a whole register chunk on a new VM
+    pub fn interpret(chunk: RegisterChunk, debug_flags: DebugFlags) -> Result<(), SSError> {
+        if debug_flags.dump_chunk {
+            super::disassemble_register_chunk(&chunk, "Register chunk instructions");
+        }
+
+        let mut vm = RegisterVM {
+            pc: 0,
+            registers: vec![Value::NULL; chunk.register_count],
+            globals: vec![None; chunk.chunk.globals.len()],
+            call_stack: Vec::new(),
+        };
+
+        safepoint(&chunk.chunk, &vm.registers, &vm.globals, std::iter::empty(), debug_flags);
+
+        match vm.execute(&chunk, debug_flags) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(SSError::RuntimeError(e, vm.backtrace(&chunk))),
+        }
+    }
+
+    /// Walk the call stack from the innermost call to generate a backtrace, using the instruction index of the failed instruction saved in pc
+    fn backtrace(&self, chunk: &RegisterChunk) -> Vec<StackFrame> {
+        let mut backtrace = Vec::<StackFrame>::with_capacity(self.call_stack.len() + 1);
+
+        // Instruction index of the instruction being executed in the current frame
+        let mut pc = self.pc;
+
+        for frame in self.call_stack.iter().rev() {
+            let function_name = match chunk
+                .chunk
+                .functions
+                .iter()
+                .find(|function| function.start == frame.function.as_fn())
+            {
+                Some(function) => function.name.clone(),
+                None => "<anonymous>".to_string(),
+            };
+
+            backtrace.push(StackFrame {
+                function_name,
+                location: chunk.locations[pc],
+            });
+
+            // The caller is executing the CALL instruction right before the return address
+            pc = frame.return_pc - 1;
+        }
+
+        backtrace.push(StackFrame {
+            function_name: "<script>".to_string(),
+            location: chunk.locations[pc],
+        });
+
+        backtrace
+    }
+
+    fn execute(&mut self, register_chunk: &RegisterChunk, debug_flags: DebugFlags) -> Result<(), RuntimeError> {
+        // Same as the stack VM, pc is kept in a local variable so that it can be kept in a CPU register
+        let mut pc: usize = 0;
+
+        let chunk = &register_chunk.chunk;
+        let instructions = &register_chunk.instructions;
+        let registers = &mut self.registers;
+        let globals = &mut self.globals;
+        let call_stack = &mut self.call_stack;
+
+        // Every function in the chunk, keyed by the byte offset of the function body, to check calls
+        let functions: HashMap<usize, &Function> =
+            chunk.functions.iter().map(|function| (function.start, function)).collect();
+
+        // Start of the current call frame's register window, where the top level script's registers start at 0
+        let mut base: usize = 0;
+
+        while pc < instructions.len() {
+            // Save the instruction index of the instruction about to be executed, to locate it if it fails with a runtime error
+            self.pc = pc;
+
+            if debug_flags.trace_exec {
+                disassemble_register_instruction(register_chunk, pc);
+            }
+
+            let instruction = instructions[pc];
+            pc += 1;
+
+            match instruction {
+                Instruction::MOVE(a, b) => registers[base + a as usize] = registers[base + b as usize],
+                Instruction::LOAD_CONSTANT(a, constant_index) => {
+                    registers[base + a as usize] = chunk.constants[constant_index as usize]
+                }
+
+                Instruction::DEFINE_GLOBAL(global_slot, b) => {
+                    globals[global_slot as usize] = Some(read_operand!(registers, base, chunk, b))
+                }
+                Instruction::GET_GLOBAL(a, global_slot) => match globals[global_slot as usize] {
+                    Some(value) => registers[base + a as usize] = value,
+                    None => {
+                        return Err(RuntimeError::UndefinedIdentifier(
+                            chunk.global_identifier(global_slot as usize).to_string(),
+                        ))
+                    }
+                },
+
+                Instruction::JUMP(target) => pc = target as usize,
+                Instruction::JUMP_IF_FALSE(b, target) => {
+                    let value = read_operand!(registers, base, chunk, b);
+
+                    // Runtime type checking, same as the stack VM
+                    if !value.is_bool() {
+                        return Err(RuntimeError::ConditionTypeError(format!(
+                            "Condition expects Bool but found {:?}",
+                            value
+                        )));
+                    }
+
+                    if !value.as_bool() {
+                        pc = target as usize;
+                    }
+                }
+                Instruction::LOOP(target) => {
+                    pc = target as usize;
+
+                    safepoint(chunk, registers, globals, call_stack.iter().map(|frame| &frame.function), debug_flags);
+                }
+
+                Instruction::CALL(a, number_of_arguments) => {
+                    let a = base + a as usize;
+
+                    safepoint(chunk, registers, globals, call_stack.iter().map(|frame| &frame.function), debug_flags);
+
+                    let function_value = registers[a];
+                    let function = callee(&functions, function_value, number_of_arguments as usize)?;
+
+                    // Every function in the function table has its register count
+                    let register_count = register_chunk.frame_register_counts[&function.start];
+
+                    // Move the arguments down into the function value's register, to where the stack VM has them after removing the function value
+                    registers.copy_within(a + 1..a + 1 + number_of_arguments as usize, a);
+
+                    // Register window of the call starts at the first argument
+                    base = a;
+                    if registers.len() < base + register_count {
+                        registers.resize(base + register_count, Value::NULL);
+                    }
+
+                    call_stack.push(CallFrame {
+                        return_pc: pc,
+                        return_register: a,
+                        base,
+                        function: function_value,
+                    });
+
+                    // Function values hold the byte offset of their body in the stack chunk, which is mapped to its instruction index
+                    pc = register_chunk.instruction_indices[function.start];
+                }
+                Instruction::RETURN(b) => {
+                    let return_value = read_operand!(registers, base, chunk, b);
+
+                    if debug_flags.trace_exec {
+                        println!("RETURN_VALUE: {:?}", return_value);
+                    }
+
+                    let frame = call_stack.pop().unwrap();
+                    registers[frame.return_register] = return_value;
+                    pc = frame.return_pc;
+                    base = call_stack.last().map_or(0, |frame| frame.base);
+                }
+
+                Instruction::ADD(a, b, c) => register_arithmetic_op!(registers, base, chunk, +, (a, b, c)),
+                Instruction::SUBTRACT(a, b, c) => register_arithmetic_op!(registers, base, chunk, -, (a, b, c)),
+                Instruction::MULTIPLY(a, b, c) => register_arithmetic_op!(registers, base, chunk, *, (a, b, c)),
+                Instruction::DIVIDE(a, b, c) => register_arithmetic_op!(registers, base, chunk, /, (a, b, c)),
+
+                Instruction::NOT(a, b) => {
+                    registers[base + a as usize] = read_operand!(registers, base, chunk, b).not()?
+                }
+                Instruction::NEGATE(a, b) => {
+                    registers[base + a as usize] = read_operand!(registers, base, chunk, b).negate()?
+                }
+
+                Instruction::EQUAL(a, b, c) => register_equality_op!(registers, base, chunk, ==, (a, b, c)),
+                Instruction::NOT_EQUAL(a, b, c) => register_equality_op!(registers, base, chunk, !=, (a, b, c)),
+                Instruction::GREATER(a, b, c) => register_comparison_op!(registers, base, chunk, >, (a, b, c)),
+                Instruction::GREATER_EQUAL(a, b, c) => register_comparison_op!(registers, base, chunk, >=, (a, b, c)),
+                Instruction::LESS(a, b, c) => register_comparison_op!(registers, base, chunk, <, (a, b, c)),
+                Instruction::LESS_EQUAL(a, b, c) => register_comparison_op!(registers, base, chunk, <=, (a, b, c)),
+
+                Instruction::PRINT(b) => {
+                    println!("{}", chunk.format_value(read_operand!(registers, base, chunk, b)));
+                }
+            }
+
+            if debug_flags.print_stack {
+                debug::print_stack(registers);
+            }
+        }
+
+        Ok(())
+    }
+}
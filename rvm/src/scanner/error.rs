@@ -3,3 +3,9 @@ pub struct ScannerError {
     pub line: usize,
     pub description: String,
 }
+
+impl std::fmt::Display for ScannerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[line {}] SyntaxError: {}", self.line, self.description)
+    }
+}
@@ -43,6 +43,7 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
         }
     }
 
@@ -110,14 +111,17 @@ impl Scanner {
                         // @todo Is extra processing needed to remove the \n from the final string? Or keep as is?
                         if self.peek() == '\n' {
                             self.line += 1;
+                            self.line_start = self.current + 1;
                         }
 
                         self.current += 1;
                     }
 
-                    // @todo Return error variant instead
                     if self.is_at_end() {
-                        panic!("Unexpected Eof while parsing for string literal");
+                        return Err(ScannerError {
+                            line: self.line,
+                            description: "Unterminated string literal".to_string(),
+                        });
                     }
 
                     // Consume the closing double quote "
@@ -154,10 +158,7 @@ impl Scanner {
                     // Since the match statement is wrapped in Ok, we cannot let this evalute to Err variant, must return explicitly instead
                     return Err(ScannerError {
                         line: self.line,
-                        description: format!(
-                            "Unexpected character '{}' on line {}",
-                            unmatched_character, self.line
-                        ),
+                        description: format!("Unexpected character '{}'", unmatched_character),
                     });
                 }
             }
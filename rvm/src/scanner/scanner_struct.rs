@@ -11,4 +11,6 @@ pub struct Scanner {
     pub start: usize,
     pub current: usize,
     pub line: usize,
+    /// Index of the first character of the current line, used to calculate the column of tokens
+    pub line_start: usize,
 }
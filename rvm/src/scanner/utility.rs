@@ -12,6 +12,8 @@ impl Scanner {
             start: self.start,
             length: self.current - self.start,
             line: self.line,
+            // Columns start from 1 just like line numbers
+            column: self.start - self.line_start + 1,
         }
     }
 
@@ -82,6 +84,7 @@ impl Scanner {
                 '\n' => {
                     self.line += 1;
                     self.current += 1;
+                    self.line_start = self.current;
                 }
 
                 // Inline Comment, a comment that goes until the end of the line.
@@ -104,6 +107,7 @@ impl Scanner {
                         // Advance the current character index, AND if current char is a newline, increment line count
                         if self.advance() == '\n' {
                             self.line += 1;
+                            self.line_start = self.current;
                         }
                     }
 
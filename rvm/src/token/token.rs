@@ -1,4 +1,5 @@
 use super::TokenType;
+use crate::line_table::SourceLocation;
 
 // Alternative way from Clox using a pointer directly instead of holding the index of the vector
 // This is for optimization, as dereferencing a pointer is faster then doing pointer arithmetic with the index before element access
@@ -17,4 +18,16 @@ pub struct Token {
   pub start: usize,
   pub length: usize,
   pub line: usize,
+  pub column: usize,
+}
+
+impl Token {
+  /// Source location of the token, used to map compiled instructions back to the source code
+  #[inline]
+  pub fn location(&self) -> SourceLocation {
+    SourceLocation {
+      line: self.line,
+      column: self.column,
+    }
+  }
 }
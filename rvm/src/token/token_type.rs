@@ -69,6 +69,7 @@ pub enum TokenType {
   Return,
   True,
   Const,
+  Ignore,
 
   // What about undefined? Void?
   // Maybe dun allow it, either enforce checking for nulls with the language server,
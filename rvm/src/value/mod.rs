@@ -0,0 +1,5 @@
+mod object;
+mod value;
+
+pub use self::object::Object;
+pub use self::value::{UnpackedValue, Value, FALSE_BITS, NAN_BITS, QNAN, SIGN_BIT, TRUE_BITS};
@@ -0,0 +1,19 @@
+use super::Value;
+
+/// Heap allocated values, which are too big to be stored inside a NaN boxed Value directly.
+/// Values hold pointers to objects on the garbage collected heap, so objects are shared instead of cloned when values are copied.
+///
+/// Objects holding values keep the objects of those values alive, so the collector traces through them.
+#[derive(Debug, PartialEq)]
+pub enum Object {
+    String(String),
+
+    /// Array(elements)
+    Array(Vec<Value>),
+
+    /// Closure(byte offset of the function body, values captured from the enclosing functions)
+    Closure(usize, Vec<Value>),
+
+    /// Record(fields), where fields are in the order they are declared
+    Record(Vec<(String, Value)>),
+}
@@ -0,0 +1,293 @@
+use std::marker::PhantomData;
+
+use super::Object;
+use crate::error::RuntimeError;
+use crate::heap::{self, HeapObject};
+
+/*
+    Values are NaN boxed into 8 bytes, just like Clox's NAN_BOXING mode.
+    https://craftinginterpreters.com/optimization.html#nan-boxing
+
+    Any f64 that is not a quiet NaN is stored as is, leaving the unused bits of quiet NaNs to encode all the other types:
+
+    Number      Any f64, where NaNs are all stored as the same canonical NaN that never has the QNAN bits set
+    Null        QNAN | 1
+    false       QNAN | 2
+    true        QNAN | 3
+    Fn          QNAN | FN_TAG | <byte offset of function body in the lower 48 bits>
+    Object      SIGN_BIT | QNAN | <pointer to garbage collected HeapObject in the lower 48 bits>
+*/
+pub const SIGN_BIT: u64 = 0x8000_0000_0000_0000;
+pub const QNAN: u64 = 0x7ffc_0000_0000_0000;
+
+/// Bit 48 is not used by QNAN, and is used to tag Fn values
+const FN_TAG: u64 = 0x0001_0000_0000_0000;
+
+/// Mask for the lower 48 bits used to store payloads, which is enough for byte offsets and pointers on 64 bit platforms
+const PAYLOAD_MASK: u64 = 0x0000_ffff_ffff_ffff;
+
+const NULL_BITS: u64 = QNAN | 1;
+pub const FALSE_BITS: u64 = QNAN | 2;
+pub const TRUE_BITS: u64 = QNAN | 3;
+
+/// Bits of the canonical NaN that all NaN numbers are stored as
+pub const NAN_BITS: u64 = 0x7ff8_0000_0000_0000;
+
+/// SS value NaN boxed into 8 bytes, so that values can be copied around the stack cheaply.
+/// Objects are owned by the garbage collected heap, so copying a value only copies the pointer to its object.
+///
+/// Value has the same layout as u64, so that JIT compiled code can read and write values on the stack as plain 64 bit integers.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct Value {
+    bits: u64,
+
+    /// Value can point to an object on the heap, which is thread local, making Value neither Send nor Sync
+    _object: PhantomData<*const HeapObject>,
+}
+
+// Ensure that values are always NaN boxed into 8 bytes
+const _: () = assert!(std::mem::size_of::<Value>() == 8);
+
+/// Unpacked view of a NaN boxed Value, used to match on the type of a value.
+/// Debug formatting of Value uses this, so values are printed like the enum variants they are unpacked into.
+#[derive(Debug, PartialEq)]
+pub enum UnpackedValue<'a> {
+    /* Primitive types */
+    Number(f64),
+    String(&'a str),
+    Bool(bool),
+    Null,
+
+    /* None primitive types, a.k.a wrapper for all complex user types */
+    /// Function, where usize is the ip
+    Fn(usize),
+    Array(&'a [Value]),
+    /// Closure(byte offset of the function body, captured values)
+    Closure(usize, &'a [Value]),
+    Record(&'a [(String, Value)]),
+}
+
+impl Value {
+    pub const NULL: Value = Value::from_bits(NULL_BITS);
+
+    /// Value with the raw NaN boxed bits, which must be the bits of a valid value, such as the bits used by JIT compiled code
+    pub(crate) const fn from_bits(bits: u64) -> Value {
+        Value {
+            bits,
+            _object: PhantomData,
+        }
+    }
+
+    /// Raw NaN boxed bits of the value, used by JIT compiled code which works on values as plain 64 bit integers
+    #[inline]
+    pub fn to_bits(&self) -> u64 {
+        self.bits
+    }
+
+    #[inline]
+    pub fn number(number: f64) -> Value {
+        // NaNs are canonicalized, as NaNs with the QNAN bits set would be mistaken for other types
+        if number.is_nan() {
+            return Value::from_bits(NAN_BITS);
+        }
+
+        Value::from_bits(number.to_bits())
+    }
+
+    #[inline]
+    pub fn bool(bool: bool) -> Value {
+        // Branchless, as true and false only differ in the lowest bit
+        Value::from_bits(FALSE_BITS | bool as u64)
+    }
+
+    /// Function value, where start is the byte offset of the function body
+    pub fn function(start: usize) -> Value {
+        // Runtime check on debug builds to ensure the byte offset fits in the payload
+        #[cfg(debug_assertions)]
+        if start as u64 > PAYLOAD_MASK {
+            panic!("Value Debug Error: Function byte offset {} does not fit in a NaN boxed value", start);
+        }
+
+        Value::from_bits(QNAN | FN_TAG | start as u64)
+    }
+
+    /// String value, where strings are interned so that the same string is never allocated twice
+    pub fn string(string: String) -> Value {
+        Value::from_heap_object(heap::intern(string))
+    }
+
+    /// Move object onto the heap, and box the pointer to it
+    pub fn object(object: Object) -> Value {
+        Value::from_heap_object(heap::allocate(object))
+    }
+
+    fn from_heap_object(heap_object: *const HeapObject) -> Value {
+        let pointer = heap_object as u64;
+
+        // Runtime check on debug builds to ensure the pointer fits in the payload
+        #[cfg(debug_assertions)]
+        if pointer > PAYLOAD_MASK {
+            panic!("Value Debug Error: Pointer {:#x} does not fit in a NaN boxed value", pointer);
+        }
+
+        Value::from_bits(SIGN_BIT | QNAN | pointer)
+    }
+
+    #[inline]
+    pub fn is_number(&self) -> bool {
+        (self.bits & QNAN) != QNAN
+    }
+
+    #[inline]
+    pub fn is_bool(&self) -> bool {
+        (self.bits | 1) == TRUE_BITS
+    }
+
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.bits == NULL_BITS
+    }
+
+    #[inline]
+    pub fn is_fn(&self) -> bool {
+        (self.bits & (SIGN_BIT | QNAN | FN_TAG)) == (QNAN | FN_TAG)
+    }
+
+    #[inline]
+    pub fn is_object(&self) -> bool {
+        (self.bits & (SIGN_BIT | QNAN)) == (SIGN_BIT | QNAN)
+    }
+
+    /// Get the number of a value, which is only meaningful if the value is a number
+    #[inline]
+    pub fn as_number(&self) -> f64 {
+        f64::from_bits(self.bits)
+    }
+
+    /// Get the bool of a value, which is only meaningful if the value is a bool
+    #[inline]
+    pub fn as_bool(&self) -> bool {
+        self.bits == TRUE_BITS
+    }
+
+    /// Get the byte offset of a function value's body, which is only meaningful if the value is a function
+    #[inline]
+    pub fn as_fn(&self) -> usize {
+        (self.bits & PAYLOAD_MASK) as usize
+    }
+
+    /// Get the heap object the value points to, if the value is an object
+    pub fn as_heap_object(&self) -> Option<&HeapObject> {
+        if !self.is_object() {
+            return None;
+        }
+
+        // Safety: Objects are only freed by collections, which never free objects of values that are still in use
+        Some(unsafe { &*((self.bits & PAYLOAD_MASK) as *const HeapObject) })
+    }
+
+    /// Get the object the value points to, if the value is an object
+    pub fn as_object(&self) -> Option<&Object> {
+        self.as_heap_object().map(|heap_object| &heap_object.object)
+    }
+
+    /// Get the string of a value, if the value is a string
+    pub fn as_str(&self) -> Option<&str> {
+        match self.as_object() {
+            Some(Object::String(string)) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Unpack the value into an enum, to match on the type of the value
+    pub fn unpack(&self) -> UnpackedValue<'_> {
+        if self.is_number() {
+            return UnpackedValue::Number(self.as_number());
+        }
+        if self.is_fn() {
+            return UnpackedValue::Fn(self.as_fn());
+        }
+
+        match self.as_object() {
+            Some(Object::String(string)) => UnpackedValue::String(string),
+            Some(Object::Array(elements)) => UnpackedValue::Array(elements),
+            Some(Object::Closure(start, captures)) => UnpackedValue::Closure(*start, captures),
+            Some(Object::Record(fields)) => UnpackedValue::Record(fields),
+            None if self.is_null() => UnpackedValue::Null,
+            None => UnpackedValue::Bool(self.as_bool()),
+        }
+    }
+
+    // A faster alternative would be to just change the value in the stack directly
+    // Rather then pop, negate, and push.
+    // Abit hard to achieve above with rust it seems compared to C
+    pub fn negate(&self) -> Result<Value, RuntimeError> {
+        if self.is_number() {
+            return Ok(Value::number(-self.as_number()));
+        }
+
+        Err(RuntimeError::TypeError(String::from("Only can negate numbers")))
+    }
+
+    // A faster alternative would be to just change the value in the stack directly
+    // Rather then pop, not, and push.
+    // Abit hard to achieve above with rust it seems compared to C
+    pub fn not(&self) -> Result<Value, RuntimeError> {
+        if self.is_bool() {
+            // true and false only differ in the lowest bit
+            return Ok(Value::from_bits(self.bits ^ 1));
+        }
+
+        Err(RuntimeError::TypeError(String::from("Only can 'not' bools")))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        // Numbers are compared as floats, since NaN is not equal to itself and 0 is equal to -0
+        if self.is_number() & other.is_number() {
+            return self.as_number() == other.as_number();
+        }
+
+        // All other values with the same bits are equal, including strings since they are interned
+        if self.bits == other.bits {
+            return true;
+        }
+
+        // Other objects are equal if their contents are equal
+        match (self.as_object(), other.as_object()) {
+            (Some(Object::String(_)), _) | (_, Some(Object::String(_))) => false,
+            (Some(object), Some(other_object)) => object == other_object,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self.unpack())
+    }
+}
+
+// Canonical format that SS programs print values in, which is the same as ri's Value Display implementation.
+// Fn values only hold the byte offset of their body, use Chunk::format_value to print functions with their names.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.unpack() {
+            UnpackedValue::Number(number) => write!(f, "{}", number),
+            UnpackedValue::String(string) => write!(f, "'{}'", string),
+            UnpackedValue::Bool(boolean) => write!(f, "{}", boolean),
+            UnpackedValue::Null => write!(f, "NULL"),
+            UnpackedValue::Fn(start) | UnpackedValue::Closure(start, _) => write!(f, "<function-ss: @{}>", start),
+            UnpackedValue::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            UnpackedValue::Record(fields) => {
+                let fields: Vec<String> = fields.iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+        }
+    }
+}
@@ -5,32 +5,34 @@ macro_rules! generic_binary_op {
     // $op_name     -> String literal name for the actual binary operation, used in error output for debugging
     // $stack       -> Takes in the identifier for the stack value too
     // $operator    -> accepts a TokenTree -> Single Token -> Punctuation -> https://doc.rust-lang.org/reference/tokens.html#punctuation
-    // $arm_pattern -> A match arm pattern for the types of values expected of the last 2 values on the stack
-    // $arm_logic   -> An expression to execute and return if the last 2 values on the stack matched the arm_pattern
-    ($op_name:literal, $stack:ident, $operator:tt, $arm_pattern:pat => $arm_logic:expr) => {
+    // $a, $b       -> Identifiers to bind the last 2 values on the stack to, for use in $type_check and $logic
+    // $type_check  -> A bool expression to check if the last 2 values on the stack have the types expected for the operation
+    // $logic       -> An expression that evaluates to the value to push onto the stack if the type check passed
+    ($op_name:literal, $stack:ident, $operator:tt, |$a:ident, $b:ident| $type_check:expr => $logic:expr) => {
         // Pop the operands off the stack in reverse order, since the first operand will be loaded first
         // Loading will be, LOAD A, LOAD B, since stack is LIFO, when we pop out 2 values, it will be B then A
-        let b = $stack.pop();
-        let a = $stack.pop();
+        let $b = $stack.pop();
+        let $a = $stack.pop();
 
         // Only run this check during debug builds, assuming correctly generated OpCodes will not have this issue
         #[cfg(debug_assertions)]
-        if a.is_none() || b.is_none(){
+        if $a.is_none() || $b.is_none(){
             panic!("VM Debug Error: Stack missing values for {} operation '{}'",  $op_name, stringify!($operator));
         }
 
-        match (a, b) {
-            $arm_pattern => $arm_logic,
+        // Unwrap the values directly assuming that they are definitely Some() variants
+        // If it fails, it means opcodes are generated wrongly where the stack is missing values needed for the opcode
+        let ($a, $b) = ($a.unwrap(), $b.unwrap());
 
-            // If the last 2 values on the stack did not match the pattern described by $arm_pattern
-            // The value types are assumed to be wrong, thus return Runtime TypeError
-            (a, b) =>
-                // Unwrap the values directly assuming that they are definitely Some() variants
-                // If it fails, it means opcodes are generated wrongly where the stack is missing values needed for the opcode
-                return Err(RuntimeError::TypeError(format!(
-                    "Invalid operand types {:?} and {:?} used for '{}' {} operation",
-                    a.unwrap(), b.unwrap(), stringify!($operator), $op_name
-                )))
+        // Type checks are bit tests on the NaN boxed values, so they are cheap enough to keep even with compile time type checking
+        if $type_check {
+            $stack.push($logic);
+        } else {
+            // If the last 2 values on the stack did not pass the type check, the value types are assumed to be wrong, thus return Runtime TypeError
+            return Err(RuntimeError::TypeError(format!(
+                "Invalid operand types {:?} and {:?} used for '{}' {} operation",
+                $a, $b, stringify!($operator), $op_name
+            )));
         }
     }
 }
@@ -48,7 +50,8 @@ macro_rules! arithmetic_binary_op {
             $operator,
 
             // Expect last 2 values on stack to be numbers, pushes a number back onto the stack
-            (Some(Value::Number(num1)), Some(Value::Number(num2))) => $stack.push(Value::Number(num1 $operator num2))
+            // Non short circuiting '&' is used to test both values without branching
+            |a, b| a.is_number() & b.is_number() => Value::number(a.as_number() $operator b.as_number())
         );
     }};
 }
@@ -65,8 +68,8 @@ macro_rules! equality_op {
             $stack,
             $operator,
 
-            // Last 2 values on stack can be any Value enum variant, compares directly using Value's derived PartialEq trait and pushes a Bool back onto the stack
-            (Some(value1), Some(value2)) => $stack.push(Value::Bool(value1 $operator value2))
+            // Last 2 values on stack can be of any type, compares directly using Value's PartialEq trait and pushes a Bool back onto the stack
+            |a, b| true => Value::bool(a $operator b)
         );
     }};
 }
@@ -84,7 +87,8 @@ macro_rules! numeric_comparison_op {
             $operator,
 
             // Expect last 2 values on stack to be numbers, pushes a bool back onto the stack
-            (Some(Value::Number(num1)), Some(Value::Number(num2))) => $stack.push(Value::Bool(num1 $operator num2))
+            // Non short circuiting '&' is used to test both values without branching
+            |a, b| a.is_number() & b.is_number() => Value::bool(a.as_number() $operator b.as_number())
         );
     }};
 }
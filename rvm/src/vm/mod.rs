@@ -1,4 +1,4 @@
 mod binary_op_macros;
 mod vm;
 
-pub use vm::VM;
+pub use vm::{callee, safepoint, VM};
@@ -1,76 +1,218 @@
+use std::collections::HashMap;
+
 use crate::{arithmetic_binary_op, equality_op, numeric_comparison_op};
 
-use crate::chunk::Chunk;
+use crate::chunk::{Chunk, Function};
+use crate::cli::{DebugFlags, JitFlags};
 use crate::debug;
-use crate::error::RuntimeError;
-use crate::opcode::OpCode;
+use crate::heap;
+use crate::jit::{Jit, JitExit};
+use crate::error::{RuntimeError, StackFrame};
+use crate::opcode::{decode, OpCode};
 use crate::value::Value;
 use crate::SSError;
 
-use std::collections::HashMap;
+// Read the byte pointed to by ip, and advance ip to point to the next byte, like Clox's READ_BYTE macro
+macro_rules! read_byte {
+    ($chunk:ident, $ip:ident) => {{
+        let byte = $chunk.codes[$ip];
+        $ip += 1;
+        byte
+    }};
+}
 
-pub struct VM {
-    pub chunk: Chunk,
+// Read the single byte stack index operand of a local opcode, and offset it by the current call frame's stack offset
+macro_rules! local_index {
+    ($chunk:ident, $ip:ident, $stack_offset:ident) => {{
+        (read_byte!($chunk, $ip) as isize + $stack_offset) as usize
+    }};
+}
 
+// Read the 2 byte wide big endian operand pointed to by ip, and advance ip past it
+macro_rules! read_u16 {
+    ($chunk:ident, $ip:ident) => {{
+        let operand = $chunk.read_u16($ip);
+        $ip += 2;
+        operand
+    }};
+}
+
+/// Function call on the call stack
+struct CallFrame {
+    /// Byte offset to return to for execution, which is the ip value after a call opcode is read
+    return_ip: usize,
+
+    /// Function value called, which is removed from the stack by the call, so frames are GC roots to keep it alive.
+    /// Its byte offset is used to find the function's name in the chunk's function table.
+    function: Value,
+
+    /// Offset added to the stack index operands of local opcodes in the called function's body.
+    /// Stack indices are compiled for the function's stack base in the function table, but the function can be called
+    /// with any number of values already on the stack, e.g. in the middle of an expression or recursively,
+    /// so the offset is the difference between where the arguments actually are and the stack base.
+    stack_offset: isize,
+}
+
+pub struct VM {
     // See this on why pointer is better then using a integer to access the vec
     // https://craftinginterpreters.com/a-virtual-machine.html#executing-instructions
     // ip: &'static usize,
 
-    // ip: Instruction Pointer, points to the current bytecode instruction being executed
-    // ip points to the instruction about to be executed, a.k.a the next instruction, not the current one being handled
+    // ip: Instruction Pointer, byte offset in the chunk to resume execution from on the next run.
+    // Only stored here between runs and for runtime errors, as the ip is copied into a local variable while executing.
     ip: usize,
+
+    // @todo Include max stack to cause stack overflow to prevent infinite stack use
+    // @todo Look into --> https://docs.rs/smallvec/1.6.1/smallvec/
+    stack: Vec<Value>,
+
+    // Global values are stored in slots resolved at compile time, where None means the global is not defined yet.
+    // Globals used in function bodies can be defined after the function, so the function might be called before they are defined.
+    globals: Vec<Option<Value>>,
+
+    // Call stack for function calls in SS, which is walked to generate a backtrace on runtime errors
+    call_stack: Vec<CallFrame>,
+
+    // Baseline JIT compiler for hot functions, only used when running a whole chunk with the JIT enabled
+    jit: Option<Jit>,
+}
+
+impl Default for VM {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VM {
-    // Wrapper method over the inner hidden _interpret method, to wrap any RuntimeError as SSError before bubbling it up
-    // @todo Instead use a trait on SSError/RuntimeError to convert between each other
-    pub fn interpret(chunk: Chunk) -> Result<Value, SSError> {
-        match VM::_interpret(chunk) {
-            Ok(value) => Ok(value),
-            Err(e) => Err(SSError::RuntimeError(e)),
+    pub fn new() -> VM {
+        VM {
+            ip: 0,
+            stack: Vec::<Value>::new(),
+            globals: Vec::<Option<Value>>::new(),
+            call_stack: Vec::<CallFrame>::new(),
+            jit: None,
         }
     }
 
-    fn _interpret(chunk: Chunk) -> Result<Value, RuntimeError> {
-        // let mut vm = VM {
-        //     chunk,
-        //     // From Clox:
-        //     // If we were trying to squeeze every ounce of speed out of our bytecode interpreter,
-        //     // we would store ip in a local variable. It gets modified so often during execution,
-        //     // that we want the compiler to keep it in a register.
-        //     ip: 0,
-        // };
-
-        // Only do this for debug builds, might add additonal debug flag to run this in vm-verbose/vm-debugging mode only
-        #[cfg(debug_assertions)]
-        println!("Chunk opcodes: {:#?}", chunk.codes);
-
-        // Local variable Instruction Pointer is a array index pointing to the current OpCode in chunk's 'codes' vector
-        let mut ip: usize = 0;
-
-        // @todo Include max stack to cause stack overflow to prevent infinite stack use
-        // let mut top_of_stack: usize = 0; // Technically just use stack.last()
-        // @todo Look into --> https://docs.rs/smallvec/1.6.1/smallvec/
-        let mut stack = Vec::<Value>::new();
-        let mut values = HashMap::<String, Value>::new();
-
-        // Call stack for function calls in SS.
-        // For now the call stack only stores the opcode_index to return to for execution, which is the ip value when a call opcode is executed
-        let mut call_stack = Vec::<usize>::new();
+    // Wrapper method to run a whole chunk on a new VM
+    pub fn interpret(chunk: Chunk, debug_flags: DebugFlags, jit_flags: JitFlags) -> Result<Value, SSError> {
+        if debug_flags.dump_chunk {
+            debug::disassemble_chunk(&chunk, "Chunk opcodes");
+        }
+
+        let mut vm = VM::new();
+
+        // Chunk is interpreted as usual if the JIT cannot be used
+        if jit_flags.jit {
+            match Jit::new(&chunk, jit_flags) {
+                Ok(jit) => vm.jit = Some(jit),
+                Err(e) => eprintln!("{}, falling back to the interpreter", e),
+            }
+        }
+
+        vm.run(&chunk, debug_flags)?;
+
+        // @todo Tmp value to return for testing
+        Ok(Value::NULL)
+    }
+
+    /// Run the chunk from where the last run stopped till the end of the chunk, keeping the globals and stack between runs,
+    /// so that the REPL can append code to the same chunk and run only the newly appended code.
+    ///
+    /// Returns the value left on the stack after running, which is the value of a REPL expression statement to echo.
+    /// Runtime errors are wrapped as SSError with a backtrace of the call stack when the error happened.
+    pub fn run(&mut self, chunk: &Chunk, debug_flags: DebugFlags) -> Result<Option<Value>, SSError> {
+        // Allocate slots for globals added since the last run
+        self.globals.resize(chunk.globals.len(), None);
+
+        // Objects of chunks that are no longer used, e.g. from REPL lines that failed to compile, can be collected before running
+        safepoint(chunk, &self.stack, &self.globals, std::iter::empty(), debug_flags);
+
+        let result = match self.execute(chunk, debug_flags) {
+            Ok(()) => Ok(self.stack.pop()),
+            Err(e) => {
+                // Values left on the stack by the failed run are never used
+                self.stack.clear();
+                Err(SSError::RuntimeError(e, self.backtrace(chunk)))
+            }
+        };
+
+        // Always resume from the end of the chunk on the next run, even if a runtime error stopped execution halfway
+        self.ip = chunk.codes.len();
+        self.call_stack.clear();
+
+        result
+    }
+
+    /// Walk the call stack from the innermost call to generate a backtrace, using the byte offset of the failed instruction saved in ip
+    fn backtrace(&self, chunk: &Chunk) -> Vec<StackFrame> {
+        let mut backtrace = Vec::<StackFrame>::with_capacity(self.call_stack.len() + 1);
+
+        // Byte offset of the instruction being executed in the current frame
+        let mut ip = self.ip;
+
+        for frame in self.call_stack.iter().rev() {
+            let function_name = match chunk
+                .functions
+                .iter()
+                .find(|function| function.start == frame.function.as_fn())
+            {
+                Some(function) => function.name.clone(),
+                None => "<anonymous>".to_string(),
+            };
+
+            backtrace.push(StackFrame {
+                function_name,
+                location: chunk.lines.location_for(ip),
+            });
+
+            // The caller is executing the CALL instruction, which is 2 bytes long and ends at the return address
+            ip = frame.return_ip - 2;
+        }
+
+        backtrace.push(StackFrame {
+            function_name: "<script>".to_string(),
+            location: chunk.lines.location_for(ip),
+        });
+
+        backtrace
+    }
+
+    fn execute(&mut self, chunk: &Chunk, debug_flags: DebugFlags) -> Result<(), RuntimeError> {
+        // From Clox:
+        // If we were trying to squeeze every ounce of speed out of our bytecode interpreter,
+        // we would store ip in a local variable. It gets modified so often during execution,
+        // that we want the compiler to keep it in a register.
+        let mut ip: usize = self.ip;
+
+        let stack = &mut self.stack;
+        let globals = &mut self.globals;
+        let call_stack = &mut self.call_stack;
+        let jit = &mut self.jit;
+
+        // Every function in the chunk, keyed by the byte offset of the function body, to check the arity and calculate the stack offset of calls
+        let functions: HashMap<usize, &Function> =
+            chunk.functions.iter().map(|function| (function.start, function)).collect();
+
+        // Stack offset of the current call frame, where top level code always runs with the stack indices it is compiled with
+        let mut stack_offset: isize = call_stack.last().map_or(0, |frame| frame.stack_offset);
 
         // Keep looping and executing as long as Instruction Pointer does not point past the length of codes in current chunk
         while ip < chunk.codes.len() {
-            // Get ref to current OpCode in chunk to execute
-            // Taking ref instead of moving it out as the code might still be executed again, e.g. in loops
-            let code = &chunk.codes[ip];
+            // Save the byte offset of the instruction about to be executed, to locate the instruction if it fails with a runtime error
+            self.ip = ip;
+
+            if debug_flags.trace_exec {
+                debug::disassemble_instruction(chunk, ip);
+            }
 
-            // Only do this for debug builds, might add additonal debug flag to run this in vm-verbose mode only
-            #[cfg(debug_assertions)]
-            debug::disassemble_instruction(&chunk, ip);
+            // Decode the current byte into an OpCode to execute, moving ip to the operand bytes if there are any
+            let byte = read_byte!(chunk, ip);
+            let code = decode(byte);
 
             match code {
                 // Pop value off stack, used at the end of expression statements
-                OpCode::POP => {
+                Some(OpCode::POP) => {
                     // Runtime check on debug builds to ensure number of pops less than number of values on stack
                     #[cfg(debug_assertions)]
                     if stack.len() == 0 {
@@ -80,10 +222,12 @@ impl VM {
                     stack.pop();
                 }
                 // Pop N number of values off stack, usually used to pop local values off stack when local scope ends
-                OpCode::POP_N(number_of_pops) => {
+                Some(OpCode::POP_N) => {
+                    let number_of_pops = read_byte!(chunk, ip) as usize;
+
                     // Runtime check on debug builds to ensure number of pops less than number of values on stack
                     #[cfg(debug_assertions)]
-                    if stack.len() < *number_of_pops {
+                    if stack.len() < number_of_pops {
                         panic!(
                             "VM Debug Error: Popping {} values from Stack of {} values",
                             number_of_pops,
@@ -97,144 +241,165 @@ impl VM {
                     stack.truncate(stack.len() - number_of_pops);
                 }
 
-                // In Clox, vm access constant value in this op code by getting next byte as index and calling from const pool
-                // But here value is stored in the enum variant, and is accessed directly instead of getting from a const pool
-                // @todo Find a way to take value out from enum to do `stack.push(value);` instead of cloning value
-                OpCode::CONSTANT(value) => stack.push(value.clone()),
+                // Just like Clox, vm access constant value in this op code by getting next byte as index and reading from const pool
+                // Values are NaN boxed 8 byte Copy types, so loading a constant only copies the value without any allocations
+                Some(OpCode::CONSTANT) => {
+                    let constant_index = read_byte!(chunk, ip) as usize;
+                    stack.push(chunk.constants[constant_index]);
+                }
+                Some(OpCode::CONSTANT_LONG) => {
+                    let constant_index = read_u16!(chunk, ip) as usize;
+                    stack.push(chunk.constants[constant_index]);
+                }
 
-                // Right now is it taking the identifier or isit copying it since identifier is a copy type?
-                OpCode::IDENTIFIER(identifier) => {
-                    // @todo Perhaps need to check if key is already used... should this be a runtime or compile time check
-                    values.insert(identifier.clone(), stack.pop().unwrap());
+                // Globals are accessed directly using their slot as index, so no hashing or allocation is needed
+                Some(OpCode::DEFINE_GLOBAL) => {
+                    let global_slot = read_u16!(chunk, ip) as usize;
+                    globals[global_slot] = stack.pop();
                 }
-                OpCode::IDENTIFIER_LOOKUP(identifier) => match values.get(identifier) {
-                    Some(value) => stack.push(value.clone()),
-                    None => {
-                        return Err(RuntimeError::UndefinedIdentifier(
-                            chunk.lines[ip],
-                            identifier.clone(),
-                        ))
+                Some(OpCode::GET_GLOBAL) => {
+                    let global_slot = read_u16!(chunk, ip) as usize;
+                    match &globals[global_slot] {
+                        Some(value) => stack.push(*value),
+                        None => {
+                            return Err(RuntimeError::UndefinedIdentifier(
+                                chunk.global_identifier(global_slot).to_string(),
+                            ))
+                        }
                     }
-                },
-
-                OpCode::GET_LOCAL(stack_index) => stack.push(stack[*stack_index].clone()),
-                OpCode::SET_LOCAL(stack_index) => stack[*stack_index] = stack.pop().unwrap(),
-
-                OpCode::TYPE_CHECK_BOOL => {
-                    let value = stack.last();
+                }
 
-                    // @todo Is runtime stack value check needed?
-                    // Only run this check during debug builds, assuming correctly compiled codes will not have this issue
-                    #[cfg(debug_assertions)]
-                    if value.is_none() {
-                        panic!("VM Debug Error: Stack missing value for TYPE_CHECK_BOOL OpCode");
-                    }
+                Some(OpCode::GET_LOCAL) => {
+                    let stack_index = local_index!(chunk, ip, stack_offset);
+                    stack.push(stack[stack_index]);
+                }
+                Some(OpCode::SET_LOCAL) => {
+                    let stack_index = local_index!(chunk, ip, stack_offset);
+                    stack[stack_index] = stack.pop().unwrap();
+                }
 
-                    match value {
-                        Some(Value::Bool(_)) => {}
+                Some(OpCode::CALL) => {
+                    let number_of_arguments_on_stack = read_byte!(chunk, ip) as usize;
 
-                        // @todo Fix the error message
-                        // Runtime type checking
-                        Some(invalid_type) => {
-                            return Err(RuntimeError::TypeError("Expect Boolean".to_string()))
-                        }
+                    safepoint(chunk, stack, globals, call_stack.iter().map(|frame| &frame.function), debug_flags);
 
-                        // This should be a no value error
-                        None => todo!(),
+                    // Function value is below the arguments on the stack, which is only missing if the chunk is generated wrongly
+                    if stack.len() <= number_of_arguments_on_stack {
+                        return Err(RuntimeError::StackUnderflow("CALL OpCode".to_string()));
                     }
-                }
 
-                OpCode::CALL(number_of_arguments_on_stack) => {
                     // 'number of arguments on stack' is used to get function value on stack
-                    // No index check required as this will not panic, since this can never be out of bounds,
-                    // because index used (stack.len() - 1 - usize) is garunteed to be less than stack.len()
-                    // @todo Might go out of lower bounds..
-                    match stack.remove(stack.len() - 1 - number_of_arguments_on_stack) {
-                        Value::Fn(opcode_index) => {
-                            // Calculate the return opcode index after function body executes a return instruction
-                            // EITHER set as ip + 1 here and return set ip = caller_ip before calling continue to skip end of loop ip increment
-                            // OR set to ip, then return set ip = caller_ip, before using end of loop increment of 1
-                            call_stack.push(ip + 1);
-
-                            // Set ip to the opcode index of the function body, so that in the next loop, this will execute the first instruction of the function body
-                            ip = opcode_index;
-
-                            // To skip rest of the loop body, skipping the ip increment code
-                            continue;
-                        }
-
-                        // @todo Fix the error message
-                        // Runtime type checking
-                        invalid_type => {
-                            // panic!("VM tmp Error: Not a function");
-                            return Err(RuntimeError::TypeError("Expect Function".to_string()));
+                    let function = stack.remove(stack.len() - 1 - number_of_arguments_on_stack);
+                    let callee = callee(&functions, function, number_of_arguments_on_stack)?;
+
+                    // Arguments start right after where the function value was, which is where the function's parameters are compiled to be at.
+                    // Locals of function bodies are never below the stack base, which is checked by the verifier for bytecode files,
+                    // so the offset stack indices are never below the arguments even if the offset is negative
+                    let arguments_start = stack.len() - number_of_arguments_on_stack;
+                    stack_offset = arguments_start as isize - callee.stack_base as isize;
+
+                    // Save the return byte offset to resume from after function body executes a return instruction
+                    // ip already points past the CALL operand, which is the next instruction of the caller
+                    call_stack.push(CallFrame {
+                        return_ip: ip,
+                        function,
+                        stack_offset,
+                    });
+
+                    // Set ip to the byte offset of the function body, so that in the next loop, this will execute the first instruction of the function body
+                    ip = function.as_fn();
+
+                    // Hot functions run as native code, either returning to the caller or resuming in the interpreter halfway through the body
+                    if let Some(jit) = jit {
+                        match jit.call(chunk, stack, globals, callee.start, arguments_start) {
+                            Some(JitExit::Returned) => {
+                                ip = call_stack.pop().unwrap().return_ip;
+                                stack_offset = call_stack.last().map_or(0, |frame| frame.stack_offset);
+                            }
+                            Some(JitExit::Interpret(resume_ip, native_calls)) => {
+                                // Calls made natively are interpreted from now on, so they get call frames like any other call
+                                for call in native_calls {
+                                    stack_offset = call.stack_offset;
+                                    call_stack.push(CallFrame {
+                                        return_ip: call.return_ip,
+                                        function: call.function,
+                                        stack_offset,
+                                    });
+                                }
+                                ip = resume_ip;
+                            }
+                            None => {}
                         }
                     }
                 }
 
-                OpCode::JUMP(offset) => {
+                Some(OpCode::JUMP) => {
+                    let offset = read_u16!(chunk, ip) as usize;
                     ip += offset;
-                    continue; // To skip rest of the loop body, skipping the ip increment code
                 }
-                OpCode::JUMP_IF_FALSE(offset) => {
+                Some(OpCode::JUMP_IF_FALSE) => {
+                    let offset = read_u16!(chunk, ip) as usize;
+
                     // Dont pop the value off the stack, just take a ref to it
                     // POP instructions will be generated seperately
                     let value = stack.last();
 
-                    // @todo Is runtime stack value check needed?
-                    // Only run this check during debug builds, assuming correctly compiled codes will not have this issue
-                    #[cfg(debug_assertions)]
-                    if value.is_none() {
-                        panic!("VM Debug Error: Stack missing value for JUMP_IF_FALSE OpCode");
-                    }
-
                     // Simple alternative without runtime type checking and without runtime stack value checking
                     // Can only be used if there is static type inference and compile time type checking
-                    // if let Some(Value::Bool(false)) = value {
+                    // if value.as_bool() == false {
                     //     ip += offset;
                     // }
                     match value {
                         // Only handle bool cases
-                        Some(Value::Bool(bool)) => {
+                        Some(value) if value.is_bool() => {
                             // Only offset VM's ip if condition evaluates to false, to skip the codes for 'true branch'
-                            if *bool == false {
+                            if !value.as_bool() {
                                 ip += offset;
-                                continue; // To skip rest of the loop body, skipping the ip increment code
                             }
                         }
 
-                        // @todo Fix the error message
                         // Runtime type checking
                         Some(invalid_type) => {
-                            return Err(RuntimeError::TypeError("Expect Boolean".to_string()))
+                            return Err(RuntimeError::ConditionTypeError(format!(
+                                "Condition expects Bool but found {:?}",
+                                invalid_type
+                            )))
                         }
 
-                        // This should be a no value error
-                        None => todo!(),
+                        None => {
+                            return Err(RuntimeError::StackUnderflow("JUMP_IF_FALSE OpCode".to_string()))
+                        }
                     }
                 }
 
-                OpCode::LOOP(offset) => {
-                    ip -= offset;
+                Some(OpCode::LOOP) => {
+                    let offset = read_u16!(chunk, ip) as usize;
 
-                    /*
-                        The offset is already calculated so that 'ip - offset' is the start of the loop conditional expression.
-                        So the rest of the loop body must be skipped with continue, to skip the ip increment code.
+                    // The offset is already calculated so that 'ip - offset' is the start of the loop conditional expression,
+                    // where ip points past the LOOP operand, so the offset includes the LOOP instruction itself.
+                    ip -= offset;
 
-                        Q:  Why not calculate offset to be 'offset + 1' so that this will work with the ip increment?
-                        A:  The edge case where the start of the loop conditional expression is ip == 0 prevents it,
-                            because ip is of usize type, which means 'ip - offset' cannot be less than 0,
-                            which means that 'ip - offset + 1' will always be at least 1, break the code in this edge case.
-                    */
-                    continue;
+                    safepoint(chunk, stack, globals, call_stack.iter().map(|frame| &frame.function), debug_flags);
                 }
 
-                OpCode::ADD => arithmetic_binary_op!(stack, +),
-                OpCode::SUBTRACT => arithmetic_binary_op!(stack, -),
-                OpCode::MULTIPLY => arithmetic_binary_op!(stack, *),
-                OpCode::DIVIDE => arithmetic_binary_op!(stack, /),
+                Some(OpCode::ADD) => arithmetic_binary_op!(stack, +),
+                Some(OpCode::SUBTRACT) => arithmetic_binary_op!(stack, -),
+                Some(OpCode::MULTIPLY) => arithmetic_binary_op!(stack, *),
+                Some(OpCode::DIVIDE) => arithmetic_binary_op!(stack, /),
 
-                OpCode::NOT => {
+                // Superinstructions are executed exactly like the pair of opcodes they replace, but with a single dispatch
+                Some(OpCode::GET_LOCAL_ADD) => {
+                    let stack_index = local_index!(chunk, ip, stack_offset);
+                    stack.push(stack[stack_index]);
+                    arithmetic_binary_op!(stack, +)
+                }
+                Some(OpCode::GET_LOCAL_SUBTRACT) => {
+                    let stack_index = local_index!(chunk, ip, stack_offset);
+                    stack.push(stack[stack_index]);
+                    arithmetic_binary_op!(stack, -)
+                }
+
+                Some(OpCode::NOT) => {
                     let value = stack.pop();
 
                     // @todo Is runtime stack value check needed?
@@ -246,7 +411,7 @@ impl VM {
 
                     stack.push(value.unwrap().not()?);
                 }
-                OpCode::NEGATE => {
+                Some(OpCode::NEGATE) => {
                     let value = stack.pop();
 
                     // @todo Is runtime stack value check needed?
@@ -259,41 +424,41 @@ impl VM {
                     stack.push(value.unwrap().negate()?);
                 }
 
-                OpCode::EQUAL => equality_op!(stack, ==),
-                OpCode::NOT_EQUAL => equality_op!(stack, !=),
-                OpCode::GREATER => numeric_comparison_op!(stack, >),
-                OpCode::GREATER_EQUAL => numeric_comparison_op!(stack, >=),
-                OpCode::LESS => numeric_comparison_op!(stack, <),
-                OpCode::LESS_EQUAL => numeric_comparison_op!(stack, <=),
+                Some(OpCode::EQUAL) => equality_op!(stack, ==),
+                Some(OpCode::NOT_EQUAL) => equality_op!(stack, !=),
+                Some(OpCode::GREATER) => numeric_comparison_op!(stack, >),
+                Some(OpCode::GREATER_EQUAL) => numeric_comparison_op!(stack, >=),
+                Some(OpCode::LESS) => numeric_comparison_op!(stack, <),
+                Some(OpCode::LESS_EQUAL) => numeric_comparison_op!(stack, <=),
 
-                OpCode::PRINT => {
-                    // @todo Dont use debug symbol
-                    println!("{:?}", stack.pop().unwrap());
+                Some(OpCode::PRINT) => {
+                    println!("{}", chunk.format_value(stack.pop().unwrap()));
                 }
 
-                OpCode::RETURN => {
-                    // Only do this for debug builds, might add additonal debug flag to run this in vm-verbose mode only
-                    #[cfg(debug_assertions)]
-                    println!("RETURN_VALUE: {:?}", stack.last());
-
-                    // Get opcode index of function caller to set as ip, to resume execution at call site
-                    ip = call_stack.pop().unwrap();
+                Some(OpCode::RETURN) => {
+                    if debug_flags.trace_exec {
+                        println!("RETURN_VALUE: {:?}", stack.last());
+                    }
 
-                    // To skip rest of the loop body, skipping the ip increment code
-                    continue;
+                    // Get byte offset of function caller to set as ip, to resume execution at call site
+                    ip = call_stack.pop().unwrap().return_ip;
+                    stack_offset = call_stack.last().map_or(0, |frame| frame.stack_offset);
                 }
                 // Implemented as a POP_N instruction followed by a RETURN
-                OpCode::RETURN_POP(number_of_pops) => {
+                Some(OpCode::RETURN_POP) => {
+                    // Operand is read without advancing ip, since ip will be set to the caller's byte offset anyway
+                    let number_of_pops = chunk.codes[ip] as usize;
+
                     // Get return value from stack first before popping locals off the stack, to prevent popping this away too
                     let return_value = stack.pop().unwrap();
 
-                    // Only do this for debug builds, might add additonal debug flag to run this in vm-verbose mode only
-                    #[cfg(debug_assertions)]
-                    println!("RETURN_VALUE: {:?}", return_value);
+                    if debug_flags.trace_exec {
+                        println!("RETURN_VALUE: {:?}", return_value);
+                    }
 
                     // Runtime check on debug builds to ensure number of pops less than number of values on stack
                     #[cfg(debug_assertions)]
-                    if stack.len() < *number_of_pops {
+                    if stack.len() < number_of_pops {
                         panic!(
                             "VM Debug Error: Popping {} values from Stack of {} values",
                             number_of_pops,
@@ -310,27 +475,76 @@ impl VM {
                     // Push the return value back onto the stack after popping locals off the stack
                     stack.push(return_value);
 
-                    // Get opcode index of function caller to set as ip, to resume execution at call site
-                    ip = call_stack.pop().unwrap();
-
-                    // To skip rest of the loop body, skipping the ip increment code
-                    continue;
+                    // Get byte offset of function caller to set as ip, to resume execution at call site
+                    ip = call_stack.pop().unwrap().return_ip;
+                    stack_offset = call_stack.last().map_or(0, |frame| frame.stack_offset);
                 }
 
-                #[allow(unreachable_patterns)]
-                ref instruction => println!("VM Error: Unknown OpCode {:?}\n", instruction),
+                None => return Err(RuntimeError::UnknownOpCode(byte)),
             }
 
-            // Only do this for debug builds, might add additonal debug flag to run this in vm-verbose mode only
-            #[cfg(debug_assertions)]
-            debug::print_stack(&stack);
+            if debug_flags.print_stack {
+                debug::print_stack(stack);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the function table entry of the function value called with the number of arguments, checking that the call is valid.
+///
+/// Every execution engine runs function bodies in a frame that starts at the function's first argument,
+/// where locals are offset from the function's stack base, so functions missing from the function table cannot be called.
+pub fn callee<'a>(
+    functions: &HashMap<usize, &'a Function>,
+    function: Value,
+    number_of_arguments: usize,
+) -> Result<&'a Function, RuntimeError> {
+    // Runtime type checking
+    if !function.is_fn() {
+        return Err(RuntimeError::CallOnNonCallable(format!("{:?}", function)));
+    }
 
-            // Increment ip (Instruction Pointer) by 1 on every loop
-            ip += 1;
+    let callee = match functions.get(&function.as_fn()) {
+        Some(&callee) => callee,
+        None => {
+            return Err(RuntimeError::InternalError(format!(
+                "Function at byte {} is missing from the function table",
+                function.as_fn()
+            )))
         }
+    };
 
-        // @todo Tmp value to return for testing
-        // InterpretResult::Ok
-        Ok(Value::Null)
+    // Compiler rejects calls with the wrong number of arguments, so this only happens with bytecode that is not generated by the compiler
+    if callee.arity != number_of_arguments {
+        return Err(RuntimeError::WrongArity(callee.name.clone(), callee.arity, number_of_arguments));
+    }
+
+    Ok(callee)
+}
+
+/// Garbage collection safepoint, where every value in use is reachable from the roots,
+/// which are the stack, the globals, the function values of the call frames,
+/// and the chunk's constants and global identifiers.
+///
+/// Safepoints are placed at function calls and loops, so that long running programs always reach one.
+#[inline]
+pub fn safepoint<'a>(
+    chunk: &'a Chunk,
+    stack: &'a [Value],
+    globals: &'a [Option<Value>],
+    frames: impl Iterator<Item = &'a Value>,
+    debug_flags: DebugFlags,
+) {
+    if debug_flags.gc_stress || heap::should_collect() {
+        heap::collect(
+            stack
+                .iter()
+                .chain(globals.iter().flatten())
+                .chain(frames)
+                .chain(chunk.constants.iter())
+                .chain(chunk.globals.iter()),
+        );
     }
 }
@@ -0,0 +1,91 @@
+// Tests for runtime errors, which run bytecode files that fail at runtime and check the error's location and backtrace.
+// Programs that fail at runtime are rejected by the type checker, so the bytecode files are compiled from programs that
+// do not fail, with a constant replaced afterwards.
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use rvm::bytecode;
+use rvm::chunk::Chunk;
+use rvm::compiler::Compiler;
+use rvm::value::Value;
+
+const PROGRAM: &str = "fn inner(n, x) {
+  if (n == 0) return x + 1;
+  return inner(n - 1, x);
+}
+fn outer(x) {
+  return inner(2, x);
+}
+print \"start\";
+print outer(10);
+print \"unreachable\";
+";
+
+const BACKTRACE: &str = "-------- Runtime ERROR --------
+[line 2] Type Error: Invalid operand types Bool(true) and Number(1.0) used for '+' Arithmetic operation
+
+Backtrace (most recent call first):
+    at inner [line 2]
+    at inner [line 3]
+    at inner [line 3]
+    at outer [line 6]
+    at <script> [line 9]
+";
+
+/// Write the program as a bytecode file where the argument of 'outer' is a bool, so that adding 1 to it fails
+fn write_failing_program(path: &Path) {
+    let mut chunk = Compiler::compile(PROGRAM.to_string(), Chunk::new()).expect("Failed to compile");
+    let argument = chunk
+        .constants
+        .iter()
+        .position(|constant| *constant == Value::number(10.0))
+        .unwrap();
+    chunk.constants[argument] = Value::bool(true);
+    fs::write(path, bytecode::serialize(&chunk)).unwrap();
+}
+
+/// Run the bytecode file with the flags, returning its stdout, stderr and exit code
+fn run(path: &Path, flags: &[&str]) -> (String, String, Option<i32>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .args(flags)
+        .arg("run")
+        .arg(path)
+        .output()
+        .expect("Failed to run rvm");
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code(),
+    )
+}
+
+#[test]
+fn runtime_errors_print_a_backtrace_of_the_call_stack() {
+    let path = std::env::temp_dir().join(format!("rvm-backtrace-{}-stack.ssc", std::process::id()));
+    write_failing_program(&path);
+    let (stdout, stderr, code) = run(&path, &[]);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(code, Some(1));
+    assert_eq!(stdout.lines().skip(1).collect::<Vec<_>>(), ["'start'"]);
+    assert_eq!(stderr.trim_end(), BACKTRACE.trim_end());
+}
+
+#[test]
+fn backtraces_are_the_same_on_every_engine() {
+    let path = std::env::temp_dir().join(format!("rvm-backtrace-{}-engines.ssc", std::process::id()));
+    write_failing_program(&path);
+
+    let interpreted = run(&path, &[]);
+    let mut runs = vec![("register", run(&path, &["--engine", "register"]))];
+    // Compiled 'inner' calls itself natively, so the error is raised after resuming in the innermost call
+    if cfg!(all(target_arch = "x86_64", target_os = "linux")) {
+        runs.push(("jit", run(&path, &["--jit", "--jit-threshold", "1"])));
+    }
+    fs::remove_file(&path).unwrap();
+
+    for (name, result) in runs {
+        assert_eq!(result, interpreted, "Runtime error is different with {}", name);
+    }
+}
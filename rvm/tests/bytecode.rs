@@ -0,0 +1,154 @@
+// Tests for bytecode files, which compile programs into .ssc files, run them, and check that malformed files are rejected
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use rvm::bytecode::{self, BytecodeError};
+use rvm::chunk::Chunk;
+use rvm::compiler::Compiler;
+use rvm::line_table::SourceLocation;
+use rvm::opcode::OpCode;
+use rvm::value::Value;
+
+const PROGRAM: &str = "const greeting = \"hi\";
+fn add(a, b) {
+  return a + b;
+}
+print greeting;
+print add(1, 2) * 2;
+print add;
+print !(1 < 2) == false;
+";
+
+fn temp_path(name: &str, extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rvm-bytecode-{}-{}.{}", std::process::id(), name, extension))
+}
+
+/// Run rvm with the arguments, returning its stdout, stderr and exit code
+fn rvm(args: &[&Path]) -> (String, String, Option<i32>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .args(args)
+        .output()
+        .expect("Failed to run rvm");
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code(),
+    )
+}
+
+/// Printed lines of a run, without the line naming the file that is run
+fn program_output(stdout: &str) -> Vec<&str> {
+    stdout.lines().skip(1).collect()
+}
+
+fn location(line: usize) -> SourceLocation {
+    SourceLocation { line, column: 1 }
+}
+
+#[test]
+fn compiled_files_run_like_their_source() {
+    let source = temp_path("round-trip", "ss");
+    let compiled = temp_path("round-trip", "ssc");
+    fs::write(&source, PROGRAM).unwrap();
+
+    let (stdout, stderr, code) = rvm(&[Path::new("compile"), &source, Path::new("-o"), &compiled]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert!(stdout.starts_with("Compiled"));
+
+    let from_source = rvm(&[Path::new("run"), &source]);
+    let from_bytecode = rvm(&[Path::new("run"), &compiled]);
+    fs::remove_file(&source).unwrap();
+    fs::remove_file(&compiled).unwrap();
+
+    assert_eq!(from_bytecode.2, Some(0), "{}", from_bytecode.1);
+    assert_eq!(program_output(&from_bytecode.0), ["'hi'", "6", "<function-ss: add>", "true"]);
+    assert_eq!(program_output(&from_bytecode.0), program_output(&from_source.0));
+}
+
+#[test]
+fn deserialized_chunks_are_the_same_as_serialized_ones() {
+    let chunk = Compiler::compile(PROGRAM.to_string(), Chunk::new()).expect("Failed to compile");
+    let deserialized = bytecode::deserialize(&bytecode::serialize(&chunk)).expect("Failed to deserialize");
+
+    assert_eq!(deserialized.codes, chunk.codes);
+    assert_eq!(deserialized.constants, chunk.constants);
+    assert_eq!(deserialized.globals, chunk.globals);
+    assert_eq!(deserialized.functions, chunk.functions);
+    for offset in 0..chunk.codes.len() {
+        assert_eq!(deserialized.lines.location_for(offset), chunk.lines.location_for(offset));
+    }
+    assert!(bytecode::verify(&deserialized).is_ok());
+}
+
+#[test]
+fn truncated_and_foreign_files_are_rejected() {
+    let chunk = Compiler::compile(PROGRAM.to_string(), Chunk::new()).expect("Failed to compile");
+    let bytes = bytecode::serialize(&chunk);
+
+    assert!(matches!(
+        bytecode::deserialize(&bytes[..bytes.len() - 1]),
+        Err(BytecodeError::UnexpectedEof)
+    ));
+    assert!(matches!(bytecode::deserialize(b"\x7fELF"), Err(BytecodeError::InvalidMagic)));
+
+    // Version is the u16 right after the magic bytes
+    let mut old_version = bytes.clone();
+    old_version[4] = bytecode::BYTECODE_VERSION as u8 - 1;
+    assert!(matches!(
+        bytecode::deserialize(&old_version),
+        Err(BytecodeError::UnsupportedVersion(_))
+    ));
+
+    let mut trailing = bytes;
+    trailing.push(0);
+    assert!(matches!(bytecode::deserialize(&trailing), Err(BytecodeError::TrailingBytes(1))));
+}
+
+#[test]
+fn verifier_rejects_jumps_into_operands() {
+    let mut chunk = Chunk::new();
+    let constant = chunk.add_constant(Value::number(1.0));
+    chunk.write_code(OpCode::JUMP, location(1));
+    chunk.write_u16(1, location(1));
+    chunk.write_code(OpCode::CONSTANT, location(2));
+    chunk.write(constant as u8, location(2));
+    chunk.write_code(OpCode::PRINT, location(2));
+
+    // Jump lands on the constant index operand at byte 4
+    assert!(matches!(bytecode::verify(&chunk), Err(BytecodeError::InvalidJumpTarget(0, 4))));
+}
+
+#[test]
+fn verifier_rejects_stack_underflows_and_imbalances() {
+    let mut underflow = Chunk::new();
+    underflow.write_code(OpCode::PRINT, location(1));
+    assert!(matches!(bytecode::verify(&underflow), Err(BytecodeError::StackUnderflow(0))));
+
+    // Only the path that does not jump pushes a value, so the paths join with different stack sizes
+    let mut imbalance = Chunk::new();
+    let condition = imbalance.add_constant(Value::bool(true));
+    imbalance.write_code(OpCode::CONSTANT, location(1));
+    imbalance.write(condition as u8, location(1));
+    imbalance.write_code(OpCode::JUMP_IF_FALSE, location(1));
+    imbalance.write_u16(2, location(1));
+    imbalance.write_code(OpCode::CONSTANT, location(1));
+    imbalance.write(condition as u8, location(1));
+    imbalance.write_code(OpCode::PRINT, location(2));
+    assert!(matches!(bytecode::verify(&imbalance), Err(BytecodeError::StackImbalance(..))));
+}
+
+#[test]
+fn malformed_files_are_not_run() {
+    let mut chunk = Chunk::new();
+    chunk.write_code(OpCode::POP, location(1));
+
+    let path = temp_path("malformed", "ssc");
+    fs::write(&path, bytecode::serialize(&chunk)).unwrap();
+    let (stdout, stderr, code) = rvm(&[Path::new("run"), &path]);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(code, Some(1));
+    assert!(program_output(&stdout).is_empty());
+    assert!(stderr.contains("Bytecode ERROR"), "{}", stderr);
+}
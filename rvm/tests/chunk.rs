@@ -0,0 +1,85 @@
+// Tests for the byte encoded instructions and the constant pool of compiled chunks
+use std::fs;
+use std::process::Command;
+
+use rvm::chunk::Chunk;
+use rvm::compiler::Compiler;
+use rvm::opcode::OpCode;
+use rvm::value::Value;
+
+fn compile(source: &str) -> Chunk {
+    Compiler::compile(source.to_string(), Chunk::new()).expect("Failed to compile")
+}
+
+#[test]
+fn instructions_are_opcode_bytes_followed_by_their_operands() {
+    let chunk = compile("print 1 + 2;\n");
+
+    assert_eq!(
+        chunk.codes,
+        [
+            OpCode::CONSTANT as u8,
+            0,
+            OpCode::CONSTANT as u8,
+            1,
+            OpCode::ADD as u8,
+            OpCode::PRINT as u8
+        ]
+    );
+    assert_eq!(chunk.constants, [Value::number(1.0), Value::number(2.0)]);
+
+    // Every byte has a location, including the operand bytes
+    assert!((0..chunk.codes.len()).all(|offset| chunk.line_for(offset) == 1));
+}
+
+#[test]
+fn constants_past_a_byte_operand_use_the_long_constant_opcode() {
+    let source: String = (0..300).map(|number| format!("print {};\n", number)).collect();
+    let chunk = compile(&source);
+    assert_eq!(chunk.constants.len(), 300);
+    assert!(chunk.codes.contains(&(OpCode::CONSTANT_LONG as u8)));
+
+    let path = std::env::temp_dir().join(format!("rvm-chunk-{}-long.ss", std::process::id()));
+    fs::write(&path, &source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .arg("run")
+        .arg(&path)
+        .output()
+        .expect("Failed to run rvm");
+    fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().skip(1).collect();
+    let expected: Vec<String> = (0..300).map(|number| number.to_string()).collect();
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn disassembler_prints_byte_offsets_and_operands() {
+    let path = std::env::temp_dir().join(format!("rvm-chunk-{}-disasm.ss", std::process::id()));
+    fs::write(&path, "const a = \"hi\";\nprint a;\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .arg("disasm")
+        .arg(&path)
+        .output()
+        .expect("Failed to run rvm");
+    fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let instructions: Vec<Vec<&str>> = stdout
+        .lines()
+        .filter(|line| !line.starts_with("======"))
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        instructions,
+        [
+            vec!["0000", "1", "CONSTANT", "0", "'String(\"hi\")'"],
+            vec!["0002", "|", "DEFINE_GLOBAL", "0", "'a'"],
+            vec!["0005", "2", "GET_GLOBAL", "0", "'a'"],
+            vec!["0008", "|", "PRINT"],
+        ]
+    );
+}
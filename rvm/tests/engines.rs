@@ -0,0 +1,90 @@
+// Tests for the register engine, which run programs on both execution engines and check that they behave the same
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../SS code for testing/conformance");
+
+/// Programs that use every kind of instruction the register engine translates, including calls nested in arguments
+const PROGRAMS: [(&str, &str); 3] = [
+    (
+        "nested-calls",
+        "fn add(a, b) {\n  return a + b;\n}\nprint add(add(1, 2), add(3, add(4, 5))) * 2;\nprint add;\n",
+    ),
+    (
+        "recursion",
+        "fn fib(n) {\n  if (n < 2) return n;\n  return fib(n - 1) + fib(n - 2);\n}\nprint fib(15);\n",
+    ),
+    (
+        "logic",
+        "const a = 1;\nif (!(a > 2) and a != 3 or false) {\n  print -a;\n} else {\n  print a;\n}\nprint a <= 1 == true;\n",
+    ),
+];
+
+/// Run the file on the engine, returning its stdout, stderr and exit code
+fn run(path: &Path, engine: &str) -> (String, String, Option<i32>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .args(["--engine", engine, "run"])
+        .arg(path)
+        .output()
+        .expect("Failed to run rvm");
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code(),
+    )
+}
+
+fn assert_same_on_both_engines(path: &Path) {
+    let stack = run(path, "stack");
+    assert_eq!(run(path, "register"), stack, "'{}' behaves differently on the register engine", path.display());
+}
+
+#[test]
+fn programs_behave_the_same_on_both_engines() {
+    for (name, program) in PROGRAMS {
+        let path = std::env::temp_dir().join(format!("rvm-engines-{}-{}.ss", std::process::id(), name));
+        fs::write(&path, program).unwrap();
+        assert_same_on_both_engines(&path);
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn corpus_behaves_the_same_on_both_engines() {
+    let mut sources: Vec<PathBuf> = fs::read_dir(CORPUS)
+        .expect("Failed to read the conformance corpus")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ss"))
+        .filter(|path| !fs::read_to_string(path).unwrap().contains("// skip rvm:"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty(), "Conformance corpus is empty");
+
+    for source in sources.iter() {
+        assert_same_on_both_engines(source);
+    }
+}
+
+#[test]
+fn register_engine_disassembles_three_address_instructions() {
+    let path = std::env::temp_dir().join(format!("rvm-engines-{}-disasm.ss", std::process::id()));
+    fs::write(&path, PROGRAMS[0].1).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .args(["--engine", "register", "disasm"])
+        .arg(&path)
+        .output()
+        .expect("Failed to run rvm");
+    fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("====== Start of register chunk:"), "{}", stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.split_whitespace().skip(2).eq(["ADD", "R2,", "R0,", "R1"])),
+        "{}",
+        stdout
+    );
+}
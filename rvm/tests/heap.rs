@@ -0,0 +1,95 @@
+// Tests for the garbage collected heap, which allocate objects through values and collect them with the given roots.
+// The heap is thread local and every test runs on its own thread, so every test starts with an empty heap.
+use std::fs;
+use std::process::Command;
+
+use rvm::bytecode::{self, BytecodeError};
+use rvm::chunk::Chunk;
+use rvm::heap;
+use rvm::value::{Object, Value};
+
+fn string(string: &str) -> Value {
+    Value::string(string.to_string())
+}
+
+#[test]
+fn objects_not_reachable_from_the_roots_are_freed() {
+    let kept = string("kept");
+    let _freed = Value::object(Object::Array(vec![string("freed"), Value::number(1.0)]));
+    assert_eq!(heap::stats().live_objects, 3);
+
+    heap::collect([kept].iter());
+
+    let stats = heap::stats();
+    assert_eq!(stats.live_objects, 1);
+    assert_eq!(stats.objects_freed, 2);
+    assert_eq!(stats.interned_strings, 1);
+    assert_eq!(kept.as_str(), Some("kept"));
+
+    // Freed strings are removed from the interning table, so the same string is allocated again
+    string("freed");
+    assert_eq!(heap::stats().objects_allocated, 4);
+}
+
+#[test]
+fn values_held_by_objects_are_traced() {
+    let array = Value::object(Object::Array(vec![string("element")]));
+    let closure = Value::object(Object::Closure(0, vec![string("capture"), array]));
+    let record = Value::object(Object::Record(vec![(String::from("closure"), closure)]));
+    assert_eq!(heap::stats().live_objects, 5);
+
+    // Every object is only reachable through the record
+    heap::collect([record].iter());
+    assert_eq!(heap::stats().live_objects, 5);
+    assert_eq!(record.to_string(), "{closure: <function-ss: @0>}");
+    assert_eq!(array.to_string(), "['element']");
+
+    heap::collect(std::iter::empty());
+    assert_eq!(heap::stats().live_objects, 0);
+}
+
+#[test]
+fn objects_with_the_same_contents_are_equal() {
+    let a = Value::object(Object::Array(vec![Value::number(1.0), string("a")]));
+    let b = Value::object(Object::Array(vec![Value::number(1.0), string("a")]));
+    let c = Value::object(Object::Array(vec![Value::number(2.0), string("a")]));
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn programs_run_with_a_collection_at_every_safepoint() {
+    let path = std::env::temp_dir().join(format!("rvm-heap-{}-stress.ss", std::process::id()));
+    fs::write(
+        &path,
+        "fn name(n) {\n  if (n == 0) return \"done\";\n  return name(n - 1);\n}\nprint name(20);\nprint \"again\";\n",
+    )
+    .unwrap();
+
+    for engine in ["stack", "register"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+            .args(["--engine", engine, "--gc-stress", "--heap-stats", "run"])
+            .arg(&path)
+            .output()
+            .expect("Failed to run rvm");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines[1..3], ["'done'", "'again'"], "on the {} engine", engine);
+        assert!(!stdout.contains("Collections:        0"), "on the {} engine", engine);
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn deserializer_rejects_unknown_constant_tags() {
+    let mut chunk = Chunk::new();
+    chunk.constants.push(Value::number(1.0));
+    let mut bytes = bytecode::serialize(&chunk);
+
+    // Tag of the first constant is after the 8 byte header and the u32 constant count.
+    // 5 is the tag of the function objects of older versions, which are never created.
+    bytes[12] = 5;
+    assert!(matches!(bytecode::deserialize(&bytes), Err(BytecodeError::InvalidConstantTag(5))));
+}
@@ -0,0 +1,99 @@
+// Tests for the JIT, which run rvm on programs with the JIT tracing what it does
+use std::fs;
+use std::process::Command;
+
+/// Run the program with the JIT compiling every function on its first call, returning the lines printed
+fn run_jit(name: &str, program: &str) -> Vec<String> {
+    let path = std::env::temp_dir().join(format!("rvm-jit-{}-{}.ss", std::process::id(), name));
+    fs::write(&path, program).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .args(["--jit", "--jit-threshold", "1", "--trace-jit", "run"])
+        .arg(&path)
+        .output()
+        .expect("Failed to run rvm");
+    fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect()
+}
+
+/// Run the program with the given flags, returning its stdout, stderr and exit code
+fn run(name: &str, program: &str, flags: &[&str]) -> (String, String, Option<i32>) {
+    let path = std::env::temp_dir().join(format!("rvm-jit-{}-{}.ss", std::process::id(), name));
+    fs::write(&path, program).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .args(flags)
+        .arg("run")
+        .arg(&path)
+        .output()
+        .expect("Failed to run rvm");
+    fs::remove_file(&path).unwrap();
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code(),
+    )
+}
+
+fn count_exits(output: &[String]) -> usize {
+    output.iter().filter(|line| line.starts_with("JIT: Exit to interpreter")).count()
+}
+
+fn sum(n: u32) -> String {
+    format!("fn sum(n) {{\n  if (n == 0) return 0;\n  return n + sum(n - 1);\n}}\nprint sum({});\n", n)
+}
+
+#[test]
+fn recursive_calls_run_native_code() {
+    if !cfg!(all(target_arch = "x86_64", target_os = "linux")) {
+        return;
+    }
+
+    // Globals are read and recursive calls are made by the compiled code, so recursion never exits to the interpreter
+    for n in [10, 200] {
+        let output = run_jit(&format!("recursion-{}", n), &sum(n));
+        assert_eq!(output.last().unwrap(), &(n * (n + 1) / 2).to_string());
+        assert!(output.contains(&String::from("JIT: Compiled function 'sum'")));
+        assert_eq!(count_exits(&output), 0, "sum({}) exits to the interpreter", n);
+    }
+}
+
+#[test]
+fn calls_deeper_than_the_native_call_limit_exit_once_per_limit() {
+    if !cfg!(all(target_arch = "x86_64", target_os = "linux")) {
+        return;
+    }
+
+    let output = run_jit("deep-recursion", &sum(2500));
+    assert_eq!(output.last().unwrap(), "3126250");
+    assert!(count_exits(&output) <= 3, "{} exits", count_exits(&output));
+}
+
+#[test]
+fn functions_called_natively_behave_like_interpreted_ones() {
+    if !cfg!(all(target_arch = "x86_64", target_os = "linux")) {
+        return;
+    }
+
+    // Printing in a function called by compiled code, and calling a function that is not compiled yet, exit to the
+    // interpreter halfway through the recursion, which continues with a call frame for every call made natively
+    let programs = [
+        ("print", "fn count(n) {\n  if (n == 0) return 0;\n  print n;\n  return count(n - 1) + 1;\n}\nprint count(5);\n"),
+        (
+            "cold-callee",
+            "fn f(a) {\n  return a * 2;\n}\nfn g(n) {\n  if (n == 0) return f(n + 1);\n  return g(n - 1) + n;\n}\nprint g(3);\n",
+        ),
+        (
+            "mutual",
+            "fn even(n) {\n  if (n == 0) return true;\n  return odd(n - 1);\n}\n\
+             fn odd(n) {\n  if (n == 0) return false;\n  print n;\n  return even(n - 1);\n}\nprint even(6);\n",
+        ),
+    ];
+    for (name, program) in programs {
+        let interpreted = run(name, program, &[]);
+        let jit = run(name, program, &["--jit", "--jit-threshold", "1"]);
+        assert_eq!(jit, interpreted, "'{}' behaves differently with the JIT", name);
+    }
+}
@@ -0,0 +1,30 @@
+// Tests for the line table, which maps every byte of a chunk back to the source location it was compiled from
+use rvm::line_table::{LineTable, SourceLocation};
+
+fn location(line: usize, column: usize) -> SourceLocation {
+    SourceLocation { line, column }
+}
+
+#[test]
+fn locations_are_found_for_every_byte_in_the_table() {
+    let mut table = LineTable::new();
+    for byte_location in [location(1, 1), location(1, 1), location(2, 5), location(3, 1), location(3, 1)] {
+        table.push(byte_location);
+    }
+
+    assert_eq!(table.runs.len(), 3);
+    assert_eq!(table.location_for(0), location(1, 1));
+    assert_eq!(table.location_for(1), location(1, 1));
+    assert_eq!(table.location_for(2), location(2, 5));
+    assert_eq!(table.location_for(4), location(3, 1));
+}
+
+#[test]
+fn offsets_outside_the_table_have_the_default_location() {
+    assert_eq!(LineTable::new().location_for(0), SourceLocation::default());
+
+    let mut table = LineTable::new();
+    table.push(location(1, 1));
+    assert_eq!(table.location_for(1), SourceLocation::default());
+    assert_eq!(table.location_for(100), SourceLocation::default());
+}
@@ -0,0 +1,70 @@
+// Tests for the optimizer, which run rvm on programs at every optimization level and check that they behave the same
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../SS code for testing/conformance");
+
+/// Programs with constant expressions whose folded values print differently from values they are equal to
+const FOLDING: &str = "print 0;
+print -(0 - 0);
+print 0 - 0;
+print -0 == 0;
+print 0 / 0;
+print -(0 / 0);
+print 0 / 0 == 0 / 0;
+print 1 / -(0 - 0);
+print !(1 < 2);
+";
+
+/// Run the program at the optimization level, returning its stdout, stderr and exit code
+fn run(path: &Path, level: u8) -> (String, String, Option<i32>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .arg(format!("-O{}", level))
+        .arg("run")
+        .arg(path)
+        .output()
+        .expect("Failed to run rvm");
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code(),
+    )
+}
+
+/// Check that the program prints the same output and exits the same way at every optimization level
+fn assert_same_at_every_level(path: &Path) {
+    let unoptimized = run(path, 0);
+    for level in 1..=2 {
+        assert_eq!(run(path, level), unoptimized, "'{}' behaves differently at -O{}", path.display(), level);
+    }
+}
+
+#[test]
+fn folded_constants_print_like_unfolded_ones() {
+    let path = std::env::temp_dir().join(format!("rvm-optimizer-{}-folding.ss", std::process::id()));
+    fs::write(&path, FOLDING).unwrap();
+
+    let (stdout, _, _) = run(&path, 2);
+    assert_same_at_every_level(&path);
+    fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = stdout.lines().skip(1).collect();
+    assert_eq!(lines, ["0", "-0", "0", "true", "NaN", "NaN", "false", "-inf", "false"]);
+}
+
+#[test]
+fn optimizing_the_corpus_does_not_change_its_output() {
+    let mut sources: Vec<PathBuf> = fs::read_dir(CORPUS)
+        .expect("Failed to read the conformance corpus")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ss"))
+        .filter(|path| !fs::read_to_string(path).unwrap().contains("// skip rvm:"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty(), "Conformance corpus is empty");
+
+    for source in sources.iter() {
+        assert_same_at_every_level(source);
+    }
+}
@@ -0,0 +1,68 @@
+// Tests for compile error recovery, which run rvm on programs with several errors and check that all of them are reported
+use std::fs;
+use std::process::Command;
+
+/// Run the program, returning its stderr and exit code
+fn compile_errors(name: &str, program: &str) -> (String, Option<i32>) {
+    let path = std::env::temp_dir().join(format!("rvm-recovery-{}-{}.ss", std::process::id(), name));
+    fs::write(&path, program).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .arg("run")
+        .arg(&path)
+        .output()
+        .expect("Failed to run rvm");
+    fs::remove_file(&path).unwrap();
+    (String::from_utf8_lossy(&output.stderr).to_string(), output.status.code())
+}
+
+/// Errors reported, without the headers and blank lines
+fn error_lines(stderr: &str) -> Vec<&str> {
+    stderr
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with("----"))
+        .collect()
+}
+
+#[test]
+fn every_statement_with_an_error_is_reported() {
+    let (stderr, code) = compile_errors("statements", "print 1\nprint 2;\nconst = 3;\nprint missing;\nprint 4;\n");
+
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        error_lines(&stderr),
+        [
+            "[line 2] Error at 'print': Expect ';' after print statement",
+            "[line 3] Error at '=': Expect const name",
+            "[line 4] ReferenceError: Tried to use undefined identifier 'missing'",
+        ]
+    );
+
+    // Only the first error is certain, as the later ones might be caused by how the compiler recovered from it
+    let first_error = stderr.find("[line 2]").unwrap();
+    let false_positives = stderr.find("---- These might be false positives ----").unwrap();
+    assert!(first_error < false_positives && false_positives < stderr.find("[line 3]").unwrap());
+}
+
+#[test]
+fn errors_in_function_bodies_do_not_hide_later_errors() {
+    let (stderr, code) = compile_errors(
+        "function",
+        "fn f(n) {\n  return n +;\n}\nprint f(1);\nprint 1 +;\n",
+    );
+
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        error_lines(&stderr),
+        ["[line 2] Error at ';': Expect expression", "[line 5] Error at ';': Expect expression"]
+    );
+}
+
+#[test]
+fn single_errors_have_no_false_positives() {
+    let (stderr, code) = compile_errors("single", "print 1 + ;\n");
+
+    assert_eq!(code, Some(1));
+    assert_eq!(error_lines(&stderr), ["[line 1] Error at ';': Expect expression"]);
+    assert!(!stderr.contains("false positives"));
+}
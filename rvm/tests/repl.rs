@@ -0,0 +1,52 @@
+// Tests for the REPL, which pipe lines into 'rvm repl' and check what every line echoes
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Run the REPL on the lines, returning the output printed after every prompt and the errors printed
+fn repl(lines: &str) -> (Vec<String>, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run rvm");
+    child.stdin.take().unwrap().write_all(lines.as_bytes()).unwrap();
+    let output = child.wait_with_output().expect("Failed to run rvm");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // Every prompt starts the output of the line read after it, and the prompt after EOF ends the output
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut outputs: Vec<String> = stdout.split("> ").skip(1).map(|output| output.trim_end().to_string()).collect();
+    assert_eq!(outputs.pop().as_deref(), Some(""));
+    (outputs, String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+#[test]
+fn expression_statements_echo_their_value() {
+    let (outputs, errors) = repl("1 + 2;\n\"s\";\n!true;\n");
+    assert_eq!(outputs, ["3", "'s'", "false"]);
+    assert!(errors.is_empty(), "{}", errors);
+}
+
+#[test]
+fn other_statements_do_not_echo() {
+    let (outputs, errors) = repl("const a = 1;\nprint a;\nfn f(n) { return n * 2; }\nif (a == 1) { print 2; }\n");
+    assert_eq!(outputs, ["", "1", "", "2"]);
+    assert!(errors.is_empty(), "{}", errors);
+}
+
+#[test]
+fn globals_and_functions_are_kept_across_lines() {
+    let (outputs, errors) = repl("const a = 1;\nfn f(n) { return n * 2; }\nf(a);\nf;\n");
+    assert_eq!(outputs, ["", "", "2", "<function-ss: f>"]);
+    assert!(errors.is_empty(), "{}", errors);
+}
+
+#[test]
+fn lines_with_errors_do_not_end_the_repl() {
+    let (outputs, errors) = repl("1 +;\nmissing;\n1;\n");
+    assert_eq!(outputs, ["", "", "1"]);
+    assert!(errors.contains("[line 1] Error at ';': Expect expression"), "{}", errors);
+    assert!(errors.contains("[line 1] ReferenceError: Tried to use undefined identifier 'missing'"), "{}", errors);
+}
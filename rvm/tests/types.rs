@@ -0,0 +1,87 @@
+// Tests for the compile time type checking that replaced the TYPE_CHECK opcodes,
+// which run rvm on programs with type errors and check that they are rejected before anything runs
+use std::fs;
+use std::process::Command;
+
+/// Run the program, returning its stdout, stderr and exit code
+fn run(name: &str, program: &str) -> (String, String, Option<i32>) {
+    let path = std::env::temp_dir().join(format!("rvm-types-{}-{}.ss", std::process::id(), name));
+    fs::write(&path, program).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rvm"))
+        .arg("run")
+        .arg(&path)
+        .output()
+        .expect("Failed to run rvm");
+    fs::remove_file(&path).unwrap();
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code(),
+    )
+}
+
+/// Check that the program is rejected with the compile error, without running the statements before the error
+fn assert_type_error(name: &str, program: &str, error: &str) {
+    let (stdout, stderr, code) = run(name, program);
+    assert_eq!(code, Some(1), "'{}' was not rejected", name);
+    assert_eq!(stdout.lines().count(), 1, "'{}' ran before it was rejected: {}", name, stdout);
+    assert!(stderr.starts_with("-------- Compile ERROR --------\n"), "{}", stderr);
+    assert!(stderr.contains(error), "'{}' failed with: {}", name, stderr);
+}
+
+#[test]
+fn operands_of_the_wrong_type_are_rejected() {
+    assert_type_error(
+        "binary",
+        "print 1;\nprint 1 + \"a\";\n",
+        "[line 2] TypeError: Binary operator expects Number but found String",
+    );
+    assert_type_error(
+        "unary",
+        "print 1;\nprint !1;\n",
+        "[line 2] TypeError: Unary operator expects Bool but found Number",
+    );
+    assert_type_error(
+        "logical",
+        "print 1;\nprint 1 and true;\n",
+        "[line 2] TypeError: Operator 'and' expects Bool but found Number",
+    );
+}
+
+#[test]
+fn conditions_must_be_bools() {
+    assert_type_error(
+        "condition",
+        "print 1;\nif (1) {\n  print 2;\n}\n",
+        "[line 2] TypeError: 'if' condition expects Bool but found Number",
+    );
+}
+
+#[test]
+fn parameter_types_are_inferred_from_the_function_body_and_its_calls() {
+    // 'n' is a number as it is added to 1, which the call with a string contradicts
+    assert_type_error(
+        "parameter",
+        "fn inc(n) {\n  return n + 1;\n}\nprint inc(\"a\");\n",
+        "[line 4] TypeError: Call to 'inc' expects fn(Number) -> Number but found fn(String) -> ",
+    );
+
+    // Functions passed as arguments are checked against how they are called
+    assert_type_error(
+        "higher-order",
+        "fn apply(g) {\n  return g(1) + 1;\n}\nprint apply(2);\n",
+        "[line 4] TypeError: Call to 'apply' expects fn(fn(Number) -> Number) -> Number but found fn(Number) -> ",
+    );
+}
+
+#[test]
+fn well_typed_programs_run() {
+    let (stdout, stderr, code) = run(
+        "well-typed",
+        "fn apply(g, x) {\n  return g(x);\n}\nfn double(n) {\n  return n * 2;\n}\n\
+         print apply(double, 4);\nprint !(1 < 2) or 1 == 1;\n",
+    );
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert_eq!(stdout.lines().skip(1).collect::<Vec<_>>(), ["8", "true"]);
+}
@@ -0,0 +1,49 @@
+// Tests for the NaN boxed values, which check that every type of value survives being boxed into 8 bytes
+use rvm::value::{UnpackedValue, Value, NAN_BITS};
+
+#[test]
+fn values_are_8_bytes() {
+    assert_eq!(std::mem::size_of::<Value>(), 8);
+}
+
+#[test]
+fn numbers_keep_their_exact_value() {
+    for number in [0.0, -0.0, 1.5, -3.25e300, f64::MIN_POSITIVE, f64::INFINITY, f64::NEG_INFINITY] {
+        let value = Value::number(number);
+        assert!(value.is_number() && !value.is_bool() && !value.is_null() && !value.is_fn() && !value.is_object());
+        assert_eq!(value.as_number().to_bits(), number.to_bits());
+    }
+}
+
+#[test]
+fn nans_are_canonicalized_so_they_are_never_mistaken_for_other_types() {
+    // NaN with every payload bit set, which would otherwise look like a boxed object
+    let value = Value::number(f64::from_bits(u64::MAX));
+    assert!(value.is_number() && !value.is_object());
+    assert_eq!(value.to_bits(), NAN_BITS);
+    assert!(value.as_number().is_nan());
+}
+
+#[test]
+fn other_types_unpack_to_what_was_boxed() {
+    assert_eq!(Value::bool(true).unpack(), UnpackedValue::Bool(true));
+    assert_eq!(Value::bool(false).unpack(), UnpackedValue::Bool(false));
+    assert_eq!(Value::NULL.unpack(), UnpackedValue::Null);
+    assert_eq!(Value::function(1234).unpack(), UnpackedValue::Fn(1234));
+    assert_eq!(Value::string(String::from("ss")).unpack(), UnpackedValue::String("ss"));
+
+    assert!(!Value::function(0).is_number());
+    assert!(!Value::bool(false).is_number());
+}
+
+#[test]
+fn bool_and_numeric_operations_work_on_the_boxed_bits() {
+    assert_eq!(Value::bool(true).not().unwrap(), Value::bool(false));
+    assert_eq!(Value::number(2.0).negate().unwrap(), Value::number(-2.0));
+    assert!(Value::number(1.0).not().is_err());
+    assert!(Value::bool(true).negate().is_err());
+
+    // Numbers are compared as floats, so 0 equals -0 but NaN is not equal to itself
+    assert_eq!(Value::number(0.0), Value::number(-0.0));
+    assert_ne!(Value::number(f64::NAN), Value::number(f64::NAN));
+}